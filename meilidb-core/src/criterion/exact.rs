@@ -5,7 +5,7 @@ use sdset::Set;
 use slice_group_by::GroupBy;
 
 use crate::criterion::Criterion;
-use crate::RawDocument;
+use crate::{Number, RawDocument};
 
 #[inline]
 fn number_exact_matches(
@@ -70,6 +70,16 @@ impl Criterion for Exact {
     fn name(&self) -> &str {
         "Exact"
     }
+
+    fn explain(&self, document: &RawDocument) -> Option<Number> {
+        let count = number_exact_matches(
+            document.query_index(),
+            document.attribute(),
+            document.is_exact(),
+            &document.fields_counts,
+        );
+        Some(Number::Unsigned(count as u64))
+    }
 }
 
 #[cfg(test)]
@@ -1,27 +1,42 @@
 mod document_id;
 mod exact;
 mod number_of_words;
+mod popularity;
 mod sort_by_attr;
 mod sum_of_typos;
 mod sum_of_words_attribute;
 mod sum_of_words_position;
 mod words_proximity;
 
-use crate::RawDocument;
+use crate::{Number, RawDocument};
 use std::cmp::Ordering;
 
 pub use self::{
-    document_id::DocumentId, exact::Exact, number_of_words::NumberOfWords,
-    sort_by_attr::SortByAttr, sum_of_typos::SumOfTypos,
-    sum_of_words_attribute::SumOfWordsAttribute, sum_of_words_position::SumOfWordsPosition,
+    document_id::DocumentId,
+    exact::Exact,
+    number_of_words::NumberOfWords,
+    popularity::Popularity,
+    sort_by_attr::{SortByAttr, SortByAttrError},
+    sum_of_typos::SumOfTypos,
+    sum_of_words_attribute::{SumOfWordsAttribute, WeightedWordsAttribute},
+    sum_of_words_position::SumOfWordsPosition,
     words_proximity::WordsProximity,
 };
 
+pub(crate) use self::words_proximity::{index_proximity, MAX_DISTANCE};
+
 pub trait Criterion: Send + Sync {
     fn evaluate(&self, lhs: &RawDocument, rhs: &RawDocument) -> Ordering;
 
     fn name(&self) -> &str;
 
+    /// The value this criterion compares for the document, used to explain
+    /// why a document is ranked before another one.
+    #[inline]
+    fn explain(&self, _document: &RawDocument) -> Option<Number> {
+        None
+    }
+
     #[inline]
     fn eq(&self, lhs: &RawDocument, rhs: &RawDocument) -> bool {
         self.evaluate(lhs, rhs) == Ordering::Equal
@@ -37,6 +52,10 @@ impl<'a, T: Criterion + ?Sized + Send + Sync> Criterion for &'a T {
         (**self).name()
     }
 
+    fn explain(&self, document: &RawDocument) -> Option<Number> {
+        (**self).explain(document)
+    }
+
     fn eq(&self, lhs: &RawDocument, rhs: &RawDocument) -> bool {
         (**self).eq(lhs, rhs)
     }
@@ -51,6 +70,10 @@ impl<T: Criterion + ?Sized> Criterion for Box<T> {
         (**self).name()
     }
 
+    fn explain(&self, document: &RawDocument) -> Option<Number> {
+        (**self).explain(document)
+    }
+
     fn eq(&self, lhs: &RawDocument, rhs: &RawDocument) -> bool {
         (**self).eq(lhs, rhs)
     }
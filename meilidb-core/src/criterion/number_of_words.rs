@@ -1,5 +1,5 @@
 use crate::criterion::Criterion;
-use crate::RawDocument;
+use crate::{Number, RawDocument};
 use slice_group_by::GroupBy;
 use std::cmp::Ordering;
 
@@ -28,4 +28,9 @@ impl Criterion for NumberOfWords {
     fn name(&self) -> &str {
         "NumberOfWords"
     }
+
+    fn explain(&self, document: &RawDocument) -> Option<Number> {
+        let words = number_of_query_words(document.query_index());
+        Some(Number::Unsigned(words as u64))
+    }
 }
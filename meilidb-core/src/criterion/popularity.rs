@@ -0,0 +1,32 @@
+use std::cmp::Ordering;
+
+use crate::criterion::Criterion;
+use crate::{Number, PopularityMap, RawDocument};
+
+/// Sorts the documents by decreasing popularity score,
+/// the most clicked or bought documents are ranked first.
+pub struct Popularity<'a> {
+    popularity_map: &'a PopularityMap,
+}
+
+impl<'a> Popularity<'a> {
+    pub fn new(popularity_map: &'a PopularityMap) -> Popularity<'a> {
+        Popularity { popularity_map }
+    }
+}
+
+impl<'a> Criterion for Popularity<'a> {
+    fn evaluate(&self, lhs: &RawDocument, rhs: &RawDocument) -> Ordering {
+        let lhs = self.popularity_map.get(lhs.id);
+        let rhs = self.popularity_map.get(rhs.id);
+        rhs.cmp(&lhs)
+    }
+
+    fn name(&self) -> &str {
+        "Popularity"
+    }
+
+    fn explain(&self, document: &RawDocument) -> Option<Number> {
+        Some(Number::Unsigned(self.popularity_map.get(document.id)))
+    }
+}
@@ -3,7 +3,7 @@ use std::error::Error;
 use std::fmt;
 
 use crate::criterion::Criterion;
-use crate::{RankedMap, RawDocument};
+use crate::{Number, RankedMap, RawDocument};
 use meilidb_schema::{Schema, SchemaAttr};
 
 /// An helper struct that permit to sort documents by
@@ -109,6 +109,10 @@ impl<'a> Criterion for SortByAttr<'a> {
     fn name(&self) -> &str {
         "SortByAttr"
     }
+
+    fn explain(&self, document: &RawDocument) -> Option<Number> {
+        self.ranked_map.get(document.id, self.attr)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -3,7 +3,7 @@ use std::cmp::Ordering;
 use slice_group_by::GroupBy;
 
 use crate::criterion::Criterion;
-use crate::RawDocument;
+use crate::{Number, RawDocument};
 
 // This function is a wrong logarithmic 10 function.
 // It is safe to panic on input number higher than 3,
@@ -34,6 +34,19 @@ fn sum_matches_typos(query_index: &[u32], distance: &[u8]) -> usize {
     (number_words as f32 / (sum_typos + 1.0) * 1000.0) as usize
 }
 
+#[inline]
+fn number_of_typos(query_index: &[u32], distance: &[u8]) -> usize {
+    let mut number_typos = 0;
+    let mut index = 0;
+
+    for group in query_index.linear_group() {
+        number_typos += distance[index] as usize;
+        index += group.len();
+    }
+
+    number_typos
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct SumOfTypos;
 
@@ -57,6 +70,12 @@ impl Criterion for SumOfTypos {
     fn name(&self) -> &str {
         "SumOfTypos"
     }
+
+    // the score compared is not readable, the number of typos is reported instead
+    fn explain(&self, document: &RawDocument) -> Option<Number> {
+        let typos = number_of_typos(document.query_index(), document.distance());
+        Some(Number::Unsigned(typos as u64))
+    }
 }
 
 #[cfg(test)]
@@ -1,7 +1,8 @@
 use crate::criterion::Criterion;
-use crate::RawDocument;
+use crate::{Number, RawDocument};
 use slice_group_by::GroupBy;
 use std::cmp::Ordering;
+use std::collections::HashMap;
 
 #[inline]
 fn sum_matches_attributes(query_index: &[u32], attribute: &[u16]) -> usize {
@@ -16,6 +17,24 @@ fn sum_matches_attributes(query_index: &[u32], attribute: &[u16]) -> usize {
     sum_attributes
 }
 
+#[inline]
+fn sum_matches_weights(
+    query_index: &[u32],
+    attribute: &[u16],
+    weights: &HashMap<u16, u16>,
+) -> usize {
+    let mut sum_weights = 0;
+    let mut index = 0;
+
+    for group in query_index.linear_group() {
+        let weight = weights.get(&attribute[index]).cloned().unwrap_or(1);
+        sum_weights += weight as usize;
+        index += group.len();
+    }
+
+    sum_weights
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct SumOfWordsAttribute;
 
@@ -39,6 +58,46 @@ impl Criterion for SumOfWordsAttribute {
     fn name(&self) -> &str {
         "SumOfWordsAttribute"
     }
+
+    fn explain(&self, document: &RawDocument) -> Option<Number> {
+        let sum = sum_matches_attributes(document.query_index(), document.attribute());
+        Some(Number::Unsigned(sum as u64))
+    }
+}
+
+/// Prefers the documents matching the query words in the attributes
+/// with the highest weights, the attributes not weighted have a weight of one.
+/// The documents with the same weights are sorted like with `SumOfWordsAttribute`.
+#[derive(Debug, Clone)]
+pub struct WeightedWordsAttribute {
+    weights: HashMap<u16, u16>,
+}
+
+impl WeightedWordsAttribute {
+    pub fn new(weights: HashMap<u16, u16>) -> WeightedWordsAttribute {
+        WeightedWordsAttribute { weights }
+    }
+}
+
+impl Criterion for WeightedWordsAttribute {
+    fn evaluate(&self, lhs: &RawDocument, rhs: &RawDocument) -> Ordering {
+        let lhs_weights = sum_matches_weights(lhs.query_index(), lhs.attribute(), &self.weights);
+        let rhs_weights = sum_matches_weights(rhs.query_index(), rhs.attribute(), &self.weights);
+
+        lhs_weights
+            .cmp(&rhs_weights)
+            .reverse()
+            .then_with(|| SumOfWordsAttribute.evaluate(lhs, rhs))
+    }
+
+    fn name(&self) -> &str {
+        "WeightedWordsAttribute"
+    }
+
+    fn explain(&self, document: &RawDocument) -> Option<Number> {
+        let sum = sum_matches_weights(document.query_index(), document.attribute(), &self.weights);
+        Some(Number::Unsigned(sum as u64))
+    }
 }
 
 #[cfg(test)]
@@ -61,4 +120,25 @@ mod tests {
         let doc1 = sum_matches_attributes(query_index1, attribute1);
         assert_eq!(doc0.cmp(&doc1), Ordering::Less);
     }
+
+    // typing: "soulier" with the description weighted three times
+    //
+    // doc0: { 0. "Soulier bleu", 1. "bla bla bla" }
+    // doc1: { 0. "Botte rouge", 1. "Soulier en cuir" }
+    #[test]
+    fn weighted_description() {
+        let mut weights = HashMap::new();
+        weights.insert(1, 3);
+
+        let query_index0 = &[0];
+        let attribute0 = &[0];
+
+        let query_index1 = &[0];
+        let attribute1 = &[1];
+
+        let doc0 = sum_matches_weights(query_index0, attribute0, &weights);
+        let doc1 = sum_matches_weights(query_index1, attribute1, &weights);
+        assert_eq!((doc0, doc1), (1, 3));
+        assert_eq!(doc0.cmp(&doc1).reverse(), Ordering::Greater);
+    }
 }
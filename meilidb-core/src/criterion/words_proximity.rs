@@ -1,16 +1,16 @@
 use crate::criterion::Criterion;
-use crate::RawDocument;
+use crate::{Number, RawDocument};
 use slice_group_by::GroupBy;
 use std::cmp::{self, Ordering};
 
-const MAX_DISTANCE: u16 = 8;
+pub(crate) const MAX_DISTANCE: u16 = 8;
 
 #[inline]
 fn clone_tuple<T: Clone, U: Clone>((a, b): (&T, &U)) -> (T, U) {
     (a.clone(), b.clone())
 }
 
-fn index_proximity(lhs: u16, rhs: u16) -> u16 {
+pub(crate) fn index_proximity(lhs: u16, rhs: u16) -> u16 {
     if lhs < rhs {
         cmp::min(rhs - lhs, MAX_DISTANCE)
     } else {
@@ -80,26 +80,27 @@ fn matches_proximity(
     proximity
 }
 
+/// Uses the proximity precomputed from the words pairs proximities store
+/// when there is one, the matches positions are only used otherwise.
+fn document_proximity(document: &RawDocument) -> u16 {
+    match document.words_proximity {
+        Some(proximity) => proximity,
+        None => matches_proximity(
+            document.query_index(),
+            document.distance(),
+            document.attribute(),
+            document.word_index(),
+        ),
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct WordsProximity;
 
 impl Criterion for WordsProximity {
     fn evaluate(&self, lhs: &RawDocument, rhs: &RawDocument) -> Ordering {
-        let lhs = {
-            let query_index = lhs.query_index();
-            let distance = lhs.distance();
-            let attribute = lhs.attribute();
-            let word_index = lhs.word_index();
-            matches_proximity(query_index, distance, attribute, word_index)
-        };
-
-        let rhs = {
-            let query_index = rhs.query_index();
-            let distance = rhs.distance();
-            let attribute = rhs.attribute();
-            let word_index = rhs.word_index();
-            matches_proximity(query_index, distance, attribute, word_index)
-        };
+        let lhs = document_proximity(lhs);
+        let rhs = document_proximity(rhs);
 
         lhs.cmp(&rhs)
     }
@@ -107,6 +108,11 @@ impl Criterion for WordsProximity {
     fn name(&self) -> &str {
         "WordsProximity"
     }
+
+    fn explain(&self, document: &RawDocument) -> Option<Number> {
+        let proximity = document_proximity(document);
+        Some(Number::Unsigned(u64::from(proximity)))
+    }
 }
 
 #[cfg(test)]
@@ -1,30 +1,132 @@
 use std::collections::hash_map::{Entry, HashMap};
 use std::fs::File;
 use std::path::Path;
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
 use std::{fs, thread};
 
-use crossbeam_channel::Receiver;
+use crossbeam_channel::{Receiver, Sender};
 use heed::types::{Str, Unit};
 use heed::{CompactionOption, Result as ZResult};
 use log::{debug, error};
 
-use crate::{store, update, Index, MResult};
+use crate::{store, update, Env, Error, Index, LimitExceeded, MResult, QueryCache};
+
+const DEFAULT_MAP_SIZE: usize = 10 * 1024 * 1024 * 1024; // 10GB
+const DEFAULT_MAX_MAP_SIZE: usize = 100 * 1024 * 1024 * 1024; // 100GB
+const DEFAULT_QUERY_CACHE_SIZE: usize = 1000;
+const ALIAS_PREFIX_KEY: &str = "alias-";
+
+fn alias_key(alias: &str) -> String {
+    format!("{}{}", ALIAS_PREFIX_KEY, alias)
+}
+
+pub enum UpdateEvent {
+    NewUpdate,
+    MustStop,
+}
+
+pub type UpdateEvents = Receiver<UpdateEvent>;
+pub type UpdateEventsEmitter = Sender<UpdateEvent>;
 
 pub type BoxUpdateFn = Box<dyn Fn(update::ProcessedUpdateResult) + Send + Sync + 'static>;
 type ArcSwapFn = arc_swap::ArcSwapOption<BoxUpdateFn>;
 
+/// The update loop gives its events receiver back once stopped.
+type UpdateLoopHandle = thread::JoinHandle<UpdateEvents>;
+
+#[derive(Debug, Copy, Clone)]
+pub struct DatabaseOptions {
+    /// The initial size of the LMDB memory map, in bytes.
+    ///
+    /// The map is automatically grown when an update does not fit in it.
+    pub map_size: usize,
+    /// The size the LMDB memory map cannot be grown beyond, in bytes.
+    ///
+    /// The updates that do not fit in a map of this size fail.
+    pub max_map_size: usize,
+    /// The maximum number of searches results kept in the query cache,
+    /// the cache is disabled when zero.
+    pub query_cache_size: usize,
+}
+
+impl Default for DatabaseOptions {
+    fn default() -> DatabaseOptions {
+        DatabaseOptions {
+            map_size: DEFAULT_MAP_SIZE,
+            max_map_size: DEFAULT_MAX_MAP_SIZE,
+            query_cache_size: DEFAULT_QUERY_CACHE_SIZE,
+        }
+    }
+}
+
 pub struct Database {
-    pub env: heed::Env,
+    pub env: Env,
+    query_cache: Arc<QueryCache>,
     common_store: heed::PolyDatabase,
     indexes_store: heed::Database<Str, Unit>,
-    indexes: RwLock<HashMap<String, (Index, Arc<ArcSwapFn>, thread::JoinHandle<()>)>>,
+    // the update loop of an index is stopped, and its handle taken, while it is renamed
+    indexes: RwLock<HashMap<String, (Index, Arc<ArcSwapFn>, Option<UpdateLoopHandle>)>>,
+    aliases: RwLock<HashMap<String, String>>,
+    // held while an index is renamed or deleted, they stop its update loop
+    indexes_changes: Mutex<()>,
+}
+
+/// Returns `false` if the map already has its maximum size, the update
+/// that did not fit in it cannot be retried.
+fn grow_map_size(env: &Env, full_size: usize) -> bool {
+    // the writer that triggered the map full error must have been
+    // aborted, the map is grown once the other transactions end
+    match env.grow_map_size(full_size) {
+        Ok(grown) => grown,
+        Err(e) => {
+            error!("LMDB map size growing failed: {}", e);
+            true
+        }
+    }
 }
 
-fn update_awaiter(receiver: Receiver<()>, env: heed::Env, update_fn: Arc<ArcSwapFn>, index: Index) {
-    for () in receiver {
+/// Marks the next update as failed because it does not fit in a map of the maximum
+/// size, returns `false` if it could not be consumed and must not be retried now.
+fn reject_update(env: &Env, index: &Index, update_fn: &ArcSwapFn) -> bool {
+    let limit = LimitExceeded::MaxMapSize {
+        limit: env.max_map_size() as u64,
+    };
+    let error = Error::from(limit);
+
+    let result = env.write_txn().map_err(Error::from).and_then(|mut writer| {
+        let status = update::reject_update(&mut writer, index.clone(), &error)?;
+        writer.commit()?;
+        Ok(status)
+    });
+
+    match result {
+        Ok(Some(status)) => {
+            if let Some(ref callback) = *update_fn.load() {
+                (callback)(status);
+            }
+            true
+        }
+        Ok(None) => false,
+        Err(e) => {
+            error!("rejected update could not be consumed: {}", e);
+            false
+        }
+    }
+}
+
+fn update_awaiter(
+    receiver: UpdateEvents,
+    env: Env,
+    query_cache: Arc<QueryCache>,
+    update_fn: Arc<ArcSwapFn>,
+    index_name: String,
+    index: Index,
+) -> UpdateEvents {
+    while let Ok(UpdateEvent::NewUpdate) = receiver.recv() {
         // consume all updates in order (oldest first)
         loop {
+            let current_map_size = env.map_size();
+
             let mut writer = match env.write_txn() {
                 Ok(writer) => writer,
                 Err(e) => {
@@ -36,12 +138,37 @@ fn update_awaiter(receiver: Receiver<()>, env: heed::Env, update_fn: Arc<ArcSwap
             match update::update_task(&mut writer, index.clone()) {
                 Ok(Some(status)) => {
                     match status.result {
-                        Ok(_) => {
-                            if let Err(e) = writer.commit() {
+                        Ok(_) => match writer.commit() {
+                            // the searches done before this update are outdated
+                            Ok(()) => query_cache.invalidate(&index_name),
+                            Err(e) => {
+                                let e = Error::from(e);
+                                if e.is_map_full() {
+                                    // the update has not been consumed, retry it with
+                                    // a bigger map or reject it if it cannot be grown
+                                    if grow_map_size(&env, current_map_size)
+                                        || reject_update(&env, &index, &update_fn)
+                                    {
+                                        continue;
+                                    }
+                                    break;
+                                }
                                 error!("update transaction failed: {}", e)
                             }
+                        },
+                        Err(_) => {
+                            // the changes of a failed update are discarded,
+                            // only its result is stored
+                            writer.abort();
+                            let result = env.write_txn().map_err(Error::from).and_then(|mut w| {
+                                update::consume_failed_update(&mut w, index.clone(), &status)?;
+                                w.commit().map_err(Into::into)
+                            });
+                            if let Err(e) = result {
+                                error!("failed update could not be consumed: {}", e);
+                                break;
+                            }
                         }
-                        Err(_) => writer.abort(),
                     }
 
                     if let Some(ref callback) = *update_fn.load() {
@@ -54,6 +181,16 @@ fn update_awaiter(receiver: Receiver<()>, env: heed::Env, update_fn: Arc<ArcSwap
                     writer.abort();
                     break;
                 }
+                Err(ref e) if e.is_map_full() => {
+                    writer.abort();
+                    // the update has not been consumed, retry it with
+                    // a bigger map or reject it if it cannot be grown
+                    if !grow_map_size(&env, current_map_size)
+                        && !reject_update(&env, &index, &update_fn)
+                    {
+                        break;
+                    }
+                }
                 Err(e) => {
                     error!("update task failed: {}", e);
                     writer.abort()
@@ -61,17 +198,71 @@ fn update_awaiter(receiver: Receiver<()>, env: heed::Env, update_fn: Arc<ArcSwap
             }
         }
     }
+
+    debug!("update loop system stopped");
+
+    receiver
+}
+
+/// Moves the updates enqueued in the stores of a renamed index to the index it
+/// has been renamed to, until every clone of the renamed index has been dropped.
+/// The moved updates are given new ids.
+fn forward_updates(receiver: UpdateEvents, env: Env, from: store::Updates, to: Index) {
+    let forward = || -> MResult<()> {
+        let mut writer = env.write_txn()?;
+        let mut forwarded = false;
+        while let Some((_, update)) = from.pop_front(&mut writer)? {
+            let update_id = update::next_update_id(&mut writer, to.updates, to.updates_results)?;
+            to.updates.put_update(&mut writer, update_id, &update)?;
+            forwarded = true;
+        }
+        writer.commit()?;
+
+        if forwarded {
+            let _ = to.updates_notifier.send(UpdateEvent::NewUpdate);
+        }
+        Ok(())
+    };
+
+    // the updates are enqueued after the event is sent, the
+    // last ones are forwarded once all the senders are dropped
+    for _event in receiver.iter() {
+        if let Err(e) = forward() {
+            error!("updates forwarding failed: {}", e);
+        }
+    }
+
+    if let Err(e) = forward() {
+        error!("updates forwarding failed: {}", e);
+    }
+}
+
+fn spawn_update_awaiter(
+    env: &Env,
+    query_cache: &Arc<QueryCache>,
+    receiver: UpdateEvents,
+    update_fn: Arc<ArcSwapFn>,
+    index_name: &str,
+    index: Index,
+) -> UpdateLoopHandle {
+    let env = env.clone();
+    let query_cache = query_cache.clone();
+    let index_name = index_name.to_owned();
+    thread::spawn(move || update_awaiter(receiver, env, query_cache, update_fn, index_name, index))
 }
 
 impl Database {
-    pub fn open_or_create(path: impl AsRef<Path>) -> MResult<Database> {
+    pub fn open_or_create(path: impl AsRef<Path>, options: DatabaseOptions) -> MResult<Database> {
         fs::create_dir_all(path.as_ref())?;
 
         let env = heed::EnvOpenOptions::new()
-            .map_size(10 * 1024 * 1024 * 1024) // 10GB
+            .map_size(options.map_size)
             .max_dbs(3000)
             .open(path)?;
 
+        let env = Env::new(env, options.map_size, options.max_map_size);
+        let query_cache = Arc::new(QueryCache::new(options.query_cache_size));
+
         let common_store = env.create_poly_database(Some("common"))?;
         let indexes_store = env.create_database::<Str, Unit>(Some("indexes"))?;
 
@@ -83,6 +274,14 @@ impl Database {
             must_open.push(index_name.to_owned());
         }
 
+        // load all the aliases and the indexes they point to
+        let mut aliases = HashMap::new();
+        for result in common_store.prefix_iter::<Str, Str>(&reader, ALIAS_PREFIX_KEY)? {
+            let (key, index_name) = result?;
+            let alias = &key[ALIAS_PREFIX_KEY.len()..];
+            aliases.insert(alias.to_owned(), index_name.to_owned());
+        }
+
         reader.abort();
 
         // open the previously aggregated indexes
@@ -100,20 +299,20 @@ impl Database {
                 }
             };
             let update_fn = Arc::new(ArcSwapFn::empty());
-
-            let env_clone = env.clone();
-            let index_clone = index.clone();
-            let update_fn_clone = update_fn.clone();
-
-            let handle = thread::spawn(move || {
-                update_awaiter(receiver, env_clone, update_fn_clone, index_clone)
-            });
+            let handle = spawn_update_awaiter(
+                &env,
+                &query_cache,
+                receiver,
+                update_fn.clone(),
+                &index_name,
+                index.clone(),
+            );
 
             // send an update notification to make sure that
             // possible pre-boot updates are consumed
-            sender.send(()).unwrap();
+            sender.send(UpdateEvent::NewUpdate).unwrap();
 
-            let result = indexes.insert(index_name, (index, update_fn, handle));
+            let result = indexes.insert(index_name, (index, update_fn, Some(handle)));
             assert!(
                 result.is_none(),
                 "The index should not have been already open"
@@ -122,51 +321,349 @@ impl Database {
 
         Ok(Database {
             env,
+            query_cache,
             common_store,
             indexes_store,
             indexes: RwLock::new(indexes),
+            aliases: RwLock::new(aliases),
+            indexes_changes: Mutex::new(()),
         })
     }
 
+    /// Opens the index with the given name, the name can also be an alias
+    /// in which case the index the alias points to is returned.
     pub fn open_index(&self, name: impl AsRef<str>) -> Option<Index> {
-        let indexes_lock = self.indexes.read().unwrap();
-        match indexes_lock.get(name.as_ref()) {
-            Some((index, ..)) => Some(index.clone()),
-            None => None,
+        self.resolve_index(name).map(|(_, index)| index)
+    }
+
+    /// Opens the index with the given name like `open_index` does and returns it
+    /// along with its name, which is not the given one if it is an alias.
+    pub fn resolve_index(&self, name: impl AsRef<str>) -> Option<(String, Index)> {
+        let name = name.as_ref();
+        if let Some((index, ..)) = self.indexes.read().unwrap().get(name) {
+            return Some((name.to_owned(), index.clone()));
         }
+
+        // the indexes lock is not held while waiting for the aliases
+        // one, an index rename holds it while the stores are moved
+        let index_name = self.aliases.read().unwrap().get(name)?.clone();
+        let indexes_lock = self.indexes.read().unwrap();
+        let (index, ..) = indexes_lock.get(&index_name)?;
+        Some((index_name, index.clone()))
     }
 
     pub fn create_index(&self, name: impl AsRef<str>) -> MResult<Index> {
         let name = name.as_ref();
+
+        // the write transaction waits for the map to be resized, it must be started
+        // before the locks are taken: a thread holding a transaction can wait for them
+        let (sender, receiver) = crossbeam_channel::bounded(100);
+        let index = store::create(&self.env, name, sender)?;
+        let mut writer = self.env.write_txn()?;
+
         let mut indexes_lock = self.indexes.write().unwrap();
 
+        if self.aliases.read().unwrap().contains_key(name) {
+            return Err(Error::AliasAlreadyExists);
+        }
+
         match indexes_lock.entry(name.to_owned()) {
-            Entry::Occupied(_) => Err(crate::Error::IndexAlreadyExists),
+            Entry::Occupied(_) => Err(Error::IndexAlreadyExists),
             Entry::Vacant(entry) => {
-                let (sender, receiver) = crossbeam_channel::bounded(100);
-                let index = store::create(&self.env, name, sender)?;
-
-                let mut writer = self.env.write_txn()?;
+                // an index could have been renamed to this name
+                if self.indexes_store.get(&writer, name)?.is_some() {
+                    return Err(Error::IndexAlreadyExists);
+                }
                 self.indexes_store.put(&mut writer, name, &())?;
-
-                let env_clone = self.env.clone();
-                let index_clone = index.clone();
+                store::put_layouts_versions(&mut writer, &index)?;
 
                 let no_update_fn = Arc::new(ArcSwapFn::empty());
-                let no_update_fn_clone = no_update_fn.clone();
-
-                let handle = thread::spawn(move || {
-                    update_awaiter(receiver, env_clone, no_update_fn_clone, index_clone)
-                });
+                let handle = spawn_update_awaiter(
+                    &self.env,
+                    &self.query_cache,
+                    receiver,
+                    no_update_fn.clone(),
+                    name,
+                    index.clone(),
+                );
 
                 writer.commit()?;
-                entry.insert((index.clone(), no_update_fn, handle));
+                entry.insert((index.clone(), no_update_fn, Some(handle)));
 
                 Ok(index)
             }
         }
     }
 
+    /// Deletes the index, its update loop is stopped and every one of its stores
+    /// is cleared. Returns `false` if no index with this name exists.
+    pub fn delete_index(&self, name: impl AsRef<str>) -> MResult<bool> {
+        self.delete_index_with(name, |_| Ok(()))
+    }
+
+    /// Deletes the index like `delete_index` does, the keys the caller stored in the
+    /// common store for this index are removed by the given function in the same
+    /// write transaction as the index stores and the aliases pointing to it.
+    ///
+    /// LMDB databases cannot be dropped once created, the stores are emptied
+    /// and their pages are given back to the map.
+    pub fn delete_index_with<F>(&self, name: impl AsRef<str>, clear_keys: F) -> MResult<bool>
+    where
+        F: FnOnce(&mut heed::RwTxn) -> MResult<()>,
+    {
+        let name = name.as_ref();
+        let _changing = self.indexes_changes.lock().unwrap();
+
+        // the lock must be released before waiting for the update loop,
+        // the update callback could try to open an index
+        let removed = self.indexes.write().unwrap().remove(name);
+        let (index, _update_fn, handle) = match removed {
+            Some(removed) => removed,
+            None => return Ok(false),
+        };
+
+        // stop the update loop and wait for it to finish
+        // the currently processed update
+        let _ = index.updates_notifier.send(UpdateEvent::MustStop);
+        let stopped = match handle {
+            Some(handle) => handle
+                .join()
+                .map(drop)
+                .map_err(|_| Error::UpdateLoopPanicked),
+            // only a rename takes the handle and none can run meanwhile
+            None => Ok(()),
+        };
+
+        // the aliases pointing to this index must not be resolved anymore
+        let mut writer = self.env.write_txn()?;
+        let mut aliases_lock = self.aliases.write().unwrap();
+        let removed_aliases: Vec<_> = aliases_lock
+            .iter()
+            .filter(|(_, index_name)| *index_name == name)
+            .map(|(alias, _)| alias.clone())
+            .collect();
+
+        for alias in &removed_aliases {
+            self.common_store
+                .delete::<Str>(&mut writer, &alias_key(alias))?;
+        }
+        self.indexes_store.delete(&mut writer, name)?;
+        store::clear(&mut writer, &index)?;
+        clear_keys(&mut *writer)?;
+        writer.commit()?;
+
+        for alias in removed_aliases {
+            aliases_lock.remove(&alias);
+        }
+
+        self.query_cache.remove_index(name);
+
+        // the index is deleted even if its update loop panicked
+        stopped.map(|_| true)
+    }
+
+    /// Renames the index, the documents, settings and update history are moved
+    /// to the stores of the new name and the aliases pointing to the index are updated.
+    ///
+    /// The index can be searched while it is renamed and is replaced by the renamed one
+    /// once the move is committed, the updates enqueued through the clones of the
+    /// renamed index are forwarded to the index with the new name.
+    pub fn rename_index(&self, from: impl AsRef<str>, to: impl AsRef<str>) -> MResult<()> {
+        self.rename_index_with(from, to, |_| Ok(()))
+    }
+
+    /// Renames the index like `rename_index` does, the keys the caller stored in the
+    /// common store for this index are moved by the given function in the same
+    /// write transaction as the index stores and the aliases pointing to it.
+    pub fn rename_index_with<F>(
+        &self,
+        from: impl AsRef<str>,
+        to: impl AsRef<str>,
+        rename_keys: F,
+    ) -> MResult<()>
+    where
+        F: FnOnce(&mut heed::RwTxn) -> MResult<()>,
+    {
+        let from = from.as_ref();
+        let to = to.as_ref();
+        let _changing = self.indexes_changes.lock().unwrap();
+
+        // the lock must be released before waiting for the update loop,
+        // the update callback could try to open an index
+        let (index, update_fn, handle) = {
+            let mut indexes_lock = self.indexes.write().unwrap();
+
+            if indexes_lock.contains_key(to) {
+                return Err(Error::IndexAlreadyExists);
+            }
+
+            if self.aliases.read().unwrap().contains_key(to) {
+                return Err(Error::AliasAlreadyExists);
+            }
+
+            match indexes_lock.get_mut(from) {
+                Some((index, update_fn, handle)) => {
+                    (index.clone(), update_fn.clone(), handle.take())
+                }
+                None => return Err(Error::IndexNotFound),
+            }
+        };
+
+        // stop the update loop, the pending updates
+        // will be processed by the new one
+        let _ = index.updates_notifier.send(UpdateEvent::MustStop);
+        let receiver = match handle.map(|handle| handle.join()) {
+            Some(Ok(receiver)) => receiver,
+            _ => {
+                // the index becomes unavailable, like when it failed to be opened
+                error!("the update loop of the index {} panicked", from);
+                self.indexes.write().unwrap().remove(from);
+                return Err(Error::UpdateLoopPanicked);
+            }
+        };
+
+        // the index is searched while its stores are copied, an index created
+        // with the new name meanwhile is detected in the move transaction
+        let (sender, new_receiver) = crossbeam_channel::bounded(100);
+        let moved = store::create(&self.env, to, sender).and_then(|new_index| {
+            let mut writer = self.env.write_txn()?;
+            self.move_index(&mut writer, &index, &new_index, from, to)?;
+            rename_keys(&mut *writer)?;
+            Ok((writer, new_index))
+        });
+
+        // the index and its aliases are replaced while the move is committed,
+        // the requests find the index either under its old name or its new one
+        let mut indexes_lock = self.indexes.write().unwrap();
+        let mut aliases_lock = self.aliases.write().unwrap();
+        let committed = moved.and_then(|(writer, new_index)| {
+            writer.commit()?;
+            Ok(new_index)
+        });
+
+        match committed {
+            Ok(new_index) => {
+                let updates = index.updates;
+                let forward_env = self.env.clone();
+                let forward_index = new_index.clone();
+                thread::spawn(move || {
+                    forward_updates(receiver, forward_env, updates, forward_index)
+                });
+
+                let handle = spawn_update_awaiter(
+                    &self.env,
+                    &self.query_cache,
+                    new_receiver,
+                    update_fn.clone(),
+                    to,
+                    new_index.clone(),
+                );
+                let _ = new_index.updates_notifier.send(UpdateEvent::NewUpdate);
+                self.query_cache.remove_index(from);
+
+                indexes_lock.remove(from);
+                indexes_lock.insert(to.to_owned(), (new_index, update_fn, Some(handle)));
+                for index_name in aliases_lock.values_mut() {
+                    if index_name == from {
+                        *index_name = to.to_owned();
+                    }
+                }
+
+                Ok(())
+            }
+            Err(e) => {
+                // restart the update loop of the index that has not been renamed
+                let handle = spawn_update_awaiter(
+                    &self.env,
+                    &self.query_cache,
+                    receiver,
+                    update_fn,
+                    from,
+                    index.clone(),
+                );
+                let _ = index.updates_notifier.send(UpdateEvent::NewUpdate);
+                if let Some((_, _, current_handle)) = indexes_lock.get_mut(from) {
+                    *current_handle = Some(handle);
+                }
+
+                Err(e)
+            }
+        }
+    }
+
+    /// Moves the stores of the index and the aliases pointing to it, the aliases
+    /// are changed in memory by the caller once the transaction is committed.
+    fn move_index(
+        &self,
+        writer: &mut heed::RwTxn,
+        index: &Index,
+        new_index: &Index,
+        from: &str,
+        to: &str,
+    ) -> MResult<()> {
+        // the aliases are only changed in write transactions,
+        // they cannot be changed while they are moved
+        let aliases_lock = self.aliases.read().unwrap();
+        if aliases_lock.contains_key(to) {
+            return Err(Error::AliasAlreadyExists);
+        }
+
+        if self.indexes_store.get(writer, to)?.is_some() {
+            return Err(Error::IndexAlreadyExists);
+        }
+
+        store::copy_all(writer, index, new_index)?;
+        store::clear(writer, index)?;
+
+        self.indexes_store.delete(writer, from)?;
+        self.indexes_store.put(writer, to, &())?;
+
+        for (alias, index_name) in aliases_lock.iter() {
+            if index_name == from {
+                self.common_store
+                    .put::<Str, Str>(writer, &alias_key(alias), to)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Creates a new index with a copy of the documents and settings of another one,
+    /// the new index starts with an empty update queue and history.
+    pub fn clone_index(&self, from: impl AsRef<str>, to: impl AsRef<str>) -> MResult<Index> {
+        let from = from.as_ref();
+        let to = to.as_ref();
+
+        let source = self.open_index(from).ok_or(Error::IndexNotFound)?;
+        let index = self.create_index(to)?;
+
+        let result: MResult<bool> =
+            self.env
+                .write_txn()
+                .map_err(Into::into)
+                .and_then(|mut writer| {
+                    store::copy_data(&mut writer, &source, &index)?;
+                    // the data copied with an older layout is rebuilt in the new index too
+                    let pushed = update::push_migrations(&mut writer, &index)?;
+                    writer.commit()?;
+                    Ok(pushed)
+                });
+
+        match result {
+            Ok(true) => {
+                let _ = index.updates_notifier.send(UpdateEvent::NewUpdate);
+            }
+            Ok(false) => (),
+            Err(e) => {
+                // do not keep a partially copied index
+                let _ = self.delete_index(to);
+                return Err(e);
+            }
+        }
+
+        Ok(index)
+    }
+
     pub fn set_update_callback(&self, name: impl AsRef<str>, update_fn: BoxUpdateFn) -> bool {
         let indexes_lock = self.indexes.read().unwrap();
         match indexes_lock.get(name.as_ref()) {
@@ -190,6 +687,11 @@ impl Database {
         }
     }
 
+    /// The cache of the searches results, shared by all the indexes.
+    pub fn query_cache(&self) -> &QueryCache {
+        &self.query_cache
+    }
+
     pub fn copy_and_compact_to_path<P: AsRef<Path>>(&self, path: P) -> ZResult<File> {
         self.env.copy_to_path(path, CompactionOption::Enabled)
     }
@@ -199,7 +701,800 @@ impl Database {
         Ok(indexes.keys().cloned().collect())
     }
 
+    /// Returns all the aliases along with the name of the index they point to.
+    pub fn aliases(&self) -> Vec<(String, String)> {
+        let aliases_lock = self.aliases.read().unwrap();
+        aliases_lock
+            .iter()
+            .map(|(alias, index_name)| (alias.clone(), index_name.clone()))
+            .collect()
+    }
+
+    /// Returns the name of the index the alias points to.
+    pub fn alias(&self, alias: impl AsRef<str>) -> Option<String> {
+        let aliases_lock = self.aliases.read().unwrap();
+        aliases_lock.get(alias.as_ref()).cloned()
+    }
+
+    /// Creates an alias or makes an existing one point to another index.
+    pub fn set_alias(&self, alias: impl AsRef<str>, index_name: impl AsRef<str>) -> MResult<()> {
+        let alias = alias.as_ref();
+        let index_name = index_name.as_ref();
+
+        let mut writer = self.env.write_txn()?;
+        let indexes_lock = self.indexes.read().unwrap();
+        let mut aliases_lock = self.aliases.write().unwrap();
+
+        if indexes_lock.contains_key(alias) {
+            return Err(Error::IndexAlreadyExists);
+        }
+
+        if !indexes_lock.contains_key(index_name) {
+            return Err(Error::IndexNotFound);
+        }
+
+        // an index could be being renamed to this name
+        if self.indexes_store.get(&writer, alias)?.is_some() {
+            return Err(Error::IndexAlreadyExists);
+        }
+        self.common_store
+            .put::<Str, Str>(&mut writer, &alias_key(alias), index_name)?;
+        writer.commit()?;
+
+        aliases_lock.insert(alias.to_owned(), index_name.to_owned());
+
+        Ok(())
+    }
+
+    /// Deletes the alias, returns `false` if it does not exist.
+    pub fn delete_alias(&self, alias: impl AsRef<str>) -> MResult<bool> {
+        let alias = alias.as_ref();
+        let mut writer = self.env.write_txn()?;
+        let mut aliases_lock = self.aliases.write().unwrap();
+
+        if !aliases_lock.contains_key(alias) {
+            return Ok(false);
+        }
+        self.common_store
+            .delete::<Str>(&mut writer, &alias_key(alias))?;
+        writer.commit()?;
+
+        aliases_lock.remove(alias);
+
+        Ok(true)
+    }
+
+    /// Atomically exchanges the indexes two aliases point to, the swap is done
+    /// in a single write transaction so that no reader can see a partial swap.
+    pub fn swap_aliases(&self, first: impl AsRef<str>, second: impl AsRef<str>) -> MResult<()> {
+        let first = first.as_ref();
+        let second = second.as_ref();
+
+        let mut writer = self.env.write_txn()?;
+        let mut aliases_lock = self.aliases.write().unwrap();
+
+        let first_index = aliases_lock.get(first).cloned();
+        let second_index = aliases_lock.get(second).cloned();
+
+        let (first_index, second_index) = match (first_index, second_index) {
+            (Some(first_index), Some(second_index)) => (first_index, second_index),
+            _ => return Err(Error::AliasNotFound),
+        };
+        self.common_store
+            .put::<Str, Str>(&mut writer, &alias_key(first), &second_index)?;
+        self.common_store
+            .put::<Str, Str>(&mut writer, &alias_key(second), &first_index)?;
+        writer.commit()?;
+
+        aliases_lock.insert(first.to_owned(), second_index);
+        aliases_lock.insert(second.to_owned(), first_index);
+
+        Ok(())
+    }
+
+    /// Returns the current size of the LMDB memory map, in bytes.
+    pub fn map_size(&self) -> usize {
+        self.env.map_size()
+    }
+
     pub fn common_store(&self) -> heed::PolyDatabase {
         self.common_store
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::TempDir;
+
+    #[test]
+    fn delete_index() {
+        let dir = TempDir::new().unwrap();
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let env = &database.env;
+
+        let index = database.create_index("test").unwrap();
+
+        let mut writer = env.write_txn().unwrap();
+        let mut addition = index.stop_words_addition();
+        addition.add_stop_word("the");
+        addition.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+
+        let mut writer = env.write_txn().unwrap();
+        let common_store = database.common_store();
+        common_store
+            .put::<Str, Str>(&mut writer, "last-update-test", "now")
+            .unwrap();
+        writer.commit().unwrap();
+
+        let deleted = database.delete_index_with("test", |writer| {
+            common_store.delete::<Str>(writer, "last-update-test")?;
+            Ok(())
+        });
+        assert!(deleted.unwrap());
+        assert!(!database.delete_index("test").unwrap());
+
+        let reader = env.read_txn().unwrap();
+        let key = common_store.get::<Str, Str>(&reader, "last-update-test");
+        assert!(key.unwrap().is_none());
+        reader.abort();
+        assert!(database.open_index("test").is_none());
+        assert!(database.indexes_names().unwrap().is_empty());
+
+        // an index with the same name must start empty
+        let index = database.create_index("test").unwrap();
+        let reader = env.read_txn().unwrap();
+        assert!(index.main.stop_words_fst(&reader).unwrap().is_none());
+        assert!(index.current_update_id(&reader).unwrap().is_none());
+    }
+
+    #[test]
+    fn map_full_update_is_retried() {
+        use meilidb_schema::{SchemaBuilder, DISPLAYED, INDEXED};
+        use serde_json::json;
+
+        const MAP_SIZE: usize = 1024 * 1024;
+
+        let dir = TempDir::new().unwrap();
+        let options = DatabaseOptions {
+            map_size: MAP_SIZE,
+            ..DatabaseOptions::default()
+        };
+        let database = Database::open_or_create(dir.path(), options).unwrap();
+        let env = &database.env;
+
+        let index = database.create_index("test").unwrap();
+
+        let mut builder = SchemaBuilder::with_identifier("id");
+        builder.new_attribute("id", DISPLAYED);
+        builder.new_attribute("title", DISPLAYED | INDEXED);
+        builder.new_attribute("blob", DISPLAYED);
+
+        // the enqueued update fits in the map but the
+        // documents it adds do not fit along with it
+        let blob = "x".repeat(4096);
+        let mut addition = index.documents_addition();
+        for id in 0..160 {
+            addition.update_document(json!({ "id": id, "title": "hello", "blob": blob }));
+        }
+
+        let mut writer = env.write_txn().unwrap();
+        index.schema_update(&mut writer, builder.build()).unwrap();
+        let update_id = addition.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+
+        let status = loop {
+            let reader = env.read_txn().unwrap();
+            match index.update_status(&reader, update_id).unwrap() {
+                update::UpdateStatus::Processed(status) => break status,
+                _ => thread::sleep(std::time::Duration::from_millis(10)),
+            }
+        };
+        assert!(status.result.is_ok());
+        assert!(database.map_size() > MAP_SIZE);
+
+        let reader = env.read_txn().unwrap();
+        assert_eq!(index.main.number_of_documents(&reader).unwrap(), 160);
+    }
+
+    #[test]
+    fn map_full_update_fails_at_max_size() {
+        use meilidb_schema::{SchemaBuilder, DISPLAYED, INDEXED};
+        use serde_json::json;
+
+        const MAP_SIZE: usize = 1024 * 1024;
+
+        let dir = TempDir::new().unwrap();
+        let options = DatabaseOptions {
+            map_size: MAP_SIZE,
+            max_map_size: MAP_SIZE,
+            ..DatabaseOptions::default()
+        };
+        let database = Database::open_or_create(dir.path(), options).unwrap();
+        let env = &database.env;
+
+        let index = database.create_index("test").unwrap();
+
+        let mut builder = SchemaBuilder::with_identifier("id");
+        builder.new_attribute("id", DISPLAYED);
+        builder.new_attribute("title", DISPLAYED | INDEXED);
+        builder.new_attribute("blob", DISPLAYED);
+
+        let blob = "x".repeat(4096);
+        let mut addition = index.documents_addition();
+        for id in 0..160 {
+            addition.update_document(json!({ "id": id, "title": "hello", "blob": blob }));
+        }
+
+        let mut writer = env.write_txn().unwrap();
+        index.schema_update(&mut writer, builder.build()).unwrap();
+        let update_id = addition.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+
+        let status = loop {
+            let reader = env.read_txn().unwrap();
+            match index.update_status(&reader, update_id).unwrap() {
+                update::UpdateStatus::Processed(status) => break status,
+                _ => thread::sleep(std::time::Duration::from_millis(10)),
+            }
+        };
+
+        // the map cannot be grown, the update fails instead of being retried
+        let error = status.result.unwrap_err();
+        assert!(error.contains("cannot be bigger"), "{}", error);
+        assert_eq!(database.map_size(), MAP_SIZE);
+
+        let reader = env.read_txn().unwrap();
+        assert_eq!(index.main.number_of_documents(&reader).unwrap(), 0);
+    }
+
+    #[test]
+    fn documents_addition_limits() {
+        use crate::IndexLimits;
+        use meilidb_schema::{SchemaBuilder, DISPLAYED, INDEXED};
+        use serde_json::json;
+
+        let dir = TempDir::new().unwrap();
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let env = &database.env;
+
+        let index = database.create_index("test").unwrap();
+
+        let mut builder = SchemaBuilder::with_identifier("id");
+        builder.new_attribute("id", DISPLAYED);
+        builder.new_attribute("title", DISPLAYED | INDEXED);
+
+        let process = |documents: Vec<serde_json::Value>| {
+            let mut addition = index.documents_addition();
+            for document in documents {
+                addition.update_document(document);
+            }
+
+            let mut writer = env.write_txn().unwrap();
+            let update_id = addition.finalize(&mut writer).unwrap();
+            writer.commit().unwrap();
+
+            loop {
+                let reader = env.read_txn().unwrap();
+                match index.update_status(&reader, update_id).unwrap() {
+                    update::UpdateStatus::Processed(status) => break status.result,
+                    _ => thread::sleep(std::time::Duration::from_millis(10)),
+                }
+            }
+        };
+
+        let mut writer = env.write_txn().unwrap();
+        index.schema_update(&mut writer, builder.build()).unwrap();
+        let limits = IndexLimits {
+            max_documents: Some(2),
+            ..IndexLimits::default()
+        };
+        index.set_limits(&mut writer, &limits).unwrap();
+        writer.commit().unwrap();
+
+        let documents = vec![
+            json!({ "id": 1, "title": "hello" }),
+            json!({ "id": 2, "title": "world" }),
+        ];
+        assert!(process(documents).is_ok());
+
+        // replacing a document does not add one
+        assert!(process(vec![json!({ "id": 2, "title": "earth" })]).is_ok());
+        assert!(process(vec![json!({ "id": 3, "title": "moon" })]).is_err());
+
+        let reader = env.read_txn().unwrap();
+        assert_eq!(index.main.number_of_documents(&reader).unwrap(), 2);
+        let size = index.stored_size(&reader).unwrap();
+        reader.abort();
+
+        // the counted size must be the size of the stores entries
+        let mut writer = env.write_txn().unwrap();
+        index.main.size.reset(&mut writer).unwrap();
+        index.postings_lists.size.reset(&mut writer).unwrap();
+        index.postings_docids.size.reset(&mut writer).unwrap();
+        index.documents_fields.size.reset(&mut writer).unwrap();
+        index.filters_docids.size.reset(&mut writer).unwrap();
+        index.docs_words.size.reset(&mut writer).unwrap();
+        index
+            .words_pairs_proximities
+            .size
+            .reset(&mut writer)
+            .unwrap();
+        assert_eq!(index.stored_size(&writer).unwrap(), size);
+
+        let limits = IndexLimits {
+            max_index_size: Some(size),
+            ..IndexLimits::default()
+        };
+        index.set_limits(&mut writer, &limits).unwrap();
+        writer.commit().unwrap();
+
+        assert!(process(vec![json!({ "id": 3, "title": "moon" })]).is_err());
+
+        let reader = env.read_txn().unwrap();
+        assert_eq!(index.main.number_of_documents(&reader).unwrap(), 2);
+    }
+
+    #[test]
+    fn aliases_swap() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path();
+
+        {
+            let database = Database::open_or_create(path, DatabaseOptions::default()).unwrap();
+            database.create_index("movies-v1").unwrap();
+            database.create_index("movies-v2").unwrap();
+
+            database.set_alias("movies", "movies-v1").unwrap();
+            database.set_alias("movies-next", "movies-v2").unwrap();
+
+            assert!(database.set_alias("movies-v1", "movies-v2").is_err());
+            assert!(database.set_alias("series", "unknown").is_err());
+            assert!(database.create_index("movies").is_err());
+
+            database.swap_aliases("movies", "movies-next").unwrap();
+            assert_eq!(database.alias("movies"), Some("movies-v2".to_owned()));
+            assert_eq!(database.alias("movies-next"), Some("movies-v1".to_owned()));
+            assert!(database.swap_aliases("movies", "unknown").is_err());
+        }
+
+        // aliases must be persisted
+        let database = Database::open_or_create(path, DatabaseOptions::default()).unwrap();
+        assert_eq!(database.alias("movies"), Some("movies-v2".to_owned()));
+        assert!(database.open_index("movies").is_some());
+        let (index_name, _) = database.resolve_index("movies").unwrap();
+        assert_eq!(index_name, "movies-v2");
+
+        // deleting an index deletes the aliases pointing to it
+        assert!(database.delete_index("movies-v2").unwrap());
+        assert!(database.alias("movies").is_none());
+        assert!(database.open_index("movies").is_none());
+
+        assert!(database.delete_alias("movies-next").unwrap());
+        assert!(!database.delete_alias("movies-next").unwrap());
+    }
+
+    #[test]
+    fn rename_and_clone_index() {
+        let dir = TempDir::new().unwrap();
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let env = &database.env;
+
+        let index = database.create_index("movies").unwrap();
+        database.set_alias("films", "movies").unwrap();
+
+        let mut writer = env.write_txn().unwrap();
+        let mut addition = index.stop_words_addition();
+        addition.add_stop_word("the");
+        let update_id = addition.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+
+        // wait for the update to be processed
+        loop {
+            let reader = env.read_txn().unwrap();
+            match index.update_status(&reader, update_id).unwrap() {
+                update::UpdateStatus::Processed(_) => break,
+                _ => thread::sleep(std::time::Duration::from_millis(10)),
+            }
+        }
+
+        database.rename_index("movies", "films-v2").unwrap();
+        assert!(database.open_index("movies").is_none());
+        assert_eq!(database.alias("films"), Some("films-v2".to_owned()));
+
+        let renamed = database.open_index("films-v2").unwrap();
+        let reader = env.read_txn().unwrap();
+        assert!(renamed.main.stop_words_fst(&reader).unwrap().is_some());
+        assert_eq!(renamed.current_update_id(&reader).unwrap(), None);
+        assert_eq!(renamed.all_updates_status(&reader).unwrap().len(), 1);
+        reader.abort();
+
+        assert!(database.rename_index("unknown", "other").is_err());
+        assert!(database.rename_index("films-v2", "films").is_err());
+
+        // the updates sent through the renamed index are not lost
+        let mut writer = env.write_txn().unwrap();
+        let mut addition = index.stop_words_addition();
+        addition.add_stop_word("a");
+        addition.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+        drop(index);
+
+        let mut forwarded = false;
+        for _ in 0..500 {
+            let reader = env.read_txn().unwrap();
+            let stop_words = renamed.main.stop_words_fst(&reader).unwrap().unwrap();
+            if stop_words.contains("a") {
+                forwarded = true;
+                break;
+            }
+            reader.abort();
+            thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert!(forwarded);
+
+        // the index keeps its name and its aliases when the rename is not committed
+        let result =
+            database.rename_index_with("films-v2", "films-v4", |_| Err(Error::MissingDocumentId));
+        assert!(result.is_err());
+        assert!(database.open_index("films-v4").is_none());
+        assert!(database.open_index("films-v2").is_some());
+        assert_eq!(database.alias("films"), Some("films-v2".to_owned()));
+
+        let cloned = database.clone_index("films-v2", "films-v3").unwrap();
+        let reader = env.read_txn().unwrap();
+        assert!(cloned.main.stop_words_fst(&reader).unwrap().is_some());
+        assert!(cloned.all_updates_status(&reader).unwrap().is_empty());
+    }
+
+    #[test]
+    fn settings_update() {
+        use crate::Settings;
+        use meilidb_schema::{SchemaBuilder, DISPLAYED, INDEXED};
+        use std::collections::BTreeSet;
+
+        let dir = TempDir::new().unwrap();
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let env = &database.env;
+
+        let index = database.create_index("movies").unwrap();
+
+        let mut builder = SchemaBuilder::with_identifier("id");
+        builder.new_attribute("id", DISPLAYED);
+        builder.new_attribute("title", DISPLAYED | INDEXED);
+
+        let stop_words = |words: &[&str]| words.iter().map(|w| w.to_string()).collect();
+
+        let mut writer = env.write_txn().unwrap();
+        index.schema_update(&mut writer, builder.build()).unwrap();
+
+        let settings = Settings {
+            stop_words: Some(stop_words(&["The", "a"])),
+            distinct_field: Some(String::from("title")),
+            ..Settings::default()
+        };
+        index.settings_update(&mut writer, settings).unwrap();
+
+        // an invalid setting fails the whole update, the stop words are not changed
+        let settings = Settings {
+            stop_words: Some(stop_words(&["of"])),
+            distinct_field: Some(String::from("brand")),
+            ..Settings::default()
+        };
+        let update_id = index.settings_update(&mut writer, settings).unwrap();
+        writer.commit().unwrap();
+
+        // wait for the updates to be processed
+        let status = loop {
+            let reader = env.read_txn().unwrap();
+            match index.update_status(&reader, update_id).unwrap() {
+                update::UpdateStatus::Processed(status) => break status,
+                _ => thread::sleep(std::time::Duration::from_millis(10)),
+            }
+        };
+        assert!(status.result.is_err());
+
+        let reader = env.read_txn().unwrap();
+        let settings = index.settings_with_stop_words(&reader).unwrap();
+        let expected: BTreeSet<_> = stop_words(&["a", "the"]);
+        assert_eq!(settings.stop_words, Some(expected));
+        assert_eq!(settings.distinct_field, Some(String::from("title")));
+        assert_eq!(index.current_update_id(&reader).unwrap(), None);
+    }
+
+    #[test]
+    fn legacy_settings_migration() {
+        use serde::Serialize;
+        use std::collections::{HashMap, HashSet};
+
+        /// The settings as the HTTP server stored them in the customs.
+        #[derive(Serialize)]
+        struct LegacySettings {
+            stop_words: Option<HashSet<String>>,
+            ranking_order: Option<Vec<String>>,
+            distinct_field: Option<String>,
+            ranking_rules: Option<HashMap<String, u32>>,
+        }
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path();
+
+        {
+            let database = Database::open_or_create(path, DatabaseOptions::default()).unwrap();
+            let index = database.create_index("movies").unwrap();
+
+            let legacy = LegacySettings {
+                stop_words: Some(vec![String::from("The")].into_iter().collect()),
+                ranking_order: None,
+                distinct_field: Some(String::from("title")),
+                ranking_rules: None,
+            };
+            let customs = bincode::serialize(&legacy).unwrap();
+
+            let mut writer = database.env.write_txn().unwrap();
+            index.main.put_customs(&mut writer, &customs).unwrap();
+            writer.commit().unwrap();
+        }
+
+        let database = Database::open_or_create(path, DatabaseOptions::default()).unwrap();
+        let env = &database.env;
+        let index = database.open_index("movies").unwrap();
+
+        // the stop words are added by the first update of the index
+        loop {
+            let reader = env.read_txn().unwrap();
+            match index.update_status(&reader, 0).unwrap() {
+                update::UpdateStatus::Processed(status) => {
+                    assert!(status.result.is_ok());
+                    break;
+                }
+                _ => thread::sleep(std::time::Duration::from_millis(10)),
+            }
+        }
+
+        let reader = env.read_txn().unwrap();
+        assert_eq!(index.main.settings_version(&reader).unwrap(), Some(1));
+
+        let settings = index.settings_with_stop_words(&reader).unwrap();
+        let expected = vec![String::from("the")].into_iter().collect();
+        assert_eq!(settings.stop_words, Some(expected));
+        assert_eq!(settings.distinct_field, Some(String::from("title")));
+    }
+
+    #[test]
+    fn words_pairs_proximities_updates() {
+        use crate::DocumentId;
+        use meilidb_schema::{SchemaBuilder, DISPLAYED, INDEXED};
+        use serde_json::json;
+
+        let dir = TempDir::new().unwrap();
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let env = &database.env;
+        let index = database.create_index("movies").unwrap();
+
+        // a new index is written with the current layouts
+        let reader = env.read_txn().unwrap();
+        assert!(index.main.postings_docids_ready(&reader).unwrap());
+        assert!(index.main.words_pairs_proximities_ready(&reader).unwrap());
+        reader.abort();
+
+        let wait_update = |update_id| loop {
+            let reader = env.read_txn().unwrap();
+            match index.update_status(&reader, update_id).unwrap() {
+                update::UpdateStatus::Processed(status) => {
+                    assert!(status.result.is_ok());
+                    break;
+                }
+                _ => thread::sleep(std::time::Duration::from_millis(10)),
+            }
+        };
+
+        let mut builder = SchemaBuilder::with_identifier("id");
+        builder.new_attribute("id", DISPLAYED);
+        builder.new_attribute("title", DISPLAYED | INDEXED);
+
+        let mut addition = index.documents_addition();
+        addition.update_document(json!({ "id": 0, "title": "soup of the day" }));
+        addition.update_document(json!({ "id": 1, "title": "the day of the soup" }));
+
+        let mut writer = env.write_txn().unwrap();
+        index.schema_update(&mut writer, builder.build()).unwrap();
+        let update_id = addition.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+        wait_update(update_id);
+
+        let mut writer = env.write_txn().unwrap();
+        let mut addition = index.stop_words_addition();
+        addition.add_stop_word("of");
+        let update_id = addition.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+        wait_update(update_id);
+
+        let pairs = &index.words_pairs_proximities;
+        let reader = env.read_txn().unwrap();
+        assert!(pairs
+            .pair_proximities(&reader, b"soup", b"of")
+            .unwrap()
+            .is_none());
+        assert!(pairs
+            .pair_proximities(&reader, b"of", b"the")
+            .unwrap()
+            .is_none());
+        let proximities = pairs.pair_proximities(&reader, b"the", b"day").unwrap();
+        assert_eq!(proximities.unwrap().len(), 2);
+        reader.abort();
+
+        let mut writer = env.write_txn().unwrap();
+        let mut deletion = index.documents_deletion();
+        deletion.delete_document_by_id(DocumentId(0));
+        let update_id = deletion.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+        wait_update(update_id);
+
+        let reader = env.read_txn().unwrap();
+        let proximities = pairs.pair_proximities(&reader, b"the", b"day").unwrap();
+        let documents_ids: Vec<_> = proximities.unwrap().iter().map(|p| p.document_id).collect();
+        assert_eq!(documents_ids, vec![DocumentId(1)]);
+        assert!(pairs
+            .pair_proximities(&reader, b"soup", b"the")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn filters_docids_updates() {
+        use crate::DocumentId;
+        use meilidb_schema::{SchemaBuilder, DISPLAYED, INDEXED};
+        use serde_json::json;
+
+        let dir = TempDir::new().unwrap();
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let env = &database.env;
+        let index = database.create_index("movies").unwrap();
+
+        let wait_update = |update_id| loop {
+            let reader = env.read_txn().unwrap();
+            match index.update_status(&reader, update_id).unwrap() {
+                update::UpdateStatus::Processed(status) => {
+                    assert!(status.result.is_ok());
+                    break;
+                }
+                _ => thread::sleep(std::time::Duration::from_millis(10)),
+            }
+        };
+
+        // the attributes that are not indexed can be filtered too
+        let mut builder = SchemaBuilder::with_identifier("id");
+        builder.new_attribute("id", DISPLAYED);
+        builder.new_attribute("title", DISPLAYED | INDEXED);
+        builder.new_attribute("color", DISPLAYED);
+        let schema = builder.build();
+        let color = schema.attribute("color").unwrap();
+
+        let mut addition = index.documents_addition();
+        addition.update_document(json!({ "id": 0, "title": "iphone", "color": "Black" }));
+        addition.update_document(json!({ "id": 1, "title": "iphone", "color": "white" }));
+        addition.update_document(json!({ "id": 2, "title": "iphone", "color": "black" }));
+
+        let mut writer = env.write_txn().unwrap();
+        index.schema_update(&mut writer, schema).unwrap();
+        let update_id = addition.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+        wait_update(update_id);
+
+        let filtered = |value: &str| {
+            let reader = env.read_txn().unwrap();
+            let filters = vec![(color, value.to_owned())];
+            let documents = index.filtered_documents(&reader, &filters).unwrap();
+            documents.iter().collect::<Vec<_>>()
+        };
+        assert_eq!(filtered("black"), vec![0, 2]);
+        assert_eq!(filtered("white"), vec![1]);
+
+        let mut addition = index.documents_addition();
+        addition.update_document(json!({ "id": 2, "title": "iphone", "color": "White" }));
+        let mut writer = env.write_txn().unwrap();
+        let update_id = addition.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+        wait_update(update_id);
+
+        let mut deletion = index.documents_deletion();
+        deletion.delete_document_by_id(DocumentId(0));
+        let mut writer = env.write_txn().unwrap();
+        let update_id = deletion.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+        wait_update(update_id);
+
+        assert!(filtered("black").is_empty());
+        assert_eq!(filtered("white"), vec![1, 2]);
+    }
+
+    #[test]
+    fn layouts_migrations() {
+        use crate::{DocumentId, PairProximity};
+        use meilidb_schema::{SchemaBuilder, DISPLAYED, INDEXED};
+        use serde_json::json;
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path();
+
+        let update_id = {
+            let database = Database::open_or_create(path, DatabaseOptions::default()).unwrap();
+            let env = &database.env;
+            let index = database.create_index("movies").unwrap();
+
+            let mut builder = SchemaBuilder::with_identifier("id");
+            builder.new_attribute("id", DISPLAYED);
+            builder.new_attribute("title", DISPLAYED | INDEXED);
+
+            let mut addition = index.documents_addition();
+            addition.update_document(json!({ "id": 0, "title": "soup of the day" }));
+
+            let mut writer = env.write_txn().unwrap();
+            index.schema_update(&mut writer, builder.build()).unwrap();
+            let update_id = addition.finalize(&mut writer).unwrap();
+            writer.commit().unwrap();
+
+            loop {
+                let reader = env.read_txn().unwrap();
+                match index.update_status(&reader, update_id).unwrap() {
+                    update::UpdateStatus::Processed(_) => break,
+                    _ => thread::sleep(std::time::Duration::from_millis(10)),
+                }
+            }
+
+            // the postings docids did not exist and the pairs were
+            // stored in both orders of their words, without versions
+            let mut writer = env.write_txn().unwrap();
+            for key in &["postings-docids-version", "words-pairs-proximities-version"] {
+                index.main.main.delete::<Str>(&mut writer, key).unwrap();
+            }
+            index.postings_docids.clear(&mut writer).unwrap();
+            let docid = DocumentId(0);
+            let proximities = sdset::SetBuf::new_unchecked(vec![PairProximity::new(docid, 1, 2)]);
+            index
+                .words_pairs_proximities
+                .put_pair_proximities(&mut writer, b"of", b"soup", &proximities)
+                .unwrap();
+            writer.commit().unwrap();
+
+            update_id
+        };
+
+        let database = Database::open_or_create(path, DatabaseOptions::default()).unwrap();
+        let env = &database.env;
+        let index = database.open_index("movies").unwrap();
+
+        // the data is rebuilt by the updates enqueued when the index is opened
+        loop {
+            let reader = env.read_txn().unwrap();
+            match index.update_status(&reader, update_id + 2).unwrap() {
+                update::UpdateStatus::Processed(status) => {
+                    assert!(status.result.is_ok());
+                    break;
+                }
+                _ => thread::sleep(std::time::Duration::from_millis(10)),
+            }
+        }
+
+        let reader = env.read_txn().unwrap();
+        assert!(index.main.postings_docids_ready(&reader).unwrap());
+        assert!(index.main.words_pairs_proximities_ready(&reader).unwrap());
+
+        let docids = index.postings_docids.postings_docids(&reader, b"soup");
+        let expected: roaring::RoaringTreemap = vec![0].into_iter().collect();
+        assert_eq!(docids.unwrap(), Some(expected));
+
+        let pairs = &index.words_pairs_proximities;
+        assert!(pairs
+            .pair_proximities(&reader, b"of", b"soup")
+            .unwrap()
+            .is_none());
+        assert!(pairs
+            .pair_proximities(&reader, b"soup", b"of")
+            .unwrap()
+            .is_some());
+    }
+}
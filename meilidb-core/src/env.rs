@@ -0,0 +1,340 @@
+use std::cell::Cell;
+use std::cmp;
+use std::fs::File;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+
+use heed::{CompactionOption, Database, PolyDatabase, Result as ZResult};
+use log::info;
+
+thread_local! {
+    /// The number of transactions opened by the current thread.
+    static THREAD_TXNS: Cell<usize> = Cell::new(0);
+}
+
+#[derive(Default)]
+struct GateState {
+    /// The number of transactions running in the whole process.
+    running: usize,
+    resizing: bool,
+}
+
+/// LMDB only allows the map to be resized when no transaction is running
+/// in the process, every transaction passes through this gate which
+/// holds the new ones back while the map is being resized.
+#[derive(Default)]
+struct TxnGate {
+    state: Mutex<GateState>,
+    resized: Condvar,
+    idle: Condvar,
+}
+
+struct GateEntry {
+    gate: Arc<TxnGate>,
+    // the count of the thread must be decreased by the thread that increased it
+    _not_send: PhantomData<*const ()>,
+}
+
+impl GateEntry {
+    fn enter(gate: &Arc<TxnGate>) -> GateEntry {
+        let nested = THREAD_TXNS.with(|count| count.get() > 0);
+
+        let mut state = gate.state.lock().unwrap();
+        // a thread that already holds a transaction must not wait for the resize,
+        // the resize itself waits for this transaction to end
+        while state.resizing && !nested {
+            state = gate.resized.wait(state).unwrap();
+        }
+        state.running += 1;
+
+        THREAD_TXNS.with(|count| count.set(count.get() + 1));
+
+        GateEntry {
+            gate: gate.clone(),
+            _not_send: PhantomData,
+        }
+    }
+}
+
+impl Drop for GateEntry {
+    fn drop(&mut self) {
+        THREAD_TXNS.with(|count| count.set(count.get() - 1));
+
+        let mut state = self.gate.state.lock().unwrap();
+        state.running -= 1;
+        if state.running == 0 {
+            self.gate.idle.notify_all();
+        }
+    }
+}
+
+/// A read transaction, the map cannot be resized while it is alive.
+pub struct ReadTxn {
+    // the transaction must end before the gate is left
+    txn: heed::RoTxn,
+    _entry: GateEntry,
+}
+
+impl ReadTxn {
+    pub fn abort(self) {
+        let ReadTxn { txn, _entry } = self;
+        txn.abort()
+    }
+}
+
+impl Deref for ReadTxn {
+    type Target = heed::RoTxn;
+
+    fn deref(&self) -> &heed::RoTxn {
+        &self.txn
+    }
+}
+
+/// A write transaction, the map cannot be resized while it is alive.
+pub struct WriteTxn {
+    // the transaction must end before the gate is left
+    txn: heed::RwTxn,
+    _entry: GateEntry,
+}
+
+impl WriteTxn {
+    pub fn commit(self) -> ZResult<()> {
+        let WriteTxn { txn, _entry } = self;
+        txn.commit()
+    }
+
+    pub fn abort(self) {
+        let WriteTxn { txn, _entry } = self;
+        txn.abort()
+    }
+}
+
+impl Deref for WriteTxn {
+    type Target = heed::RwTxn;
+
+    fn deref(&self) -> &heed::RwTxn {
+        &self.txn
+    }
+}
+
+impl DerefMut for WriteTxn {
+    fn deref_mut(&mut self) -> &mut heed::RwTxn {
+        &mut self.txn
+    }
+}
+
+/// The LMDB environment shared by all the indexes, its map
+/// can be grown while the database is in use.
+#[derive(Clone)]
+pub struct Env {
+    env: heed::Env,
+    map_size: Arc<AtomicUsize>,
+    max_map_size: usize,
+    gate: Arc<TxnGate>,
+}
+
+impl Env {
+    pub(crate) fn new(env: heed::Env, map_size: usize, max_map_size: usize) -> Env {
+        Env {
+            env,
+            map_size: Arc::new(AtomicUsize::new(map_size)),
+            max_map_size,
+            gate: Arc::new(TxnGate::default()),
+        }
+    }
+
+    pub fn read_txn(&self) -> ZResult<ReadTxn> {
+        let entry = GateEntry::enter(&self.gate);
+        let txn = self.env.read_txn()?;
+        Ok(ReadTxn { txn, _entry: entry })
+    }
+
+    pub fn write_txn(&self) -> ZResult<WriteTxn> {
+        let entry = GateEntry::enter(&self.gate);
+        let txn = self.env.write_txn()?;
+        Ok(WriteTxn { txn, _entry: entry })
+    }
+
+    pub fn create_database<KC, DC>(&self, name: Option<&str>) -> ZResult<Database<KC, DC>>
+    where
+        KC: 'static,
+        DC: 'static,
+    {
+        let _entry = GateEntry::enter(&self.gate);
+        self.env.create_database(name)
+    }
+
+    pub fn open_database<KC, DC>(&self, name: Option<&str>) -> ZResult<Option<Database<KC, DC>>>
+    where
+        KC: 'static,
+        DC: 'static,
+    {
+        let _entry = GateEntry::enter(&self.gate);
+        self.env.open_database(name)
+    }
+
+    pub fn create_poly_database(&self, name: Option<&str>) -> ZResult<PolyDatabase> {
+        let _entry = GateEntry::enter(&self.gate);
+        self.env.create_poly_database(name)
+    }
+
+    pub fn open_poly_database(&self, name: Option<&str>) -> ZResult<Option<PolyDatabase>> {
+        let _entry = GateEntry::enter(&self.gate);
+        self.env.open_poly_database(name)
+    }
+
+    pub fn copy_to_path<P: AsRef<Path>>(&self, path: P, option: CompactionOption) -> ZResult<File> {
+        let _entry = GateEntry::enter(&self.gate);
+        self.env.copy_to_path(path, option)
+    }
+
+    /// Returns the current size of the map, in bytes.
+    pub fn map_size(&self) -> usize {
+        self.map_size.load(Ordering::SeqCst)
+    }
+
+    /// Returns the size the map cannot be grown beyond, in bytes.
+    pub fn max_map_size(&self) -> usize {
+        self.max_map_size
+    }
+
+    /// Doubles the size of the map, up to its maximum size, if it has not been grown since
+    /// it was found full with the given size. Returns `false` if the map cannot be grown
+    /// because it already has its maximum size.
+    ///
+    /// The new transactions wait for the resize which itself waits for the
+    /// running ones to end, the calling thread must not hold any transaction.
+    pub(crate) fn grow_map_size(&self, full_size: usize) -> ZResult<bool> {
+        debug_assert_eq!(
+            THREAD_TXNS.with(Cell::get),
+            0,
+            "the map cannot be resized by a thread holding a transaction"
+        );
+
+        let mut state = self.gate.state.lock().unwrap();
+
+        // another update loop could be growing the map
+        while state.resizing {
+            state = self.gate.resized.wait(state).unwrap();
+        }
+
+        let map_size = self.map_size();
+        if map_size > full_size {
+            return Ok(true);
+        }
+
+        if map_size >= self.max_map_size {
+            return Ok(false);
+        }
+
+        state.resizing = true;
+        while state.running > 0 {
+            state = self.gate.idle.wait(state).unwrap();
+        }
+
+        let new_size = cmp::min(map_size.saturating_mul(2), self.max_map_size);
+        // no transaction is running and the new ones are held back by the gate
+        let result = unsafe { self.env.resize(new_size) };
+        if result.is_ok() {
+            info!(
+                "LMDB map size grown from {} to {} bytes",
+                map_size, new_size
+            );
+            self.map_size.store(new_size, Ordering::SeqCst);
+        }
+
+        state.resizing = false;
+        self.gate.resized.notify_all();
+
+        result.map(|()| true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    use tempfile::TempDir;
+
+    const MAP_SIZE: usize = 1024 * 1024;
+    const MAX_MAP_SIZE: usize = 3 * MAP_SIZE;
+
+    fn open_env(dir: &TempDir) -> Env {
+        let env = heed::EnvOpenOptions::new()
+            .map_size(MAP_SIZE)
+            .open(dir.path())
+            .unwrap();
+        Env::new(env, MAP_SIZE, MAX_MAP_SIZE)
+    }
+
+    #[test]
+    fn resize_waits_for_transactions() {
+        let dir = TempDir::new().unwrap();
+        let env = open_env(&dir);
+
+        let (reader_opened, opened) = mpsc::channel();
+        let (release_reader, release) = mpsc::channel::<()>();
+
+        let reader_env = env.clone();
+        let reader = thread::spawn(move || {
+            let reader = reader_env.read_txn().unwrap();
+            // a nested transaction must not wait for the resize
+            reader_opened.send(()).unwrap();
+            release.recv().unwrap();
+            let nested = reader_env.write_txn().unwrap();
+            nested.abort();
+            reader.abort();
+        });
+
+        opened.recv().unwrap();
+
+        let (resized_sender, resized) = mpsc::channel();
+        let resize_env = env.clone();
+        let resizer = thread::spawn(move || {
+            resize_env.grow_map_size(MAP_SIZE).unwrap();
+            resized_sender.send(()).unwrap();
+        });
+
+        // the resize cannot be done while the reader is alive
+        thread::sleep(Duration::from_millis(50));
+        assert!(resized.try_recv().is_err());
+        assert_eq!(env.map_size(), MAP_SIZE);
+
+        release_reader.send(()).unwrap();
+        reader.join().unwrap();
+        resizer.join().unwrap();
+
+        assert!(resized.try_recv().is_ok());
+        assert_eq!(env.map_size(), MAP_SIZE * 2);
+
+        // the map has already been grown since it was full
+        assert!(env.grow_map_size(MAP_SIZE).unwrap());
+        assert_eq!(env.map_size(), MAP_SIZE * 2);
+
+        let writer = env.write_txn().unwrap();
+        writer.commit().unwrap();
+    }
+
+    #[test]
+    fn map_is_not_grown_beyond_its_maximum_size() {
+        let dir = TempDir::new().unwrap();
+        let env = open_env(&dir);
+
+        assert!(env.grow_map_size(MAP_SIZE).unwrap());
+        assert_eq!(env.map_size(), MAP_SIZE * 2);
+
+        // the last growth is cut to the maximum size
+        assert!(env.grow_map_size(MAP_SIZE * 2).unwrap());
+        assert_eq!(env.map_size(), MAX_MAP_SIZE);
+
+        assert!(!env.grow_map_size(MAX_MAP_SIZE).unwrap());
+        assert_eq!(env.map_size(), MAX_MAP_SIZE);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::error_code::ErrorCode;
 use crate::serde::{DeserializerError, SerializerError};
 use serde_json::Error as SerdeJsonError;
 use std::{error, fmt, io};
@@ -8,6 +9,9 @@ pub type MResult<T> = Result<T, Error>;
 pub enum Error {
     Io(io::Error),
     IndexAlreadyExists,
+    IndexNotFound,
+    AliasAlreadyExists,
+    AliasNotFound,
     SchemaDiffer,
     SchemaMissing,
     WordIndexMissing,
@@ -19,6 +23,10 @@ pub enum Error {
     Serializer(SerializerError),
     Deserializer(DeserializerError),
     UnsupportedOperation(UnsupportedOperation),
+    LimitExceeded(LimitExceeded),
+    InvalidSetting(InvalidSetting),
+    UnsupportedSettingsVersion(u32),
+    UpdateLoopPanicked,
 }
 
 impl From<io::Error> for Error {
@@ -69,12 +77,27 @@ impl From<UnsupportedOperation> for Error {
     }
 }
 
+impl From<LimitExceeded> for Error {
+    fn from(limit: LimitExceeded) -> Error {
+        Error::LimitExceeded(limit)
+    }
+}
+
+impl From<InvalidSetting> for Error {
+    fn from(setting: InvalidSetting) -> Error {
+        Error::InvalidSetting(setting)
+    }
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         use self::Error::*;
         match self {
             Io(e) => write!(f, "{}", e),
             IndexAlreadyExists => write!(f, "index already exists"),
+            IndexNotFound => write!(f, "index not found"),
+            AliasAlreadyExists => write!(f, "an alias with this name already exists"),
+            AliasNotFound => write!(f, "alias not found"),
             SchemaDiffer => write!(f, "schemas differ"),
             SchemaMissing => write!(f, "this index does not have a schema"),
             WordIndexMissing => write!(f, "this index does not have a word index"),
@@ -86,12 +109,61 @@ impl fmt::Display for Error {
             Serializer(e) => write!(f, "serializer error; {}", e),
             Deserializer(e) => write!(f, "deserializer error; {}", e),
             UnsupportedOperation(op) => write!(f, "unsupported operation; {}", op),
+            LimitExceeded(limit) => write!(f, "index limit exceeded; {}", limit),
+            InvalidSetting(setting) => write!(f, "invalid setting; {}", setting),
+            UnsupportedSettingsVersion(version) => {
+                write!(
+                    f,
+                    "the settings are stored with the unknown version {}",
+                    version
+                )
+            }
+            UpdateLoopPanicked => write!(f, "the update loop of the index panicked"),
         }
     }
 }
 
 impl error::Error for Error {}
 
+impl Error {
+    /// The code of this error as it is exposed to the users.
+    pub fn error_code(&self) -> ErrorCode {
+        use self::Error::*;
+        match self {
+            IndexAlreadyExists => ErrorCode::IndexAlreadyExists,
+            IndexNotFound => ErrorCode::IndexNotFound,
+            AliasAlreadyExists => ErrorCode::AliasAlreadyExists,
+            AliasNotFound => ErrorCode::AliasNotFound,
+            SchemaDiffer => ErrorCode::SchemaDiffer,
+            SchemaMissing => ErrorCode::SchemaMissing,
+            MissingDocumentId => ErrorCode::MissingDocumentId,
+            Serializer(e) => e.error_code(),
+            UnsupportedOperation(op) => op.error_code(),
+            LimitExceeded(limit) => limit.error_code(),
+            InvalidSetting(_) => ErrorCode::InvalidSettings,
+            Io(_)
+            | WordIndexMissing
+            | Zlmdb(_)
+            | Fst(_)
+            | SerdeJson(_)
+            | Bincode(_)
+            | Deserializer(_)
+            | UnsupportedSettingsVersion(_)
+            | UpdateLoopPanicked => ErrorCode::Internal,
+        }
+    }
+
+    /// Whether this error is due to the LMDB memory map being full.
+    pub(crate) fn is_map_full(&self) -> bool {
+        use heed::MdbError::MapFull;
+        match self {
+            Error::Zlmdb(heed::Error::Mdb(MapFull)) => true,
+            Error::Serializer(SerializerError::Zlmdb(heed::Error::Mdb(MapFull))) => true,
+            _ => false,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum UnsupportedOperation {
     SchemaAlreadyExists,
@@ -101,6 +173,15 @@ pub enum UnsupportedOperation {
     CannotRemoveSchemaAttribute,
 }
 
+impl UnsupportedOperation {
+    pub fn error_code(&self) -> ErrorCode {
+        match self {
+            UnsupportedOperation::SchemaAlreadyExists => ErrorCode::SchemaAlreadyExists,
+            _ => ErrorCode::UnsupportedSchemaChange,
+        }
+    }
+}
+
 impl fmt::Display for UnsupportedOperation {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         use self::UnsupportedOperation::*;
@@ -115,3 +196,80 @@ impl fmt::Display for UnsupportedOperation {
         }
     }
 }
+
+#[derive(Debug)]
+pub enum LimitExceeded {
+    MaxDocuments { limit: u64 },
+    MaxDocumentSize { limit: u64, size: u64 },
+    MaxFieldsPerDocument { limit: u64, fields: u64 },
+    MaxIndexSize { limit: u64 },
+    MaxMapSize { limit: u64 },
+}
+
+impl LimitExceeded {
+    /// The limits of a single document are told apart from
+    /// the limits of the whole index, the index quotas.
+    pub fn error_code(&self) -> ErrorCode {
+        use self::LimitExceeded::*;
+        match self {
+            MaxDocumentSize { .. } | MaxFieldsPerDocument { .. } => {
+                ErrorCode::DocumentLimitExceeded
+            }
+            MaxDocuments { .. } | MaxIndexSize { .. } | MaxMapSize { .. } => {
+                ErrorCode::IndexLimitExceeded
+            }
+        }
+    }
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::LimitExceeded::*;
+        match self {
+            MaxDocuments { limit } => {
+                write!(f, "the index cannot contain more than {} documents", limit)
+            }
+            MaxDocumentSize { limit, size } => write!(
+                f,
+                "a document of {} bytes exceeds the limit of {} bytes",
+                size, limit
+            ),
+            MaxFieldsPerDocument { limit, fields } => write!(
+                f,
+                "a document with {} fields exceeds the limit of {} fields",
+                fields, limit
+            ),
+            MaxIndexSize { limit } => write!(f, "the index cannot be bigger than {} bytes", limit),
+            MaxMapSize { limit } => {
+                write!(f, "the database cannot be bigger than {} bytes", limit)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvalidSetting {
+    UnknownAttribute(String),
+    AttributeNotRanked(String),
+    InvalidRankingRule(String),
+    DuplicateRankingRule(String),
+}
+
+impl fmt::Display for InvalidSetting {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::InvalidSetting::*;
+        match self {
+            UnknownAttribute(name) => write!(f, "the attribute {} is not in the schema", name),
+            AttributeNotRanked(name) => write!(f, "the attribute {} is not ranked", name),
+            InvalidRankingRule(rule) => write!(
+                f,
+                "the ranking rule {} is invalid, it must be one of typo, words, proximity, \
+                 attribute, wordsPosition, exactness, popularity, asc(attribute) or desc(attribute)",
+                rule
+            ),
+            DuplicateRankingRule(rule) => {
+                write!(f, "the ranking rule {} is used more than once", rule)
+            }
+        }
+    }
+}
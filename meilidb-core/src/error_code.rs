@@ -0,0 +1,116 @@
+use std::fmt;
+
+/// The codes identifying the errors returned to the users, they are
+/// part of the public API: a code must never be renamed nor reused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    // indexes
+    IndexAlreadyExists,
+    IndexNotFound,
+    AliasAlreadyExists,
+    AliasNotFound,
+    IndexLimitExceeded,
+
+    // schemas
+    SchemaMissing,
+    SchemaAlreadyExists,
+    SchemaDiffer,
+    UnsupportedSchemaChange,
+
+    // settings
+    InvalidSettings,
+
+    // documents
+    MissingDocumentId,
+    InvalidDocumentId,
+    InvalidDocument,
+    DocumentNotFound,
+    DocumentLimitExceeded,
+
+    // search
+    InvalidFilter,
+    InvalidSearchParameter,
+
+    // requests
+    BadRequest,
+    BadParameter,
+    NotFound,
+    MissingAuthorizationHeader,
+    InvalidToken,
+    TooManyRequests,
+    Maintenance,
+
+    Internal,
+}
+
+/// The family of an error code, tells whether the request must be fixed or retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorType {
+    InvalidRequest,
+    Authentication,
+    Internal,
+}
+
+impl ErrorCode {
+    pub fn name(self) -> &'static str {
+        use self::ErrorCode::*;
+        match self {
+            IndexAlreadyExists => "index_already_exists",
+            IndexNotFound => "index_not_found",
+            AliasAlreadyExists => "alias_already_exists",
+            AliasNotFound => "alias_not_found",
+            IndexLimitExceeded => "index_limit_exceeded",
+            SchemaMissing => "schema_missing",
+            SchemaAlreadyExists => "schema_already_exists",
+            SchemaDiffer => "schema_differ",
+            UnsupportedSchemaChange => "unsupported_schema_change",
+            InvalidSettings => "invalid_settings",
+            MissingDocumentId => "missing_document_id",
+            InvalidDocumentId => "invalid_document_id",
+            InvalidDocument => "invalid_document",
+            DocumentNotFound => "document_not_found",
+            DocumentLimitExceeded => "document_limit_exceeded",
+            InvalidFilter => "invalid_filter",
+            InvalidSearchParameter => "invalid_search_parameter",
+            BadRequest => "bad_request",
+            BadParameter => "bad_parameter",
+            NotFound => "not_found",
+            MissingAuthorizationHeader => "missing_authorization_header",
+            InvalidToken => "invalid_token",
+            TooManyRequests => "too_many_requests",
+            Maintenance => "maintenance",
+            Internal => "internal",
+        }
+    }
+
+    pub fn error_type(self) -> ErrorType {
+        use self::ErrorCode::*;
+        match self {
+            MissingAuthorizationHeader | InvalidToken => ErrorType::Authentication,
+            Internal | Maintenance => ErrorType::Internal,
+            _ => ErrorType::InvalidRequest,
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl ErrorType {
+    pub fn name(self) -> &'static str {
+        match self {
+            ErrorType::InvalidRequest => "invalid_request_error",
+            ErrorType::Authentication => "authentication_error",
+            ErrorType::Internal => "internal_error",
+        }
+    }
+}
+
+impl fmt::Display for ErrorType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
@@ -6,23 +6,42 @@ mod automaton;
 pub mod criterion;
 mod database;
 mod distinct_map;
+mod env;
 mod error;
+mod error_code;
 mod levenshtein;
+mod limits;
 mod number;
+mod popularity_map;
 mod query_builder;
+mod query_cache;
+mod query_rule;
 mod ranked_map;
+mod ranking_rule;
 mod raw_document;
 pub mod raw_indexer;
 mod reordered_attrs;
 pub mod serde;
+mod settings;
 pub mod store;
 mod update;
 
-pub use self::database::{BoxUpdateFn, Database};
-pub use self::error::{Error, MResult};
+pub use self::database::{
+    BoxUpdateFn, Database, DatabaseOptions, UpdateEvent, UpdateEventsEmitter,
+};
+pub use self::env::{Env, ReadTxn, WriteTxn};
+pub use self::error::{Error, InvalidSetting, LimitExceeded, MResult, UnsupportedOperation};
+pub use self::error_code::{ErrorCode, ErrorType};
+pub use self::limits::IndexLimits;
 pub use self::number::{Number, ParseNumberError};
+pub use self::popularity_map::PopularityMap;
+pub use self::query_builder::{QueryBuilder, QueryStats};
+pub use self::query_cache::{CachedSearch, QueryCache, QueryCacheKey, QueryCacheStats};
+pub use self::query_rule::{Anchoring, PinnedDocument, QueryRule, RuleCondition};
 pub use self::ranked_map::RankedMap;
+pub use self::ranking_rule::{ranking_rules_criteria, validate_ranking_rules, RankingRule};
 pub use self::raw_document::RawDocument;
+pub use self::settings::Settings;
 pub use self::store::Index;
 pub use self::update::{EnqueuedUpdateResult, ProcessedUpdateResult, UpdateStatus, UpdateType};
 
@@ -75,6 +94,37 @@ pub struct DocIndex {
     pub char_length: u16,
 }
 
+/// The proximity between two words in an attribute of a document.
+///
+/// This is stored in the map for the pairs of words near enough from
+/// each other, generated at index time, used to rank at search time.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, AsBytes, FromBytes)]
+#[repr(C)]
+pub struct PairProximity {
+    /// The document identifier where the words were found.
+    pub document_id: DocumentId,
+
+    /// The attribute in the document where the words were found.
+    pub attribute: u16,
+
+    /// The lowest proximity between the two words in this attribute,
+    /// it is greater by one when the second word is found before the first one.
+    pub proximity: u16,
+
+    padding: [u8; 4],
+}
+
+impl PairProximity {
+    pub fn new(document_id: DocumentId, attribute: u16, proximity: u16) -> PairProximity {
+        PairProximity {
+            document_id,
+            attribute,
+            proximity,
+            padding: [0; 4],
+        }
+    }
+}
+
 /// This structure represent a matching word with informations
 /// on the location of the word in the document.
 ///
@@ -113,6 +163,9 @@ pub struct TmpMatch {
 pub struct Document {
     pub id: DocumentId,
     pub highlights: Vec<Highlight>,
+    /// The values compared by each criterion, in the order of the criteria,
+    /// only computed when asked to the query builder.
+    pub ranking_info: Option<Vec<Option<Number>>>,
 
     #[cfg(test)]
     pub matches: Vec<TmpMatch>,
@@ -124,6 +177,7 @@ impl Document {
         Document {
             id: raw.id,
             highlights: raw.highlights,
+            ranking_info: None,
         }
     }
 
@@ -153,6 +207,7 @@ impl Document {
             id: raw.id,
             matches,
             highlights: raw.highlights,
+            ranking_info: None,
         }
     }
 }
@@ -166,4 +221,9 @@ mod tests {
     fn docindex_mem_size() {
         assert_eq!(mem::size_of::<DocIndex>(), 16);
     }
+
+    #[test]
+    fn pair_proximity_mem_size() {
+        assert_eq!(mem::size_of::<PairProximity>(), 16);
+    }
 }
@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::LimitExceeded;
+
+/// The limits an index must respect, a limit that is not defined is not enforced.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct IndexLimits {
+    /// The maximum number of documents the index can contain.
+    pub max_documents: Option<u64>,
+    /// The maximum size of a document, in bytes of its JSON representation.
+    pub max_document_size: Option<u64>,
+    /// The maximum number of fields a document can have.
+    pub max_fields_per_document: Option<u64>,
+    /// The maximum size of the data stored by the index, in bytes.
+    pub max_index_size: Option<u64>,
+}
+
+impl IndexLimits {
+    /// Checks that a single document, serialized in `size` bytes, respects the limits.
+    pub fn check_document(
+        &self,
+        document: &serde_json::Value,
+        size: usize,
+    ) -> Result<(), LimitExceeded> {
+        let size = size as u64;
+        if let Some(limit) = self.max_document_size {
+            if size > limit {
+                return Err(LimitExceeded::MaxDocumentSize { limit, size });
+            }
+        }
+
+        if let Some(limit) = self.max_fields_per_document {
+            let fields = document.as_object().map_or(0, |o| o.len()) as u64;
+            if fields > limit {
+                return Err(LimitExceeded::MaxFieldsPerDocument { limit, fields });
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn check_number_of_documents(&self, number: u64) -> Result<(), LimitExceeded> {
+        match self.max_documents {
+            Some(limit) if number > limit => Err(LimitExceeded::MaxDocuments { limit }),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn check_index_size(&self, size: u64) -> Result<(), LimitExceeded> {
+        match self.max_index_size {
+            Some(limit) if size > limit => Err(LimitExceeded::MaxIndexSize { limit }),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn document_limits() {
+        let limits = IndexLimits {
+            max_document_size: Some(30),
+            max_fields_per_document: Some(2),
+            ..IndexLimits::default()
+        };
+
+        let document = json!({ "id": 1, "title": "hello" });
+        let size = serde_json::to_vec(&document).unwrap().len();
+        assert!(limits.check_document(&document, size).is_ok());
+
+        let document = json!({ "id": 1, "title": "hello", "overview": "world" });
+        let size = serde_json::to_vec(&document).unwrap().len();
+        assert_matches!(
+            limits.check_document(&document, size),
+            Err(LimitExceeded::MaxFieldsPerDocument {
+                limit: 2,
+                fields: 3
+            })
+        );
+
+        let document = json!({ "id": 1, "title": "a very long title for this document" });
+        let size = serde_json::to_vec(&document).unwrap().len();
+        assert_matches!(
+            limits.check_document(&document, size),
+            Err(LimitExceeded::MaxDocumentSize { limit: 30, .. })
+        );
+    }
+
+    #[test]
+    fn no_limits() {
+        let limits = IndexLimits::default();
+        assert!(limits.check_number_of_documents(u64::max_value()).is_ok());
+        assert!(limits.check_index_size(u64::max_value()).is_ok());
+    }
+}
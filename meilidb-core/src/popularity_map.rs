@@ -0,0 +1,35 @@
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::DocumentId;
+
+/// The popularity scores of the documents, computed from the
+/// events (e.g. clicks) the users sent on these documents.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PopularityMap(HashMap<DocumentId, u64>);
+
+impl PopularityMap {
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Adds to the score of a document, the score saturates instead of overflowing.
+    pub fn increase(&mut self, document: DocumentId, score: u64) {
+        let current = self.0.entry(document).or_insert(0);
+        *current = current.saturating_add(score);
+    }
+
+    pub fn remove(&mut self, document: DocumentId) {
+        self.0.remove(&document);
+    }
+
+    /// Returns the score of a document, documents without events have a score of zero.
+    pub fn get(&self, document: DocumentId) -> u64 {
+        self.0.get(&document).cloned().unwrap_or(0)
+    }
+}
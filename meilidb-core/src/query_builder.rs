@@ -1,4 +1,6 @@
-use hashbrown::HashMap;
+use hashbrown::{HashMap, HashSet};
+use std::borrow::Cow;
+use std::cmp::Ordering;
 use std::convert::TryFrom;
 use std::mem;
 use std::ops::Range;
@@ -6,15 +8,20 @@ use std::rc::Rc;
 use std::time::{Duration, Instant};
 
 use fst::{IntoStreamer, Streamer};
-use sdset::SetBuf;
+use rayon::prelude::*;
+use roaring::RoaringTreemap;
+use sdset::{Set, SetBuf};
 use slice_group_by::{GroupBy, GroupByMut};
 
-use crate::automaton::{Automaton, AutomatonGroup, AutomatonProducer, QueryEnhancer};
+use crate::automaton::{
+    normalize_str, Automaton, AutomatonGroup, AutomatonProducer, QueryEnhancer,
+};
+use crate::criterion::{Criteria, MAX_DISTANCE};
 use crate::distinct_map::{BufferedDistinctMap, DistinctMap};
 use crate::levenshtein::prefix_damerau_levenshtein;
 use crate::raw_document::{raw_documents_from, RawDocument};
-use crate::{criterion::Criteria, Document, DocumentId, Highlight, TmpMatch};
 use crate::{reordered_attrs::ReorderedAttrs, store, MResult};
+use crate::{DocIndex, Document, DocumentId, Highlight, TmpMatch};
 
 pub struct QueryBuilder<'c, 'f, 'd> {
     criteria: Criteria<'c>,
@@ -22,10 +29,27 @@ pub struct QueryBuilder<'c, 'f, 'd> {
     filter: Option<Box<dyn Fn(DocumentId) -> bool + 'f>>,
     distinct: Option<(Box<dyn Fn(DocumentId) -> Option<u64> + 'd>, usize)>,
     timeout: Option<Duration>,
+    ranking_info: bool,
+    candidates: Option<RoaringTreemap>,
     main_store: store::Main,
     postings_lists_store: store::PostingsLists,
+    postings_docids_store: store::PostingsDocids,
     documents_fields_counts_store: store::DocumentsFieldsCounts,
     synonyms_store: store::Synonyms,
+    words_pairs_proximities_store: store::WordsPairsProximities,
+}
+
+/// Informations on the way a query has been processed.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct QueryStats {
+    /// The time spent to find and sort the documents.
+    pub processing_time: Duration,
+    /// Whether the fetch timeout has been reached, the documents
+    /// returned may not be the most relevant ones.
+    pub timed_out: bool,
+    /// The number of documents matching the query, the ones returned and the ones
+    /// out of the requested range, before the filter function and the distinct are applied.
+    pub nb_hits: usize,
 }
 
 fn multiword_rewrite_matches(
@@ -138,113 +162,374 @@ fn multiword_rewrite_matches(
     SetBuf::new_unchecked(padded_matches)
 }
 
+/// Returns the documents containing one of the negative words of the query.
+fn excluded_documents(
+    reader: &heed::RoTxn,
+    negative_words: &[String],
+    docids_ready: bool,
+    postings_lists_store: store::PostingsLists,
+    postings_docids_store: store::PostingsDocids,
+) -> MResult<RoaringTreemap> {
+    let mut excluded = RoaringTreemap::new();
+    for word in negative_words {
+        let word = word.as_bytes();
+        if let Some(docids) = word_docids(
+            reader,
+            word,
+            docids_ready,
+            postings_lists_store,
+            postings_docids_store,
+        )? {
+            excluded.union_with(&docids);
+        }
+    }
+    Ok(excluded)
+}
+
+/// Returns the documents containing the word, they are read from the postings
+/// lists while the postings docids are being filled by a migration.
+fn word_docids(
+    reader: &heed::RoTxn,
+    word: &[u8],
+    docids_ready: bool,
+    postings_lists_store: store::PostingsLists,
+    postings_docids_store: store::PostingsDocids,
+) -> MResult<Option<RoaringTreemap>> {
+    if docids_ready {
+        return Ok(postings_docids_store.postings_docids(reader, word)?);
+    }
+
+    match postings_lists_store.postings_list(reader, word)? {
+        Some(doc_indexes) => Ok(Some(doc_indexes.iter().map(|d| d.document_id.0).collect())),
+        None => Ok(None),
+    }
+}
+
+/// Returns the doc indexes of the given documents only, the postings list is searched
+/// for each of them instead of being read entirely as it is sorted by document.
+fn documents_doc_indexes(doc_indexes: &Set<DocIndex>, docids: &RoaringTreemap) -> SetBuf<DocIndex> {
+    let mut documents_doc_indexes = Vec::new();
+    let mut remaining = doc_indexes.as_slice();
+
+    for docid in docids.iter() {
+        // the first doc index of the document, there is no equal element
+        let start = remaining
+            .binary_search_by(|di| di.document_id.0.cmp(&docid).then(Ordering::Greater))
+            .unwrap_err();
+        remaining = &remaining[start..];
+
+        let len = remaining
+            .iter()
+            .take_while(|di| di.document_id.0 == docid)
+            .count();
+        documents_doc_indexes.extend_from_slice(&remaining[..len]);
+        remaining = &remaining[len..];
+    }
+
+    SetBuf::new_unchecked(documents_doc_indexes)
+}
+
+/// Splits the words prefixed by a minus sign from the query,
+/// the documents that contain these words are not returned.
+fn split_negative_words(query: &str) -> (Cow<str>, Vec<String>) {
+    let mut positive_words = Vec::new();
+    let mut negative_words = Vec::new();
+
+    for word in query.split_whitespace() {
+        if word.starts_with('-') && word.len() > 1 {
+            negative_words.push(normalize_str(&word[1..]));
+        } else {
+            positive_words.push(word);
+        }
+    }
+
+    if negative_words.is_empty() {
+        (Cow::Borrowed(query), negative_words)
+    } else {
+        (Cow::Owned(positive_words.join(" ")), negative_words)
+    }
+}
+
+/// Returns the query to search for and the documents that must not be
+/// returned, the ones containing the negative words of the query.
+fn negative_query<'q>(
+    reader: &heed::RoTxn,
+    query: &'q str,
+    main_store: store::Main,
+    postings_lists_store: store::PostingsLists,
+    postings_docids_store: store::PostingsDocids,
+) -> MResult<(Cow<'q, str>, RoaringTreemap)> {
+    let (query, negative_words) = split_negative_words(query);
+    let excluded = excluded_documents(
+        reader,
+        &negative_words,
+        main_store.postings_docids_ready(reader)?,
+        postings_lists_store,
+        postings_docids_store,
+    )?;
+
+    Ok((query, excluded))
+}
+
+/// A word of the dictionary matching a query automaton.
+struct AutomatonWord {
+    word: Vec<u8>,
+    distance: u8,
+    is_exact: bool,
+    covered_area: usize,
+}
+
+fn automaton_words(words: &fst::Set, automaton: &Automaton) -> Vec<AutomatonWord> {
+    let Automaton {
+        is_exact,
+        query_len,
+        query,
+        ..
+    } = automaton;
+    let dfa = automaton.dfa();
+
+    let mut automaton_words = Vec::new();
+    let mut stream = words.search(&dfa).into_stream();
+    while let Some(input) = stream.next() {
+        let distance = dfa.eval(input).to_u8();
+        let is_exact = *is_exact && distance == 0 && input.len() == *query_len;
+
+        let covered_area = if query.len() > input.len() {
+            input.len()
+        } else {
+            prefix_damerau_levenshtein(query.as_bytes(), input).1
+        };
+
+        automaton_words.push(AutomatonWord {
+            word: input.to_vec(),
+            distance,
+            is_exact,
+            covered_area,
+        });
+    }
+
+    automaton_words
+}
+
+/// Returns the matches of an automatons group, the postings lists only contain the
+/// candidate documents and are associated with the automaton position in the group.
+fn group_matches(
+    group: &AutomatonGroup,
+    group_postings: &[(usize, u32, AutomatonWord, SetBuf<DocIndex>)],
+    searchables: Option<&ReorderedAttrs>,
+) -> Vec<(DocumentId, TmpMatch, Highlight)> {
+    let phrase_query_len = group.automatons.len();
+    let mut tmp_matches = Vec::new();
+
+    for (id, query_index, automaton_word, doc_indexes) in group_postings {
+        for di in doc_indexes.as_slice() {
+            let attribute = searchables.map_or(Some(di.attribute), |r| r.get(di.attribute));
+            if let Some(attribute) = attribute {
+                let match_ = TmpMatch {
+                    query_index: *query_index,
+                    distance: automaton_word.distance,
+                    attribute,
+                    word_index: di.word_index,
+                    is_exact: automaton_word.is_exact,
+                };
+
+                let highlight = Highlight {
+                    attribute: di.attribute,
+                    char_index: di.char_index,
+                    char_length: u16::try_from(automaton_word.covered_area)
+                        .unwrap_or(u16::max_value()),
+                };
+
+                tmp_matches.push((di.document_id, *id, match_, highlight));
+            }
+        }
+    }
+
+    if !group.is_phrase_query {
+        return tmp_matches
+            .into_iter()
+            .map(|(id, _, match_, highlight)| (id, match_, highlight))
+            .collect();
+    }
+
+    let mut matches = Vec::new();
+    tmp_matches.sort_unstable_by_key(|(id, _, m, _)| (*id, m.attribute, m.word_index));
+    for group in tmp_matches.linear_group_by_key(|(id, _, m, _)| (*id, m.attribute)) {
+        for window in group.windows(2) {
+            let (ida, ia, ma, ha) = window[0];
+            let (idb, ib, mb, hb) = window[1];
+
+            debug_assert_eq!(ida, idb);
+
+            // if matches must follow and actually follows themselves
+            if ia + 1 == ib && ma.word_index + 1 == mb.word_index {
+                // TODO we must make it work for phrase query longer than 2
+                // if the second match is the last phrase query word
+                if ib + 1 == phrase_query_len {
+                    // insert first match
+                    matches.push((ida, ma, ha));
+
+                    // insert second match
+                    matches.push((idb, mb, hb));
+                }
+            }
+        }
+    }
+
+    matches
+}
+
 fn fetch_raw_documents(
     reader: &heed::RoTxn,
     automatons_groups: &[AutomatonGroup],
     query_enhancer: &QueryEnhancer,
     searchables: Option<&ReorderedAttrs>,
+    candidates: Option<&RoaringTreemap>,
+    excluded: &RoaringTreemap,
     main_store: store::Main,
     postings_lists_store: store::PostingsLists,
+    postings_docids_store: store::PostingsDocids,
     documents_fields_counts_store: store::DocumentsFieldsCounts,
+    words_pairs_proximities_store: store::WordsPairsProximities,
 ) -> MResult<Vec<RawDocument>> {
-    let mut matches = Vec::new();
-    let mut highlights = Vec::new();
-
-    for group in automatons_groups {
-        let AutomatonGroup {
-            is_phrase_query,
-            automatons,
-        } = group;
-        let phrase_query_len = automatons.len();
+    let words = match main_store.words_fst(reader)? {
+        Some(words) => words,
+        None => return Ok(Vec::new()),
+    };
 
-        let mut tmp_matches = Vec::new();
-        for (id, automaton) in automatons.into_iter().enumerate() {
-            let Automaton {
-                index,
-                is_exact,
-                query_len,
-                query,
-                ..
-            } = automaton;
-            let dfa = automaton.dfa();
+    // 1. find the words matching the automatons, the automatons are independent
+    //    from each other and the words dictionary is searched concurrently
+    let groups_words: Vec<Vec<_>> = automatons_groups
+        .par_iter()
+        .map(|group| {
+            group
+                .automatons
+                .par_iter()
+                .map(|automaton| automaton_words(&words, automaton))
+                .collect()
+        })
+        .collect();
 
-            let words = match main_store.words_fst(reader)? {
-                Some(words) => words,
-                None => return Ok(Vec::new()),
-            };
+    // 2. find the documents containing the query words using the bitmaps only,
+    //    the phrase queries words must all be in the same documents
+    let docids_ready = main_store.postings_docids_ready(reader)?;
+    let mut docids = RoaringTreemap::new();
+    let mut groups_words_docids = Vec::with_capacity(groups_words.len());
+
+    for (group, group_words) in automatons_groups.iter().zip(&groups_words) {
+        let mut group_docids: Option<RoaringTreemap> = None;
+        let mut group_words_docids = Vec::with_capacity(group_words.len());
+
+        for automaton_words in group_words {
+            let mut automaton_docids = RoaringTreemap::new();
+            let mut words_docids = Vec::with_capacity(automaton_words.len());
+            for automaton_word in automaton_words {
+                let word_docids = word_docids(
+                    reader,
+                    &automaton_word.word,
+                    docids_ready,
+                    postings_lists_store,
+                    postings_docids_store,
+                )?;
+                if let Some(word_docids) = &word_docids {
+                    automaton_docids.union_with(word_docids);
+                }
+                words_docids.push(word_docids);
+            }
+            group_words_docids.push(words_docids);
 
-            let mut stream = words.search(&dfa).into_stream();
-            while let Some(input) = stream.next() {
-                let distance = dfa.eval(input).to_u8();
-                let is_exact = *is_exact && distance == 0 && input.len() == *query_len;
+            group_docids = Some(match group_docids {
+                Some(mut docids) if group.is_phrase_query => {
+                    docids.intersect_with(&automaton_docids);
+                    docids
+                }
+                Some(mut docids) => {
+                    docids.union_with(&automaton_docids);
+                    docids
+                }
+                None => automaton_docids,
+            });
+        }
 
-                let covered_area = if query.len() > input.len() {
-                    input.len()
-                } else {
-                    prefix_damerau_levenshtein(query.as_bytes(), input).1
-                };
+        if let Some(group_docids) = group_docids {
+            docids.union_with(&group_docids);
+        }
+        groups_words_docids.push(group_words_docids);
+    }
 
-                let doc_indexes = match postings_lists_store.postings_list(reader, input)? {
-                    Some(doc_indexes) => doc_indexes,
+    if let Some(candidates) = candidates {
+        docids.intersect_with(candidates);
+    }
+    docids.difference_with(excluded);
+
+    // 3. load the positions of the words in the remaining documents only, the
+    //    transaction cannot be shared between threads, it is done sequentially
+    let mut query_words = Vec::new();
+    let mut phrase_query_indexes = Vec::new();
+    let mut groups_postings = Vec::with_capacity(automatons_groups.len());
+
+    for ((group, group_words), group_words_docids) in automatons_groups
+        .iter()
+        .zip(groups_words)
+        .zip(groups_words_docids)
+    {
+        let mut group_postings = Vec::new();
+
+        for (id, ((automaton, automaton_words), words_docids)) in group
+            .automatons
+            .iter()
+            .zip(group_words)
+            .zip(group_words_docids)
+            .enumerate()
+        {
+            let query_index = automaton.index as u32;
+
+            for (automaton_word, word_docids) in automaton_words.into_iter().zip(words_docids) {
+                let mut word_docids = match word_docids {
+                    Some(word_docids) => word_docids,
                     None => continue,
                 };
 
-                tmp_matches.reserve(doc_indexes.len());
-
-                for di in doc_indexes.as_ref() {
-                    let attribute = searchables.map_or(Some(di.attribute), |r| r.get(di.attribute));
-                    if let Some(attribute) = attribute {
-                        let match_ = TmpMatch {
-                            query_index: *index as u32,
-                            distance,
-                            attribute,
-                            word_index: di.word_index,
-                            is_exact,
-                        };
+                word_docids.intersect_with(&docids);
+                if word_docids.is_empty() {
+                    continue;
+                }
 
-                        let highlight = Highlight {
-                            attribute: di.attribute,
-                            char_index: di.char_index,
-                            char_length: u16::try_from(covered_area).unwrap_or(u16::max_value()),
-                        };
+                let doc_indexes =
+                    match postings_lists_store.postings_list(reader, &automaton_word.word)? {
+                        Some(doc_indexes) => documents_doc_indexes(&doc_indexes, &word_docids),
+                        None => continue,
+                    };
 
-                        tmp_matches.push((di.document_id, id, match_, highlight));
-                    }
+                if group.is_phrase_query {
+                    phrase_query_indexes.push(query_index);
+                } else {
+                    query_words.push((
+                        query_index,
+                        automaton_word.word.clone(),
+                        automaton_word.distance,
+                    ));
                 }
-            }
-        }
 
-        if *is_phrase_query {
-            tmp_matches.sort_unstable_by_key(|(id, _, m, _)| (*id, m.attribute, m.word_index));
-            for group in tmp_matches.linear_group_by_key(|(id, _, m, _)| (*id, m.attribute)) {
-                for window in group.windows(2) {
-                    let (ida, ia, ma, ha) = window[0];
-                    let (idb, ib, mb, hb) = window[1];
-
-                    debug_assert_eq!(ida, idb);
-
-                    // if matches must follow and actually follows themselves
-                    if ia + 1 == ib && ma.word_index + 1 == mb.word_index {
-                        // TODO we must make it work for phrase query longer than 2
-                        // if the second match is the last phrase query word
-                        if ib + 1 == phrase_query_len {
-                            // insert first match
-                            matches.push((ida, ma));
-                            highlights.push((ida, ha));
-
-                            // insert second match
-                            matches.push((idb, mb));
-                            highlights.push((idb, hb));
-                        }
-                    }
-                }
-            }
-        } else {
-            for (id, _, match_, highlight) in tmp_matches {
-                matches.push((id, match_));
-                highlights.push((id, highlight));
+                group_postings.push((id, query_index, automaton_word, doc_indexes));
             }
         }
+
+        groups_postings.push((group, group_postings));
+    }
+
+    // 4. keep the matches of the remaining documents only,
+    //    the groups are independent and processed concurrently
+    let groups_matches: Vec<_> = groups_postings
+        .into_par_iter()
+        .map(|(group, group_postings)| group_matches(group, &group_postings, searchables))
+        .collect();
+
+    let mut matches = Vec::new();
+    let mut highlights = Vec::new();
+    for (id, match_, highlight) in groups_matches.into_iter().flatten() {
+        matches.push((id, match_));
+        highlights.push((id, highlight));
     }
 
     let matches = multiword_rewrite_matches(matches, &query_enhancer);
@@ -265,21 +550,209 @@ fn fetch_raw_documents(
         SetBuf::new(fields_counts).unwrap()
     };
 
-    Ok(raw_documents_from(matches, highlights, fields_counts))
+    let mut raw_documents = raw_documents_from(matches, highlights, fields_counts);
+
+    // the pairs are not used while they are being rebuilt by a migration
+    if main_store.words_pairs_proximities_ready(reader)? {
+        compute_words_proximities(
+            reader,
+            &mut raw_documents,
+            query_words,
+            &phrase_query_indexes,
+            query_enhancer,
+            searchables,
+            words_pairs_proximities_store,
+        )?;
+    }
+
+    Ok(raw_documents)
+}
+
+/// The maximum number of words derived from a query word for which the words pairs
+/// proximities are looked up, the documents matching the query word with a word not
+/// considered use the positions of the words to compute their proximity.
+const MAX_PROXIMITY_WORDS: usize = 30;
+
+/// The proximities of the pairs of words matching two query words in the documents,
+/// along with the distances of these words to the query words.
+type PairsProximities = HashMap<DocumentId, Vec<(u8, u8, u16)>>;
+
+fn query_pairs_proximities(
+    reader: &heed::RoTxn,
+    words_a: &[(Vec<u8>, u8)],
+    words_b: &[(Vec<u8>, u8)],
+    documents_ids: &HashSet<DocumentId>,
+    searchables: Option<&ReorderedAttrs>,
+    words_pairs_proximities_store: store::WordsPairsProximities,
+) -> MResult<PairsProximities> {
+    let mut pairs_proximities = HashMap::new();
+
+    for (word_a, distance_a) in words_a {
+        for (word_b, distance_b) in words_b {
+            // only the pairs in the order of the words in the documents are stored,
+            // the reversed pairs are one word farther away from each other
+            let pairs = [(word_a, word_b, 0), (word_b, word_a, 1)];
+
+            for (first, second, offset) in pairs.iter() {
+                let proximities =
+                    match words_pairs_proximities_store.pair_proximities(reader, first, second)? {
+                        Some(proximities) => proximities,
+                        None => continue,
+                    };
+
+                for pair in proximities.as_ref() {
+                    let proximity = pair.proximity + *offset;
+                    if proximity >= MAX_DISTANCE || !documents_ids.contains(&pair.document_id) {
+                        continue;
+                    }
+
+                    if searchables.map_or(true, |r| r.get(pair.attribute).is_some()) {
+                        pairs_proximities
+                            .entry(pair.document_id)
+                            .or_insert_with(Vec::new)
+                            .push((*distance_a, *distance_b, proximity));
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(pairs_proximities)
+}
+
+/// Computes the proximity of the documents from the words pairs proximities generated
+/// at index time, without using the positions of the matches. The documents that match
+/// query words rewritten by multi-words synonyms or phrase queries keep using them,
+/// so do the ones matching a query word with a word too far from it.
+fn compute_words_proximities(
+    reader: &heed::RoTxn,
+    raw_documents: &mut [RawDocument],
+    query_words: Vec<(u32, Vec<u8>, u8)>,
+    phrase_query_indexes: &[u32],
+    query_enhancer: &QueryEnhancer,
+    searchables: Option<&ReorderedAttrs>,
+    words_pairs_proximities_store: store::WordsPairsProximities,
+) -> MResult<()> {
+    let mut rewritten_indexes = HashSet::new();
+    for index in phrase_query_indexes {
+        rewritten_indexes.extend(query_enhancer.replacement(*index));
+    }
+
+    let mut words = Vec::with_capacity(query_words.len());
+    for (index, word, distance) in query_words {
+        let replacement = query_enhancer.replacement(index);
+        if replacement.len() == 1 {
+            words.push((replacement.start, word, distance));
+        } else {
+            rewritten_indexes.extend(replacement);
+        }
+    }
+
+    // a word matched by multiple automatons is only kept with its lowest distance
+    words.sort_unstable();
+    words.dedup_by(|(qa, wa, _), (qb, wb, _)| qa == qb && wa == wb);
+    words.sort_unstable_by_key(|(query_index, _, distance)| (*query_index, *distance));
+
+    // the nearest words of each query word are kept, all the words
+    // of a distance are kept or none to not miss any pair of them
+    let mut query_words = HashMap::new();
+    for query_words_group in words.linear_group_by_key(|(query_index, ..)| *query_index) {
+        let mut kept_words = Vec::new();
+        let mut max_distance = None;
+
+        for distance_group in query_words_group.linear_group_by_key(|(_, _, distance)| *distance) {
+            if kept_words.len() + distance_group.len() > MAX_PROXIMITY_WORDS {
+                break;
+            }
+
+            let distance = distance_group[0].2;
+            kept_words.extend(distance_group.iter().map(|(_, w, d)| (w.clone(), *d)));
+            max_distance = Some(distance);
+        }
+
+        if let Some(max_distance) = max_distance {
+            let query_index = query_words_group[0].0;
+            query_words.insert(query_index, (max_distance, kept_words));
+        }
+    }
+
+    let documents_ids: HashSet<_> = raw_documents.iter().map(|d| d.id).collect();
+
+    let mut pairs_cache = HashMap::new();
+    for document in raw_documents {
+        // the matches are sorted by query index then by distance
+        let mut query_distances: Vec<_> = document
+            .query_index()
+            .iter()
+            .cloned()
+            .zip(document.distance().iter().cloned())
+            .collect();
+        query_distances.dedup_by_key(|(query_index, _)| *query_index);
+
+        let use_positions = query_distances.iter().any(|(q, distance)| {
+            rewritten_indexes.contains(q)
+                || query_words
+                    .get(q)
+                    .map_or(true, |(max_distance, _)| distance > max_distance)
+        });
+
+        if use_positions {
+            continue;
+        }
+
+        let mut proximity = 0;
+        for window in query_distances.windows(2) {
+            let (query_a, distance_a) = window[0];
+            let (query_b, distance_b) = window[1];
+
+            let key = (query_a, query_b);
+            if !pairs_cache.contains_key(&key) {
+                let pairs = query_pairs_proximities(
+                    reader,
+                    &query_words[&query_a].1,
+                    &query_words[&query_b].1,
+                    &documents_ids,
+                    searchables,
+                    words_pairs_proximities_store,
+                )?;
+                pairs_cache.insert(key, pairs);
+            }
+
+            // the words that are not near enough have the maximum proximity
+            proximity += pairs_cache[&key]
+                .get(&document.id)
+                .and_then(|pairs| {
+                    pairs
+                        .iter()
+                        .filter(|(a, b, _)| *a == distance_a && *b == distance_b)
+                        .map(|(_, _, p)| *p)
+                        .min()
+                })
+                .unwrap_or(MAX_DISTANCE);
+        }
+
+        document.words_proximity = Some(proximity);
+    }
+
+    Ok(())
 }
 
 impl<'c, 'f, 'd> QueryBuilder<'c, 'f, 'd> {
     pub fn new(
         main: store::Main,
         postings_lists: store::PostingsLists,
+        postings_docids: store::PostingsDocids,
         documents_fields_counts: store::DocumentsFieldsCounts,
         synonyms: store::Synonyms,
+        words_pairs_proximities: store::WordsPairsProximities,
     ) -> QueryBuilder<'c, 'f, 'd> {
         QueryBuilder::with_criteria(
             main,
             postings_lists,
+            postings_docids,
             documents_fields_counts,
             synonyms,
+            words_pairs_proximities,
             Criteria::default(),
         )
     }
@@ -287,8 +760,10 @@ impl<'c, 'f, 'd> QueryBuilder<'c, 'f, 'd> {
     pub fn with_criteria(
         main: store::Main,
         postings_lists: store::PostingsLists,
+        postings_docids: store::PostingsDocids,
         documents_fields_counts: store::DocumentsFieldsCounts,
         synonyms: store::Synonyms,
+        words_pairs_proximities: store::WordsPairsProximities,
         criteria: Criteria<'c>,
     ) -> QueryBuilder<'c, 'f, 'd> {
         QueryBuilder {
@@ -297,10 +772,14 @@ impl<'c, 'f, 'd> QueryBuilder<'c, 'f, 'd> {
             filter: None,
             distinct: None,
             timeout: None,
+            ranking_info: false,
+            candidates: None,
             main_store: main,
             postings_lists_store: postings_lists,
+            postings_docids_store: postings_docids,
             documents_fields_counts_store: documents_fields_counts,
             synonyms_store: synonyms,
+            words_pairs_proximities_store: words_pairs_proximities,
         }
     }
 }
@@ -317,13 +796,24 @@ impl<'c, 'f, 'd> QueryBuilder<'c, 'f, 'd> {
         self.timeout = Some(timeout)
     }
 
-    pub fn with_distinct<F, K>(&mut self, function: F, size: usize)
+    pub fn with_distinct<F>(&mut self, function: F, size: usize)
     where
         F: Fn(DocumentId) -> Option<u64> + 'd,
     {
         self.distinct = Some((Box::new(function), size))
     }
 
+    /// Reports the value of each criterion for the returned documents.
+    pub fn with_ranking_info(&mut self) {
+        self.ranking_info = true
+    }
+
+    /// Only returns the documents in this set, it is intersected with the
+    /// documents of the query words before their positions are read.
+    pub fn with_candidates(&mut self, candidates: RoaringTreemap) {
+        self.candidates = Some(candidates)
+    }
+
     pub fn add_searchable_attribute(&mut self, attribute: u16) {
         let reorders = self
             .searchable_attrs
@@ -337,6 +827,16 @@ impl<'c, 'f, 'd> QueryBuilder<'c, 'f, 'd> {
         query: &str,
         range: Range<usize>,
     ) -> MResult<Vec<Document>> {
+        self.query_with_stats(reader, query, range)
+            .map(|(documents, _)| documents)
+    }
+
+    pub fn query_with_stats(
+        self,
+        reader: &heed::RoTxn,
+        query: &str,
+        range: Range<usize>,
+    ) -> MResult<(Vec<Document>, QueryStats)> {
         match self.distinct {
             Some((distinct, distinct_size)) => raw_query_with_distinct(
                 reader,
@@ -346,12 +846,16 @@ impl<'c, 'f, 'd> QueryBuilder<'c, 'f, 'd> {
                 distinct,
                 distinct_size,
                 self.timeout,
+                self.ranking_info,
+                self.candidates,
                 self.criteria,
                 self.searchable_attrs,
                 self.main_store,
                 self.postings_lists_store,
+                self.postings_docids_store,
                 self.documents_fields_counts_store,
                 self.synonyms_store,
+                self.words_pairs_proximities_store,
             ),
             None => raw_query(
                 reader,
@@ -359,17 +863,84 @@ impl<'c, 'f, 'd> QueryBuilder<'c, 'f, 'd> {
                 range,
                 self.filter,
                 self.timeout,
+                self.ranking_info,
+                self.candidates,
                 self.criteria,
                 self.searchable_attrs,
                 self.main_store,
                 self.postings_lists_store,
+                self.postings_docids_store,
                 self.documents_fields_counts_store,
                 self.synonyms_store,
+                self.words_pairs_proximities_store,
             ),
         }
     }
 }
 
+fn document_from_raw(raw: RawDocument, criteria: &Criteria, ranking_info: bool) -> Document {
+    let ranking_info = if ranking_info {
+        let info = criteria.as_ref().iter().map(|c| c.explain(&raw)).collect();
+        Some(info)
+    } else {
+        None
+    };
+
+    Document {
+        ranking_info,
+        ..Document::from_raw(raw)
+    }
+}
+
+/// Sorts the documents by the criteria, only the groups of documents that
+/// overlap with the requested range are sorted, concurrently, and split.
+fn bucket_sort(raw_documents: &mut [RawDocument], criteria: &Criteria, range: &Range<usize>) {
+    let mut groups = vec![raw_documents];
+
+    'criteria: for criterion in criteria.as_ref() {
+        let tmp_groups = mem::replace(&mut groups, Vec::new());
+
+        // find the groups that must be sorted, the ones that do not overlap with
+        // the requested range are kept unsorted and the ones after it are dropped
+        let mut documents_seen = 0;
+        let mut selected_groups = Vec::new();
+        for group in tmp_groups {
+            let must_sort = documents_seen + group.len() >= range.start;
+            documents_seen += group.len();
+            selected_groups.push((must_sort, group));
+
+            if must_sort && documents_seen >= range.end {
+                break;
+            }
+        }
+
+        selected_groups
+            .par_iter_mut()
+            .filter(|(must_sort, _)| *must_sort)
+            .for_each(|(_, group)| group.sort_unstable_by(|a, b| criterion.evaluate(a, b)));
+
+        let mut documents_seen = 0;
+        for (must_sort, group) in selected_groups {
+            if !must_sort {
+                documents_seen += group.len();
+                groups.push(group);
+                continue;
+            }
+
+            for group in group.binary_group_by_mut(|a, b| criterion.eq(a, b)) {
+                documents_seen += group.len();
+                groups.push(group);
+
+                // we have sort enough documents if the last document sorted is after
+                // the end of the requested range, we can continue to the next criterion
+                if documents_seen >= range.end {
+                    continue 'criteria;
+                }
+            }
+        }
+    }
+}
+
 fn raw_query<'c, FI>(
     reader: &heed::RoTxn,
 
@@ -378,15 +949,19 @@ fn raw_query<'c, FI>(
 
     filter: Option<FI>,
     timeout: Option<Duration>,
+    ranking_info: bool,
+    candidates: Option<RoaringTreemap>,
 
     criteria: Criteria<'c>,
     searchable_attrs: Option<ReorderedAttrs>,
 
     main_store: store::Main,
     postings_lists_store: store::PostingsLists,
+    postings_docids_store: store::PostingsDocids,
     documents_fields_counts_store: store::DocumentsFieldsCounts,
     synonyms_store: store::Synonyms,
-) -> MResult<Vec<Document>>
+    words_pairs_proximities_store: store::WordsPairsProximities,
+) -> MResult<(Vec<Document>, QueryStats)>
 where
     FI: Fn(DocumentId) -> bool,
 {
@@ -403,28 +978,42 @@ where
             distinct,
             distinct_size,
             timeout,
+            ranking_info,
+            candidates,
             criteria,
             searchable_attrs,
             main_store,
             postings_lists_store,
+            postings_docids_store,
             documents_fields_counts_store,
             synonyms_store,
+            words_pairs_proximities_store,
         );
     }
 
     let start_processing = Instant::now();
     let mut raw_documents_processed = Vec::with_capacity(range.len());
 
-    let (automaton_producer, query_enhancer) = AutomatonProducer::new(
+    let (query, excluded) = negative_query(
         reader,
         query,
         main_store,
         postings_lists_store,
+        postings_docids_store,
+    )?;
+
+    let (automaton_producer, query_enhancer) = AutomatonProducer::new(
+        reader,
+        &query,
+        main_store,
+        postings_lists_store,
         synonyms_store,
     )?;
 
     let automaton_producer = automaton_producer.into_iter();
     let mut automatons = Vec::new();
+    let mut timed_out = false;
+    let mut nb_hits = 0;
 
     // aggregate automatons groups by groups after time
     for auts in automaton_producer {
@@ -437,50 +1026,28 @@ where
             &automatons,
             &query_enhancer,
             searchable_attrs.as_ref(),
+            candidates.as_ref(),
+            &excluded,
             main_store,
             postings_lists_store,
+            postings_docids_store,
             documents_fields_counts_store,
+            words_pairs_proximities_store,
         )?;
 
         // stop processing when time is running out
         if let Some(timeout) = timeout {
             if !raw_documents_processed.is_empty() && start_processing.elapsed() > timeout {
+                timed_out = true;
                 break;
             }
         }
 
-        let mut groups = vec![raw_documents.as_mut_slice()];
-
-        'criteria: for criterion in criteria.as_ref() {
-            let tmp_groups = mem::replace(&mut groups, Vec::new());
-            let mut documents_seen = 0;
-
-            for group in tmp_groups {
-                // if this group does not overlap with the requested range,
-                // push it without sorting and splitting it
-                if documents_seen + group.len() < range.start {
-                    documents_seen += group.len();
-                    groups.push(group);
-                    continue;
-                }
-
-                group.sort_unstable_by(|a, b| criterion.evaluate(a, b));
-
-                for group in group.binary_group_by_mut(|a, b| criterion.eq(a, b)) {
-                    documents_seen += group.len();
-                    groups.push(group);
-
-                    // we have sort enough documents if the last document sorted is after
-                    // the end of the requested range, we can continue to the next criterion
-                    if documents_seen >= range.end {
-                        continue 'criteria;
-                    }
-                }
-            }
-        }
+        bucket_sort(&mut raw_documents, &criteria, &range);
 
         // once we classified the documents related to the current
         // automatons we save that as the next valid result
+        nb_hits = raw_documents.len();
         let iter = raw_documents
             .into_iter()
             .skip(range.start)
@@ -491,6 +1058,7 @@ where
         // stop processing when time is running out
         if let Some(timeout) = timeout {
             if start_processing.elapsed() > timeout {
+                timed_out = true;
                 break;
             }
         }
@@ -500,10 +1068,16 @@ where
     // those must be returned
     let documents = raw_documents_processed
         .into_iter()
-        .map(Document::from_raw)
+        .map(|raw| document_from_raw(raw, &criteria, ranking_info))
         .collect();
 
-    Ok(documents)
+    let stats = QueryStats {
+        processing_time: start_processing.elapsed(),
+        timed_out,
+        nb_hits,
+    };
+
+    Ok((documents, stats))
 }
 
 fn raw_query_with_distinct<'c, FI, FD>(
@@ -517,15 +1091,19 @@ fn raw_query_with_distinct<'c, FI, FD>(
     distinct: FD,
     distinct_size: usize,
     timeout: Option<Duration>,
+    ranking_info: bool,
+    candidates: Option<RoaringTreemap>,
 
     criteria: Criteria<'c>,
     searchable_attrs: Option<ReorderedAttrs>,
 
     main_store: store::Main,
     postings_lists_store: store::PostingsLists,
+    postings_docids_store: store::PostingsDocids,
     documents_fields_counts_store: store::DocumentsFieldsCounts,
     synonyms_store: store::Synonyms,
-) -> MResult<Vec<Document>>
+    words_pairs_proximities_store: store::WordsPairsProximities,
+) -> MResult<(Vec<Document>, QueryStats)>
 where
     FI: Fn(DocumentId) -> bool,
     FD: Fn(DocumentId) -> Option<u64>,
@@ -533,16 +1111,26 @@ where
     let start_processing = Instant::now();
     let mut raw_documents_processed = Vec::new();
 
-    let (automaton_producer, query_enhancer) = AutomatonProducer::new(
+    let (query, excluded) = negative_query(
         reader,
         query,
         main_store,
         postings_lists_store,
+        postings_docids_store,
+    )?;
+
+    let (automaton_producer, query_enhancer) = AutomatonProducer::new(
+        reader,
+        &query,
+        main_store,
+        postings_lists_store,
         synonyms_store,
     )?;
 
     let automaton_producer = automaton_producer.into_iter();
     let mut automatons = Vec::new();
+    let mut timed_out = false;
+    let mut nb_hits = 0;
 
     // aggregate automatons groups by groups after time
     for auts in automaton_producer {
@@ -555,14 +1143,19 @@ where
             &automatons,
             &query_enhancer,
             searchable_attrs.as_ref(),
+            candidates.as_ref(),
+            &excluded,
             main_store,
             postings_lists_store,
+            postings_docids_store,
             documents_fields_counts_store,
+            words_pairs_proximities_store,
         )?;
 
         // stop processing when time is running out
         if let Some(timeout) = timeout {
             if !raw_documents_processed.is_empty() && start_processing.elapsed() > timeout {
+                timed_out = true;
                 break;
             }
         }
@@ -643,6 +1236,7 @@ where
         // automatons we save that as the next valid result
         let mut seen = BufferedDistinctMap::new(&mut distinct_map);
         raw_documents_processed.clear();
+        nb_hits = raw_documents.len();
 
         for document in raw_documents.into_iter().skip(distinct_raw_offset) {
             let filter_accepted = match &filter {
@@ -669,6 +1263,7 @@ where
         // stop processing when time is running out
         if let Some(timeout) = timeout {
             if start_processing.elapsed() > timeout {
+                timed_out = true;
                 break;
             }
         }
@@ -678,10 +1273,16 @@ where
     // those must be returned
     let documents = raw_documents_processed
         .into_iter()
-        .map(Document::from_raw)
+        .map(|raw| document_from_raw(raw, &criteria, ranking_info))
         .collect();
 
-    Ok(documents)
+    let stats = QueryStats {
+        processing_time: start_processing.elapsed(),
+        timed_out,
+        nb_hits,
+    };
+
+    Ok((documents, stats))
 }
 
 #[cfg(test)]
@@ -693,13 +1294,15 @@ mod tests {
 
     use fst::{IntoStreamer, Set};
     use meilidb_schema::SchemaAttr;
+    use proptest::prelude::*;
     use sdset::SetBuf;
     use tempfile::TempDir;
 
     use crate::automaton::normalize_str;
-    use crate::database::Database;
+    use crate::database::{Database, DatabaseOptions};
+    use crate::raw_indexer::near_words_pairs;
     use crate::store::Index;
-    use crate::DocIndex;
+    use crate::{DocIndex, PairProximity};
 
     fn set_from_stream<'f, I, S>(stream: I) -> Set
     where
@@ -803,7 +1406,7 @@ mod tests {
     impl<'a> FromIterator<(&'a str, &'a [DocIndex])> for TempDatabase {
         fn from_iter<I: IntoIterator<Item = (&'a str, &'a [DocIndex])>>(iter: I) -> Self {
             let tempdir = TempDir::new().unwrap();
-            let database = Database::open_or_create(&tempdir).unwrap();
+            let database = Database::open_or_create(&tempdir, DatabaseOptions::default()).unwrap();
             let index = database.create_index("default").unwrap();
 
             let env = &database.env;
@@ -812,25 +1415,58 @@ mod tests {
             let mut words_fst = BTreeSet::new();
             let mut postings_lists = HashMap::new();
             let mut fields_counts = HashMap::<_, u64>::new();
+            let mut attributes_positions = HashMap::new();
 
             for (word, indexes) in iter {
                 let word = word.to_lowercase().into_bytes();
                 words_fst.insert(word.clone());
                 postings_lists
-                    .entry(word)
+                    .entry(word.clone())
                     .or_insert_with(Vec::new)
                     .extend_from_slice(indexes);
                 for idx in indexes {
                     fields_counts.insert((idx.document_id, idx.attribute, idx.word_index), 1);
+                    attributes_positions
+                        .entry((idx.document_id, idx.attribute))
+                        .or_insert_with(Vec::new)
+                        .push((idx.word_index, word.clone()));
+                }
+            }
+
+            // generate the words pairs proximities like the indexer does
+            let mut words_pairs_proximities = HashMap::new();
+            for ((docid, attr), mut positions) in attributes_positions {
+                positions.sort_unstable();
+                positions.dedup();
+                for (a, b, proximity) in near_words_pairs(&positions) {
+                    words_pairs_proximities
+                        .entry((a.clone(), b.clone()))
+                        .or_insert_with(Vec::new)
+                        .push(PairProximity::new(docid, attr, proximity));
                 }
             }
 
+            for ((word_a, word_b), mut proximities) in words_pairs_proximities {
+                proximities.sort_unstable();
+                proximities.dedup_by_key(|p| (p.document_id, p.attribute));
+                let proximities = SetBuf::new_unchecked(proximities);
+                index
+                    .words_pairs_proximities
+                    .put_pair_proximities(&mut writer, &word_a, &word_b, &proximities)
+                    .unwrap();
+            }
+
             let words_fst = Set::from_iter(words_fst).unwrap();
 
             index.main.put_words_fst(&mut writer, &words_fst).unwrap();
 
             for (word, postings_list) in postings_lists {
                 let postings_list = SetBuf::from_dirty(postings_list);
+                let docids = postings_list.iter().map(|d| d.document_id.0).collect();
+                index
+                    .postings_docids
+                    .put_postings_docids(&mut writer, &word, &docids)
+                    .unwrap();
                 index
                     .postings_lists
                     .put_postings_list(&mut writer, &word, &postings_list)
@@ -886,6 +1522,408 @@ mod tests {
         assert_matches!(iter.next(), None);
     }
 
+    #[test]
+    fn query_stats_timeout() {
+        let store = TempDatabase::from_iter(vec![
+            ("iphone", &[doc_char_index(0, 0, 0)][..]),
+            ("from", &[doc_char_index(0, 1, 1)][..]),
+            ("apple", &[doc_char_index(0, 2, 2)][..]),
+        ]);
+
+        let env = &store.database.env;
+        let reader = env.read_txn().unwrap();
+
+        let builder = store.query_builder();
+        let (results, stats) = builder
+            .query_with_stats(&reader, "iphone from apple", 0..20)
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(!stats.timed_out);
+
+        let mut builder = store.query_builder();
+        builder.with_fetch_timeout(Duration::from_secs(0));
+        let (results, stats) = builder
+            .query_with_stats(&reader, "iphone from apple", 0..20)
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(stats.timed_out);
+    }
+
+    #[test]
+    fn distinct_size() {
+        let store = TempDatabase::from_iter(vec![(
+            "iphone",
+            &[
+                doc_char_index(0, 0, 0),
+                doc_char_index(1, 0, 0),
+                doc_char_index(2, 0, 0),
+                doc_char_index(3, 0, 0),
+            ][..],
+        )]);
+
+        let env = &store.database.env;
+        let reader = env.read_txn().unwrap();
+
+        // the documents 0, 1 and 2 share the same distinct key, 3 has none
+        let key = |id: DocumentId| if id.0 < 3 { Some(42) } else { None };
+
+        let mut builder = store.query_builder();
+        builder.with_distinct(key, 1);
+        let results = builder.query(&reader, "iphone", 0..20).unwrap();
+        let ids: Vec<_> = results.into_iter().map(|d| d.id.0).collect();
+        assert_eq!(ids, vec![0, 3]);
+
+        let mut builder = store.query_builder();
+        builder.with_distinct(key, 2);
+        let results = builder.query(&reader, "iphone", 0..20).unwrap();
+        let ids: Vec<_> = results.into_iter().map(|d| d.id.0).collect();
+        assert_eq!(ids, vec![0, 1, 3]);
+
+        let mut builder = store.query_builder();
+        builder.with_distinct(key, 2);
+        let results = builder.query(&reader, "iphone", 1..20).unwrap();
+        let ids: Vec<_> = results.into_iter().map(|d| d.id.0).collect();
+        assert_eq!(ids, vec![1, 3]);
+    }
+
+    #[test]
+    fn filter_and_negative_words() {
+        let store = TempDatabase::from_iter(vec![
+            (
+                "iphone",
+                &[
+                    doc_char_index(0, 0, 0),
+                    doc_char_index(1, 0, 0),
+                    doc_char_index(2, 0, 0),
+                    doc_char_index(3, 0, 0),
+                ][..],
+            ),
+            (
+                "case",
+                &[doc_char_index(1, 1, 1), doc_char_index(3, 1, 1)][..],
+            ),
+        ]);
+
+        let env = &store.database.env;
+        let reader = env.read_txn().unwrap();
+
+        let mut builder = store.query_builder();
+        builder.with_filter(|id| id.0 != 2);
+        let results = builder.query(&reader, "iphone", 0..20).unwrap();
+        let mut ids: Vec<_> = results.into_iter().map(|d| d.id.0).collect();
+        ids.sort_unstable();
+        assert_eq!(ids, vec![0, 1, 3]);
+
+        let mut builder = store.query_builder();
+        builder.with_candidates(vec![1, 2].into_iter().collect());
+        let results = builder.query(&reader, "iphone", 0..20).unwrap();
+        let mut ids: Vec<_> = results.into_iter().map(|d| d.id.0).collect();
+        ids.sort_unstable();
+        assert_eq!(ids, vec![1, 2]);
+
+        let builder = store.query_builder();
+        let results = builder.query(&reader, "iphone -Case", 0..20).unwrap();
+        let mut ids: Vec<_> = results.into_iter().map(|d| d.id.0).collect();
+        ids.sort_unstable();
+        assert_eq!(ids, vec![0, 2]);
+
+        let mut builder = store.query_builder();
+        builder.with_candidates(vec![1, 2].into_iter().collect());
+        let results = builder.query(&reader, "iphone -case", 0..20).unwrap();
+        let ids: Vec<_> = results.into_iter().map(|d| d.id.0).collect();
+        assert_eq!(ids, vec![2]);
+
+        let mut builder = store.query_builder();
+        builder.with_filter(|id| id.0 != 2);
+        builder.with_distinct(|_| None, 1);
+        let results = builder.query(&reader, "iphone -case", 0..20).unwrap();
+        let ids: Vec<_> = results.into_iter().map(|d| d.id.0).collect();
+        assert_eq!(ids, vec![0]);
+    }
+
+    #[test]
+    fn split_query_negative_words() {
+        let (query, negative_words) = split_negative_words("iphone  -case");
+        assert_eq!(query, "iphone");
+        assert_eq!(negative_words, vec!["case".to_string()]);
+
+        // a minus sign alone or inside a word is kept in the query
+        let (query, negative_words) = split_negative_words("t-shirt - blue");
+        assert_eq!(query, "t-shirt - blue");
+        assert!(negative_words.is_empty());
+    }
+
+    fn sequential_bucket_sort(
+        raw_documents: &mut [RawDocument],
+        criteria: &Criteria,
+        range: &Range<usize>,
+    ) {
+        let mut groups = vec![raw_documents];
+
+        'criteria: for criterion in criteria.as_ref() {
+            let tmp_groups = mem::replace(&mut groups, Vec::new());
+            let mut documents_seen = 0;
+
+            for group in tmp_groups {
+                if documents_seen + group.len() < range.start {
+                    documents_seen += group.len();
+                    groups.push(group);
+                    continue;
+                }
+
+                group.sort_unstable_by(|a, b| criterion.evaluate(a, b));
+
+                for group in group.binary_group_by_mut(|a, b| criterion.eq(a, b)) {
+                    documents_seen += group.len();
+                    groups.push(group);
+
+                    if documents_seen >= range.end {
+                        continue 'criteria;
+                    }
+                }
+            }
+        }
+    }
+
+    type ArbitraryMatch = (u64, u32, u8, u16, u16, bool);
+
+    fn raw_documents_from_matches(arbitrary_matches: &[ArbitraryMatch]) -> Vec<RawDocument> {
+        let mut matches = Vec::new();
+        let mut highlights = Vec::new();
+        let mut fields_counts = Vec::new();
+
+        for &(id, query_index, distance, attribute, word_index, is_exact) in arbitrary_matches {
+            let id = DocumentId(id);
+            let match_ = TmpMatch {
+                query_index,
+                distance,
+                attribute,
+                word_index,
+                is_exact,
+            };
+            let highlight = Highlight {
+                attribute,
+                char_index: word_index,
+                char_length: 1,
+            };
+
+            matches.push((id, match_));
+            highlights.push((id, highlight));
+            fields_counts.push((id, SchemaAttr(attribute), 10));
+        }
+
+        raw_documents_from(
+            SetBuf::from_dirty(matches),
+            SetBuf::from_dirty(highlights),
+            SetBuf::from_dirty(fields_counts),
+        )
+    }
+
+    proptest! {
+        #[test]
+        fn parallel_bucket_sort(
+            arbitrary_matches in prop::collection::vec(
+                (0u64..60, 0u32..4, 0u8..3, 0u16..4, 0u16..20, any::<bool>()),
+                1..300,
+            ),
+            start in 0usize..80,
+            len in 0usize..40,
+        ) {
+            use crate::criterion::{CriteriaBuilder, NumberOfWords, SumOfTypos, WordsProximity};
+
+            let range = start..start + len;
+
+            // the documents ids break the ties of the default criteria but not of these ones
+            let partial_criteria = CriteriaBuilder::with_capacity(3)
+                .add(SumOfTypos)
+                .add(NumberOfWords)
+                .add(WordsProximity)
+                .build();
+
+            for criteria in &[Criteria::default(), partial_criteria] {
+                let mut expected = raw_documents_from_matches(&arbitrary_matches);
+                sequential_bucket_sort(&mut expected, criteria, &range);
+                let expected: Vec<_> = expected.iter().map(|d| d.id).collect();
+
+                let mut documents = raw_documents_from_matches(&arbitrary_matches);
+                bucket_sort(&mut documents, criteria, &range);
+                let documents: Vec<_> = documents.iter().map(|d| d.id).collect();
+
+                prop_assert_eq!(documents, expected);
+            }
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(32))]
+
+        #[test]
+        fn query_ranges_consistency(
+            words in prop::collection::vec((0usize..4, 0u64..30, 0u16..10), 1..100),
+            start in 0usize..20,
+            len in 0usize..20,
+        ) {
+            let vocabulary = ["iphone", "case", "apple", "phone"];
+
+            let mut postings_lists = HashMap::new();
+            for (word, id, word_index) in words {
+                postings_lists
+                    .entry(vocabulary[word])
+                    .or_insert_with(Vec::new)
+                    .push(doc_char_index(id, word_index, word_index));
+            }
+
+            let store = TempDatabase::from_iter(
+                postings_lists.iter().map(|(word, indexes)| (*word, indexes.as_slice())),
+            );
+
+            let env = &store.database.env;
+            let reader = env.read_txn().unwrap();
+
+            let builder = store.query_builder();
+            let results = builder.query(&reader, "iphone case app", 0..100).unwrap();
+            let expected: Vec<_> = results.into_iter().skip(start).take(len).map(|d| d.id).collect();
+
+            let builder = store.query_builder();
+            let results = builder.query(&reader, "iphone case app", start..start + len).unwrap();
+            let ids: Vec<_> = results.into_iter().map(|d| d.id).collect();
+
+            prop_assert_eq!(ids, expected);
+        }
+    }
+
+    #[test]
+    fn popularity_criterion() {
+        use crate::criterion::{CriteriaBuilder, DocumentId as DocumentIdCriterion, Popularity};
+        use crate::PopularityMap;
+
+        let store = TempDatabase::from_iter(vec![(
+            "iphone",
+            &[doc_char_index(0, 0, 0), doc_char_index(1, 0, 0)][..],
+        )]);
+
+        let mut popularity_map = PopularityMap::default();
+        popularity_map.increase(DocumentId(1), 5);
+
+        let env = &store.database.env;
+        let reader = env.read_txn().unwrap();
+
+        let criteria = CriteriaBuilder::with_capacity(2)
+            .add(Popularity::new(&popularity_map))
+            .add(DocumentIdCriterion)
+            .build();
+        let builder = store.index.query_builder_with_criteria(criteria);
+        let results = builder.query(&reader, "iphone", 0..20).unwrap();
+        let mut iter = results.into_iter();
+
+        assert_matches!(
+            iter.next(),
+            Some(Document {
+                id: DocumentId(1),
+                ..
+            })
+        );
+        assert_matches!(
+            iter.next(),
+            Some(Document {
+                id: DocumentId(0),
+                ..
+            })
+        );
+        assert_matches!(iter.next(), None);
+    }
+
+    #[test]
+    fn ranking_info() {
+        use crate::criterion::{CriteriaBuilder, DocumentId as DocumentIdCriterion};
+        use crate::criterion::{Popularity, SumOfTypos};
+        use crate::{Number, PopularityMap};
+
+        let store = TempDatabase::from_iter(vec![(
+            "iphone",
+            &[doc_char_index(0, 0, 0), doc_char_index(1, 0, 0)][..],
+        )]);
+
+        let mut popularity_map = PopularityMap::default();
+        popularity_map.increase(DocumentId(1), 5);
+
+        let env = &store.database.env;
+        let reader = env.read_txn().unwrap();
+
+        let criteria = || {
+            CriteriaBuilder::with_capacity(3)
+                .add(Popularity::new(&popularity_map))
+                .add(SumOfTypos)
+                .add(DocumentIdCriterion)
+                .build()
+        };
+
+        let builder = store.index.query_builder_with_criteria(criteria());
+        let results = builder.query(&reader, "iphone", 0..20).unwrap();
+        assert!(results.iter().all(|d| d.ranking_info.is_none()));
+
+        let mut builder = store.index.query_builder_with_criteria(criteria());
+        builder.with_ranking_info();
+        let results = builder.query(&reader, "iphone", 0..20).unwrap();
+        let infos: Vec<_> = results.into_iter().map(|d| d.ranking_info).collect();
+
+        let info = |popularity| {
+            Some(vec![
+                Some(Number::Unsigned(popularity)),
+                Some(Number::Unsigned(0)),
+                None,
+            ])
+        };
+        assert_eq!(infos, vec![info(5), info(0)]);
+    }
+
+    #[test]
+    fn words_pairs_proximity() {
+        use crate::criterion::WordsProximity;
+        use crate::criterion::{CriteriaBuilder, DocumentId as DocumentIdCriterion};
+        use crate::Number;
+
+        let other_attribute = DocIndex {
+            attribute: 1,
+            ..doc_index(2, 0)
+        };
+
+        let store = TempDatabase::from_iter(vec![
+            (
+                "soup",
+                &[doc_index(0, 5), doc_index(1, 0), doc_index(2, 0)][..],
+            ),
+            (
+                "day",
+                &[doc_index(0, 0), doc_index(1, 1), other_attribute][..],
+            ),
+        ]);
+
+        let env = &store.database.env;
+        let reader = env.read_txn().unwrap();
+
+        let criteria = CriteriaBuilder::with_capacity(2)
+            .add(WordsProximity)
+            .add(DocumentIdCriterion)
+            .build();
+
+        let mut builder = store.index.query_builder_with_criteria(criteria);
+        builder.with_ranking_info();
+        let results = builder.query(&reader, "soup day", 0..20).unwrap();
+        let results: Vec<_> = results
+            .into_iter()
+            .map(|d| (d.id, d.ranking_info.unwrap()[0].clone()))
+            .collect();
+
+        // the words found in different attributes have the maximum proximity
+        let expected = vec![
+            (DocumentId(1), Some(Number::Unsigned(1))),
+            (DocumentId(0), Some(Number::Unsigned(6))),
+            (DocumentId(2), Some(Number::Unsigned(8))),
+        ];
+        assert_eq!(results, expected);
+    }
+
     #[test]
     fn simple_synonyms() {
         let mut store = TempDatabase::from_iter(vec![("hello", &[doc_index(0, 0)][..])]);
@@ -0,0 +1,313 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Mutex;
+
+use lru::LruCache;
+use serde::Serialize;
+
+use crate::Document;
+
+/// The parameters of a search that determine the documents it returns.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct QueryCacheKey {
+    index: String,
+    query: String,
+    range: Range<usize>,
+    filters: Vec<String>,
+    criteria: u64,
+}
+
+impl QueryCacheKey {
+    /// The criteria fingerprint must change with anything else that
+    /// changes the order of the documents: the ranking rules, the
+    /// searchable attributes, the distinct attribute...
+    pub fn new(
+        index: &str,
+        query: &str,
+        range: Range<usize>,
+        filters: Vec<String>,
+        criteria: u64,
+    ) -> QueryCacheKey {
+        // the query words are lowercased and the
+        // spaces between them are not significant
+        let query = query
+            .split_whitespace()
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        QueryCacheKey {
+            index: index.to_owned(),
+            query,
+            range,
+            filters,
+            criteria,
+        }
+    }
+}
+
+/// The results of a search kept in the cache.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedSearch {
+    pub documents: Vec<Document>,
+    /// The number of documents matching the query, not only the returned ones.
+    pub nb_hits: usize,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryCacheStats {
+    /// The number of searches of the index in the cache.
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+#[derive(Default)]
+struct IndexCache {
+    version: u64,
+    /// The number of searches of the current version in the cache.
+    entries: usize,
+    stats: QueryCacheStats,
+}
+
+struct Inner {
+    /// The searches with the version of the index they have been cached at,
+    /// the ones of an older version are unreachable and evicted lazily.
+    entries: LruCache<QueryCacheKey, (u64, CachedSearch)>,
+    indexes: HashMap<String, IndexCache>,
+}
+
+impl Inner {
+    fn current_version(&self, index: &str) -> u64 {
+        self.indexes.get(index).map_or(0, |cache| cache.version)
+    }
+
+    fn forget(&mut self, key: &QueryCacheKey, version: u64) {
+        if let Some(cache) = self.indexes.get_mut(&key.index) {
+            if cache.version == version {
+                cache.entries -= 1;
+            }
+        }
+    }
+}
+
+/// A cache of the documents returned by the searches, the least recently used
+/// searches are evicted when the cache is full and the searches of an index
+/// are invalidated every time an update of this index is committed.
+pub struct QueryCache {
+    size: usize,
+    inner: Mutex<Inner>,
+}
+
+impl QueryCache {
+    /// Creates a cache keeping at most this number of searches, the cache is disabled when zero.
+    pub fn new(size: usize) -> QueryCache {
+        let inner = Inner {
+            entries: LruCache::new(size),
+            indexes: HashMap::new(),
+        };
+
+        QueryCache {
+            size,
+            inner: Mutex::new(inner),
+        }
+    }
+
+    /// Returns the current version of the searches of the index, it must be read before
+    /// the transaction used by a search is created and given back when inserting its results.
+    pub fn version(&self, index: &str) -> u64 {
+        let inner = self.inner.lock().unwrap();
+        inner.current_version(index)
+    }
+
+    pub fn get(&self, key: &QueryCacheKey) -> Option<CachedSearch> {
+        if self.size == 0 {
+            return None;
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        let current_version = inner.current_version(&key.index);
+
+        let search = match inner.entries.get(key) {
+            Some((version, search)) if *version == current_version => Some(search.clone()),
+            Some(_) => {
+                inner.entries.pop(key);
+                None
+            }
+            None => None,
+        };
+
+        let cache = inner.indexes.entry(key.index.clone()).or_default();
+        match search {
+            Some(_) => cache.stats.hits += 1,
+            None => cache.stats.misses += 1,
+        }
+
+        search
+    }
+
+    /// Inserts the results of a search, they are ignored if the index
+    /// has been updated since the given version has been read.
+    pub fn insert(&self, key: QueryCacheKey, version: u64, search: CachedSearch) {
+        if self.size == 0 {
+            return;
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        if inner.current_version(&key.index) != version {
+            return;
+        }
+
+        // the replaced or evicted search is no more counted in the entries of its index
+        if let Some((old_version, _)) = inner.entries.pop(&key) {
+            inner.forget(&key, old_version);
+        } else if inner.entries.len() == self.size {
+            if let Some((old_key, (old_version, _))) = inner.entries.pop_lru() {
+                inner.forget(&old_key, old_version);
+            }
+        }
+
+        inner.indexes.entry(key.index.clone()).or_default().entries += 1;
+        inner.entries.put(key, (version, search));
+    }
+
+    /// Invalidates the searches of the index, must be called every time
+    /// the documents or the settings of the index are modified.
+    pub fn invalidate(&self, index: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        let cache = inner.indexes.entry(index.to_owned()).or_default();
+        cache.version += 1;
+        cache.entries = 0;
+    }
+
+    /// Invalidates the searches and resets the statistics of a deleted or renamed index,
+    /// the version is kept for the searches started before the index was removed.
+    pub fn remove_index(&self, index: &str) {
+        self.invalidate(index);
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(cache) = inner.indexes.get_mut(index) {
+            cache.stats = QueryCacheStats::default();
+        }
+    }
+
+    pub fn stats(&self, index: &str) -> QueryCacheStats {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .indexes
+            .get(index)
+            .map_or_else(QueryCacheStats::default, |cache| QueryCacheStats {
+                entries: cache.entries,
+                ..cache.stats
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DocumentId;
+
+    fn documents(ids: &[u64]) -> CachedSearch {
+        let documents = ids
+            .iter()
+            .map(|id| Document {
+                id: DocumentId(*id),
+                highlights: Vec::new(),
+                ranking_info: None,
+                matches: Vec::new(),
+            })
+            .collect();
+
+        CachedSearch {
+            documents,
+            nb_hits: ids.len(),
+        }
+    }
+
+    fn key(index: &str, query: &str) -> QueryCacheKey {
+        QueryCacheKey::new(index, query, 0..20, Vec::new(), 0)
+    }
+
+    #[test]
+    fn hits_and_misses() {
+        let cache = QueryCache::new(10);
+
+        let version = cache.version("movies");
+        assert_eq!(cache.get(&key("movies", "star wars")), None);
+        cache.insert(key("movies", "star wars"), version, documents(&[1, 2]));
+
+        let results = cache.get(&key("movies", "  Star   WARS "));
+        assert_eq!(results, Some(documents(&[1, 2])));
+        assert_eq!(cache.get(&key("books", "star wars")), None);
+
+        let expected = QueryCacheStats {
+            entries: 1,
+            hits: 1,
+            misses: 1,
+        };
+        assert_eq!(cache.stats("movies"), expected);
+    }
+
+    #[test]
+    fn least_recently_used_eviction() {
+        let cache = QueryCache::new(2);
+
+        cache.insert(key("movies", "a"), 0, documents(&[1]));
+        cache.insert(key("movies", "b"), 0, documents(&[2]));
+        assert!(cache.get(&key("movies", "a")).is_some());
+        cache.insert(key("movies", "c"), 0, documents(&[3]));
+
+        assert!(cache.get(&key("movies", "a")).is_some());
+        assert!(cache.get(&key("movies", "b")).is_none());
+        assert!(cache.get(&key("movies", "c")).is_some());
+        assert_eq!(cache.stats("movies").entries, 2);
+    }
+
+    #[test]
+    fn invalidation() {
+        let cache = QueryCache::new(10);
+
+        cache.insert(key("movies", "a"), 0, documents(&[1]));
+        cache.insert(key("books", "a"), 0, documents(&[2]));
+
+        // a search started before the update must not be cached
+        let version = cache.version("movies");
+        cache.invalidate("movies");
+        cache.insert(key("movies", "b"), version, documents(&[3]));
+
+        assert!(cache.get(&key("movies", "a")).is_none());
+        assert!(cache.get(&key("movies", "b")).is_none());
+        assert!(cache.get(&key("books", "a")).is_some());
+
+        let version = cache.version("movies");
+        cache.insert(key("movies", "b"), version, documents(&[3]));
+        assert!(cache.get(&key("movies", "b")).is_some());
+        assert_eq!(cache.stats("movies").entries, 1);
+        assert_eq!(cache.stats("books").entries, 1);
+    }
+
+    #[test]
+    fn invalidated_searches_are_evicted_first() {
+        let cache = QueryCache::new(2);
+
+        cache.insert(key("movies", "a"), 0, documents(&[1]));
+        cache.insert(key("books", "a"), 0, documents(&[2]));
+        cache.invalidate("movies");
+
+        // the invalidated search is the least recently used one
+        cache.insert(key("movies", "b"), 1, documents(&[3]));
+        assert!(cache.get(&key("books", "a")).is_some());
+        assert!(cache.get(&key("movies", "b")).is_some());
+        assert_eq!(cache.stats("movies").entries, 1);
+        assert_eq!(cache.stats("books").entries, 1);
+    }
+
+    #[test]
+    fn disabled() {
+        let cache = QueryCache::new(0);
+        cache.insert(key("movies", "a"), 0, documents(&[1]));
+        assert!(cache.get(&key("movies", "a")).is_none());
+        assert_eq!(cache.stats("movies"), QueryCacheStats::default());
+    }
+}
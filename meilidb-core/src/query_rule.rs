@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+
+/// How the pattern of a rule must match the query.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Anchoring {
+    /// The query must be equal to the pattern.
+    Is,
+    /// The query must start with the pattern.
+    StartsWith,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct RuleCondition {
+    pub pattern: String,
+    pub anchoring: Anchoring,
+}
+
+impl RuleCondition {
+    /// The query and the pattern must be normalized the same way.
+    pub fn matches(&self, query: &str) -> bool {
+        match self.anchoring {
+            Anchoring::Is => query == self.pattern,
+            Anchoring::StartsWith => query.starts_with(&self.pattern),
+        }
+    }
+}
+
+/// A document to move at a given position of the results, the first position is zero.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct PinnedDocument {
+    pub id: String,
+    pub position: usize,
+}
+
+/// Pins and hides documents in the results of the queries matching its condition,
+/// the documents are referenced by their identifier.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct QueryRule {
+    pub condition: RuleCondition,
+    #[serde(default)]
+    pub pinned: Vec<PinnedDocument>,
+    #[serde(default)]
+    pub hidden: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn condition_matches() {
+        let is = RuleCondition {
+            pattern: String::from("iphone"),
+            anchoring: Anchoring::Is,
+        };
+        assert!(is.matches("iphone"));
+        assert!(!is.matches("iphone x"));
+
+        let starts_with = RuleCondition {
+            pattern: String::from("iphone"),
+            anchoring: Anchoring::StartsWith,
+        };
+        assert!(starts_with.matches("iphone"));
+        assert!(starts_with.matches("iphone x"));
+        assert!(!starts_with.matches("my iphone"));
+    }
+}
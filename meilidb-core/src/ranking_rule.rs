@@ -0,0 +1,265 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+use meilidb_schema::Schema;
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+use crate::criterion::*;
+use crate::error::InvalidSetting;
+use crate::{PopularityMap, RankedMap};
+
+/// A rule used to sort the documents, written `typo` or `desc(release_date)`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RankingRule {
+    Typo,
+    Words,
+    Proximity,
+    Attribute,
+    WordsPosition,
+    Exactness,
+    Popularity,
+    Asc(String),
+    Desc(String),
+}
+
+impl RankingRule {
+    /// The rules applied when an index does not define its own.
+    pub fn default_rules() -> Vec<RankingRule> {
+        vec![
+            RankingRule::Typo,
+            RankingRule::Words,
+            RankingRule::Proximity,
+            RankingRule::Attribute,
+            RankingRule::WordsPosition,
+            RankingRule::Exactness,
+        ]
+    }
+
+    /// The attribute this rule sorts the documents by, if any.
+    pub fn attribute(&self) -> Option<&str> {
+        match self {
+            RankingRule::Asc(attribute) | RankingRule::Desc(attribute) => Some(attribute),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for RankingRule {
+    type Err = InvalidSetting;
+
+    fn from_str(s: &str) -> Result<RankingRule, InvalidSetting> {
+        let rule = match s.trim() {
+            "typo" => RankingRule::Typo,
+            "words" => RankingRule::Words,
+            "proximity" => RankingRule::Proximity,
+            "attribute" => RankingRule::Attribute,
+            "wordsPosition" => RankingRule::WordsPosition,
+            "exactness" => RankingRule::Exactness,
+            "popularity" => RankingRule::Popularity,
+            s => {
+                let (function, attribute) = match (s.find('('), s.ends_with(')')) {
+                    (Some(open), true) => (&s[..open], s[open + 1..s.len() - 1].trim()),
+                    _ => return Err(InvalidSetting::InvalidRankingRule(s.to_owned())),
+                };
+
+                if attribute.is_empty() || attribute.contains(|c| c == '(' || c == ')') {
+                    return Err(InvalidSetting::InvalidRankingRule(s.to_owned()));
+                }
+
+                match function.trim() {
+                    "asc" => RankingRule::Asc(attribute.to_owned()),
+                    "desc" => RankingRule::Desc(attribute.to_owned()),
+                    _ => return Err(InvalidSetting::InvalidRankingRule(s.to_owned())),
+                }
+            }
+        };
+
+        Ok(rule)
+    }
+}
+
+impl fmt::Display for RankingRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RankingRule::Typo => f.write_str("typo"),
+            RankingRule::Words => f.write_str("words"),
+            RankingRule::Proximity => f.write_str("proximity"),
+            RankingRule::Attribute => f.write_str("attribute"),
+            RankingRule::WordsPosition => f.write_str("wordsPosition"),
+            RankingRule::Exactness => f.write_str("exactness"),
+            RankingRule::Popularity => f.write_str("popularity"),
+            RankingRule::Asc(attribute) => write!(f, "asc({})", attribute),
+            RankingRule::Desc(attribute) => write!(f, "desc({})", attribute),
+        }
+    }
+}
+
+impl Serialize for RankingRule {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for RankingRule {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<RankingRule, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        RankingRule::from_str(&s).map_err(de::Error::custom)
+    }
+}
+
+/// Checks that the rules are not repeated and only sort
+/// the documents by the ranked attributes of the schema.
+pub fn validate_ranking_rules(
+    rules: &[RankingRule],
+    schema: Option<&Schema>,
+) -> Result<(), InvalidSetting> {
+    let mut seen = HashSet::new();
+    for rule in rules {
+        if !seen.insert(rule) {
+            return Err(InvalidSetting::DuplicateRankingRule(rule.to_string()));
+        }
+
+        if let Some(name) = rule.attribute() {
+            let attribute = schema.and_then(|s| s.attribute(name).map(|a| (s, a)));
+            match attribute {
+                Some((schema, attr)) if schema.props(attr).is_ranked() => (),
+                Some(_) => return Err(InvalidSetting::AttributeNotRanked(name.to_owned())),
+                None => return Err(InvalidSetting::UnknownAttribute(name.to_owned())),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Builds the criteria corresponding to the rules, the document id
+/// is always used as the last criterion to break the ties.
+///
+/// The attribute rule uses the weights when there is some, these must be given
+/// for the attributes as they are seen by the query builder, after the reordering
+/// done by the searchable attributes.
+pub fn ranking_rules_criteria<'a>(
+    rules: &[RankingRule],
+    schema: &Schema,
+    ranked_map: &'a RankedMap,
+    popularity_map: &'a PopularityMap,
+    attributes_weights: &HashMap<u16, u16>,
+) -> Result<Criteria<'a>, InvalidSetting> {
+    validate_ranking_rules(rules, Some(schema))?;
+
+    let mut builder = CriteriaBuilder::with_capacity(rules.len() + 1);
+    for rule in rules {
+        match rule {
+            RankingRule::Typo => builder.push(SumOfTypos),
+            RankingRule::Words => builder.push(NumberOfWords),
+            RankingRule::Proximity => builder.push(WordsProximity),
+            RankingRule::Attribute if attributes_weights.is_empty() => {
+                builder.push(SumOfWordsAttribute)
+            }
+            RankingRule::Attribute => {
+                builder.push(WeightedWordsAttribute::new(attributes_weights.clone()))
+            }
+            RankingRule::WordsPosition => builder.push(SumOfWordsPosition),
+            RankingRule::Exactness => builder.push(Exact),
+            RankingRule::Popularity => builder.push(Popularity::new(popularity_map)),
+            RankingRule::Asc(attribute) => {
+                let sort = SortByAttr::lower_is_better(ranked_map, schema, attribute);
+                builder.push(sort.map_err(|e| sort_error(e, attribute))?);
+            }
+            RankingRule::Desc(attribute) => {
+                let sort = SortByAttr::higher_is_better(ranked_map, schema, attribute);
+                builder.push(sort.map_err(|e| sort_error(e, attribute))?);
+            }
+        }
+    }
+    builder.push(DocumentId);
+
+    Ok(builder.build())
+}
+
+fn sort_error(error: SortByAttrError, attribute: &str) -> InvalidSetting {
+    match error {
+        SortByAttrError::AttributeNotFound => {
+            InvalidSetting::UnknownAttribute(attribute.to_owned())
+        }
+        SortByAttrError::AttributeNotRegisteredForRanking => {
+            InvalidSetting::AttributeNotRanked(attribute.to_owned())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use meilidb_schema::{SchemaBuilder, DISPLAYED, INDEXED, RANKED};
+
+    #[test]
+    fn parse_and_display() {
+        let rules = &[
+            "typo",
+            "words",
+            "proximity",
+            "attribute",
+            "wordsPosition",
+            "exactness",
+            "popularity",
+            "asc(price)",
+            "desc(release_date)",
+        ];
+
+        for rule in rules {
+            let parsed = RankingRule::from_str(rule).unwrap();
+            assert_eq!(&parsed.to_string(), rule);
+        }
+
+        assert_eq!(
+            RankingRule::from_str(" desc( release_date ) "),
+            Ok(RankingRule::Desc(String::from("release_date")))
+        );
+
+        for invalid in &[
+            "typos",
+            "desc()",
+            "desc(price",
+            "sort(price)",
+            "asc((price))",
+        ] {
+            let error = InvalidSetting::InvalidRankingRule(invalid.trim().to_owned());
+            assert_eq!(RankingRule::from_str(invalid), Err(error));
+        }
+    }
+
+    #[test]
+    fn criteria_errors() {
+        let mut builder = SchemaBuilder::with_identifier("id");
+        builder.new_attribute("id", DISPLAYED);
+        builder.new_attribute("title", DISPLAYED | INDEXED);
+        builder.new_attribute("release_date", DISPLAYED | RANKED);
+        let schema = builder.build();
+
+        let ranked_map = RankedMap::default();
+        let popularity_map = PopularityMap::default();
+        let weights = HashMap::new();
+        let criteria = |rules: &[RankingRule]| {
+            ranking_rules_criteria(rules, &schema, &ranked_map, &popularity_map, &weights)
+                .map(|criteria| criteria.as_ref().len())
+        };
+
+        let rules = &[RankingRule::Typo, RankingRule::Desc("release_date".into())];
+        assert_eq!(criteria(rules), Ok(3));
+
+        let rules = &[RankingRule::Typo, RankingRule::Typo];
+        let error = InvalidSetting::DuplicateRankingRule(String::from("typo"));
+        assert_eq!(criteria(rules), Err(error));
+
+        let rules = &[RankingRule::Asc("title".into())];
+        let error = InvalidSetting::AttributeNotRanked(String::from("title"));
+        assert_eq!(criteria(rules), Err(error));
+
+        let rules = &[RankingRule::Asc("price".into())];
+        let error = InvalidSetting::UnknownAttribute(String::from("price"));
+        assert_eq!(criteria(rules), Err(error));
+    }
+}
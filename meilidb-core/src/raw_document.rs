@@ -13,6 +13,9 @@ pub struct RawDocument {
     pub matches: SharedMatches,
     pub highlights: Vec<Highlight>,
     pub fields_counts: SetBuf<(SchemaAttr, u64)>,
+    /// The proximity of the query words computed from
+    /// the words pairs proximities generated at index time.
+    pub words_proximity: Option<u16>,
 }
 
 impl RawDocument {
@@ -137,6 +140,7 @@ pub fn raw_documents_from(
                 matches,
                 highlights,
                 fields_counts,
+                words_proximity: None,
             }
         })
         .collect()
@@ -1,11 +1,15 @@
 use std::collections::{BTreeMap, HashMap};
 use std::convert::TryFrom;
 
-use crate::{DocIndex, DocumentId};
+use crate::criterion::{index_proximity, MAX_DISTANCE};
+use crate::store::filter_values;
+use crate::{DocIndex, DocumentId, PairProximity};
 use deunicode::deunicode_with_tofu;
 use meilidb_schema::SchemaAttr;
 use meilidb_tokenizer::{is_cjk, SeqTokenizer, Token, Tokenizer};
+use roaring::RoaringTreemap;
 use sdset::SetBuf;
+use serde_json::Value;
 
 type Word = Vec<u8>; // TODO make it be a SmallVec
 
@@ -14,11 +18,15 @@ pub struct RawIndexer {
     stop_words: fst::Set,
     words_doc_indexes: BTreeMap<Word, Vec<DocIndex>>,
     docs_words: HashMap<DocumentId, Vec<Word>>,
+    words_pairs_proximities: HashMap<(Word, Word), Vec<PairProximity>>,
+    filters_docids: BTreeMap<(SchemaAttr, String), RoaringTreemap>,
 }
 
 pub struct Indexed {
     pub words_doc_indexes: BTreeMap<Word, SetBuf<DocIndex>>,
     pub docs_words: HashMap<DocumentId, fst::Set>,
+    pub words_pairs_proximities: BTreeMap<(Word, Word), SetBuf<PairProximity>>,
+    pub filters_docids: BTreeMap<(SchemaAttr, String), RoaringTreemap>,
 }
 
 impl RawIndexer {
@@ -32,11 +40,25 @@ impl RawIndexer {
             stop_words,
             words_doc_indexes: BTreeMap::new(),
             docs_words: HashMap::new(),
+            words_pairs_proximities: HashMap::new(),
+            filters_docids: BTreeMap::new(),
+        }
+    }
+
+    /// Registers the values of an attribute of the document that filters can match,
+    /// every attribute is registered, whether it is indexed or not.
+    pub fn index_filter_values(&mut self, id: DocumentId, attr: SchemaAttr, value: &Value) {
+        for value in filter_values(value) {
+            self.filters_docids
+                .entry((attr, value))
+                .or_insert_with(RoaringTreemap::new)
+                .insert(id.0);
         }
     }
 
     pub fn index_text(&mut self, id: DocumentId, attr: SchemaAttr, text: &str) -> usize {
         let mut number_of_words = 0;
+        let mut positions = Vec::new();
         let lowercase_text = text.to_lowercase();
         let deunicoded = deunicode_with_tofu(&lowercase_text, "");
 
@@ -61,6 +83,7 @@ impl RawIndexer {
                     &self.stop_words,
                     &mut self.words_doc_indexes,
                     &mut self.docs_words,
+                    &mut positions,
                 );
 
                 if !must_continue {
@@ -71,6 +94,8 @@ impl RawIndexer {
             }
         }
 
+        self.index_pairs(id, attr, positions);
+
         number_of_words
     }
 
@@ -81,6 +106,7 @@ impl RawIndexer {
     {
         // TODO serialize this to one call to the SeqTokenizer loop
 
+        let mut positions = Vec::new();
         let lowercased: Vec<_> = iter.into_iter().map(str::to_lowercase).collect();
         let iter = lowercased.iter().map(|t| t.as_str());
 
@@ -93,6 +119,7 @@ impl RawIndexer {
                 &self.stop_words,
                 &mut self.words_doc_indexes,
                 &mut self.docs_words,
+                &mut positions,
             );
 
             if !must_continue {
@@ -125,12 +152,29 @@ impl RawIndexer {
                 &self.stop_words,
                 &mut self.words_doc_indexes,
                 &mut self.docs_words,
+                &mut positions,
             );
 
             if !must_continue {
                 break;
             }
         }
+
+        self.index_pairs(id, attr, positions);
+    }
+
+    /// Registers the proximities of the pairs of words found in an attribute of the document.
+    fn index_pairs(&mut self, id: DocumentId, attr: SchemaAttr, mut positions: Vec<(u16, Word)>) {
+        positions.sort_unstable();
+        positions.dedup();
+
+        for (a, b, proximity) in near_words_pairs(&positions) {
+            let proximity = PairProximity::new(id, attr.0, proximity);
+            self.words_pairs_proximities
+                .entry((a.clone(), b.clone()))
+                .or_insert_with(Vec::new)
+                .push(proximity);
+        }
     }
 
     pub fn build(self) -> Indexed {
@@ -150,13 +194,53 @@ impl RawIndexer {
             })
             .collect();
 
+        // only keep the lowest proximity of each attribute of the documents
+        let words_pairs_proximities = self
+            .words_pairs_proximities
+            .into_iter()
+            .map(|(pair, mut proximities)| {
+                proximities.sort_unstable();
+                proximities.dedup_by_key(|p| (p.document_id, p.attribute));
+                (pair, SetBuf::new_unchecked(proximities))
+            })
+            .collect();
+
         Indexed {
             words_doc_indexes,
             docs_words,
+            words_pairs_proximities,
+            filters_docids: self.filters_docids,
         }
     }
 }
 
+/// Returns the pairs of words of an attribute that are near enough from each other
+/// along with their proximity, the others pairs have the maximum proximity.
+///
+/// The pairs are only returned in the order of the words in the attribute, the proximity
+/// of the reversed pair is the one of the pair plus one and is computed when searching.
+///
+/// The positions must be sorted, the words at the same position are variants of the same word.
+pub(crate) fn near_words_pairs(positions: &[(u16, Word)]) -> Vec<(&Word, &Word, u16)> {
+    let mut pairs = Vec::new();
+
+    for (i, (lwi, lword)) in positions.iter().enumerate() {
+        for (rwi, rword) in &positions[i + 1..] {
+            if rwi - lwi >= MAX_DISTANCE {
+                break;
+            }
+
+            if lwi == rwi {
+                continue;
+            }
+
+            pairs.push((lword, rword, index_proximity(*lwi, *rwi)));
+        }
+    }
+
+    pairs
+}
+
 fn index_token(
     token: Token,
     id: DocumentId,
@@ -165,6 +249,7 @@ fn index_token(
     stop_words: &fst::Set,
     words_doc_indexes: &mut BTreeMap<Word, Vec<DocIndex>>,
     docs_words: &mut HashMap<DocumentId, Vec<Word>>,
+    positions: &mut Vec<(u16, Word)>,
 ) -> bool {
     if token.word_index >= word_limit {
         return false;
@@ -178,7 +263,11 @@ fn index_token(
                     .entry(word.clone())
                     .or_insert_with(Vec::new)
                     .push(docindex);
-                docs_words.entry(id).or_insert_with(Vec::new).push(word);
+                docs_words
+                    .entry(id)
+                    .or_insert_with(Vec::new)
+                    .push(word.clone());
+                positions.push((docindex.word_index, word));
             }
             None => return false,
         }
@@ -255,6 +344,45 @@ mod tests {
             .is_some());
     }
 
+    #[test]
+    fn words_pairs_proximities() {
+        let stop_words = fst::Set::from_iter(vec!["the"]).unwrap();
+        let mut indexer = RawIndexer::new(stop_words);
+
+        let docid = DocumentId(0);
+        let attr = SchemaAttr(0);
+        let text = "The soup of the day, and the soup of the night";
+        indexer.index_text(docid, attr, text);
+
+        let Indexed {
+            words_pairs_proximities,
+            ..
+        } = indexer.build();
+
+        let proximity = |a: &str, b: &str| {
+            let pair = (a.as_bytes().to_vec(), b.as_bytes().to_vec());
+            words_pairs_proximities
+                .get(&pair)
+                .map(|proximities| proximities.as_slice().to_vec())
+        };
+
+        // only the lowest proximity of the attribute is kept
+        let expected = vec![PairProximity::new(docid, 0, 1)];
+        assert_eq!(proximity("soup", "of"), Some(expected));
+
+        // the reversed pairs are not stored
+        assert_eq!(proximity("of", "soup"), None);
+        assert_eq!(proximity("day", "soup"), None);
+
+        // the words are too far from each other, separated by a comma
+        assert_eq!(proximity("day", "night"), None);
+
+        // the stop words are not indexed but keep their positions
+        let expected = vec![PairProximity::new(docid, 0, 3)];
+        assert_eq!(proximity("soup", "day"), Some(expected));
+        assert_eq!(proximity("the", "soup"), None);
+    }
+
     #[test]
     fn basic_stop_words() {
         let stop_words = sdset::SetBuf::from_dirty(vec!["l", "j", "ai", "de"]);
@@ -283,4 +411,32 @@ mod tests {
             .get(&"l’éteindre".to_owned().into_bytes())
             .is_some());
     }
+
+    #[test]
+    fn filters_values() {
+        use serde_json::json;
+
+        let mut indexer = RawIndexer::new(fst::Set::default());
+
+        let attr = SchemaAttr(0);
+        indexer.index_filter_values(DocumentId(0), attr, &json!("Black"));
+        indexer.index_filter_values(DocumentId(1), attr, &json!(["black", "White"]));
+        indexer.index_filter_values(DocumentId(2), attr, &json!(42));
+        indexer.index_filter_values(DocumentId(3), attr, &json!(true));
+        indexer.index_filter_values(DocumentId(4), attr, &json!(null));
+
+        let Indexed { filters_docids, .. } = indexer.build();
+        let docids = |value: &str| {
+            let docids = filters_docids.get(&(attr, value.to_owned()));
+            docids.map(|docids| docids.iter().collect::<Vec<_>>())
+        };
+
+        // the strings of the arrays are not lowercased, like when they are filtered
+        assert_eq!(docids("black"), Some(vec![0, 1]));
+        assert_eq!(docids("White"), Some(vec![1]));
+        assert_eq!(docids("white"), None);
+        assert_eq!(docids("42"), Some(vec![2]));
+        assert_eq!(docids("true"), Some(vec![3]));
+        assert_eq!(filters_docids.len(), 4);
+    }
 }
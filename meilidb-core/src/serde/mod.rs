@@ -27,7 +27,7 @@ use std::{error::Error, fmt};
 use serde::ser;
 use serde_json::Error as SerdeJsonError;
 
-use crate::ParseNumberError;
+use crate::{ErrorCode, ParseNumberError};
 
 #[derive(Debug)]
 pub enum SerializerError {
@@ -42,6 +42,24 @@ pub enum SerializerError {
     Custom(String),
 }
 
+impl SerializerError {
+    /// The code of this error as it is exposed to the users, documents
+    /// that cannot be serialized are invalid documents sent by the users.
+    pub fn error_code(&self) -> ErrorCode {
+        match self {
+            SerializerError::DocumentIdNotFound => ErrorCode::MissingDocumentId,
+            SerializerError::InvalidDocumentIdType => ErrorCode::InvalidDocumentId,
+            SerializerError::Zlmdb(_) => ErrorCode::Internal,
+            SerializerError::SerdeJson(_)
+            | SerializerError::ParseNumber(_)
+            | SerializerError::UnserializableType { .. }
+            | SerializerError::UnindexableType { .. }
+            | SerializerError::UnrankableType { .. }
+            | SerializerError::Custom(_) => ErrorCode::InvalidDocument,
+        }
+    }
+}
+
 impl ser::Error for SerializerError {
     fn custom<T: fmt::Display>(msg: T) -> Self {
         SerializerError::Custom(msg.to_string())
@@ -313,6 +313,9 @@ where
     let serialized = serde_json::to_vec(value)?;
     document_store.put_document_field(txn, document_id, attribute, &serialized)?;
 
+    let stored: serde_json::Value = serde_json::from_slice(&serialized)?;
+    indexer.index_filter_values(document_id, attribute, &stored);
+
     if props.is_indexed() {
         let indexer = Indexer {
             attribute,
@@ -0,0 +1,220 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use meilidb_schema::Schema;
+use serde::{Deserialize, Serialize};
+
+use crate::error::InvalidSetting;
+use crate::ranking_rule::{validate_ranking_rules, RankingRule};
+
+/// The version of the layout of the stored settings, it must be increased every time
+/// the `Settings` struct changes and the settings stored with an older layout migrated.
+pub(crate) const SETTINGS_VERSION: u32 = 1;
+
+/// The settings of an index, a setting that is not defined
+/// keeps its current value when the settings are updated.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Settings {
+    /// The words ignored in the documents and in the queries.
+    pub stop_words: Option<BTreeSet<String>>,
+    /// The rules used to sort the documents, in order.
+    pub ranking_rules: Option<Vec<RankingRule>>,
+    /// The attribute used to keep a single document for each of its values.
+    pub distinct_field: Option<String>,
+    /// The boosts of the attributes used by the attribute ranking rule,
+    /// the attributes not listed have a weight of one.
+    pub attributes_weights: Option<BTreeMap<String, u16>>,
+}
+
+impl Settings {
+    /// Replaces the settings with the ones defined in `other`.
+    pub fn merge(&mut self, other: Settings) {
+        if other.stop_words.is_some() {
+            self.stop_words = other.stop_words;
+        }
+        if other.ranking_rules.is_some() {
+            self.ranking_rules = other.ranking_rules;
+        }
+        if other.distinct_field.is_some() {
+            self.distinct_field = other.distinct_field;
+        }
+        if other.attributes_weights.is_some() {
+            self.attributes_weights = other.attributes_weights;
+        }
+    }
+
+    /// Checks that the ranking rules, the distinct field and
+    /// the attributes weights only reference the attributes of the schema.
+    pub fn validate(&self, schema: Option<&Schema>) -> Result<(), InvalidSetting> {
+        if let Some(ranking_rules) = &self.ranking_rules {
+            validate_ranking_rules(ranking_rules, schema)?;
+        }
+
+        if let Some(distinct_field) = &self.distinct_field {
+            if schema.and_then(|s| s.attribute(distinct_field)).is_none() {
+                return Err(InvalidSetting::UnknownAttribute(distinct_field.clone()));
+            }
+        }
+
+        if let Some(attributes_weights) = &self.attributes_weights {
+            for name in attributes_weights.keys() {
+                if schema.and_then(|s| s.attribute(name)).is_none() {
+                    return Err(InvalidSetting::UnknownAttribute(name.clone()));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Copy, Clone, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum LegacyRankingOrdering {
+    Asc,
+    Dsc,
+}
+
+/// The settings as the HTTP server stored them in the customs of the indexes,
+/// the builtin rules were named like `_sum_of_typos` and could be mixed with
+/// the names of the attributes to sort by.
+#[derive(Debug, Deserialize)]
+pub(crate) struct LegacySettings {
+    stop_words: Option<HashSet<String>>,
+    ranking_order: Option<Vec<String>>,
+    distinct_field: Option<String>,
+    ranking_rules: Option<HashMap<String, LegacyRankingOrdering>>,
+}
+
+impl From<LegacySettings> for Settings {
+    fn from(legacy: LegacySettings) -> Settings {
+        let LegacySettings {
+            stop_words,
+            ranking_order,
+            distinct_field,
+            ranking_rules,
+        } = legacy;
+
+        let sort_rule = |name: &str, order: LegacyRankingOrdering| match order {
+            LegacyRankingOrdering::Asc => RankingRule::Asc(name.to_owned()),
+            LegacyRankingOrdering::Dsc => RankingRule::Desc(name.to_owned()),
+        };
+
+        // the ranking order was ignored when there was no ranking rules
+        let ranking_rules = ranking_rules.map(|custom_rules| match ranking_order {
+            Some(ranking_order) => ranking_order
+                .iter()
+                .filter_map(|name| match name.as_str() {
+                    "_sum_of_typos" => Some(RankingRule::Typo),
+                    "_number_of_words" => Some(RankingRule::Words),
+                    "_word_proximity" => Some(RankingRule::Proximity),
+                    "_sum_of_words_attribute" => Some(RankingRule::Attribute),
+                    "_sum_of_words_position" => Some(RankingRule::WordsPosition),
+                    "_exact" => Some(RankingRule::Exactness),
+                    "_popularity" => Some(RankingRule::Popularity),
+                    name => custom_rules.get(name).map(|o| sort_rule(name, *o)),
+                })
+                .collect(),
+            None => {
+                let mut custom_rules: Vec<_> = custom_rules.into_iter().collect();
+                custom_rules.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+                let mut rules = RankingRule::default_rules();
+                rules.extend(custom_rules.iter().map(|(name, o)| sort_rule(name, *o)));
+                rules
+            }
+        });
+
+        Settings {
+            stop_words: stop_words.map(|s| s.into_iter().collect()),
+            ranking_rules,
+            distinct_field,
+            attributes_weights: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use meilidb_schema::{SchemaBuilder, DISPLAYED, INDEXED, RANKED};
+
+    #[test]
+    fn merge_settings() {
+        let mut settings = Settings {
+            distinct_field: Some(String::from("title")),
+            ranking_rules: Some(vec![RankingRule::Exactness]),
+            ..Settings::default()
+        };
+
+        settings.merge(Settings {
+            ranking_rules: Some(vec![RankingRule::Typo]),
+            ..Settings::default()
+        });
+
+        assert_eq!(settings.distinct_field, Some(String::from("title")));
+        assert_eq!(settings.ranking_rules, Some(vec![RankingRule::Typo]));
+    }
+
+    #[test]
+    fn validate_settings() {
+        let mut builder = SchemaBuilder::with_identifier("id");
+        builder.new_attribute("id", DISPLAYED);
+        builder.new_attribute("title", DISPLAYED | INDEXED);
+        builder.new_attribute("release_date", DISPLAYED | RANKED);
+        let schema = builder.build();
+
+        let settings = Settings {
+            ranking_rules: Some(vec![
+                RankingRule::Typo,
+                RankingRule::Desc(String::from("release_date")),
+            ]),
+            distinct_field: Some(String::from("title")),
+            ..Settings::default()
+        };
+        assert_eq!(settings.validate(Some(&schema)), Ok(()));
+
+        // without a schema the attributes are unknown
+        let error = InvalidSetting::UnknownAttribute(String::from("release_date"));
+        assert_eq!(settings.validate(None), Err(error));
+
+        let mut invalid = settings.clone();
+        invalid.distinct_field = Some(String::from("brand"));
+        let error = InvalidSetting::UnknownAttribute(String::from("brand"));
+        assert_eq!(invalid.validate(Some(&schema)), Err(error));
+
+        let mut weights = BTreeMap::new();
+        weights.insert(String::from("title"), 3);
+        weights.insert(String::from("description"), 2);
+
+        let mut invalid = settings;
+        invalid.attributes_weights = Some(weights);
+        let error = InvalidSetting::UnknownAttribute(String::from("description"));
+        assert_eq!(invalid.validate(Some(&schema)), Err(error));
+    }
+
+    #[test]
+    fn legacy_settings() {
+        let mut custom_rules = HashMap::new();
+        custom_rules.insert(String::from("release_date"), LegacyRankingOrdering::Dsc);
+        custom_rules.insert(String::from("price"), LegacyRankingOrdering::Asc);
+
+        let legacy = LegacySettings {
+            stop_words: None,
+            ranking_order: Some(vec![
+                String::from("_exact"),
+                String::from("unknown"),
+                String::from("release_date"),
+            ]),
+            distinct_field: None,
+            ranking_rules: Some(custom_rules),
+        };
+
+        let settings = Settings::from(legacy);
+        let expected = vec![
+            RankingRule::Exactness,
+            RankingRule::Desc(String::from("release_date")),
+        ];
+        assert_eq!(settings.ranking_rules, Some(expected));
+    }
+}
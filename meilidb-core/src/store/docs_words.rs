@@ -1,12 +1,14 @@
-use super::BEU64;
+use super::{StoredSize, BEU64};
 use crate::DocumentId;
 use heed::types::{ByteSlice, OwnedType};
 use heed::Result as ZResult;
+use std::mem;
 use std::sync::Arc;
 
 #[derive(Copy, Clone)]
 pub struct DocsWords {
     pub(crate) docs_words: heed::Database<OwnedType<BEU64>, ByteSlice>,
+    pub(crate) size: StoredSize,
 }
 
 impl DocsWords {
@@ -18,16 +20,62 @@ impl DocsWords {
     ) -> ZResult<()> {
         let document_id = BEU64::new(document_id.0);
         let bytes = words.as_fst().as_bytes();
-        self.docs_words.put(writer, &document_id, bytes)
+
+        let old = self.entry_size(writer, document_id)?;
+        self.docs_words.put(writer, &document_id, bytes)?;
+        let new = mem::size_of::<BEU64>() + bytes.len();
+        self.size
+            .replace(writer, old, new, |reader| self.count_stored_size(reader))
     }
 
     pub fn del_doc_words(self, writer: &mut heed::RwTxn, document_id: DocumentId) -> ZResult<bool> {
         let document_id = BEU64::new(document_id.0);
-        self.docs_words.delete(writer, &document_id)
+        let old = self.entry_size(writer, document_id)?;
+        let deleted = self.docs_words.delete(writer, &document_id)?;
+        self.size
+            .replace(writer, old, 0, |reader| self.count_stored_size(reader))?;
+        Ok(deleted)
     }
 
     pub fn clear(self, writer: &mut heed::RwTxn) -> ZResult<()> {
-        self.docs_words.clear(writer)
+        self.docs_words.clear(writer)?;
+        self.size.reset(writer)
+    }
+
+    fn entry_size(self, reader: &heed::RoTxn, document_id: BEU64) -> ZResult<usize> {
+        let words = self.docs_words.get(reader, &document_id)?;
+        Ok(words.map_or(0, |words| mem::size_of::<BEU64>() + words.len()))
+    }
+
+    pub fn copy_to(self, writer: &mut heed::RwTxn, other: DocsWords) -> ZResult<()> {
+        let mut entries = Vec::new();
+        for result in self.docs_words.iter(writer)? {
+            let (document_id, words) = result?;
+            entries.push((document_id, words.to_vec()));
+        }
+
+        for (document_id, words) in entries {
+            other.docs_words.put(writer, &document_id, &words)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns an estimation of the number of bytes stored in this store.
+    pub fn stored_size(self, reader: &heed::RoTxn) -> ZResult<u64> {
+        match self.size.get(reader)? {
+            Some(size) => Ok(size),
+            None => self.count_stored_size(reader),
+        }
+    }
+
+    fn count_stored_size(self, reader: &heed::RoTxn) -> ZResult<u64> {
+        let mut size = 0;
+        for result in self.docs_words.iter(reader)? {
+            let (_, words) = result?;
+            size += (mem::size_of::<BEU64>() + words.len()) as u64;
+        }
+        Ok(size)
     }
 
     pub fn doc_words(
@@ -1,13 +1,15 @@
 use heed::types::{ByteSlice, OwnedType};
 use heed::Result as ZResult;
 use meilidb_schema::SchemaAttr;
+use std::mem;
 
-use super::DocumentAttrKey;
+use super::{DocumentAttrKey, StoredSize};
 use crate::DocumentId;
 
 #[derive(Copy, Clone)]
 pub struct DocumentsFields {
     pub(crate) documents_fields: heed::Database<OwnedType<DocumentAttrKey>, ByteSlice>,
+    pub(crate) size: StoredSize,
 }
 
 impl DocumentsFields {
@@ -19,7 +21,13 @@ impl DocumentsFields {
         value: &[u8],
     ) -> ZResult<()> {
         let key = DocumentAttrKey::new(document_id, attribute);
-        self.documents_fields.put(writer, &key, value)
+
+        let old = self.documents_fields.get(writer, &key)?;
+        let old = old.map_or(0, |bytes| mem::size_of::<DocumentAttrKey>() + bytes.len());
+        self.documents_fields.put(writer, &key, value)?;
+        let new = mem::size_of::<DocumentAttrKey>() + value.len();
+        self.size
+            .replace(writer, old, new, |reader| self.count_stored_size(reader))
     }
 
     pub fn del_all_document_fields(
@@ -29,11 +37,53 @@ impl DocumentsFields {
     ) -> ZResult<usize> {
         let start = DocumentAttrKey::new(document_id, SchemaAttr::min());
         let end = DocumentAttrKey::new(document_id, SchemaAttr::max());
-        self.documents_fields.delete_range(writer, &(start..=end))
+
+        let mut old = 0;
+        for result in self.documents_fields.range(writer, &(start..=end))? {
+            let (_, bytes) = result?;
+            old += mem::size_of::<DocumentAttrKey>() + bytes.len();
+        }
+
+        let deleted = self.documents_fields.delete_range(writer, &(start..=end))?;
+        self.size
+            .replace(writer, old, 0, |reader| self.count_stored_size(reader))?;
+        Ok(deleted)
     }
 
     pub fn clear(self, writer: &mut heed::RwTxn) -> ZResult<()> {
-        self.documents_fields.clear(writer)
+        self.documents_fields.clear(writer)?;
+        self.size.reset(writer)
+    }
+
+    pub fn copy_to(self, writer: &mut heed::RwTxn, other: DocumentsFields) -> ZResult<()> {
+        let mut entries = Vec::new();
+        for result in self.documents_fields.iter(writer)? {
+            let (key, bytes) = result?;
+            entries.push((key, bytes.to_vec()));
+        }
+
+        for (key, bytes) in entries {
+            other.documents_fields.put(writer, &key, &bytes)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns an estimation of the number of bytes stored in this store.
+    pub fn stored_size(self, reader: &heed::RoTxn) -> ZResult<u64> {
+        match self.size.get(reader)? {
+            Some(size) => Ok(size),
+            None => self.count_stored_size(reader),
+        }
+    }
+
+    fn count_stored_size(self, reader: &heed::RoTxn) -> ZResult<u64> {
+        let mut size = 0;
+        for result in self.documents_fields.iter(reader)? {
+            let (_, bytes) = result?;
+            size += (mem::size_of::<DocumentAttrKey>() + bytes.len()) as u64;
+        }
+        Ok(size)
     }
 
     pub fn document_attribute<'txn>(
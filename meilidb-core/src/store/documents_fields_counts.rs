@@ -36,6 +36,19 @@ impl DocumentsFieldsCounts {
         self.documents_fields_counts.clear(writer)
     }
 
+    pub fn copy_to(self, writer: &mut heed::RwTxn, other: DocumentsFieldsCounts) -> ZResult<()> {
+        let mut entries = Vec::new();
+        for result in self.documents_fields_counts.iter(writer)? {
+            entries.push(result?);
+        }
+
+        for (key, count) in entries {
+            other.documents_fields_counts.put(writer, &key, &count)?;
+        }
+
+        Ok(())
+    }
+
     pub fn document_field_count(
         self,
         reader: &heed::RoTxn,
@@ -0,0 +1,143 @@
+use heed::types::ByteSlice;
+use heed::Result as ZResult;
+use meilidb_schema::SchemaAttr;
+use roaring::RoaringTreemap;
+use serde_json::Value;
+
+use super::StoredSize;
+
+/// The version of the layout of the filters docids, the indexes
+/// created before them are filled by a migration.
+pub(crate) const FILTERS_DOCIDS_VERSION: u32 = 1;
+
+/// The longest value that can be filtered, the longer ones are not stored.
+pub const MAX_FILTER_VALUE_LEN: usize = 256;
+
+fn filter_key(attribute: SchemaAttr, value: &str) -> Vec<u8> {
+    let mut key = Vec::with_capacity(2 + value.len());
+    key.extend_from_slice(&attribute.0.to_be_bytes());
+    key.extend_from_slice(value.as_bytes());
+    key
+}
+
+/// Returns the values a filter must have to match this document value: strings
+/// are compared without their case and only the strings of the arrays are compared.
+pub(crate) fn filter_values(value: &Value) -> Vec<String> {
+    let values = match value {
+        Value::String(s) => vec![s.to_lowercase()],
+        Value::Bool(b) => vec![b.to_string()],
+        Value::Number(n) => vec![n.to_string()],
+        Value::Array(a) => a
+            .iter()
+            .filter_map(|v| v.as_str().map(ToOwned::to_owned))
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    values
+        .into_iter()
+        .filter(|v| v.len() <= MAX_FILTER_VALUE_LEN)
+        .collect()
+}
+
+/// The identifiers of the documents having each value for each attribute, stored
+/// as compressed bitmaps to filter the documents without reading their fields.
+#[derive(Copy, Clone)]
+pub struct FiltersDocids {
+    pub(crate) filters_docids: heed::Database<ByteSlice, ByteSlice>,
+    pub(crate) size: StoredSize,
+}
+
+impl FiltersDocids {
+    pub fn put_filter_docids(
+        self,
+        writer: &mut heed::RwTxn,
+        attribute: SchemaAttr,
+        value: &str,
+        docids: &RoaringTreemap,
+    ) -> ZResult<()> {
+        let key = filter_key(attribute, value);
+        let mut bytes = Vec::with_capacity(docids.serialized_size());
+        // writing into a vector cannot fail
+        docids.serialize_into(&mut bytes).unwrap();
+
+        let old = self.entry_size(writer, &key)?;
+        self.filters_docids.put(writer, &key, &bytes)?;
+        let new = key.len() + bytes.len();
+        self.size
+            .replace(writer, old, new, |reader| self.count_stored_size(reader))
+    }
+
+    pub fn del_filter_docids(
+        self,
+        writer: &mut heed::RwTxn,
+        attribute: SchemaAttr,
+        value: &str,
+    ) -> ZResult<bool> {
+        let key = filter_key(attribute, value);
+        let old = self.entry_size(writer, &key)?;
+        let deleted = self.filters_docids.delete(writer, &key)?;
+        self.size
+            .replace(writer, old, 0, |reader| self.count_stored_size(reader))?;
+        Ok(deleted)
+    }
+
+    pub fn clear(self, writer: &mut heed::RwTxn) -> ZResult<()> {
+        self.filters_docids.clear(writer)?;
+        self.size.reset(writer)
+    }
+
+    fn entry_size(self, reader: &heed::RoTxn, key: &[u8]) -> ZResult<usize> {
+        let docids = self.filters_docids.get(reader, key)?;
+        Ok(docids.map_or(0, |docids| key.len() + docids.len()))
+    }
+
+    pub fn copy_to(self, writer: &mut heed::RwTxn, other: FiltersDocids) -> ZResult<()> {
+        let mut entries = Vec::new();
+        for result in self.filters_docids.iter(writer)? {
+            let (key, docids) = result?;
+            entries.push((key.to_vec(), docids.to_vec()));
+        }
+
+        for (key, docids) in entries {
+            other.filters_docids.put(writer, &key, &docids)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns an estimation of the number of bytes stored in this store.
+    pub fn stored_size(self, reader: &heed::RoTxn) -> ZResult<u64> {
+        match self.size.get(reader)? {
+            Some(size) => Ok(size),
+            None => self.count_stored_size(reader),
+        }
+    }
+
+    fn count_stored_size(self, reader: &heed::RoTxn) -> ZResult<u64> {
+        let mut size = 0;
+        for result in self.filters_docids.iter(reader)? {
+            let (key, docids) = result?;
+            size += (key.len() + docids.len()) as u64;
+        }
+        Ok(size)
+    }
+
+    /// Returns the documents having this value for this attribute, the value
+    /// must be lowercased as the strings of the documents are stored lowercased.
+    pub fn filter_docids(
+        self,
+        reader: &heed::RoTxn,
+        attribute: SchemaAttr,
+        value: &str,
+    ) -> ZResult<Option<RoaringTreemap>> {
+        let key = filter_key(attribute, value);
+        match self.filters_docids.get(reader, &key)? {
+            Some(bytes) => match RoaringTreemap::deserialize_from(bytes) {
+                Ok(docids) => Ok(Some(docids)),
+                Err(_) => Err(heed::Error::Decoding),
+            },
+            None => Ok(None),
+        }
+    }
+}
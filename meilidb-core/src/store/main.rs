@@ -1,26 +1,102 @@
-use crate::RankedMap;
+use super::filters_docids::FILTERS_DOCIDS_VERSION;
+use super::postings_docids::POSTINGS_DOCIDS_VERSION;
+use super::stored_size::{StoredSize, STORED_SIZE_PREFIX};
+use super::words_pairs_proximities::WORDS_PAIRS_PROXIMITIES_VERSION;
+use crate::settings::SETTINGS_VERSION;
+use crate::{IndexLimits, PopularityMap, RankedMap, Settings};
 use heed::types::{ByteSlice, OwnedType, SerdeBincode, Str};
 use heed::Result as ZResult;
 use meilidb_schema::Schema;
 use std::sync::Arc;
 
 const CUSTOMS_KEY: &str = "customs-key";
+const FILTERS_DOCIDS_VERSION_KEY: &str = "filters-docids-version";
+const LIMITS_KEY: &str = "limits";
 const NUMBER_OF_DOCUMENTS_KEY: &str = "number-of-documents";
+const POPULARITY_MAP_KEY: &str = "popularity-map";
+const POSTINGS_DOCIDS_VERSION_KEY: &str = "postings-docids-version";
 const RANKED_MAP_KEY: &str = "ranked-map";
 const SCHEMA_KEY: &str = "schema";
+const SETTINGS_KEY: &str = "settings";
+const SETTINGS_VERSION_KEY: &str = "settings-version";
 const SYNONYMS_KEY: &str = "synonyms";
 const STOP_WORDS_KEY: &str = "stop-words";
 const WORDS_KEY: &str = "words";
+const WORDS_PAIRS_PROXIMITIES_VERSION_KEY: &str = "words-pairs-proximities-version";
 
 #[derive(Copy, Clone)]
 pub struct Main {
     pub(crate) main: heed::PolyDatabase,
+    pub(crate) size: StoredSize,
 }
 
 impl Main {
+    pub fn clear(self, writer: &mut heed::RwTxn) -> ZResult<()> {
+        self.main.clear(writer)
+    }
+
+    /// Replaces the entries of the other store, including the
+    /// versions of the layouts written when it was created.
+    pub fn copy_to(self, writer: &mut heed::RwTxn, other: Main) -> ZResult<()> {
+        other.main.clear(writer)?;
+
+        let mut entries = Vec::new();
+        for result in self.main.iter::<ByteSlice, ByteSlice>(writer)? {
+            let (key, value) = result?;
+            entries.push((key.to_vec(), value.to_vec()));
+        }
+
+        for (key, value) in entries {
+            other
+                .main
+                .put::<ByteSlice, ByteSlice>(writer, &key, &value)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns an estimation of the number of bytes stored in this store.
+    pub fn stored_size(self, reader: &heed::RoTxn) -> ZResult<u64> {
+        match self.size.get(reader)? {
+            Some(size) => Ok(size),
+            None => self.count_stored_size(reader),
+        }
+    }
+
+    fn count_stored_size(self, reader: &heed::RoTxn) -> ZResult<u64> {
+        let mut size = 0;
+        for result in self.main.iter::<ByteSlice, ByteSlice>(reader)? {
+            let (key, value) = result?;
+            // the sizes of the stores are not part of the data
+            if !key.starts_with(STORED_SIZE_PREFIX.as_bytes()) {
+                size += (key.len() + value.len()) as u64;
+            }
+        }
+        Ok(size)
+    }
+
+    fn entry_size(self, reader: &heed::RoTxn, key: &str) -> ZResult<usize> {
+        let value = self.main.get::<Str, ByteSlice>(reader, key)?;
+        Ok(value.map_or(0, |value| key.len() + value.len()))
+    }
+
+    /// Writes an entry with `put` and accounts for the change of the stored size.
+    fn put_counted<F>(self, writer: &mut heed::RwTxn, key: &str, put: F) -> ZResult<()>
+    where
+        F: FnOnce(&mut heed::RwTxn) -> ZResult<()>,
+    {
+        let old = self.entry_size(writer, key)?;
+        put(writer)?;
+        let new = self.entry_size(writer, key)?;
+        self.size
+            .replace(writer, old, new, |reader| self.count_stored_size(reader))
+    }
+
     pub fn put_words_fst(self, writer: &mut heed::RwTxn, fst: &fst::Set) -> ZResult<()> {
         let bytes = fst.as_fst().as_bytes();
-        self.main.put::<Str, ByteSlice>(writer, WORDS_KEY, bytes)
+        self.put_counted(writer, WORDS_KEY, |writer| {
+            self.main.put::<Str, ByteSlice>(writer, WORDS_KEY, bytes)
+        })
     }
 
     pub fn words_fst(self, reader: &heed::RoTxn) -> ZResult<Option<fst::Set>> {
@@ -36,8 +112,10 @@ impl Main {
     }
 
     pub fn put_schema(self, writer: &mut heed::RwTxn, schema: &Schema) -> ZResult<()> {
-        self.main
-            .put::<Str, SerdeBincode<Schema>>(writer, SCHEMA_KEY, schema)
+        self.put_counted(writer, SCHEMA_KEY, |writer| {
+            self.main
+                .put::<Str, SerdeBincode<Schema>>(writer, SCHEMA_KEY, schema)
+        })
     }
 
     pub fn schema(self, reader: &heed::RoTxn) -> ZResult<Option<Schema>> {
@@ -46,8 +124,10 @@ impl Main {
     }
 
     pub fn put_ranked_map(self, writer: &mut heed::RwTxn, ranked_map: &RankedMap) -> ZResult<()> {
-        self.main
-            .put::<Str, SerdeBincode<RankedMap>>(writer, RANKED_MAP_KEY, &ranked_map)
+        self.put_counted(writer, RANKED_MAP_KEY, |writer| {
+            self.main
+                .put::<Str, SerdeBincode<RankedMap>>(writer, RANKED_MAP_KEY, &ranked_map)
+        })
     }
 
     pub fn ranked_map(self, reader: &heed::RoTxn) -> ZResult<Option<RankedMap>> {
@@ -55,9 +135,30 @@ impl Main {
             .get::<Str, SerdeBincode<RankedMap>>(reader, RANKED_MAP_KEY)
     }
 
+    pub fn put_popularity_map(
+        self,
+        writer: &mut heed::RwTxn,
+        popularity_map: &PopularityMap,
+    ) -> ZResult<()> {
+        self.put_counted(writer, POPULARITY_MAP_KEY, |writer| {
+            self.main.put::<Str, SerdeBincode<PopularityMap>>(
+                writer,
+                POPULARITY_MAP_KEY,
+                popularity_map,
+            )
+        })
+    }
+
+    pub fn popularity_map(self, reader: &heed::RoTxn) -> ZResult<Option<PopularityMap>> {
+        self.main
+            .get::<Str, SerdeBincode<PopularityMap>>(reader, POPULARITY_MAP_KEY)
+    }
+
     pub fn put_synonyms_fst(self, writer: &mut heed::RwTxn, fst: &fst::Set) -> ZResult<()> {
         let bytes = fst.as_fst().as_bytes();
-        self.main.put::<Str, ByteSlice>(writer, SYNONYMS_KEY, bytes)
+        self.put_counted(writer, SYNONYMS_KEY, |writer| {
+            self.main.put::<Str, ByteSlice>(writer, SYNONYMS_KEY, bytes)
+        })
     }
 
     pub fn synonyms_fst(self, reader: &heed::RoTxn) -> ZResult<Option<fst::Set>> {
@@ -74,8 +175,10 @@ impl Main {
 
     pub fn put_stop_words_fst(self, writer: &mut heed::RwTxn, fst: &fst::Set) -> ZResult<()> {
         let bytes = fst.as_fst().as_bytes();
-        self.main
-            .put::<Str, ByteSlice>(writer, STOP_WORDS_KEY, bytes)
+        self.put_counted(writer, STOP_WORDS_KEY, |writer| {
+            self.main
+                .put::<Str, ByteSlice>(writer, STOP_WORDS_KEY, bytes)
+        })
     }
 
     pub fn stop_words_fst(self, reader: &heed::RoTxn) -> ZResult<Option<fst::Set>> {
@@ -95,8 +198,10 @@ impl Main {
         F: Fn(u64) -> u64,
     {
         let new = self.number_of_documents(writer).map(f)?;
-        self.main
-            .put::<Str, OwnedType<u64>>(writer, NUMBER_OF_DOCUMENTS_KEY, &new)?;
+        self.put_counted(writer, NUMBER_OF_DOCUMENTS_KEY, |writer| {
+            self.main
+                .put::<Str, OwnedType<u64>>(writer, NUMBER_OF_DOCUMENTS_KEY, &new)
+        })?;
         Ok(new)
     }
 
@@ -111,11 +216,122 @@ impl Main {
     }
 
     pub fn put_customs(self, writer: &mut heed::RwTxn, customs: &[u8]) -> ZResult<()> {
-        self.main
-            .put::<Str, ByteSlice>(writer, CUSTOMS_KEY, customs)
+        self.put_counted(writer, CUSTOMS_KEY, |writer| {
+            self.main
+                .put::<Str, ByteSlice>(writer, CUSTOMS_KEY, customs)
+        })
     }
 
     pub fn customs<'txn>(self, reader: &'txn heed::RoTxn) -> ZResult<Option<&'txn [u8]>> {
         self.main.get::<Str, ByteSlice>(reader, CUSTOMS_KEY)
     }
+
+    /// Stores the settings along with the version of their layout.
+    pub fn put_settings(self, writer: &mut heed::RwTxn, settings: &Settings) -> ZResult<()> {
+        self.put_counted(writer, SETTINGS_VERSION_KEY, |writer| {
+            self.main
+                .put::<Str, OwnedType<u32>>(writer, SETTINGS_VERSION_KEY, &SETTINGS_VERSION)
+        })?;
+        self.put_counted(writer, SETTINGS_KEY, |writer| {
+            self.main
+                .put::<Str, SerdeBincode<Settings>>(writer, SETTINGS_KEY, settings)
+        })
+    }
+
+    /// The version of the layout of the stored settings, the settings stored before
+    /// the layout was versioned use the first one. `None` if there is no settings.
+    pub fn settings_version(self, reader: &heed::RoTxn) -> ZResult<Option<u32>> {
+        match self
+            .main
+            .get::<Str, OwnedType<u32>>(reader, SETTINGS_VERSION_KEY)?
+        {
+            Some(version) => Ok(Some(version)),
+            None => match self.main.get::<Str, ByteSlice>(reader, SETTINGS_KEY)? {
+                Some(_) => Ok(Some(1)),
+                None => Ok(None),
+            },
+        }
+    }
+
+    /// Marks the postings docids as stored with the current layout.
+    pub fn put_postings_docids_version(self, writer: &mut heed::RwTxn) -> ZResult<()> {
+        self.put_counted(writer, POSTINGS_DOCIDS_VERSION_KEY, |writer| {
+            self.main.put::<Str, OwnedType<u32>>(
+                writer,
+                POSTINGS_DOCIDS_VERSION_KEY,
+                &POSTINGS_DOCIDS_VERSION,
+            )
+        })
+    }
+
+    /// Whether the postings docids are stored with the current layout,
+    /// the searches read the postings lists until they are.
+    pub fn postings_docids_ready(self, reader: &heed::RoTxn) -> ZResult<bool> {
+        let version = self
+            .main
+            .get::<Str, OwnedType<u32>>(reader, POSTINGS_DOCIDS_VERSION_KEY)?;
+        Ok(version == Some(POSTINGS_DOCIDS_VERSION))
+    }
+
+    /// Marks the filters docids as stored with the current layout.
+    pub fn put_filters_docids_version(self, writer: &mut heed::RwTxn) -> ZResult<()> {
+        self.put_counted(writer, FILTERS_DOCIDS_VERSION_KEY, |writer| {
+            self.main.put::<Str, OwnedType<u32>>(
+                writer,
+                FILTERS_DOCIDS_VERSION_KEY,
+                &FILTERS_DOCIDS_VERSION,
+            )
+        })
+    }
+
+    /// Whether the filters docids are stored with the current layout,
+    /// the searches read the documents fields until they are.
+    pub fn filters_docids_ready(self, reader: &heed::RoTxn) -> ZResult<bool> {
+        let version = self
+            .main
+            .get::<Str, OwnedType<u32>>(reader, FILTERS_DOCIDS_VERSION_KEY)?;
+        Ok(version == Some(FILTERS_DOCIDS_VERSION))
+    }
+
+    /// Marks the words pairs proximities as stored with the current layout.
+    pub fn put_words_pairs_proximities_version(self, writer: &mut heed::RwTxn) -> ZResult<()> {
+        self.put_counted(writer, WORDS_PAIRS_PROXIMITIES_VERSION_KEY, |writer| {
+            self.main.put::<Str, OwnedType<u32>>(
+                writer,
+                WORDS_PAIRS_PROXIMITIES_VERSION_KEY,
+                &WORDS_PAIRS_PROXIMITIES_VERSION,
+            )
+        })
+    }
+
+    /// The version of the layout of the stored words pairs proximities,
+    /// `None` if the pairs have never been stored for this index.
+    pub fn words_pairs_proximities_version(self, reader: &heed::RoTxn) -> ZResult<Option<u32>> {
+        self.main
+            .get::<Str, OwnedType<u32>>(reader, WORDS_PAIRS_PROXIMITIES_VERSION_KEY)
+    }
+
+    /// Whether the words pairs proximities are stored with the current layout,
+    /// the searches use the positions of the words until they are.
+    pub fn words_pairs_proximities_ready(self, reader: &heed::RoTxn) -> ZResult<bool> {
+        let version = self.words_pairs_proximities_version(reader)?;
+        Ok(version == Some(WORDS_PAIRS_PROXIMITIES_VERSION))
+    }
+
+    pub fn settings(self, reader: &heed::RoTxn) -> ZResult<Option<Settings>> {
+        self.main
+            .get::<Str, SerdeBincode<Settings>>(reader, SETTINGS_KEY)
+    }
+
+    pub fn put_limits(self, writer: &mut heed::RwTxn, limits: &IndexLimits) -> ZResult<()> {
+        self.put_counted(writer, LIMITS_KEY, |writer| {
+            self.main
+                .put::<Str, SerdeBincode<IndexLimits>>(writer, LIMITS_KEY, limits)
+        })
+    }
+
+    pub fn limits(self, reader: &heed::RoTxn) -> ZResult<Option<IndexLimits>> {
+        self.main
+            .get::<Str, SerdeBincode<IndexLimits>>(reader, LIMITS_KEY)
+    }
 }
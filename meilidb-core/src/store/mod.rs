@@ -1,33 +1,48 @@
 mod docs_words;
 mod documents_fields;
 mod documents_fields_counts;
+mod filters_docids;
 mod main;
+mod postings_docids;
 mod postings_lists;
+mod query_rules;
+mod stored_size;
 mod synonyms;
 mod updates;
 mod updates_results;
+mod words_pairs_proximities;
 
 pub use self::docs_words::DocsWords;
 pub use self::documents_fields::{DocumentFieldsIter, DocumentsFields};
 pub use self::documents_fields_counts::{
     DocumentFieldsCountsIter, DocumentsFieldsCounts, DocumentsIdsIter,
 };
+pub(crate) use self::filters_docids::filter_values;
+pub use self::filters_docids::{FiltersDocids, MAX_FILTER_VALUE_LEN};
 pub use self::main::Main;
+pub use self::postings_docids::PostingsDocids;
 pub use self::postings_lists::PostingsLists;
+pub use self::query_rules::QueryRules;
+pub use self::stored_size::StoredSize;
 pub use self::synonyms::Synonyms;
 pub use self::updates::Updates;
 pub use self::updates_results::UpdatesResults;
+pub use self::words_pairs_proximities::WordsPairsProximities;
 
 use std::collections::HashSet;
 
 use heed::Result as ZResult;
 use meilidb_schema::{Schema, SchemaAttr};
+use roaring::RoaringTreemap;
 use serde::de;
 use zerocopy::{AsBytes, FromBytes};
 
 use crate::criterion::Criteria;
+use crate::database::{UpdateEvent, UpdateEventsEmitter};
 use crate::serde::Deserializer;
-use crate::{query_builder::QueryBuilder, update, DocumentId, Error, MResult};
+use crate::{
+    query_builder::QueryBuilder, update, DocumentId, Env, Error, IndexLimits, MResult, Settings,
+};
 
 type BEU64 = zerocopy::U64<byteorder::BigEndian>;
 type BEU16 = zerocopy::U16<byteorder::BigEndian>;
@@ -56,6 +71,10 @@ fn postings_lists_name(name: &str) -> String {
     format!("store-{}-postings-lists", name)
 }
 
+fn postings_docids_name(name: &str) -> String {
+    format!("store-{}-postings-docids", name)
+}
+
 fn documents_fields_name(name: &str) -> String {
     format!("store-{}-documents-fields", name)
 }
@@ -64,14 +83,26 @@ fn documents_fields_counts_name(name: &str) -> String {
     format!("store-{}-documents-fields-counts", name)
 }
 
+fn filters_docids_name(name: &str) -> String {
+    format!("store-{}-filters-docids", name)
+}
+
 fn synonyms_name(name: &str) -> String {
     format!("store-{}-synonyms", name)
 }
 
+fn query_rules_name(name: &str) -> String {
+    format!("store-{}-query-rules", name)
+}
+
 fn docs_words_name(name: &str) -> String {
     format!("store-{}-docs-words", name)
 }
 
+fn words_pairs_proximities_name(name: &str) -> String {
+    format!("store-{}-words-pairs-proximities", name)
+}
+
 fn updates_name(name: &str) -> String {
     format!("store-{}-updates", name)
 }
@@ -84,14 +115,18 @@ fn updates_results_name(name: &str) -> String {
 pub struct Index {
     pub main: Main,
     pub postings_lists: PostingsLists,
+    pub postings_docids: PostingsDocids,
     pub documents_fields: DocumentsFields,
     pub documents_fields_counts: DocumentsFieldsCounts,
+    pub filters_docids: FiltersDocids,
     pub synonyms: Synonyms,
     pub docs_words: DocsWords,
+    pub query_rules: QueryRules,
+    pub words_pairs_proximities: WordsPairsProximities,
 
     pub updates: Updates,
     pub updates_results: UpdatesResults,
-    updates_notifier: crossbeam_channel::Sender<()>,
+    pub(crate) updates_notifier: UpdateEventsEmitter,
 }
 
 impl Index {
@@ -140,18 +175,65 @@ impl Index {
         }
     }
 
+    /// Returns the documents having all these values, the strings must be lowercased.
+    /// The filters docids are only complete once `Main::filters_docids_ready` is true.
+    pub fn filtered_documents(
+        &self,
+        reader: &heed::RoTxn,
+        filters: &[(SchemaAttr, String)],
+    ) -> MResult<RoaringTreemap> {
+        let mut documents: Option<RoaringTreemap> = None;
+        for (attribute, value) in filters {
+            let docids = self
+                .filters_docids
+                .filter_docids(reader, *attribute, value)?;
+            let docids = docids.unwrap_or_else(RoaringTreemap::new);
+            documents = Some(match documents {
+                Some(mut documents) => {
+                    documents.intersect_with(&docids);
+                    documents
+                }
+                None => docids,
+            });
+        }
+        Ok(documents.unwrap_or_else(RoaringTreemap::new))
+    }
+
     pub fn schema_update(&self, writer: &mut heed::RwTxn, schema: Schema) -> MResult<u64> {
-        let _ = self.updates_notifier.send(());
+        let _ = self.updates_notifier.send(UpdateEvent::NewUpdate);
         update::push_schema_update(writer, self.updates, self.updates_results, schema)
     }
 
     pub fn customs_update(&self, writer: &mut heed::RwTxn, customs: Vec<u8>) -> ZResult<u64> {
-        let _ = self.updates_notifier.send(());
+        let _ = self.updates_notifier.send(UpdateEvent::NewUpdate);
         update::push_customs_update(writer, self.updates, self.updates_results, customs)
     }
 
+    pub fn settings_update(&self, writer: &mut heed::RwTxn, settings: Settings) -> MResult<u64> {
+        let _ = self.updates_notifier.send(UpdateEvent::NewUpdate);
+        update::push_settings_update(writer, self.updates, self.updates_results, settings)
+    }
+
+    /// Returns the settings of this index without its stop words, the stop
+    /// words are kept in their fst which is only read when they are needed.
+    pub fn settings(&self, reader: &heed::RoTxn) -> MResult<Settings> {
+        update::stored_settings(reader, self.main)
+    }
+
+    /// Returns the settings of this index, including its stop words.
+    pub fn settings_with_stop_words(&self, reader: &heed::RoTxn) -> MResult<Settings> {
+        let mut settings = self.settings(reader)?;
+
+        let stop_words = self.main.stop_words_fst(reader)?.unwrap_or_default();
+        let stop_words = stop_words.stream().into_strs()?;
+        settings.stop_words = Some(stop_words.into_iter().collect());
+
+        Ok(settings)
+    }
+
     pub fn documents_addition<D>(&self) -> update::DocumentsAddition<D> {
         update::DocumentsAddition::new(
+            self.main,
             self.updates,
             self.updates_results,
             self.updates_notifier.clone(),
@@ -167,7 +249,7 @@ impl Index {
     }
 
     pub fn clear_all(&self, writer: &mut heed::RwTxn) -> MResult<u64> {
-        let _ = self.updates_notifier.send(());
+        let _ = self.updates_notifier.send(UpdateEvent::NewUpdate);
         update::push_clear_all(writer, self.updates, self.updates_results)
     }
 
@@ -244,12 +326,38 @@ impl Index {
         Ok(updates)
     }
 
+    /// Returns an estimation of the number of bytes stored by this index,
+    /// the updates and the LMDB pages overhead are not counted.
+    pub fn stored_size(&self, reader: &heed::RoTxn) -> MResult<u64> {
+        let size = self.main.stored_size(reader)?
+            + self.postings_lists.stored_size(reader)?
+            + self.postings_docids.stored_size(reader)?
+            + self.documents_fields.stored_size(reader)?
+            + self.filters_docids.stored_size(reader)?
+            + self.docs_words.stored_size(reader)?
+            + self.words_pairs_proximities.stored_size(reader)?;
+        Ok(size)
+    }
+
+    pub fn limits(&self, reader: &heed::RoTxn) -> MResult<IndexLimits> {
+        let limits = self.main.limits(reader)?;
+        Ok(limits.unwrap_or_default())
+    }
+
+    /// Changes the limits of this index, the limits are only enforced
+    /// on the documents additions processed after this call.
+    pub fn set_limits(&self, writer: &mut heed::RwTxn, limits: &IndexLimits) -> MResult<()> {
+        self.main.put_limits(writer, limits).map_err(Into::into)
+    }
+
     pub fn query_builder(&self) -> QueryBuilder {
         QueryBuilder::new(
             self.main,
             self.postings_lists,
+            self.postings_docids,
             self.documents_fields_counts,
             self.synonyms,
+            self.words_pairs_proximities,
         )
     }
 
@@ -260,65 +368,111 @@ impl Index {
         QueryBuilder::with_criteria(
             self.main,
             self.postings_lists,
+            self.postings_docids,
             self.documents_fields_counts,
             self.synonyms,
+            self.words_pairs_proximities,
             criteria,
         )
     }
 }
 
-pub fn create(
-    env: &heed::Env,
-    name: &str,
-    updates_notifier: crossbeam_channel::Sender<()>,
-) -> MResult<Index> {
+pub fn create(env: &Env, name: &str, updates_notifier: UpdateEventsEmitter) -> MResult<Index> {
     // create all the store names
     let main_name = main_name(name);
     let postings_lists_name = postings_lists_name(name);
+    let postings_docids_name = postings_docids_name(name);
     let documents_fields_name = documents_fields_name(name);
     let documents_fields_counts_name = documents_fields_counts_name(name);
+    let filters_docids_name = filters_docids_name(name);
     let synonyms_name = synonyms_name(name);
     let docs_words_name = docs_words_name(name);
+    let query_rules_name = query_rules_name(name);
+    let words_pairs_proximities_name = words_pairs_proximities_name(name);
     let updates_name = updates_name(name);
     let updates_results_name = updates_results_name(name);
 
     // open all the stores
     let main = env.create_poly_database(Some(&main_name))?;
     let postings_lists = env.create_database(Some(&postings_lists_name))?;
+    let postings_docids = env.create_database(Some(&postings_docids_name))?;
     let documents_fields = env.create_database(Some(&documents_fields_name))?;
     let documents_fields_counts = env.create_database(Some(&documents_fields_counts_name))?;
+    let filters_docids = env.create_database(Some(&filters_docids_name))?;
     let synonyms = env.create_database(Some(&synonyms_name))?;
     let docs_words = env.create_database(Some(&docs_words_name))?;
+    let query_rules = env.create_database(Some(&query_rules_name))?;
+    let words_pairs_proximities = env.create_database(Some(&words_pairs_proximities_name))?;
     let updates = env.create_database(Some(&updates_name))?;
     let updates_results = env.create_database(Some(&updates_results_name))?;
 
-    Ok(Index {
-        main: Main { main },
-        postings_lists: PostingsLists { postings_lists },
-        documents_fields: DocumentsFields { documents_fields },
+    let index = Index {
+        main: Main {
+            main,
+            size: StoredSize::new(main, "stored-size-main"),
+        },
+        postings_lists: PostingsLists {
+            postings_lists,
+            size: StoredSize::new(main, "stored-size-postings-lists"),
+        },
+        postings_docids: PostingsDocids {
+            postings_docids,
+            size: StoredSize::new(main, "stored-size-postings-docids"),
+        },
+        documents_fields: DocumentsFields {
+            documents_fields,
+            size: StoredSize::new(main, "stored-size-documents-fields"),
+        },
         documents_fields_counts: DocumentsFieldsCounts {
             documents_fields_counts,
         },
+        filters_docids: FiltersDocids {
+            filters_docids,
+            size: StoredSize::new(main, "stored-size-filters-docids"),
+        },
         synonyms: Synonyms { synonyms },
-        docs_words: DocsWords { docs_words },
+        docs_words: DocsWords {
+            docs_words,
+            size: StoredSize::new(main, "stored-size-docs-words"),
+        },
+        query_rules: QueryRules { query_rules },
+        words_pairs_proximities: WordsPairsProximities {
+            words_pairs_proximities,
+            size: StoredSize::new(main, "stored-size-words-pairs-proximities"),
+        },
         updates: Updates { updates },
         updates_results: UpdatesResults { updates_results },
         updates_notifier,
-    })
+    };
+
+    Ok(index)
+}
+
+/// Marks the stores of a new index as written with the current layouts, the
+/// data of the other indexes is rebuilt by migrations when it is not.
+pub fn put_layouts_versions(writer: &mut heed::RwTxn, index: &Index) -> MResult<()> {
+    index.main.put_postings_docids_version(writer)?;
+    index.main.put_words_pairs_proximities_version(writer)?;
+    index.main.put_filters_docids_version(writer)?;
+    Ok(())
 }
 
 pub fn open(
-    env: &heed::Env,
+    env: &Env,
     name: &str,
-    updates_notifier: crossbeam_channel::Sender<()>,
+    updates_notifier: UpdateEventsEmitter,
 ) -> MResult<Option<Index>> {
     // create all the store names
     let main_name = main_name(name);
     let postings_lists_name = postings_lists_name(name);
+    let postings_docids_name = postings_docids_name(name);
     let documents_fields_name = documents_fields_name(name);
     let documents_fields_counts_name = documents_fields_counts_name(name);
+    let filters_docids_name = filters_docids_name(name);
     let synonyms_name = synonyms_name(name);
     let docs_words_name = docs_words_name(name);
+    let query_rules_name = query_rules_name(name);
+    let words_pairs_proximities_name = words_pairs_proximities_name(name);
     let updates_name = updates_name(name);
     let updates_results_name = updates_results_name(name);
 
@@ -347,6 +501,29 @@ pub fn open(
         Some(docs_words) => docs_words,
         None => return Ok(None),
     };
+    // the indexes created before the query rules do not have this store
+    let query_rules = match env.open_database(Some(&query_rules_name))? {
+        Some(query_rules) => query_rules,
+        None => env.create_database(Some(&query_rules_name))?,
+    };
+    // the indexes created before the postings docids do not have this store,
+    // it is filled from the postings lists by a migration update
+    let postings_docids = match env.open_database(Some(&postings_docids_name))? {
+        Some(postings_docids) => postings_docids,
+        None => env.create_database(Some(&postings_docids_name))?,
+    };
+    // the indexes created before the words pairs proximities do not have this store,
+    // it is filled from the documents already indexed by a migration update
+    let words_pairs_proximities = match env.open_database(Some(&words_pairs_proximities_name))? {
+        Some(words_pairs_proximities) => words_pairs_proximities,
+        None => env.create_database(Some(&words_pairs_proximities_name))?,
+    };
+    // the indexes created before the filters docids do not have this store,
+    // it is filled from the documents fields by a migration update
+    let filters_docids = match env.open_database(Some(&filters_docids_name))? {
+        Some(filters_docids) => filters_docids,
+        None => env.create_database(Some(&filters_docids_name))?,
+    };
     let updates = match env.open_database(Some(&updates_name))? {
         Some(updates) => updates,
         None => return Ok(None),
@@ -356,17 +533,111 @@ pub fn open(
         None => return Ok(None),
     };
 
-    Ok(Some(Index {
-        main: Main { main },
-        postings_lists: PostingsLists { postings_lists },
-        documents_fields: DocumentsFields { documents_fields },
+    let index = Index {
+        main: Main {
+            main,
+            size: StoredSize::new(main, "stored-size-main"),
+        },
+        postings_lists: PostingsLists {
+            postings_lists,
+            size: StoredSize::new(main, "stored-size-postings-lists"),
+        },
+        postings_docids: PostingsDocids {
+            postings_docids,
+            size: StoredSize::new(main, "stored-size-postings-docids"),
+        },
+        documents_fields: DocumentsFields {
+            documents_fields,
+            size: StoredSize::new(main, "stored-size-documents-fields"),
+        },
         documents_fields_counts: DocumentsFieldsCounts {
             documents_fields_counts,
         },
+        filters_docids: FiltersDocids {
+            filters_docids,
+            size: StoredSize::new(main, "stored-size-filters-docids"),
+        },
         synonyms: Synonyms { synonyms },
-        docs_words: DocsWords { docs_words },
+        docs_words: DocsWords {
+            docs_words,
+            size: StoredSize::new(main, "stored-size-docs-words"),
+        },
+        query_rules: QueryRules { query_rules },
+        words_pairs_proximities: WordsPairsProximities {
+            words_pairs_proximities,
+            size: StoredSize::new(main, "stored-size-words-pairs-proximities"),
+        },
         updates: Updates { updates },
         updates_results: UpdatesResults { updates_results },
         updates_notifier,
-    }))
+    };
+
+    // the settings stored as customs by the HTTP server are stored typed,
+    // their stop words have never been applied and are applied by an update
+    let reader = env.read_txn()?;
+    let must_migrate_settings =
+        index.main.settings_version(&reader)?.is_none() && index.main.customs(&reader)?.is_some();
+    reader.abort();
+
+    if must_migrate_settings {
+        let mut writer = env.write_txn()?;
+        update::migrate_legacy_settings(
+            &mut writer,
+            index.main,
+            index.updates,
+            index.updates_results,
+        )?;
+        writer.commit()?;
+    }
+
+    // the data stored with an older layout is rebuilt by updates, which are
+    // processed once the index is opened, and is not searched until it is
+    let mut writer = env.write_txn()?;
+    update::push_migrations(&mut writer, &index)?;
+    writer.commit()?;
+
+    Ok(Some(index))
+}
+
+pub fn clear(writer: &mut heed::RwTxn, index: &Index) -> MResult<()> {
+    // clear all the stores
+    index.main.clear(writer)?;
+    index.postings_lists.clear(writer)?;
+    index.postings_docids.clear(writer)?;
+    index.documents_fields.clear(writer)?;
+    index.documents_fields_counts.clear(writer)?;
+    index.filters_docids.clear(writer)?;
+    index.synonyms.clear(writer)?;
+    index.docs_words.clear(writer)?;
+    index.query_rules.clear(writer)?;
+    index.words_pairs_proximities.clear(writer)?;
+    index.updates.clear(writer)?;
+    index.updates_results.clear(writer)?;
+    Ok(())
+}
+
+/// Copies the documents, words and settings of an index into another one,
+/// the updates and updates results stores are not copied.
+pub fn copy_data(writer: &mut heed::RwTxn, from: &Index, to: &Index) -> MResult<()> {
+    from.main.copy_to(writer, to.main)?;
+    from.postings_lists.copy_to(writer, to.postings_lists)?;
+    from.postings_docids.copy_to(writer, to.postings_docids)?;
+    from.documents_fields.copy_to(writer, to.documents_fields)?;
+    from.documents_fields_counts
+        .copy_to(writer, to.documents_fields_counts)?;
+    from.filters_docids.copy_to(writer, to.filters_docids)?;
+    from.synonyms.copy_to(writer, to.synonyms)?;
+    from.docs_words.copy_to(writer, to.docs_words)?;
+    from.query_rules.copy_to(writer, to.query_rules)?;
+    from.words_pairs_proximities
+        .copy_to(writer, to.words_pairs_proximities)?;
+    Ok(())
+}
+
+/// Copies all the stores of an index into another one, updates history included.
+pub fn copy_all(writer: &mut heed::RwTxn, from: &Index, to: &Index) -> MResult<()> {
+    copy_data(writer, from, to)?;
+    from.updates.copy_to(writer, to.updates)?;
+    from.updates_results.copy_to(writer, to.updates_results)?;
+    Ok(())
 }
@@ -0,0 +1,99 @@
+use heed::types::ByteSlice;
+use heed::Result as ZResult;
+use roaring::RoaringTreemap;
+
+use super::StoredSize;
+
+/// The version of the layout of the postings docids, the indexes
+/// created before them are filled by a migration.
+pub(crate) const POSTINGS_DOCIDS_VERSION: u32 = 1;
+
+/// The identifiers of the documents containing each word, stored
+/// as compressed bitmaps to be intersected without the positions.
+#[derive(Copy, Clone)]
+pub struct PostingsDocids {
+    pub(crate) postings_docids: heed::Database<ByteSlice, ByteSlice>,
+    pub(crate) size: StoredSize,
+}
+
+impl PostingsDocids {
+    pub fn put_postings_docids(
+        self,
+        writer: &mut heed::RwTxn,
+        word: &[u8],
+        docids: &RoaringTreemap,
+    ) -> ZResult<()> {
+        let mut bytes = Vec::with_capacity(docids.serialized_size());
+        // writing into a vector cannot fail
+        docids.serialize_into(&mut bytes).unwrap();
+
+        let old = self.entry_size(writer, word)?;
+        self.postings_docids.put(writer, word, &bytes)?;
+        let new = word.len() + bytes.len();
+        self.size
+            .replace(writer, old, new, |reader| self.count_stored_size(reader))
+    }
+
+    pub fn del_postings_docids(self, writer: &mut heed::RwTxn, word: &[u8]) -> ZResult<bool> {
+        let old = self.entry_size(writer, word)?;
+        let deleted = self.postings_docids.delete(writer, word)?;
+        self.size
+            .replace(writer, old, 0, |reader| self.count_stored_size(reader))?;
+        Ok(deleted)
+    }
+
+    pub fn clear(self, writer: &mut heed::RwTxn) -> ZResult<()> {
+        self.postings_docids.clear(writer)?;
+        self.size.reset(writer)
+    }
+
+    fn entry_size(self, reader: &heed::RoTxn, word: &[u8]) -> ZResult<usize> {
+        let docids = self.postings_docids.get(reader, word)?;
+        Ok(docids.map_or(0, |docids| word.len() + docids.len()))
+    }
+
+    pub fn copy_to(self, writer: &mut heed::RwTxn, other: PostingsDocids) -> ZResult<()> {
+        let mut entries = Vec::new();
+        for result in self.postings_docids.iter(writer)? {
+            let (word, docids) = result?;
+            entries.push((word.to_vec(), docids.to_vec()));
+        }
+
+        for (word, docids) in entries {
+            other.postings_docids.put(writer, &word, &docids)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns an estimation of the number of bytes stored in this store.
+    pub fn stored_size(self, reader: &heed::RoTxn) -> ZResult<u64> {
+        match self.size.get(reader)? {
+            Some(size) => Ok(size),
+            None => self.count_stored_size(reader),
+        }
+    }
+
+    fn count_stored_size(self, reader: &heed::RoTxn) -> ZResult<u64> {
+        let mut size = 0;
+        for result in self.postings_docids.iter(reader)? {
+            let (word, docids) = result?;
+            size += (word.len() + docids.len()) as u64;
+        }
+        Ok(size)
+    }
+
+    pub fn postings_docids(
+        self,
+        reader: &heed::RoTxn,
+        word: &[u8],
+    ) -> ZResult<Option<RoaringTreemap>> {
+        match self.postings_docids.get(reader, word)? {
+            Some(bytes) => match RoaringTreemap::deserialize_from(bytes) {
+                Ok(docids) => Ok(Some(docids)),
+                Err(_) => Err(heed::Error::Decoding),
+            },
+            None => Ok(None),
+        }
+    }
+}
@@ -1,12 +1,19 @@
+use super::StoredSize;
 use crate::DocIndex;
 use heed::types::{ByteSlice, CowSlice};
 use heed::Result as ZResult;
 use sdset::{Set, SetBuf};
 use std::borrow::Cow;
+use std::mem;
 
 #[derive(Copy, Clone)]
 pub struct PostingsLists {
     pub(crate) postings_lists: heed::Database<ByteSlice, CowSlice<DocIndex>>,
+    pub(crate) size: StoredSize,
+}
+
+fn entry_size(word: &[u8], postings_list: &[DocIndex]) -> usize {
+    word.len() + postings_list.len() * mem::size_of::<DocIndex>()
 }
 
 impl PostingsLists {
@@ -16,15 +23,60 @@ impl PostingsLists {
         word: &[u8],
         words_indexes: &Set<DocIndex>,
     ) -> ZResult<()> {
-        self.postings_lists.put(writer, word, words_indexes)
+        let old = self.entry_size(writer, word)?;
+        self.postings_lists.put(writer, word, words_indexes)?;
+        let new = entry_size(word, words_indexes);
+        self.size
+            .replace(writer, old, new, |reader| self.count_stored_size(reader))
     }
 
     pub fn del_postings_list(self, writer: &mut heed::RwTxn, word: &[u8]) -> ZResult<bool> {
-        self.postings_lists.delete(writer, word)
+        let old = self.entry_size(writer, word)?;
+        let deleted = self.postings_lists.delete(writer, word)?;
+        self.size
+            .replace(writer, old, 0, |reader| self.count_stored_size(reader))?;
+        Ok(deleted)
     }
 
     pub fn clear(self, writer: &mut heed::RwTxn) -> ZResult<()> {
-        self.postings_lists.clear(writer)
+        self.postings_lists.clear(writer)?;
+        self.size.reset(writer)
+    }
+
+    fn entry_size(self, reader: &heed::RoTxn, word: &[u8]) -> ZResult<usize> {
+        let postings_list = self.postings_lists.get(reader, word)?;
+        Ok(postings_list.map_or(0, |list| entry_size(word, &list)))
+    }
+
+    pub fn copy_to(self, writer: &mut heed::RwTxn, other: PostingsLists) -> ZResult<()> {
+        let mut entries = Vec::new();
+        for result in self.postings_lists.iter(writer)? {
+            let (word, postings_list) = result?;
+            entries.push((word.to_vec(), postings_list.into_owned()));
+        }
+
+        for (word, postings_list) in entries {
+            other.postings_lists.put(writer, &word, &postings_list)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns an estimation of the number of bytes stored in this store.
+    pub fn stored_size(self, reader: &heed::RoTxn) -> ZResult<u64> {
+        match self.size.get(reader)? {
+            Some(size) => Ok(size),
+            None => self.count_stored_size(reader),
+        }
+    }
+
+    fn count_stored_size(self, reader: &heed::RoTxn) -> ZResult<u64> {
+        let mut size = 0;
+        for result in self.postings_lists.iter(reader)? {
+            let (word, postings_list) = result?;
+            size += entry_size(word, &postings_list) as u64;
+        }
+        Ok(size)
     }
 
     pub fn postings_list<'txn>(
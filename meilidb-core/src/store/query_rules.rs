@@ -0,0 +1,58 @@
+use heed::types::{SerdeJson, Str};
+use heed::Result as ZResult;
+
+use crate::query_rule::QueryRule;
+
+/// The query rules of an index, by their id.
+#[derive(Copy, Clone)]
+pub struct QueryRules {
+    pub(crate) query_rules: heed::Database<Str, SerdeJson<QueryRule>>,
+}
+
+impl QueryRules {
+    pub fn put_rule(self, writer: &mut heed::RwTxn, id: &str, rule: &QueryRule) -> ZResult<()> {
+        self.query_rules.put(writer, id, rule)
+    }
+
+    pub fn del_rule(self, writer: &mut heed::RwTxn, id: &str) -> ZResult<bool> {
+        self.query_rules.delete(writer, id)
+    }
+
+    pub fn rule(self, reader: &heed::RoTxn, id: &str) -> ZResult<Option<QueryRule>> {
+        self.query_rules.get(reader, id)
+    }
+
+    /// Returns all the rules sorted by id.
+    pub fn rules(self, reader: &heed::RoTxn) -> ZResult<Vec<(String, QueryRule)>> {
+        let mut rules = Vec::new();
+        for result in self.query_rules.iter(reader)? {
+            let (id, rule) = result?;
+            rules.push((id.to_owned(), rule));
+        }
+        Ok(rules)
+    }
+
+    /// Returns the rules whose condition matches the normalized query, sorted by id.
+    pub fn matching_rules(self, reader: &heed::RoTxn, query: &str) -> ZResult<Vec<QueryRule>> {
+        let mut rules = Vec::new();
+        for result in self.query_rules.iter(reader)? {
+            let (_, rule) = result?;
+            if rule.condition.matches(query) {
+                rules.push(rule);
+            }
+        }
+        Ok(rules)
+    }
+
+    pub fn clear(self, writer: &mut heed::RwTxn) -> ZResult<()> {
+        self.query_rules.clear(writer)
+    }
+
+    pub fn copy_to(self, writer: &mut heed::RwTxn, other: QueryRules) -> ZResult<()> {
+        let rules = self.rules(writer)?;
+        for (id, rule) in rules {
+            other.query_rules.put(writer, &id, &rule)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+use heed::types::{OwnedType, Str};
+use heed::Result as ZResult;
+
+/// The prefix of the keys of the sizes in the main store.
+pub(crate) const STORED_SIZE_PREFIX: &str = "stored-size-";
+
+/// The number of bytes stored in one of the stores of an index, it is kept in
+/// the main store and updated by the writes so that the stores are not iterated.
+#[derive(Copy, Clone)]
+pub struct StoredSize {
+    main: heed::PolyDatabase,
+    key: &'static str,
+}
+
+impl StoredSize {
+    pub(crate) fn new(main: heed::PolyDatabase, key: &'static str) -> StoredSize {
+        StoredSize { main, key }
+    }
+
+    /// Returns the counted size, `None` if it has never been counted.
+    pub fn get(self, reader: &heed::RoTxn) -> ZResult<Option<u64>> {
+        self.main.get::<Str, OwnedType<u64>>(reader, self.key)
+    }
+
+    /// Accounts for an entry of `old` bytes replaced by one of `new` bytes, the size
+    /// of a store that has never been counted is computed by iterating it with `count`.
+    pub(crate) fn replace<F>(
+        self,
+        writer: &mut heed::RwTxn,
+        old: usize,
+        new: usize,
+        count: F,
+    ) -> ZResult<()>
+    where
+        F: FnOnce(&heed::RoTxn) -> ZResult<u64>,
+    {
+        let size = match self.get(writer)? {
+            Some(size) => (size + new as u64).saturating_sub(old as u64),
+            None => {
+                let reader: &heed::RoTxn = writer;
+                count(reader)?
+            }
+        };
+        self.main
+            .put::<Str, OwnedType<u64>>(writer, self.key, &size)
+    }
+
+    /// Forgets the size, it is counted again on the next write.
+    pub(crate) fn reset(self, writer: &mut heed::RwTxn) -> ZResult<()> {
+        self.main.delete::<Str>(writer, self.key).map(drop)
+    }
+}
@@ -22,6 +22,24 @@ impl Synonyms {
         self.synonyms.delete(writer, word)
     }
 
+    pub fn clear(self, writer: &mut heed::RwTxn) -> ZResult<()> {
+        self.synonyms.clear(writer)
+    }
+
+    pub fn copy_to(self, writer: &mut heed::RwTxn, other: Synonyms) -> ZResult<()> {
+        let mut entries = Vec::new();
+        for result in self.synonyms.iter(writer)? {
+            let (word, synonyms) = result?;
+            entries.push((word.to_vec(), synonyms.to_vec()));
+        }
+
+        for (word, synonyms) in entries {
+            other.synonyms.put(writer, &word, &synonyms)?;
+        }
+
+        Ok(())
+    }
+
     pub fn synonyms(self, reader: &heed::RoTxn, word: &[u8]) -> ZResult<Option<fst::Set>> {
         match self.synonyms.get(reader, word)? {
             Some(bytes) => {
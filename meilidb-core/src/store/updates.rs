@@ -31,6 +31,29 @@ impl Updates {
         self.updates.get(reader, &update_id)
     }
 
+    /// The number of updates waiting to be processed.
+    pub fn len(self, reader: &heed::RoTxn) -> ZResult<usize> {
+        self.updates.len(reader)
+    }
+
+    pub fn is_empty(self, reader: &heed::RoTxn) -> ZResult<bool> {
+        self.len(reader).map(|len| len == 0)
+    }
+
+    /// Whether one of the updates waiting to be processed matches the predicate.
+    pub fn any<F>(self, reader: &heed::RoTxn, predicate: F) -> ZResult<bool>
+    where
+        F: Fn(&Update) -> bool,
+    {
+        for result in self.updates.iter(reader)? {
+            let (_, update) = result?;
+            if predicate(&update) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
     pub fn put_update(
         self,
         writer: &mut heed::RwTxn,
@@ -42,6 +65,23 @@ impl Updates {
         self.updates.put(writer, &update_id, update)
     }
 
+    pub fn clear(self, writer: &mut heed::RwTxn) -> ZResult<()> {
+        self.updates.clear(writer)
+    }
+
+    pub fn copy_to(self, writer: &mut heed::RwTxn, other: Updates) -> ZResult<()> {
+        let mut entries = Vec::new();
+        for result in self.updates.iter(writer)? {
+            entries.push(result?);
+        }
+
+        for (update_id, update) in entries {
+            other.updates.put(writer, &update_id, &update)?;
+        }
+
+        Ok(())
+    }
+
     pub fn pop_front(self, writer: &mut heed::RwTxn) -> ZResult<Option<(u64, Update)>> {
         match self.first_update_id(writer)? {
             Some((update_id, update)) => {
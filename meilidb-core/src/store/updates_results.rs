@@ -30,6 +30,25 @@ impl UpdatesResults {
         self.updates_results.put(writer, &update_id, update_result)
     }
 
+    pub fn clear(self, writer: &mut heed::RwTxn) -> ZResult<()> {
+        self.updates_results.clear(writer)
+    }
+
+    pub fn copy_to(self, writer: &mut heed::RwTxn, other: UpdatesResults) -> ZResult<()> {
+        let mut entries = Vec::new();
+        for result in self.updates_results.iter(writer)? {
+            entries.push(result?);
+        }
+
+        for (update_id, update_result) in entries {
+            other
+                .updates_results
+                .put(writer, &update_id, &update_result)?;
+        }
+
+        Ok(())
+    }
+
     pub fn update_result(
         self,
         reader: &heed::RoTxn,
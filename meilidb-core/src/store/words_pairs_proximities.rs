@@ -0,0 +1,169 @@
+use super::StoredSize;
+use crate::{DocumentId, PairProximity};
+use heed::types::{ByteSlice, CowSlice};
+use heed::Result as ZResult;
+use sdset::{duo::DifferenceByKey, Set, SetBuf, SetOperation};
+use std::borrow::Cow;
+use std::mem;
+
+/// The version of the layout of the stored pairs, the first
+/// one stored the pairs in both orders of their words.
+pub(crate) const WORDS_PAIRS_PROXIMITIES_VERSION: u32 = 2;
+
+/// Separates the two words of a pair in the keys,
+/// this byte never appears in an UTF-8 string.
+const PAIR_SEPARATOR: u8 = 0xFF;
+
+fn pair_key(word_a: &[u8], word_b: &[u8]) -> Vec<u8> {
+    let mut key = Vec::with_capacity(word_a.len() + 1 + word_b.len());
+    key.extend_from_slice(word_a);
+    key.push(PAIR_SEPARATOR);
+    key.extend_from_slice(word_b);
+    key
+}
+
+#[derive(Copy, Clone)]
+pub struct WordsPairsProximities {
+    pub(crate) words_pairs_proximities: heed::Database<ByteSlice, CowSlice<PairProximity>>,
+    pub(crate) size: StoredSize,
+}
+
+fn entry_size(key: &[u8], proximities: &[PairProximity]) -> usize {
+    key.len() + proximities.len() * mem::size_of::<PairProximity>()
+}
+
+impl WordsPairsProximities {
+    pub fn put_pair_proximities(
+        self,
+        writer: &mut heed::RwTxn,
+        word_a: &[u8],
+        word_b: &[u8],
+        proximities: &Set<PairProximity>,
+    ) -> ZResult<()> {
+        let key = pair_key(word_a, word_b);
+        let old = self.entry_size(writer, &key)?;
+        self.words_pairs_proximities
+            .put(writer, &key, proximities)?;
+        let new = entry_size(&key, proximities);
+        self.size
+            .replace(writer, old, new, |reader| self.count_stored_size(reader))
+    }
+
+    /// Removes the documents from the proximities of every pair starting with
+    /// this word, the documents must contain the word to be removed from a pair.
+    pub fn del_documents_pairs(
+        self,
+        writer: &mut heed::RwTxn,
+        word_a: &[u8],
+        documents_ids: &Set<DocumentId>,
+    ) -> ZResult<()> {
+        let prefix = pair_key(word_a, &[]);
+
+        let mut changes = Vec::new();
+        for result in self.words_pairs_proximities.prefix_iter(writer, &prefix)? {
+            let (key, proximities) = result?;
+            let proximities = Set::new_unchecked(proximities.as_ref());
+            let op = DifferenceByKey::new(proximities, documents_ids, |p| p.document_id, |id| *id);
+            let remaining = op.into_set_buf();
+            if remaining.len() != proximities.len() {
+                changes.push((key.to_vec(), remaining));
+            }
+        }
+
+        for (key, proximities) in changes {
+            let old = self.entry_size(writer, &key)?;
+            let new = if proximities.is_empty() {
+                self.words_pairs_proximities.delete(writer, &key)?;
+                0
+            } else {
+                self.words_pairs_proximities
+                    .put(writer, &key, &proximities)?;
+                entry_size(&key, &proximities)
+            };
+            self.size
+                .replace(writer, old, new, |reader| self.count_stored_size(reader))?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes every pair containing one of these words, whatever its position in the pair.
+    pub fn del_words_pairs(self, writer: &mut heed::RwTxn, words: &fst::Set) -> ZResult<()> {
+        let mut keys = Vec::new();
+        for result in self.words_pairs_proximities.iter(writer)? {
+            let (key, _) = result?;
+            let mut pair = key.splitn(2, |b| *b == PAIR_SEPARATOR);
+            if pair.any(|word| words.contains(word)) {
+                keys.push(key.to_vec());
+            }
+        }
+
+        for key in keys {
+            let old = self.entry_size(writer, &key)?;
+            self.words_pairs_proximities.delete(writer, &key)?;
+            self.size
+                .replace(writer, old, 0, |reader| self.count_stored_size(reader))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn clear(self, writer: &mut heed::RwTxn) -> ZResult<()> {
+        self.words_pairs_proximities.clear(writer)?;
+        self.size.reset(writer)
+    }
+
+    fn entry_size(self, reader: &heed::RoTxn, key: &[u8]) -> ZResult<usize> {
+        let proximities = self.words_pairs_proximities.get(reader, key)?;
+        Ok(proximities.map_or(0, |proximities| entry_size(key, &proximities)))
+    }
+
+    pub fn copy_to(self, writer: &mut heed::RwTxn, other: WordsPairsProximities) -> ZResult<()> {
+        let mut entries = Vec::new();
+        for result in self.words_pairs_proximities.iter(writer)? {
+            let (key, proximities) = result?;
+            entries.push((key.to_vec(), proximities.into_owned()));
+        }
+
+        for (key, proximities) in entries {
+            other
+                .words_pairs_proximities
+                .put(writer, &key, &proximities)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns an estimation of the number of bytes stored in this store.
+    pub fn stored_size(self, reader: &heed::RoTxn) -> ZResult<u64> {
+        match self.size.get(reader)? {
+            Some(size) => Ok(size),
+            None => self.count_stored_size(reader),
+        }
+    }
+
+    fn count_stored_size(self, reader: &heed::RoTxn) -> ZResult<u64> {
+        let mut size = 0;
+        for result in self.words_pairs_proximities.iter(reader)? {
+            let (key, proximities) = result?;
+            size += entry_size(key, &proximities) as u64;
+        }
+        Ok(size)
+    }
+
+    /// Returns the proximities of the first word followed
+    /// by the second one in the documents that contain both.
+    pub fn pair_proximities<'txn>(
+        self,
+        reader: &'txn heed::RoTxn,
+        word_a: &[u8],
+        word_b: &[u8],
+    ) -> ZResult<Option<Cow<'txn, Set<PairProximity>>>> {
+        let key = pair_key(word_a, word_b);
+        match self.words_pairs_proximities.get(reader, &key)? {
+            Some(Cow::Borrowed(slice)) => Ok(Some(Cow::Borrowed(Set::new_unchecked(slice)))),
+            Some(Cow::Owned(vec)) => Ok(Some(Cow::Owned(SetBuf::new_unchecked(vec)))),
+            None => Ok(None),
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use crate::update::{next_update_id, Update};
-use crate::{store, MResult, RankedMap};
+use crate::{store, MResult, PopularityMap, RankedMap};
 
 pub fn apply_clear_all(
     writer: &mut heed::RwTxn,
@@ -7,15 +7,22 @@ pub fn apply_clear_all(
     documents_fields_store: store::DocumentsFields,
     documents_fields_counts_store: store::DocumentsFieldsCounts,
     postings_lists_store: store::PostingsLists,
+    postings_docids_store: store::PostingsDocids,
+    filters_docids_store: store::FiltersDocids,
     docs_words_store: store::DocsWords,
+    words_pairs_proximities_store: store::WordsPairsProximities,
 ) -> MResult<()> {
     main_store.put_words_fst(writer, &fst::Set::default())?;
     main_store.put_ranked_map(writer, &RankedMap::default())?;
+    main_store.put_popularity_map(writer, &PopularityMap::default())?;
     main_store.put_number_of_documents(writer, |_| 0)?;
     documents_fields_store.clear(writer)?;
     documents_fields_counts_store.clear(writer)?;
     postings_lists_store.clear(writer)?;
+    postings_docids_store.clear(writer)?;
+    filters_docids_store.clear(writer)?;
     docs_words_store.clear(writer)?;
+    words_pairs_proximities_store.clear(writer)?;
 
     Ok(())
 }
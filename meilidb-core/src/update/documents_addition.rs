@@ -1,29 +1,35 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use fst::{set::OpBuilder, SetBuilder};
-use sdset::{duo::Union, SetOperation};
+use meilidb_schema::{Schema, SchemaAttr};
+use roaring::RoaringTreemap;
+use sdset::{duo::Union, SetBuf, SetOperation};
 use serde::Serialize;
 
+use crate::database::{UpdateEvent, UpdateEventsEmitter};
 use crate::raw_indexer::RawIndexer;
-use crate::serde::{extract_document_id, serialize_value, Serializer};
+use crate::serde::{extract_document_id, serialize_value, Indexer, Serializer};
 use crate::store;
 use crate::update::{apply_documents_deletion, next_update_id, Update};
-use crate::{Error, MResult, RankedMap};
+use crate::{DocumentId, Error, MResult, PairProximity, RankedMap};
 
 pub struct DocumentsAddition<D> {
+    main_store: store::Main,
     updates_store: store::Updates,
     updates_results_store: store::UpdatesResults,
-    updates_notifier: crossbeam_channel::Sender<()>,
+    updates_notifier: UpdateEventsEmitter,
     documents: Vec<D>,
 }
 
 impl<D> DocumentsAddition<D> {
     pub fn new(
+        main_store: store::Main,
         updates_store: store::Updates,
         updates_results_store: store::UpdatesResults,
-        updates_notifier: crossbeam_channel::Sender<()>,
+        updates_notifier: UpdateEventsEmitter,
     ) -> DocumentsAddition<D> {
         DocumentsAddition {
+            main_store,
             updates_store,
             updates_results_store,
             updates_notifier,
@@ -39,9 +45,10 @@ impl<D> DocumentsAddition<D> {
     where
         D: serde::Serialize,
     {
-        let _ = self.updates_notifier.send(());
+        let _ = self.updates_notifier.send(UpdateEvent::NewUpdate);
         let update_id = push_documents_addition(
             writer,
+            self.main_store,
             self.updates_store,
             self.updates_results_store,
             self.documents,
@@ -58,14 +65,18 @@ impl<D> Extend<D> for DocumentsAddition<D> {
 
 pub fn push_documents_addition<D: serde::Serialize>(
     writer: &mut heed::RwTxn,
+    main_store: store::Main,
     updates_store: store::Updates,
     updates_results_store: store::UpdatesResults,
     addition: Vec<D>,
 ) -> MResult<u64> {
+    let limits = main_store.limits(writer)?.unwrap_or_default();
+
     let mut values = Vec::with_capacity(addition.len());
     for add in addition {
         let vec = serde_json::to_vec(&add)?;
         let add = serde_json::from_slice(&vec)?;
+        limits.check_document(&add, vec.len())?;
         values.push(add);
     }
 
@@ -83,7 +94,10 @@ pub fn apply_documents_addition(
     documents_fields_store: store::DocumentsFields,
     documents_fields_counts_store: store::DocumentsFieldsCounts,
     postings_lists_store: store::PostingsLists,
+    postings_docids_store: store::PostingsDocids,
+    filters_docids_store: store::FiltersDocids,
     docs_words_store: store::DocsWords,
+    words_pairs_proximities_store: store::WordsPairsProximities,
     addition: Vec<serde_json::Value>,
 ) -> MResult<()> {
     let mut documents_additions = HashMap::new();
@@ -105,7 +119,22 @@ pub fn apply_documents_addition(
         documents_additions.insert(document_id, document);
     }
 
-    // 2. remove the documents posting lists
+    // 2. check the index limits before writing anything,
+    //    the limits could have changed since the update was pushed
+    check_documents_addition_limits(
+        writer,
+        main_store,
+        documents_fields_store,
+        documents_fields_counts_store,
+        postings_lists_store,
+        postings_docids_store,
+        filters_docids_store,
+        docs_words_store,
+        words_pairs_proximities_store,
+        &documents_additions,
+    )?;
+
+    // 3. remove the documents posting lists
     let number_of_inserted_documents = documents_additions.len();
     let documents_ids = documents_additions.iter().map(|(id, _)| *id).collect();
     apply_documents_deletion(
@@ -114,7 +143,10 @@ pub fn apply_documents_addition(
         documents_fields_store,
         documents_fields_counts_store,
         postings_lists_store,
+        postings_docids_store,
+        filters_docids_store,
         docs_words_store,
+        words_pairs_proximities_store,
         documents_ids,
     )?;
 
@@ -128,7 +160,7 @@ pub fn apply_documents_addition(
         None => fst::Set::default(),
     };
 
-    // 3. index the documents fields in the stores
+    // 4. index the documents fields in the stores
     let mut indexer = RawIndexer::new(stop_words);
 
     for (document_id, document) in documents_additions {
@@ -149,20 +181,77 @@ pub fn apply_documents_addition(
         writer,
         main_store,
         postings_lists_store,
+        postings_docids_store,
+        filters_docids_store,
         docs_words_store,
+        words_pairs_proximities_store,
         &ranked_map,
         number_of_inserted_documents,
         indexer,
     )
 }
 
+fn check_documents_addition_limits(
+    reader: &heed::RoTxn,
+    main_store: store::Main,
+    documents_fields_store: store::DocumentsFields,
+    documents_fields_counts_store: store::DocumentsFieldsCounts,
+    postings_lists_store: store::PostingsLists,
+    postings_docids_store: store::PostingsDocids,
+    filters_docids_store: store::FiltersDocids,
+    docs_words_store: store::DocsWords,
+    words_pairs_proximities_store: store::WordsPairsProximities,
+    documents_additions: &HashMap<DocumentId, serde_json::Value>,
+) -> MResult<()> {
+    let limits = main_store.limits(reader)?.unwrap_or_default();
+
+    let mut size = 0;
+    for document in documents_additions.values() {
+        let bytes = serde_json::to_vec(document)?;
+        limits.check_document(document, bytes.len())?;
+        size += bytes.len() as u64;
+    }
+
+    if limits.max_documents.is_some() {
+        let mut new_documents = 0;
+        for document_id in documents_additions.keys() {
+            let mut fields_counts =
+                documents_fields_counts_store.document_fields_counts(reader, *document_id)?;
+            if fields_counts.next().is_none() {
+                new_documents += 1;
+            }
+        }
+
+        let number_of_documents = main_store.number_of_documents(reader)?;
+        limits.check_number_of_documents(number_of_documents + new_documents)?;
+    }
+
+    // the size of the documents replaced is not subtracted, the estimation
+    // is an upper bound of the size of the index once the addition is done
+    if limits.max_index_size.is_some() {
+        let index_size = main_store.stored_size(reader)?
+            + postings_lists_store.stored_size(reader)?
+            + postings_docids_store.stored_size(reader)?
+            + filters_docids_store.stored_size(reader)?
+            + documents_fields_store.stored_size(reader)?
+            + docs_words_store.stored_size(reader)?
+            + words_pairs_proximities_store.stored_size(reader)?;
+        limits.check_index_size(index_size + size)?;
+    }
+
+    Ok(())
+}
+
 pub fn reindex_all_documents(
     writer: &mut heed::RwTxn,
     main_store: store::Main,
     documents_fields_store: store::DocumentsFields,
     documents_fields_counts_store: store::DocumentsFieldsCounts,
     postings_lists_store: store::PostingsLists,
+    postings_docids_store: store::PostingsDocids,
+    filters_docids_store: store::FiltersDocids,
     docs_words_store: store::DocsWords,
+    words_pairs_proximities_store: store::WordsPairsProximities,
 ) -> MResult<()> {
     let schema = match main_store.schema(writer)? {
         Some(schema) => schema,
@@ -183,7 +272,10 @@ pub fn reindex_all_documents(
     main_store.put_ranked_map(writer, &ranked_map)?;
     main_store.put_number_of_documents(writer, |_| 0)?;
     postings_lists_store.clear(writer)?;
+    postings_docids_store.clear(writer)?;
+    filters_docids_store.clear(writer)?;
     docs_words_store.clear(writer)?;
+    words_pairs_proximities_store.clear(writer)?;
 
     // 3. re-index chunks of documents (otherwise we make the borrow checker unhappy)
     for documents_ids in documents_ids_to_reindex.chunks(100) {
@@ -223,7 +315,10 @@ pub fn reindex_all_documents(
             writer,
             main_store,
             postings_lists_store,
+            postings_docids_store,
+            filters_docids_store,
             docs_words_store,
+            words_pairs_proximities_store,
             &ranked_map,
             number_of_inserted_documents,
             indexer,
@@ -237,7 +332,10 @@ pub fn write_documents_addition_index(
     writer: &mut heed::RwTxn,
     main_store: store::Main,
     postings_lists_store: store::PostingsLists,
+    postings_docids_store: store::PostingsDocids,
+    filters_docids_store: store::FiltersDocids,
     docs_words_store: store::DocsWords,
+    words_pairs_proximities_store: store::WordsPairsProximities,
     ranked_map: &RankedMap,
     number_of_inserted_documents: usize,
     indexer: RawIndexer,
@@ -248,6 +346,14 @@ pub fn write_documents_addition_index(
     for (word, delta_set) in indexed.words_doc_indexes {
         delta_words_builder.insert(&word).unwrap();
 
+        let mut docids = postings_docids_store
+            .postings_docids(writer, &word)?
+            .unwrap_or_else(RoaringTreemap::new);
+        for doc_index in delta_set.iter() {
+            docids.insert(doc_index.document_id.0);
+        }
+        postings_docids_store.put_postings_docids(writer, &word, &docids)?;
+
         let set = match postings_lists_store.postings_list(writer, &word)? {
             Some(set) => Union::new(&set, &delta_set).into_set_buf(),
             None => delta_set,
@@ -260,6 +366,14 @@ pub fn write_documents_addition_index(
         docs_words_store.put_doc_words(writer, id, &words)?;
     }
 
+    write_filters_docids(writer, filters_docids_store, indexed.filters_docids)?;
+
+    write_words_pairs_proximities(
+        writer,
+        words_pairs_proximities_store,
+        indexed.words_pairs_proximities,
+    )?;
+
     let delta_words = delta_words_builder
         .into_inner()
         .and_then(fst::Set::from_bytes)
@@ -288,3 +402,159 @@ pub fn write_documents_addition_index(
 
     Ok(())
 }
+
+fn write_words_pairs_proximities(
+    writer: &mut heed::RwTxn,
+    words_pairs_proximities_store: store::WordsPairsProximities,
+    words_pairs_proximities: BTreeMap<(Vec<u8>, Vec<u8>), SetBuf<PairProximity>>,
+) -> MResult<()> {
+    for ((word_a, word_b), delta_set) in words_pairs_proximities {
+        let set = match words_pairs_proximities_store.pair_proximities(writer, &word_a, &word_b)? {
+            Some(set) => Union::new(&set, &delta_set).into_set_buf(),
+            None => delta_set,
+        };
+
+        words_pairs_proximities_store.put_pair_proximities(writer, &word_a, &word_b, &set)?;
+    }
+
+    Ok(())
+}
+
+fn write_filters_docids(
+    writer: &mut heed::RwTxn,
+    filters_docids_store: store::FiltersDocids,
+    filters_docids: BTreeMap<(SchemaAttr, String), RoaringTreemap>,
+) -> MResult<()> {
+    for ((attr, value), delta_docids) in filters_docids {
+        let mut docids = filters_docids_store
+            .filter_docids(writer, attr, &value)?
+            .unwrap_or_else(RoaringTreemap::new);
+        docids.union_with(&delta_docids);
+        filters_docids_store.put_filter_docids(writer, attr, &value, &docids)?;
+    }
+
+    Ok(())
+}
+
+/// Indexes the stored fields of the documents to find back their words,
+/// without writing anything in the stores.
+pub fn index_stored_documents(
+    reader: &heed::RoTxn,
+    schema: &Schema,
+    documents_fields_store: store::DocumentsFields,
+    documents_ids: &[DocumentId],
+    indexer: &mut RawIndexer,
+) -> MResult<()> {
+    for document_id in documents_ids {
+        for result in documents_fields_store.document_fields(reader, *document_id)? {
+            let (attr, bytes) = result?;
+            if schema.props(attr).is_indexed() {
+                let value: serde_json::Value = serde_json::from_slice(bytes)?;
+                let indexer = Indexer {
+                    attribute: attr,
+                    indexer: &mut *indexer,
+                    document_id: *document_id,
+                };
+                value.serialize(indexer)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Fills the postings docids store from the postings lists, used by
+/// the migration of the indexes created before this store existed.
+pub fn index_postings_docids(
+    writer: &mut heed::RwTxn,
+    postings_lists_store: store::PostingsLists,
+    postings_docids_store: store::PostingsDocids,
+) -> MResult<()> {
+    let mut postings_docids = Vec::new();
+    for result in postings_lists_store.postings_lists.iter(writer)? {
+        let (word, doc_indexes) = result?;
+        let docids: RoaringTreemap = doc_indexes.iter().map(|d| d.document_id.0).collect();
+        postings_docids.push((word.to_vec(), docids));
+    }
+
+    for (word, docids) in postings_docids {
+        postings_docids_store.put_postings_docids(writer, &word, &docids)?;
+    }
+
+    Ok(())
+}
+
+/// Fills the filters docids store from the documents fields, used by the
+/// migration of the indexes created before this store existed.
+pub fn index_filters_docids(
+    writer: &mut heed::RwTxn,
+    documents_fields_store: store::DocumentsFields,
+    documents_fields_counts_store: store::DocumentsFieldsCounts,
+    filters_docids_store: store::FiltersDocids,
+) -> MResult<()> {
+    let mut documents_ids = Vec::new();
+    for result in documents_fields_counts_store.documents_ids(writer)? {
+        documents_ids.push(result?);
+    }
+
+    for documents_ids in documents_ids.chunks(100) {
+        let mut indexer = RawIndexer::new(fst::Set::default());
+        for document_id in documents_ids {
+            for result in documents_fields_store.document_fields(writer, *document_id)? {
+                let (attr, bytes) = result?;
+                let value: serde_json::Value = serde_json::from_slice(bytes)?;
+                indexer.index_filter_values(*document_id, attr, &value);
+            }
+        }
+
+        let indexed = indexer.build();
+        write_filters_docids(writer, filters_docids_store, indexed.filters_docids)?;
+    }
+
+    Ok(())
+}
+
+/// Fills the words pairs proximities store from the documents already indexed, used by
+/// the migration of the indexes created before this store or its current layout existed.
+pub fn index_words_pairs_proximities(
+    writer: &mut heed::RwTxn,
+    main_store: store::Main,
+    documents_fields_store: store::DocumentsFields,
+    documents_fields_counts_store: store::DocumentsFieldsCounts,
+    words_pairs_proximities_store: store::WordsPairsProximities,
+) -> MResult<()> {
+    let schema = match main_store.schema(writer)? {
+        Some(schema) => schema,
+        None => return Ok(()),
+    };
+
+    let mut documents_ids = Vec::new();
+    for result in documents_fields_counts_store.documents_ids(writer)? {
+        documents_ids.push(result?);
+    }
+
+    for documents_ids in documents_ids.chunks(100) {
+        let stop_words = match main_store.stop_words_fst(writer)? {
+            Some(stop_words) => stop_words,
+            None => fst::Set::default(),
+        };
+
+        let mut indexer = RawIndexer::new(stop_words);
+        index_stored_documents(
+            writer,
+            &schema,
+            documents_fields_store,
+            documents_ids,
+            &mut indexer,
+        )?;
+
+        let indexed = indexer.build();
+        write_words_pairs_proximities(
+            writer,
+            words_pairs_proximities_store,
+            indexed.words_pairs_proximities,
+        )?;
+    }
+
+    Ok(())
+}
@@ -1,9 +1,11 @@
-use std::collections::{BTreeSet, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
 use fst::{SetBuilder, Streamer};
 use meilidb_schema::Schema;
+use roaring::RoaringTreemap;
 use sdset::{duo::DifferenceByKey, SetBuf, SetOperation};
 
+use crate::database::{UpdateEvent, UpdateEventsEmitter};
 use crate::serde::extract_document_id;
 use crate::store;
 use crate::update::{next_update_id, Update};
@@ -12,7 +14,7 @@ use crate::{DocumentId, Error, MResult, RankedMap};
 pub struct DocumentsDeletion {
     updates_store: store::Updates,
     updates_results_store: store::UpdatesResults,
-    updates_notifier: crossbeam_channel::Sender<()>,
+    updates_notifier: UpdateEventsEmitter,
     documents: Vec<DocumentId>,
 }
 
@@ -20,7 +22,7 @@ impl DocumentsDeletion {
     pub fn new(
         updates_store: store::Updates,
         updates_results_store: store::UpdatesResults,
-        updates_notifier: crossbeam_channel::Sender<()>,
+        updates_notifier: UpdateEventsEmitter,
     ) -> DocumentsDeletion {
         DocumentsDeletion {
             updates_store,
@@ -50,7 +52,7 @@ impl DocumentsDeletion {
     }
 
     pub fn finalize(self, writer: &mut heed::RwTxn) -> MResult<u64> {
-        let _ = self.updates_notifier.send(());
+        let _ = self.updates_notifier.send(UpdateEvent::NewUpdate);
         let update_id = push_documents_deletion(
             writer,
             self.updates_store,
@@ -87,7 +89,10 @@ pub fn apply_documents_deletion(
     documents_fields_store: store::DocumentsFields,
     documents_fields_counts_store: store::DocumentsFieldsCounts,
     postings_lists_store: store::PostingsLists,
+    postings_docids_store: store::PostingsDocids,
+    filters_docids_store: store::FiltersDocids,
     docs_words_store: store::DocsWords,
+    words_pairs_proximities_store: store::WordsPairsProximities,
     deletion: Vec<DocumentId>,
 ) -> MResult<()> {
     let idset = SetBuf::from_dirty(deletion);
@@ -116,6 +121,34 @@ pub fn apply_documents_deletion(
         )
         .collect();
 
+    // find back the values of the documents that filters can match,
+    // the stored fields are read before they are deleted
+    let mut filters_document_ids = BTreeMap::new();
+    for id in idset.iter() {
+        for result in documents_fields_store.document_fields(writer, *id)? {
+            let (attr, bytes) = result?;
+            let value: serde_json::Value = serde_json::from_slice(bytes)?;
+            for value in store::filter_values(&value) {
+                filters_document_ids
+                    .entry((attr, value))
+                    .or_insert_with(RoaringTreemap::new)
+                    .insert(id.0);
+            }
+        }
+    }
+
+    for ((attr, value), document_ids) in filters_document_ids {
+        if let Some(mut docids) = filters_docids_store.filter_docids(writer, attr, &value)? {
+            docids.difference_with(&document_ids);
+
+            if !docids.is_empty() {
+                filters_docids_store.put_filter_docids(writer, attr, &value, &docids)?;
+            } else {
+                filters_docids_store.del_filter_docids(writer, attr, &value)?;
+            }
+        }
+    }
+
     let mut words_document_ids = HashMap::new();
     for id in idset {
         // remove all the ranked attributes from the ranked_map
@@ -148,10 +181,25 @@ pub fn apply_documents_deletion(
                 postings_lists_store.put_postings_list(writer, &word, &doc_indexes)?;
             } else {
                 postings_lists_store.del_postings_list(writer, &word)?;
-                removed_words.insert(word);
+                removed_words.insert(word.clone());
+            }
+        }
+
+        if let Some(mut docids) = postings_docids_store.postings_docids(writer, &word)? {
+            for id in document_ids.iter() {
+                docids.remove(id.0);
+            }
+
+            if !docids.is_empty() {
+                postings_docids_store.put_postings_docids(writer, &word, &docids)?;
+            } else {
+                postings_docids_store.del_postings_docids(writer, &word)?;
             }
         }
 
+        // the pairs of words of a document are made of the words it contains
+        words_pairs_proximities_store.del_documents_pairs(writer, &word, &document_ids)?;
+
         for id in document_ids {
             documents_fields_counts_store.del_all_document_fields_counts(writer, id)?;
             if documents_fields_store.del_all_document_fields(writer, id)? != 0 {
@@ -189,3 +237,20 @@ pub fn apply_documents_deletion(
 
     Ok(())
 }
+
+/// Removes the popularity of the deleted documents, it must not be called when
+/// the documents are deleted to be replaced, their popularity must be kept.
+pub fn remove_documents_popularity(
+    writer: &mut heed::RwTxn,
+    main_store: store::Main,
+    deletion: &[DocumentId],
+) -> MResult<()> {
+    if let Some(mut popularity_map) = main_store.popularity_map(writer)? {
+        for id in deletion {
+            popularity_map.remove(*id);
+        }
+        main_store.put_popularity_map(writer, &popularity_map)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+
+use crate::update::{
+    index_filters_docids, index_postings_docids, index_words_pairs_proximities, next_update_id,
+    Update,
+};
+use crate::{store, MResult};
+
+/// The data of an index that must be rebuilt because it is missing or stored
+/// with an older layout, it can take a long time and is done by an update.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Migration {
+    WordsPairsProximities,
+    PostingsDocids,
+    FiltersDocids,
+}
+
+pub fn apply_migration(
+    writer: &mut heed::RwTxn,
+    main_store: store::Main,
+    postings_lists_store: store::PostingsLists,
+    postings_docids_store: store::PostingsDocids,
+    filters_docids_store: store::FiltersDocids,
+    documents_fields_store: store::DocumentsFields,
+    documents_fields_counts_store: store::DocumentsFieldsCounts,
+    words_pairs_proximities_store: store::WordsPairsProximities,
+    migration: Migration,
+) -> MResult<()> {
+    match migration {
+        Migration::WordsPairsProximities => {
+            // the migration is enqueued again if the index is reopened before it is done
+            if main_store.words_pairs_proximities_ready(writer)? {
+                return Ok(());
+            }
+
+            // the pairs written with the older layout are not kept
+            words_pairs_proximities_store.clear(writer)?;
+            index_words_pairs_proximities(
+                writer,
+                main_store,
+                documents_fields_store,
+                documents_fields_counts_store,
+                words_pairs_proximities_store,
+            )?;
+            main_store.put_words_pairs_proximities_version(writer)?;
+        }
+        Migration::PostingsDocids => {
+            if main_store.postings_docids_ready(writer)? {
+                return Ok(());
+            }
+
+            // the words removed since the store was created are not kept
+            postings_docids_store.clear(writer)?;
+            index_postings_docids(writer, postings_lists_store, postings_docids_store)?;
+            main_store.put_postings_docids_version(writer)?;
+        }
+        Migration::FiltersDocids => {
+            if main_store.filters_docids_ready(writer)? {
+                return Ok(());
+            }
+
+            // the values of the documents deleted since the store was created are not kept
+            filters_docids_store.clear(writer)?;
+            index_filters_docids(
+                writer,
+                documents_fields_store,
+                documents_fields_counts_store,
+                filters_docids_store,
+            )?;
+            main_store.put_filters_docids_version(writer)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn push_migration(
+    writer: &mut heed::RwTxn,
+    updates_store: store::Updates,
+    updates_results_store: store::UpdatesResults,
+    migration: Migration,
+) -> MResult<u64> {
+    let last_update_id = next_update_id(writer, updates_store, updates_results_store)?;
+    let update = Update::Migration(migration);
+    updates_store.put_update(writer, last_update_id, &update)?;
+
+    Ok(last_update_id)
+}
+
+/// Enqueues the migrations of the data of an index that is not stored with the
+/// current layout and not already enqueued, returns whether one has been enqueued.
+pub fn push_migrations(writer: &mut heed::RwTxn, index: &store::Index) -> MResult<bool> {
+    let mut migrations = Vec::new();
+    if !index.main.postings_docids_ready(writer)? {
+        migrations.push(Migration::PostingsDocids);
+    }
+    if !index.main.words_pairs_proximities_ready(writer)? {
+        migrations.push(Migration::WordsPairsProximities);
+    }
+    if !index.main.filters_docids_ready(writer)? {
+        migrations.push(Migration::FiltersDocids);
+    }
+
+    let mut pushed = false;
+    for migration in migrations {
+        let is_enqueued = index.updates.any(writer, |update| match update {
+            Update::Migration(enqueued) => *enqueued == migration,
+            _ => false,
+        })?;
+
+        if !is_enqueued {
+            push_migration(writer, index.updates, index.updates_results, migration)?;
+            pushed = true;
+        }
+    }
+
+    Ok(pushed)
+}
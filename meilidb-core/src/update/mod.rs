@@ -2,7 +2,9 @@ mod clear_all;
 mod customs_update;
 mod documents_addition;
 mod documents_deletion;
+mod migration;
 mod schema_update;
+mod settings_update;
 mod stop_words_addition;
 mod stop_words_deletion;
 mod synonyms_addition;
@@ -10,10 +12,21 @@ mod synonyms_deletion;
 
 pub use self::clear_all::{apply_clear_all, push_clear_all};
 pub use self::customs_update::{apply_customs_update, push_customs_update};
-pub use self::documents_addition::{apply_documents_addition, DocumentsAddition};
-pub use self::documents_deletion::{apply_documents_deletion, DocumentsDeletion};
+pub use self::documents_addition::{
+    apply_documents_addition, index_filters_docids, index_postings_docids,
+    index_words_pairs_proximities, DocumentsAddition,
+};
+pub use self::documents_deletion::{
+    apply_documents_deletion, remove_documents_popularity, DocumentsDeletion,
+};
+pub use self::migration::{apply_migration, push_migration, push_migrations, Migration};
 pub use self::schema_update::{apply_schema_update, push_schema_update};
-pub use self::stop_words_addition::{apply_stop_words_addition, StopWordsAddition};
+pub use self::settings_update::{
+    apply_settings_update, migrate_legacy_settings, push_settings_update, stored_settings,
+};
+pub use self::stop_words_addition::{
+    apply_stop_words_addition, push_stop_words_addition, StopWordsAddition,
+};
 pub use self::stop_words_deletion::{apply_stop_words_deletion, StopWordsDeletion};
 pub use self::synonyms_addition::{apply_synonyms_addition, SynonymsAddition};
 pub use self::synonyms_deletion::{apply_synonyms_deletion, SynonymsDeletion};
@@ -26,7 +39,7 @@ use heed::Result as ZResult;
 use log::debug;
 use serde::{Deserialize, Serialize};
 
-use crate::{store, DocumentId, MResult};
+use crate::{store, DocumentId, Error, MResult, Settings};
 use meilidb_schema::Schema;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,12 +47,14 @@ pub enum Update {
     ClearAll,
     Schema(Schema),
     Customs(Vec<u8>),
+    Settings(Settings),
     DocumentsAddition(Vec<serde_json::Value>),
     DocumentsDeletion(Vec<DocumentId>),
     SynonymsAddition(BTreeMap<String, Vec<String>>),
     SynonymsDeletion(BTreeMap<String, Option<Vec<String>>>),
     StopWordsAddition(BTreeSet<String>),
     StopWordsDeletion(BTreeSet<String>),
+    Migration(Migration),
 }
 
 impl Update {
@@ -50,6 +65,7 @@ impl Update {
                 schema: schema.clone(),
             },
             Update::Customs(_) => UpdateType::Customs,
+            Update::Settings(_) => UpdateType::Settings,
             Update::DocumentsAddition(addition) => UpdateType::DocumentsAddition {
                 number: addition.len(),
             },
@@ -68,6 +84,9 @@ impl Update {
             Update::StopWordsDeletion(deletion) => UpdateType::StopWordsDeletion {
                 number: deletion.len(),
             },
+            Update::Migration(migration) => UpdateType::Migration {
+                migration: *migration,
+            },
         }
     }
 }
@@ -77,12 +96,33 @@ pub enum UpdateType {
     ClearAll,
     Schema { schema: Schema },
     Customs,
+    Settings,
     DocumentsAddition { number: usize },
     DocumentsDeletion { number: usize },
     SynonymsAddition { number: usize },
     SynonymsDeletion { number: usize },
     StopWordsAddition { number: usize },
     StopWordsDeletion { number: usize },
+    Migration { migration: Migration },
+}
+
+impl UpdateType {
+    /// The name of the kind of update, without its details.
+    pub fn name(&self) -> &'static str {
+        match self {
+            UpdateType::ClearAll => "clear_all",
+            UpdateType::Schema { .. } => "schema",
+            UpdateType::Customs => "customs",
+            UpdateType::Settings => "settings",
+            UpdateType::DocumentsAddition { .. } => "documents_addition",
+            UpdateType::DocumentsDeletion { .. } => "documents_deletion",
+            UpdateType::SynonymsAddition { .. } => "synonyms_addition",
+            UpdateType::SynonymsDeletion { .. } => "synonyms_deletion",
+            UpdateType::StopWordsAddition { .. } => "stop_words_addition",
+            UpdateType::StopWordsDeletion { .. } => "stop_words_deletion",
+            UpdateType::Migration { .. } => "migration",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -171,7 +211,10 @@ pub fn update_task(
                 index.documents_fields,
                 index.documents_fields_counts,
                 index.postings_lists,
+                index.postings_docids,
+                index.filters_docids,
                 index.docs_words,
+                index.words_pairs_proximities,
             );
 
             (update_type, result, start.elapsed())
@@ -189,7 +232,10 @@ pub fn update_task(
                 index.documents_fields,
                 index.documents_fields_counts,
                 index.postings_lists,
+                index.postings_docids,
+                index.filters_docids,
                 index.docs_words,
+                index.words_pairs_proximities,
             );
 
             (update_type, result, start.elapsed())
@@ -202,6 +248,25 @@ pub fn update_task(
 
             (update_type, result, start.elapsed())
         }
+        Update::Settings(settings) => {
+            let start = Instant::now();
+
+            let update_type = UpdateType::Settings;
+            let result = apply_settings_update(
+                writer,
+                index.main,
+                index.documents_fields,
+                index.documents_fields_counts,
+                index.postings_lists,
+                index.postings_docids,
+                index.filters_docids,
+                index.docs_words,
+                index.words_pairs_proximities,
+                settings,
+            );
+
+            (update_type, result, start.elapsed())
+        }
         Update::DocumentsAddition(documents) => {
             let start = Instant::now();
 
@@ -215,7 +280,10 @@ pub fn update_task(
                 index.documents_fields,
                 index.documents_fields_counts,
                 index.postings_lists,
+                index.postings_docids,
+                index.filters_docids,
                 index.docs_words,
+                index.words_pairs_proximities,
                 documents,
             );
 
@@ -228,15 +296,21 @@ pub fn update_task(
                 number: documents.len(),
             };
 
-            let result = apply_documents_deletion(
-                writer,
-                index.main,
-                index.documents_fields,
-                index.documents_fields_counts,
-                index.postings_lists,
-                index.docs_words,
-                documents,
-            );
+            let result =
+                remove_documents_popularity(writer, index.main, &documents).and_then(|()| {
+                    apply_documents_deletion(
+                        writer,
+                        index.main,
+                        index.documents_fields,
+                        index.documents_fields_counts,
+                        index.postings_lists,
+                        index.postings_docids,
+                        index.filters_docids,
+                        index.docs_words,
+                        index.words_pairs_proximities,
+                        documents,
+                    )
+                });
 
             (update_type, result, start.elapsed())
         }
@@ -269,8 +343,14 @@ pub fn update_task(
                 number: stop_words.len(),
             };
 
-            let result =
-                apply_stop_words_addition(writer, index.main, index.postings_lists, stop_words);
+            let result = apply_stop_words_addition(
+                writer,
+                index.main,
+                index.postings_lists,
+                index.postings_docids,
+                index.words_pairs_proximities,
+                stop_words,
+            );
 
             (update_type, result, start.elapsed())
         }
@@ -287,14 +367,43 @@ pub fn update_task(
                 index.documents_fields,
                 index.documents_fields_counts,
                 index.postings_lists,
+                index.postings_docids,
+                index.filters_docids,
                 index.docs_words,
+                index.words_pairs_proximities,
                 stop_words,
             );
 
+            (update_type, result, start.elapsed())
+        }
+        Update::Migration(migration) => {
+            let start = Instant::now();
+
+            let update_type = UpdateType::Migration { migration };
+
+            let result = apply_migration(
+                writer,
+                index.main,
+                index.postings_lists,
+                index.postings_docids,
+                index.filters_docids,
+                index.documents_fields,
+                index.documents_fields_counts,
+                index.words_pairs_proximities,
+                migration,
+            );
+
             (update_type, result, start.elapsed())
         }
     };
 
+    // the update could not be written because the map is full, it must
+    // not be marked as processed, the caller must grow the map and retry it
+    let result = match result {
+        Err(e) if e.is_map_full() => return Err(e),
+        result => result,
+    };
+
     debug!(
         "Processed update number {} {:?} {:?}",
         update_id, update_type, result
@@ -314,3 +423,45 @@ pub fn update_task(
 
     Ok(Some(status))
 }
+
+/// Consumes an update that failed and stores its result, the changes made
+/// by the update must have been discarded by aborting its transaction.
+pub fn consume_failed_update(
+    writer: &mut heed::RwTxn,
+    index: store::Index,
+    status: &ProcessedUpdateResult,
+) -> MResult<()> {
+    index.updates.pop_front(writer)?;
+    index
+        .updates_results
+        .put_update_result(writer, status.update_id, status)?;
+    Ok(())
+}
+
+/// Consumes the next update without applying it and stores the given error
+/// as its result, it is used for the updates that cannot be written.
+pub fn reject_update(
+    writer: &mut heed::RwTxn,
+    index: store::Index,
+    error: &Error,
+) -> MResult<Option<ProcessedUpdateResult>> {
+    let (update_id, update) = match index.updates.pop_front(writer)? {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+
+    let status = ProcessedUpdateResult {
+        update_id,
+        update_type: update.update_type(),
+        result: Err(error.to_string()),
+        detailed_duration: DetailedDuration {
+            main: Duration::default(),
+        },
+    };
+
+    index
+        .updates_results
+        .put_update_result(writer, update_id, &status)?;
+
+    Ok(Some(status))
+}
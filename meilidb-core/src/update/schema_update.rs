@@ -11,7 +11,10 @@ pub fn apply_schema_update(
     documents_fields_store: store::DocumentsFields,
     documents_fields_counts_store: store::DocumentsFieldsCounts,
     postings_lists_store: store::PostingsLists,
+    postings_docids_store: store::PostingsDocids,
+    filters_docids_store: store::FiltersDocids,
     docs_words_store: store::DocsWords,
+    words_pairs_proximities_store: store::WordsPairsProximities,
 ) -> MResult<()> {
     use UnsupportedOperation::{
         CannotIntroduceNewSchemaAttribute, CannotRemoveSchemaAttribute,
@@ -48,7 +51,10 @@ pub fn apply_schema_update(
             documents_fields_store,
             documents_fields_counts_store,
             postings_lists_store,
+            postings_docids_store,
+            filters_docids_store,
             docs_words_store,
+            words_pairs_proximities_store,
         )?
     }
 
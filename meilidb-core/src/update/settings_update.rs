@@ -0,0 +1,151 @@
+use std::collections::BTreeSet;
+
+use crate::automaton::normalize_str;
+use crate::settings::{LegacySettings, SETTINGS_VERSION};
+use crate::update::{apply_stop_words_addition, apply_stop_words_deletion};
+use crate::update::{next_update_id, push_stop_words_addition, Update};
+use crate::{store, Error, MResult, Settings};
+
+/// Returns the settings stored by the index, without the stop words which are
+/// stored in their own fst. The indexes created before the settings were typed
+/// stored them as opaque customs, these are read when there is no typed settings.
+pub fn stored_settings(reader: &heed::RoTxn, main_store: store::Main) -> MResult<Settings> {
+    match main_store.settings_version(reader)? {
+        Some(SETTINGS_VERSION) => {
+            if let Some(settings) = main_store.settings(reader)? {
+                return Ok(settings);
+            }
+        }
+        Some(version) => return Err(Error::UnsupportedSettingsVersion(version)),
+        None => (),
+    }
+
+    let settings = match main_store.customs(reader)? {
+        Some(bytes) => match bincode::deserialize::<LegacySettings>(bytes) {
+            Ok(legacy) => Settings::from(legacy),
+            Err(_) => Settings::default(),
+        },
+        None => Settings::default(),
+    };
+
+    Ok(Settings {
+        stop_words: None,
+        ..settings
+    })
+}
+
+/// Stores the settings stored as customs by the HTTP server as typed settings, their
+/// stop words have never been applied to the index and are added by an update.
+pub fn migrate_legacy_settings(
+    writer: &mut heed::RwTxn,
+    main_store: store::Main,
+    updates_store: store::Updates,
+    updates_results_store: store::UpdatesResults,
+) -> MResult<()> {
+    if main_store.settings_version(writer)?.is_some() {
+        return Ok(());
+    }
+
+    let legacy = match main_store.customs(writer)? {
+        Some(bytes) => match bincode::deserialize::<LegacySettings>(bytes) {
+            Ok(legacy) => legacy,
+            Err(_) => return Ok(()),
+        },
+        None => return Ok(()),
+    };
+
+    let mut settings = Settings::from(legacy);
+    let stop_words = settings.stop_words.take().unwrap_or_default();
+    main_store.put_settings(writer, &settings)?;
+
+    if !stop_words.is_empty() {
+        let stop_words = stop_words.iter().map(|w| normalize_str(w)).collect();
+        push_stop_words_addition(writer, updates_store, updates_results_store, stop_words)?;
+    }
+
+    Ok(())
+}
+
+pub fn apply_settings_update(
+    writer: &mut heed::RwTxn,
+    main_store: store::Main,
+    documents_fields_store: store::DocumentsFields,
+    documents_fields_counts_store: store::DocumentsFieldsCounts,
+    postings_lists_store: store::PostingsLists,
+    postings_docids_store: store::PostingsDocids,
+    filters_docids_store: store::FiltersDocids,
+    docs_words_store: store::DocsWords,
+    words_pairs_proximities_store: store::WordsPairsProximities,
+    update: Settings,
+) -> MResult<()> {
+    let mut settings = stored_settings(writer, main_store)?;
+    settings.merge(update);
+
+    // nothing must be written before the settings are validated
+    let schema = main_store.schema(writer)?;
+    settings.validate(schema.as_ref())?;
+
+    if let Some(stop_words) = settings.stop_words.take() {
+        let stop_words: BTreeSet<_> = stop_words.iter().map(|w| normalize_str(w)).collect();
+
+        let current_stop_words = main_store.stop_words_fst(writer)?.unwrap_or_default();
+        let current_stop_words: BTreeSet<_> = current_stop_words
+            .stream()
+            .into_strs()?
+            .into_iter()
+            .collect();
+
+        let deletion: BTreeSet<_> = current_stop_words
+            .difference(&stop_words)
+            .cloned()
+            .collect();
+        let addition: BTreeSet<_> = stop_words
+            .difference(&current_stop_words)
+            .cloned()
+            .collect();
+
+        if !deletion.is_empty() {
+            apply_stop_words_deletion(
+                writer,
+                main_store,
+                documents_fields_store,
+                documents_fields_counts_store,
+                postings_lists_store,
+                postings_docids_store,
+                filters_docids_store,
+                docs_words_store,
+                words_pairs_proximities_store,
+                deletion,
+            )?;
+        }
+
+        if !addition.is_empty() {
+            apply_stop_words_addition(
+                writer,
+                main_store,
+                postings_lists_store,
+                postings_docids_store,
+                words_pairs_proximities_store,
+                addition,
+            )?;
+        }
+    }
+
+    main_store.put_settings(writer, &settings)?;
+
+    Ok(())
+}
+
+pub fn push_settings_update(
+    writer: &mut heed::RwTxn,
+    updates_store: store::Updates,
+    updates_results_store: store::UpdatesResults,
+    settings: Settings,
+) -> MResult<u64> {
+    let last_update_id = next_update_id(writer, updates_store, updates_results_store)?;
+
+    let update = Update::Settings(settings);
+    updates_store.put_update(writer, last_update_id, &update)?;
+
+    Ok(last_update_id)
+}
@@ -3,13 +3,14 @@ use std::collections::BTreeSet;
 use fst::{set::OpBuilder, SetBuilder};
 
 use crate::automaton::normalize_str;
+use crate::database::{UpdateEvent, UpdateEventsEmitter};
 use crate::update::{next_update_id, Update};
 use crate::{store, MResult};
 
 pub struct StopWordsAddition {
     updates_store: store::Updates,
     updates_results_store: store::UpdatesResults,
-    updates_notifier: crossbeam_channel::Sender<()>,
+    updates_notifier: UpdateEventsEmitter,
     stop_words: BTreeSet<String>,
 }
 
@@ -17,7 +18,7 @@ impl StopWordsAddition {
     pub fn new(
         updates_store: store::Updates,
         updates_results_store: store::UpdatesResults,
-        updates_notifier: crossbeam_channel::Sender<()>,
+        updates_notifier: UpdateEventsEmitter,
     ) -> StopWordsAddition {
         StopWordsAddition {
             updates_store,
@@ -33,7 +34,7 @@ impl StopWordsAddition {
     }
 
     pub fn finalize(self, writer: &mut heed::RwTxn) -> MResult<u64> {
-        let _ = self.updates_notifier.send(());
+        let _ = self.updates_notifier.send(UpdateEvent::NewUpdate);
         let update_id = push_stop_words_addition(
             writer,
             self.updates_store,
@@ -62,6 +63,8 @@ pub fn apply_stop_words_addition(
     writer: &mut heed::RwTxn,
     main_store: store::Main,
     postings_lists_store: store::PostingsLists,
+    postings_docids_store: store::PostingsDocids,
+    words_pairs_proximities_store: store::WordsPairsProximities,
     addition: BTreeSet<String>,
 ) -> MResult<()> {
     let mut stop_words_builder = SetBuilder::memory();
@@ -70,6 +73,7 @@ pub fn apply_stop_words_addition(
         stop_words_builder.insert(&word).unwrap();
         // we remove every posting list associated to a new stop word
         postings_lists_store.del_postings_list(writer, word.as_bytes())?;
+        postings_docids_store.del_postings_docids(writer, word.as_bytes())?;
     }
 
     // create the new delta stop words fst
@@ -78,6 +82,9 @@ pub fn apply_stop_words_addition(
         .and_then(fst::Set::from_bytes)
         .unwrap();
 
+    // and every pair of words made with a new stop word
+    words_pairs_proximities_store.del_words_pairs(writer, &delta_stop_words)?;
+
     // we also need to remove all the stop words from the main fst
     if let Some(word_fst) = main_store.words_fst(writer)? {
         let op = OpBuilder::new()
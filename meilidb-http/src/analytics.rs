@@ -0,0 +1,397 @@
+use std::cmp::Reverse;
+use std::collections::{HashMap, VecDeque};
+use std::mem;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use heed::types::{SerdeBincode, Str};
+use meilidb_core::{Database, MResult};
+use serde::{Deserialize, Serialize};
+
+/// The number of queries kept by index, the oldest ones are overwritten.
+pub const QUERY_LOG_CAPACITY: u64 = 10_000;
+
+const QUERY_LOG_PREFIX_KEY: &str = "_query-log_";
+const QUERY_LOG_HEAD_PREFIX_KEY: &str = "_query-log-head_";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryLogEntry {
+    pub query: String,
+    pub hits: usize,
+    pub processing_time_ms: usize,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Lowercases the query and collapses its whitespaces,
+/// the same query typed differently must be counted once.
+pub fn normalize_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The queries made on every index that are not stored yet.
+#[derive(Default)]
+pub struct SearchAnalytics {
+    pending: Mutex<HashMap<String, VecDeque<QueryLogEntry>>>,
+}
+
+impl SearchAnalytics {
+    /// Logs a query made on this index, empty queries are ignored.
+    pub fn record(&self, index_name: &str, query: &str, hits: usize, processing_time_ms: usize) {
+        let query = normalize_query(query);
+        if query.is_empty() {
+            return;
+        }
+
+        let entry = QueryLogEntry {
+            query,
+            hits,
+            processing_time_ms,
+            timestamp: Utc::now(),
+        };
+
+        let mut pending = self.pending.lock().unwrap();
+        let pending = pending.entry(index_name.to_owned()).or_default();
+
+        // the older entries would be overwritten by the newer ones anyway
+        if pending.len() as u64 >= QUERY_LOG_CAPACITY {
+            pending.pop_front();
+        }
+        pending.push_back(entry);
+    }
+
+    /// The queries made on this index that are not stored yet.
+    pub fn pending(&self, index_name: &str) -> Vec<QueryLogEntry> {
+        let pending = self.pending.lock().unwrap();
+        pending
+            .get(index_name)
+            .map_or_else(Vec::new, |entries| entries.iter().cloned().collect())
+    }
+
+    /// Forgets the pending queries of a deleted index.
+    pub fn remove_index(&self, index_name: &str) {
+        let mut pending = self.pending.lock().unwrap();
+        pending.remove(index_name);
+    }
+
+    pub fn rename_index(&self, index_name: &str, new_name: &str) {
+        let mut pending = self.pending.lock().unwrap();
+        if let Some(entries) = pending.remove(index_name) {
+            pending.insert(new_name.to_owned(), entries);
+        }
+    }
+
+    /// Appends the pending queries to the query logs of their index, this is done
+    /// by a background thread. The queries are kept to be written later if the write fails.
+    pub fn flush(&self, db: &Database) -> MResult<()> {
+        let pending = {
+            let mut pending = self.pending.lock().unwrap();
+            mem::replace(&mut *pending, HashMap::new())
+        };
+
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        if let Err(e) = write_query_logs(db, &pending) {
+            // the queries made in the meantime are newer than the restored ones
+            let mut current = self.pending.lock().unwrap();
+            for (index_name, mut entries) in pending {
+                let newer = current.remove(&index_name).unwrap_or_default();
+                entries.extend(newer);
+                while entries.len() as u64 > QUERY_LOG_CAPACITY {
+                    entries.pop_front();
+                }
+                current.insert(index_name, entries);
+            }
+            return Err(e);
+        }
+
+        Ok(())
+    }
+}
+
+fn write_query_logs(
+    db: &Database,
+    pending: &HashMap<String, VecDeque<QueryLogEntry>>,
+) -> MResult<()> {
+    let env = &db.env;
+    let mut writer = env.write_txn()?;
+
+    for (index_name, entries) in pending {
+        for entry in entries {
+            push_query_log(db, &mut writer, index_name, entry)?;
+        }
+    }
+
+    writer.commit()?;
+
+    Ok(())
+}
+
+fn head_key(index_name: &str) -> String {
+    format!("{}{}", QUERY_LOG_HEAD_PREFIX_KEY, index_name)
+}
+
+fn entry_key(index_name: &str, slot: u64) -> String {
+    format!("{}{}_{}", QUERY_LOG_PREFIX_KEY, index_name, slot)
+}
+
+/// The number of entries ever pushed in the query log of this index.
+fn query_log_head(db: &Database, reader: &heed::RoTxn, index_name: &str) -> MResult<u64> {
+    let head = db
+        .common_store()
+        .get::<Str, SerdeBincode<u64>>(reader, &head_key(index_name))?;
+    Ok(head.unwrap_or(0))
+}
+
+fn push_query_log(
+    db: &Database,
+    writer: &mut heed::RwTxn,
+    index_name: &str,
+    entry: &QueryLogEntry,
+) -> MResult<()> {
+    let head = query_log_head(db, writer, index_name)?;
+    let slot = head % QUERY_LOG_CAPACITY;
+
+    let common_store = db.common_store();
+    common_store.put::<Str, SerdeBincode<QueryLogEntry>>(
+        writer,
+        &entry_key(index_name, slot),
+        entry,
+    )?;
+    common_store.put::<Str, SerdeBincode<u64>>(writer, &head_key(index_name), &(head + 1))?;
+
+    Ok(())
+}
+
+/// Returns the entries of the query log of this index, in no particular order.
+pub fn query_log(
+    db: &Database,
+    reader: &heed::RoTxn,
+    index_name: &str,
+) -> MResult<Vec<QueryLogEntry>> {
+    let head = query_log_head(db, reader, index_name)?;
+    let len = head.min(QUERY_LOG_CAPACITY);
+
+    let common_store = db.common_store();
+    let mut entries = Vec::with_capacity(len as usize);
+    for slot in 0..len {
+        let key = entry_key(index_name, slot);
+        if let Some(entry) = common_store.get::<Str, SerdeBincode<QueryLogEntry>>(reader, &key)? {
+            entries.push(entry);
+        }
+    }
+
+    Ok(entries)
+}
+
+pub fn clear_query_log(db: &Database, writer: &mut heed::RwTxn, index_name: &str) -> MResult<()> {
+    let head = query_log_head(db, writer, index_name)?;
+    let len = head.min(QUERY_LOG_CAPACITY);
+
+    let common_store = db.common_store();
+    for slot in 0..len {
+        common_store.delete::<Str>(writer, &entry_key(index_name, slot))?;
+    }
+    common_store.delete::<Str>(writer, &head_key(index_name))?;
+
+    Ok(())
+}
+
+/// Copies the query log of an index to another index, which must not have one.
+pub fn copy_query_log(
+    db: &Database,
+    writer: &mut heed::RwTxn,
+    index_name: &str,
+    new_name: &str,
+) -> MResult<()> {
+    let head = query_log_head(db, writer, index_name)?;
+    if head == 0 {
+        return Ok(());
+    }
+
+    let common_store = db.common_store();
+    for slot in 0..head.min(QUERY_LOG_CAPACITY) {
+        let key = entry_key(index_name, slot);
+        if let Some(entry) = common_store.get::<Str, SerdeBincode<QueryLogEntry>>(writer, &key)? {
+            common_store.put::<Str, SerdeBincode<QueryLogEntry>>(
+                writer,
+                &entry_key(new_name, slot),
+                &entry,
+            )?;
+        }
+    }
+    common_store.put::<Str, SerdeBincode<u64>>(writer, &head_key(new_name), &head)?;
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryStat {
+    pub query: String,
+    pub count: usize,
+    pub average_hits: f64,
+    pub average_processing_time_ms: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryAnalytics {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub total_queries: usize,
+    pub zero_hit_queries: usize,
+    pub top_queries: Vec<QueryStat>,
+    pub top_zero_hit_queries: Vec<QueryStat>,
+}
+
+/// Aggregates the entries logged between `from` and `to`, both inclusive,
+/// the queries are sorted by decreasing number of occurrences.
+pub fn aggregate(
+    entries: &[QueryLogEntry],
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    limit: usize,
+) -> QueryAnalytics {
+    let mut stats: HashMap<&str, QueryStat> = HashMap::new();
+    let mut zero_hit_stats: HashMap<&str, QueryStat> = HashMap::new();
+    let mut total_queries = 0;
+    let mut zero_hit_queries = 0;
+
+    let in_window = entries
+        .iter()
+        .filter(|e| e.timestamp >= from && e.timestamp <= to);
+
+    for entry in in_window {
+        total_queries += 1;
+        add_to_stats(&mut stats, entry);
+
+        if entry.hits == 0 {
+            zero_hit_queries += 1;
+            add_to_stats(&mut zero_hit_stats, entry);
+        }
+    }
+
+    QueryAnalytics {
+        from,
+        to,
+        total_queries,
+        zero_hit_queries,
+        top_queries: top_stats(stats, limit),
+        top_zero_hit_queries: top_stats(zero_hit_stats, limit),
+    }
+}
+
+fn add_to_stats<'a>(stats: &mut HashMap<&'a str, QueryStat>, entry: &'a QueryLogEntry) {
+    let stat = stats.entry(&entry.query).or_insert_with(|| QueryStat {
+        query: entry.query.clone(),
+        count: 0,
+        average_hits: 0.0,
+        average_processing_time_ms: 0.0,
+    });
+
+    // the averages are computed incrementally
+    stat.count += 1;
+    let count = stat.count as f64;
+    stat.average_hits += (entry.hits as f64 - stat.average_hits) / count;
+    stat.average_processing_time_ms +=
+        (entry.processing_time_ms as f64 - stat.average_processing_time_ms) / count;
+}
+
+fn top_stats(stats: HashMap<&str, QueryStat>, limit: usize) -> Vec<QueryStat> {
+    let mut stats: Vec<_> = stats.into_iter().map(|(_, stat)| stat).collect();
+    stats.sort_by(|a, b| (Reverse(a.count), &a.query).cmp(&(Reverse(b.count), &b.query)));
+    stats.truncate(limit);
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::TimeZone;
+    use meilidb_core::DatabaseOptions;
+    use tempfile::TempDir;
+
+    fn entry(query: &str, hits: usize, second: u32) -> QueryLogEntry {
+        QueryLogEntry {
+            query: query.to_owned(),
+            hits,
+            processing_time_ms: 2,
+            timestamp: Utc.ymd(2019, 11, 4).and_hms(10, 0, second),
+        }
+    }
+
+    #[test]
+    fn normalized_queries() {
+        assert_eq!(normalize_query("  Star   WARS "), "star wars");
+        assert_eq!(normalize_query("   "), "");
+    }
+
+    #[test]
+    fn aggregate_in_window() {
+        let entries = vec![
+            entry("star wars", 12, 1),
+            entry("star wars", 10, 2),
+            entry("batman", 3, 3),
+            entry("starw ars", 0, 4),
+            entry("starw ars", 0, 5),
+            entry("zorro", 0, 6),
+            entry("batman", 3, 59),
+        ];
+
+        let from = Utc.ymd(2019, 11, 4).and_hms(10, 0, 0);
+        let to = Utc.ymd(2019, 11, 4).and_hms(10, 0, 30);
+        let analytics = aggregate(&entries, from, to, 2);
+
+        assert_eq!(analytics.total_queries, 6);
+        assert_eq!(analytics.zero_hit_queries, 3);
+
+        let top: Vec<_> = analytics
+            .top_queries
+            .iter()
+            .map(|s| (&*s.query, s.count))
+            .collect();
+        assert_eq!(top, vec![("star wars", 2), ("starw ars", 2)]);
+        assert_eq!(analytics.top_queries[0].average_hits, 11.0);
+
+        let top: Vec<_> = analytics
+            .top_zero_hit_queries
+            .iter()
+            .map(|s| (&*s.query, s.count))
+            .collect();
+        assert_eq!(top, vec![("starw ars", 2), ("zorro", 1)]);
+    }
+
+    #[test]
+    fn bounded_query_log() {
+        let dir = TempDir::new().unwrap();
+        let options = DatabaseOptions::default();
+        let db = Database::open_or_create(dir.path(), options).unwrap();
+
+        let mut writer = db.env.write_txn().unwrap();
+        for second in 0..3 {
+            push_query_log(&db, &mut writer, "movies", &entry("batman", 1, second)).unwrap();
+        }
+        writer.commit().unwrap();
+
+        let reader = db.env.read_txn().unwrap();
+        assert_eq!(query_log(&db, &reader, "movies").unwrap().len(), 3);
+        assert!(query_log(&db, &reader, "movie").unwrap().is_empty());
+        reader.abort();
+
+        let mut writer = db.env.write_txn().unwrap();
+        copy_query_log(&db, &mut writer, "movies", "films").unwrap();
+        clear_query_log(&db, &mut writer, "movies").unwrap();
+        writer.commit().unwrap();
+
+        let reader = db.env.read_txn().unwrap();
+        assert!(query_log(&db, &reader, "movies").unwrap().is_empty());
+        assert_eq!(query_log(&db, &reader, "films").unwrap().len(), 3);
+    }
+}
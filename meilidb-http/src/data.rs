@@ -1,17 +1,30 @@
 use std::collections::HashMap;
 use std::ops::Deref;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
+use std::thread;
+use std::time::Duration;
 
 use chrono::{DateTime, Utc};
 use heed::types::{SerdeBincode, Str};
 use log::*;
-use meilidb_core::{Database, MResult};
+use meilidb_core::{Database, DatabaseOptions, MResult};
 use sysinfo::Pid;
 
+use crate::analytics::{self, SearchAnalytics};
+use crate::metrics::Metrics;
+use crate::middleware::RateLimiter;
+use crate::models::token::{
+    split_api_key, HashedSecret, LegacyToken, Token, LEGACY_TOKEN_PREFIX_KEY, TOKEN_PREFIX_KEY,
+};
 use crate::option::Opt;
+use crate::popularity::PopularityEvents;
 use crate::routes::index::index_update_callback;
 
+/// The usages of the API keys, the search analytics and the
+/// popularity events are written to the database once every interval.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+
 pub type FreqsMap = HashMap<String, usize>;
 type SerdeFreqsMap = SerdeBincode<FreqsMap>;
 type SerdeDatetime = SerdeBincode<DateTime<Utc>>;
@@ -36,6 +49,10 @@ pub struct DataInner {
     pub admin_token: Option<String>,
     pub server_pid: Pid,
     pub accept_updates: Arc<AtomicBool>,
+    pub rate_limiter: Arc<RateLimiter>,
+    pub metrics: Arc<Metrics>,
+    pub analytics: Arc<SearchAnalytics>,
+    pub popularity: Arc<PopularityEvents>,
 }
 
 impl DataInner {
@@ -144,6 +161,43 @@ impl DataInner {
         Ok(())
     }
 
+    pub fn clear_index_keys(&self, writer: &mut heed::RwTxn, index_name: &str) -> MResult<()> {
+        let common_store = self.db.common_store();
+
+        let key = format!("last-update-{}", index_name);
+        common_store.delete::<Str>(writer, &key)?;
+
+        let key = format!("fields-frequency-{}", index_name);
+        common_store.delete::<Str>(writer, &key)?;
+
+        analytics::clear_query_log(&self.db, writer, index_name)
+    }
+
+    pub fn rename_index_keys(
+        &self,
+        writer: &mut heed::RwTxn,
+        index_name: &str,
+        new_name: &str,
+    ) -> MResult<()> {
+        if let Some(datetime) = self.last_update(writer, index_name)? {
+            let key = format!("last-update-{}", new_name);
+            self.db
+                .common_store()
+                .put::<Str, SerdeDatetime>(writer, &key, &datetime)?;
+        }
+
+        if let Some(freqs) = self.fields_frequency(writer, index_name)? {
+            let key = format!("fields-frequency-{}", new_name);
+            self.db
+                .common_store()
+                .put::<Str, SerdeFreqsMap>(writer, &key, &freqs)?;
+        }
+
+        analytics::copy_query_log(&self.db, writer, index_name, new_name)?;
+
+        self.clear_index_keys(writer, index_name)
+    }
+
     pub fn stop_accept_updates(&self) {
         self.accept_updates.store(false, Ordering::Relaxed);
     }
@@ -159,7 +213,13 @@ impl Data {
         let admin_token = opt.admin_token.clone();
         let server_pid = sysinfo::get_current_pid().unwrap();
 
-        let db = Arc::new(Database::open_or_create(opt.database_path.clone()).unwrap());
+        let db_options = DatabaseOptions {
+            map_size: opt.map_size,
+            max_map_size: opt.max_map_size,
+            query_cache_size: opt.query_cache_size,
+        };
+
+        let db = Arc::new(Database::open_or_create(opt.database_path.clone(), db_options).unwrap());
         let accept_updates = Arc::new(AtomicBool::new(true));
 
         let inner_data = DataInner {
@@ -168,12 +228,20 @@ impl Data {
             admin_token,
             server_pid,
             accept_updates,
+            rate_limiter: Arc::new(RateLimiter::default()),
+            metrics: Arc::new(Metrics::new()),
+            analytics: Arc::new(SearchAnalytics::default()),
+            popularity: Arc::new(PopularityEvents::default()),
         };
 
         let data = Data {
             inner: Arc::new(inner_data),
         };
 
+        spawn_flusher(Arc::downgrade(&data.inner));
+
+        migrate_legacy_tokens(&db).unwrap();
+
         for index_name in db.indexes_names().unwrap() {
             let callback_context = data.clone();
             let callback_name = index_name.clone();
@@ -188,3 +256,95 @@ impl Data {
         data
     }
 }
+
+/// Writes what is counted in memory to the database in the background,
+/// outside of the requests. The thread stops once the data is dropped.
+fn spawn_flusher(inner: Weak<DataInner>) {
+    thread::spawn(move || loop {
+        thread::sleep(FLUSH_INTERVAL);
+
+        let inner = match inner.upgrade() {
+            Some(inner) => inner,
+            None => break,
+        };
+
+        if let Err(e) = inner.rate_limiter.flush(&inner.db) {
+            error!("impossible to store the usage of the API keys; {}", e);
+        }
+
+        if let Err(e) = inner.analytics.flush(&inner.db) {
+            error!("impossible to store the search analytics; {}", e);
+        }
+
+        if let Err(e) = inner.popularity.flush(&inner.db) {
+            error!("impossible to store the popularity events; {}", e);
+        }
+    });
+}
+
+/// Replaces the tokens stored with their raw API key by tokens storing a hash of it,
+/// the API keys stay the same: the first characters become the key id.
+///
+/// The API keys too short to be split or whose key id is already used by another
+/// token cannot be migrated, they are kept as they are and reported at every start.
+pub fn migrate_legacy_tokens(db: &Database) -> MResult<()> {
+    let env = &db.env;
+    let mut writer = env.write_txn()?;
+    let common_store = db.common_store();
+
+    let mut legacy_tokens = Vec::new();
+    for result in common_store
+        .prefix_iter::<Str, SerdeBincode<LegacyToken>>(&writer, LEGACY_TOKEN_PREFIX_KEY)?
+    {
+        let (key, token) = result?;
+        legacy_tokens.push((key.to_owned(), token));
+    }
+
+    for (legacy_key, legacy) in legacy_tokens {
+        let (key_id, secret) = match split_api_key(&legacy.key) {
+            Some(parts) => parts,
+            None => {
+                error!(
+                    "legacy API key {:?} kept unhashed, it is too short to be migrated",
+                    legacy.description
+                );
+                continue;
+            }
+        };
+
+        let token_key = format!("{}{}", TOKEN_PREFIX_KEY, key_id);
+        if common_store
+            .get::<Str, SerdeBincode<Token>>(&writer, &token_key)?
+            .is_some()
+        {
+            error!(
+                "legacy API key {:?} kept unhashed, its key id is already used by another key",
+                legacy.description
+            );
+            continue;
+        }
+
+        common_store.delete::<Str>(&mut writer, &legacy_key)?;
+
+        let token = Token {
+            key_id: key_id.to_owned(),
+            description: legacy.description,
+            acl: legacy.acl,
+            indexes: legacy.indexes,
+            created_at: legacy.created_at,
+            updated_at: legacy.updated_at,
+            expires_at: legacy.expires_at,
+            revoked: legacy.revoked,
+            secret: HashedSecret::new(secret),
+            previous_secret: None,
+            rate_limit: None,
+        };
+
+        common_store.put::<Str, SerdeBincode<Token>>(&mut writer, &token_key, &token)?;
+        info!("API key {} migrated to a hashed secret", key_id);
+    }
+
+    writer.commit()?;
+
+    Ok(())
+}
@@ -1,11 +1,16 @@
 use std::fmt::Display;
+use std::time::Duration;
 
+use http::header::{HeaderValue, RETRY_AFTER};
 use http::status::StatusCode;
 use log::{error, warn};
-use serde::{Deserialize, Serialize};
+use meilidb_core::{ErrorCode, ErrorType};
+use serde::Serialize;
 use tide::response::IntoResponse;
 use tide::Response;
 
+use crate::helpers::meilidb::Error as SearchError;
+
 pub type SResult<T> = Result<T, ResponseError>;
 
 pub enum ResponseError {
@@ -17,8 +22,11 @@ pub enum ResponseError {
     DocumentNotFound(String),
     MissingHeader(String),
     BadParameter(String, String),
-    CreateIndex(String),
+    InvalidFilter(String),
+    InvalidSearchParameter(String),
     Maintenance,
+    TooManyRequests(Duration),
+    Meilidb(meilidb_core::Error),
 }
 
 impl ResponseError {
@@ -54,64 +62,180 @@ impl ResponseError {
         ResponseError::BadParameter(name.to_string(), message.to_string())
     }
 
-    pub fn create_index(message: impl Display) -> ResponseError {
-        ResponseError::CreateIndex(message.to_string())
+    pub fn too_many_requests(retry_after: Duration) -> ResponseError {
+        ResponseError::TooManyRequests(retry_after)
     }
-}
 
-impl IntoResponse for ResponseError {
-    fn into_response(self) -> Response {
+    pub fn error_code(&self) -> ErrorCode {
         match self {
-            ResponseError::Internal(err) => {
-                error!("internal server error: {}", err);
-                error(
-                    String::from("Internal server error"),
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                )
+            ResponseError::Internal(_) => ErrorCode::Internal,
+            ResponseError::BadRequest(_) => ErrorCode::BadRequest,
+            ResponseError::InvalidToken(_) => ErrorCode::InvalidToken,
+            ResponseError::NotFound(_) => ErrorCode::NotFound,
+            ResponseError::IndexNotFound(_) => ErrorCode::IndexNotFound,
+            ResponseError::DocumentNotFound(_) => ErrorCode::DocumentNotFound,
+            ResponseError::MissingHeader(_) => ErrorCode::MissingAuthorizationHeader,
+            ResponseError::BadParameter(..) => ErrorCode::BadParameter,
+            ResponseError::InvalidFilter(_) => ErrorCode::InvalidFilter,
+            ResponseError::InvalidSearchParameter(_) => ErrorCode::InvalidSearchParameter,
+            ResponseError::Maintenance => ErrorCode::Maintenance,
+            ResponseError::TooManyRequests(_) => ErrorCode::TooManyRequests,
+            ResponseError::Meilidb(e) => e.error_code(),
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            ResponseError::Internal(_) => String::from("Internal server error"),
+            ResponseError::BadRequest(err) => err.to_string(),
+            ResponseError::InvalidToken(err) => format!("Invalid Token: {}", err),
+            ResponseError::NotFound(err) => err.to_string(),
+            ResponseError::IndexNotFound(index) => format!("Index {} not found", index),
+            ResponseError::DocumentNotFound(id) => format!("Document with id {} not found", id),
+            ResponseError::MissingHeader(header) => format!("Header {} is missing", header),
+            ResponseError::BadParameter(param, e) => {
+                format!("Url parameter {} error: {}", param, e)
             }
-            ResponseError::BadRequest(err) => {
-                warn!("bad request: {}", err);
-                error(err, StatusCode::BAD_REQUEST)
+            ResponseError::InvalidFilter(err) => format!("Invalid filter: {}", err),
+            ResponseError::InvalidSearchParameter(err) => err.to_string(),
+            ResponseError::Maintenance => {
+                String::from("Server is in maintenance, please try again later")
             }
-            ResponseError::InvalidToken(err) => {
-                error(format!("Invalid Token: {}", err), StatusCode::FORBIDDEN)
+            ResponseError::TooManyRequests(retry_after) => format!(
+                "Too many requests, retry in {} seconds",
+                retry_after_seconds(*retry_after)
+            ),
+            ResponseError::Meilidb(e) => match e.error_code() {
+                ErrorCode::Internal => String::from("Internal server error"),
+                _ => e.to_string(),
+            },
+        }
+    }
+}
+
+impl From<meilidb_core::Error> for ResponseError {
+    fn from(error: meilidb_core::Error) -> ResponseError {
+        ResponseError::Meilidb(error)
+    }
+}
+
+impl From<heed::Error> for ResponseError {
+    fn from(error: heed::Error) -> ResponseError {
+        ResponseError::Meilidb(error.into())
+    }
+}
+
+impl From<SearchError> for ResponseError {
+    fn from(error: SearchError) -> ResponseError {
+        match error {
+            SearchError::Meilidb(e) => ResponseError::Meilidb(e),
+            SearchError::MissingFilterValue | SearchError::UnknownFilteredAttribute => {
+                ResponseError::InvalidFilter(error.to_string())
             }
-            ResponseError::NotFound(err) => error(err, StatusCode::NOT_FOUND),
-            ResponseError::IndexNotFound(index) => {
-                error(format!("Index {} not found", index), StatusCode::NOT_FOUND)
+            SearchError::CropFieldWrongType(_)
+            | SearchError::UnknownDistinctAttribute(_)
+            | SearchError::InvalidRankingRules(_)
+            | SearchError::UnknownSortAttribute(_)
+            | SearchError::SortAttributeNotRanked(_)
+            | SearchError::AttributeNotFoundOnDocument(_)
+            | SearchError::AttributeNotFoundOnSchema(_) => {
+                ResponseError::InvalidSearchParameter(error.to_string())
+            }
+            SearchError::RetrieveDocument(..) | SearchError::DocumentNotFound(_) => {
+                ResponseError::Internal(error.to_string())
             }
-            ResponseError::DocumentNotFound(id) => error(
-                format!("Document with id {} not found", id),
-                StatusCode::NOT_FOUND,
-            ),
-            ResponseError::MissingHeader(header) => error(
-                format!("Header {} is missing", header),
-                StatusCode::UNAUTHORIZED,
-            ),
-            ResponseError::BadParameter(param, e) => error(
-                format!("Url parameter {} error: {}", param, e),
-                StatusCode::BAD_REQUEST,
-            ),
-            ResponseError::CreateIndex(err) => error(
-                format!("Impossible to create index; {}", err),
-                StatusCode::BAD_REQUEST,
-            ),
-            ResponseError::Maintenance => error(
-                String::from("Server is in maintenance, please try again later"),
-                StatusCode::SERVICE_UNAVAILABLE,
-            ),
         }
     }
 }
 
-#[derive(Serialize, Deserialize)]
+fn status_code(code: ErrorCode) -> StatusCode {
+    match code {
+        ErrorCode::IndexNotFound
+        | ErrorCode::AliasNotFound
+        | ErrorCode::DocumentNotFound
+        | ErrorCode::SchemaMissing
+        | ErrorCode::NotFound => StatusCode::NOT_FOUND,
+        ErrorCode::MissingAuthorizationHeader => StatusCode::UNAUTHORIZED,
+        ErrorCode::InvalidToken | ErrorCode::IndexLimitExceeded => StatusCode::FORBIDDEN,
+        ErrorCode::DocumentLimitExceeded => StatusCode::PAYLOAD_TOO_LARGE,
+        ErrorCode::TooManyRequests => StatusCode::TOO_MANY_REQUESTS,
+        ErrorCode::Maintenance => StatusCode::SERVICE_UNAVAILABLE,
+        ErrorCode::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        _ => StatusCode::BAD_REQUEST,
+    }
+}
+
+/// The Retry-After header is a whole number of seconds.
+fn retry_after_seconds(retry_after: Duration) -> u64 {
+    retry_after.as_secs() + (retry_after.subsec_nanos() > 0) as u64
+}
+
+impl IntoResponse for ResponseError {
+    fn into_response(self) -> Response {
+        let code = self.error_code();
+        let status = status_code(code);
+
+        match &self {
+            ResponseError::Internal(err) => error!("internal server error: {}", err),
+            ResponseError::Meilidb(err) if code == ErrorCode::Internal => {
+                error!("internal server error: {}", err)
+            }
+            ResponseError::BadRequest(err) => warn!("bad request: {}", err),
+            _ => (),
+        }
+
+        let message = ErrorMessage {
+            message: self.message(),
+            error_code: code.name(),
+            error_type: code.error_type().name(),
+        };
+
+        let mut response = tide::response::json(message)
+            .with_status(status)
+            .into_response();
+
+        if let ResponseError::TooManyRequests(retry_after) = self {
+            let seconds = retry_after_seconds(retry_after);
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(seconds));
+        }
+
+        response
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
 struct ErrorMessage {
     message: String,
+    error_code: &'static str,
+    error_type: &'static str,
 }
 
-fn error(message: String, status: StatusCode) -> Response {
-    let message = ErrorMessage { message };
-    tide::response::json(message)
-        .with_status(status)
-        .into_response()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn core_errors_keep_their_code() {
+        let error = ResponseError::from(meilidb_core::Error::SchemaDiffer);
+        assert_eq!(error.error_code(), ErrorCode::SchemaDiffer);
+        assert_eq!(status_code(error.error_code()), StatusCode::BAD_REQUEST);
+
+        let error = ResponseError::from(meilidb_core::Error::IndexNotFound);
+        assert_eq!(status_code(error.error_code()), StatusCode::NOT_FOUND);
+
+        let error = ResponseError::from(SearchError::UnknownFilteredAttribute);
+        assert_eq!(error.error_code(), ErrorCode::InvalidFilter);
+        assert_eq!(error.error_code().error_type(), ErrorType::InvalidRequest);
+    }
+
+    #[test]
+    fn internal_errors_are_not_exposed() {
+        let io = std::io::Error::new(std::io::ErrorKind::Other, "secret path");
+        let error = ResponseError::from(meilidb_core::Error::Io(io));
+        assert_eq!(error.error_code(), ErrorCode::Internal);
+        assert_eq!(error.message(), "Internal server error");
+    }
 }
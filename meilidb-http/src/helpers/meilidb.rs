@@ -1,22 +1,28 @@
-use crate::routes::setting::{RankingOrdering, SettingBody};
+use crate::analytics::{normalize_query, SearchAnalytics};
 use indexmap::IndexMap;
 use log::*;
-use meilidb_core::criterion::*;
+use meilidb_core::criterion::Criteria;
+use meilidb_core::serde::compute_document_id;
 use meilidb_core::Highlight;
-use meilidb_core::{Index, RankedMap};
+use meilidb_core::{
+    ranking_rules_criteria, CachedSearch, Document, DocumentId, Index, Number, PopularityMap,
+    QueryCache, QueryCacheKey, QueryRule, QueryStats, RankedMap, RankingRule, Settings,
+};
 use meilidb_schema::{Schema, SchemaAttr};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::{HashMap, HashSet};
 use std::convert::From;
 use std::error;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
 use std::time::{Duration, Instant};
 
 #[derive(Debug)]
 pub enum Error {
-    SearchDocuments(String),
     RetrieveDocument(u64, String),
     DocumentNotFound(u64),
     CropFieldWrongType(String),
@@ -24,7 +30,11 @@ pub enum Error {
     AttributeNotFoundOnSchema(String),
     MissingFilterValue,
     UnknownFilteredAttribute,
-    Internal(String),
+    UnknownDistinctAttribute(String),
+    InvalidRankingRules(String),
+    UnknownSortAttribute(String),
+    SortAttributeNotRanked(String),
+    Meilidb(meilidb_core::Error),
 }
 
 impl error::Error for Error {}
@@ -34,7 +44,6 @@ impl fmt::Display for Error {
         use Error::*;
 
         match self {
-            SearchDocuments(err) => write!(f, "impossible to search documents; {}", err),
             RetrieveDocument(id, err) => write!(
                 f,
                 "impossible to retrieve the document with id: {}; {}",
@@ -52,14 +61,26 @@ impl fmt::Display for Error {
             UnknownFilteredAttribute => {
                 f.write_str("a filter is specifying an unknown schema attribute")
             }
-            Internal(err) => write!(f, "internal error; {}", err),
+            UnknownDistinctAttribute(field) => {
+                write!(f, "the distinct attribute {} is not found on schema", field)
+            }
+            InvalidRankingRules(err) => write!(f, "invalid ranking rules; {}", err),
+            UnknownSortAttribute(field) => {
+                write!(f, "the sort attribute {} is not found on schema", field)
+            }
+            SortAttributeNotRanked(field) => write!(
+                f,
+                "the attribute {} cannot be used to sort, it is not ranked",
+                field
+            ),
+            Meilidb(err) => write!(f, "{}", err),
         }
     }
 }
 
 impl From<meilidb_core::Error> for Error {
     fn from(error: meilidb_core::Error) -> Self {
-        Error::Internal(error.to_string())
+        Error::Meilidb(error)
     }
 }
 
@@ -78,9 +99,18 @@ impl IndexSearchExt for Index {
             attributes_to_retrieve: None,
             attributes_to_search_in: None,
             attributes_to_highlight: None,
-            filters: None,
+            filters: Vec::new(),
             timeout: Duration::from_millis(30),
             matches: false,
+            ranking_details: false,
+            distinct: None,
+            distinct_size: 1,
+            ranking_rules: None,
+            attributes_weights: None,
+            sort: Vec::new(),
+            sort_position: 0,
+            analytics: None,
+            query_cache: None,
         }
     }
 }
@@ -94,9 +124,18 @@ pub struct SearchBuilder<'a> {
     attributes_to_retrieve: Option<HashSet<String>>,
     attributes_to_search_in: Option<HashSet<String>>,
     attributes_to_highlight: Option<HashSet<String>>,
-    filters: Option<String>,
+    filters: Vec<String>,
     timeout: Duration,
     matches: bool,
+    ranking_details: bool,
+    distinct: Option<String>,
+    distinct_size: usize,
+    ranking_rules: Option<Vec<RankingRule>>,
+    attributes_weights: Option<HashMap<String, u16>>,
+    sort: Vec<RankingRule>,
+    sort_position: usize,
+    analytics: Option<(&'a SearchAnalytics, &'a str)>,
+    query_cache: Option<(&'a QueryCache, &'a str, u64)>,
 }
 
 impl<'a> SearchBuilder<'a> {
@@ -136,8 +175,9 @@ impl<'a> SearchBuilder<'a> {
         self
     }
 
+    /// Adds a filter to this search, the documents must match every filter.
     pub fn filters(&mut self, value: String) -> &SearchBuilder {
-        self.filters = Some(value);
+        self.filters.push(value);
         self
     }
 
@@ -151,75 +191,138 @@ impl<'a> SearchBuilder<'a> {
         self
     }
 
+    /// Returns the value of each ranking rule for the hits, to explain their order.
+    pub fn show_ranking_details(&mut self) -> &SearchBuilder {
+        self.ranking_details = true;
+        self
+    }
+
+    /// Overrides the distinct attribute of the index settings.
+    pub fn distinct(&mut self, value: String) -> &SearchBuilder {
+        self.distinct = Some(value);
+        self
+    }
+
+    /// The number of documents to keep for each value of the distinct attribute.
+    pub fn distinct_size(&mut self, value: usize) -> &SearchBuilder {
+        self.distinct_size = value;
+        self
+    }
+
+    /// Overrides the ranking rules of the index settings, only for this search.
+    pub fn ranking_rules(&mut self, value: Vec<RankingRule>) -> &SearchBuilder {
+        self.ranking_rules = Some(value);
+        self
+    }
+
+    /// Overrides the weights of these attributes, the other ones
+    /// keep the weights defined in the index settings.
+    pub fn attributes_weights(&mut self, value: HashMap<String, u16>) -> &SearchBuilder {
+        self.attributes_weights = Some(value);
+        self
+    }
+
+    /// Sorts the documents by these `asc` and `desc` rules, without
+    /// changing the ranking rules of the index settings.
+    pub fn sort(&mut self, value: Vec<RankingRule>) -> &SearchBuilder {
+        self.sort = value;
+        self
+    }
+
+    /// The number of ranking rules applied before the sort rules, by default
+    /// the documents are sorted first and the relevance only breaks the ties.
+    pub fn sort_position(&mut self, value: usize) -> &SearchBuilder {
+        self.sort_position = value;
+        self
+    }
+
+    /// Logs the query in the search analytics of the index, under this name.
+    pub fn log_query(
+        &mut self,
+        analytics: &'a SearchAnalytics,
+        index_name: &'a str,
+    ) -> &SearchBuilder {
+        self.analytics = Some((analytics, index_name));
+        self
+    }
+
+    /// Reads and stores the documents of this search in the query cache, the version of
+    /// the index must be read from the cache before the transaction of the search is created.
+    pub fn query_cache(
+        &mut self,
+        query_cache: &'a QueryCache,
+        index_name: &'a str,
+        version: u64,
+    ) -> &SearchBuilder {
+        self.query_cache = Some((query_cache, index_name, version));
+        self
+    }
+
     pub fn search(&self, reader: &heed::RoTxn) -> Result<SearchResult, Error> {
         let schema = self.index.main.schema(reader);
-        let schema = schema.map_err(|e| Error::Internal(e.to_string()))?;
+        let schema = schema.map_err(meilidb_core::Error::from)?;
         let schema = match schema {
             Some(schema) => schema,
-            None => return Err(Error::Internal(String::from("missing schema"))),
+            None => return Err(Error::Meilidb(meilidb_core::Error::SchemaMissing)),
         };
 
-        let ranked_map = self.index.main.ranked_map(reader);
-        let ranked_map = ranked_map.map_err(|e| Error::Internal(e.to_string()))?;
-        let ranked_map = ranked_map.unwrap_or_default();
-
         let start = Instant::now();
 
-        // Change criteria
-        let mut query_builder = match self.get_criteria(reader, &ranked_map, &schema)? {
-            Some(criteria) => self.index.query_builder_with_criteria(criteria),
-            None => self.index.query_builder(),
-        };
+        // the popularity events only invalidate the searches of the indexes ranked by
+        // popularity in their settings, the searches ranked by it on demand are not cached
+        let ranked_by_popularity = self
+            .ranking_rules
+            .iter()
+            .flatten()
+            .any(|rule| *rule == RankingRule::Popularity);
+
+        // the searches of an index are invalidated every time its documents, settings or
+        // rules change, the cached ones are found before reading anything else of the index
+        let cache_key = self.query_cache.filter(|_| !ranked_by_popularity).map(
+            |(query_cache, index_name, version)| (query_cache, self.cache_key(index_name), version),
+        );
 
-        // Filter searchable fields
-        if let Some(fields) = &self.attributes_to_search_in {
-            for attribute in fields.iter().filter_map(|f| schema.attribute(f)) {
-                query_builder.add_searchable_attribute(attribute.0);
+        let cached = cache_key
+            .as_ref()
+            .and_then(|(query_cache, key, _)| query_cache.get(key));
+
+        let (docs, nb_hits, query_stats, ranking_rules) = match cached {
+            Some(search) => {
+                // the ranking rules only name the values of the ranking info
+                let ranking_rules = if self.ranking_details {
+                    self.ranking_rules(&self.index.settings(reader)?).0
+                } else {
+                    Vec::new()
+                };
+                (search.documents, search.nb_hits, None, ranking_rules)
             }
-        }
-
-        if let Some(filters) = &self.filters {
-            let mut split = filters.split(':');
-            match (split.next(), split.next()) {
-                (Some(_), None) | (Some(_), Some("")) => return Err(Error::MissingFilterValue),
-                (Some(attr), Some(value)) => {
-                    let ref_reader = reader;
-                    let ref_index = &self.index;
-                    let value = value.trim().to_lowercase();
-
-                    let attr = match schema.attribute(attr) {
-                        Some(attr) => attr,
-                        None => return Err(Error::UnknownFilteredAttribute),
-                    };
-
-                    query_builder.with_filter(move |id| {
-                        let attr = attr;
-                        let index = ref_index;
-                        let reader = ref_reader;
-
-                        match index.document_attribute::<Value>(reader, id, attr) {
-                            Ok(Some(Value::String(s))) => s.to_lowercase() == value,
-                            Ok(Some(Value::Bool(b))) => {
-                                (value == "true" && b) || (value == "false" && !b)
-                            }
-                            Ok(Some(Value::Array(a))) => {
-                                a.into_iter().any(|s| s.as_str() == Some(&value))
-                            }
-                            _ => false,
-                        }
-                    });
+            None => {
+                let (docs, nb_hits, query_stats, ranking_rules) =
+                    self.find_documents(reader, &schema)?;
+
+                // the documents of a search that timed out may not be the most relevant ones
+                if let Some((query_cache, key, version)) = cache_key {
+                    if !query_stats.timed_out {
+                        let search = CachedSearch {
+                            documents: docs.clone(),
+                            nb_hits,
+                        };
+                        query_cache.insert(key, version, search);
+                    }
                 }
-                (_, _) => (),
-            }
-        }
-
-        query_builder.with_fetch_timeout(self.timeout);
 
-        let docs =
-            query_builder.query(reader, &self.query, self.offset..(self.offset + self.limit));
+                (docs, nb_hits, Some(query_stats), ranking_rules)
+            }
+        };
 
         let mut hits = Vec::with_capacity(self.limit);
-        for doc in docs.map_err(|e| Error::SearchDocuments(e.to_string()))? {
+        for Document {
+            id: document_id,
+            highlights,
+            ranking_info,
+            ..
+        } in docs
+        {
             // retrieve the content of document in kv store
             let mut fields: Option<HashSet<&str>> = None;
             if let Some(attributes_to_retrieve) = &self.attributes_to_retrieve {
@@ -231,11 +334,11 @@ impl<'a> SearchBuilder<'a> {
             }
             let mut document: IndexMap<String, Value> = self
                 .index
-                .document(reader, fields.as_ref(), doc.id)
-                .map_err(|e| Error::RetrieveDocument(doc.id.0, e.to_string()))?
-                .ok_or(Error::DocumentNotFound(doc.id.0))?;
+                .document(reader, fields.as_ref(), document_id)
+                .map_err(|e| Error::RetrieveDocument(document_id.0, e.to_string()))?
+                .ok_or(Error::DocumentNotFound(document_id.0))?;
 
-            let mut matches = doc.highlights.clone();
+            let mut matches = highlights;
 
             // Crops fields if needed
             if let Some(fields) = self.attributes_to_crop.clone() {
@@ -264,9 +367,21 @@ impl<'a> SearchBuilder<'a> {
 
             let matches_info = if self.matches { Some(matches) } else { None };
 
+            // the pinned documents are not ranked and have no ranking info
+            let ranking_info = ranking_info.map(|info| {
+                ranking_rules
+                    .iter()
+                    .zip(info)
+                    .map(|(rule, value)| {
+                        (rule.to_string(), value.map_or(Value::Null, number_to_value))
+                    })
+                    .collect()
+            });
+
             let hit = SearchHit {
                 hit: document,
                 matches_info,
+                ranking_info,
             };
 
             hits.push(hit);
@@ -274,90 +389,344 @@ impl<'a> SearchBuilder<'a> {
 
         let time_ms = start.elapsed().as_millis() as usize;
 
+        // the next pages of a query are not counted as new queries
+        if let Some((analytics, index_name)) = self.analytics {
+            if self.offset == 0 {
+                analytics.record(index_name, &self.query, nb_hits, time_ms);
+            }
+        }
+
         let results = SearchResult {
             hits,
             offset: self.offset,
             limit: self.limit,
             processing_time_ms: time_ms,
             query: self.query.to_string(),
+            query_stats,
         };
 
         Ok(results)
     }
 
-    pub fn get_criteria(
+    /// Finds the documents of the requested page, the pinned ones included, along with
+    /// the number of documents matching the query and the ranking rules used.
+    fn find_documents(
         &self,
         reader: &heed::RoTxn,
-        ranked_map: &'a RankedMap,
         schema: &Schema,
-    ) -> Result<Option<Criteria<'a>>, Error> {
-        let current_settings = match self.index.main.customs(reader).unwrap() {
-            Some(bytes) => bincode::deserialize(bytes).unwrap(),
-            None => SettingBody::default(),
+    ) -> Result<(Vec<Document>, usize, QueryStats, Vec<RankingRule>), Error> {
+        let ranked_map = self.index.main.ranked_map(reader);
+        let ranked_map = ranked_map.map_err(meilidb_core::Error::from)?;
+        let ranked_map = ranked_map.unwrap_or_default();
+
+        let settings = self.index.settings(reader)?;
+
+        // the popularity map is only read by the popularity rule
+        let popularity_map = if self
+            .ranking_rules(&settings)
+            .0
+            .contains(&RankingRule::Popularity)
+        {
+            let popularity_map = self.index.main.popularity_map(reader);
+            let popularity_map = popularity_map.map_err(meilidb_core::Error::from)?;
+            popularity_map.unwrap_or_default()
+        } else {
+            PopularityMap::default()
         };
 
-        let ranking_rules = &current_settings.ranking_rules;
-        let ranking_order = &current_settings.ranking_order;
-
-        if let Some(ranking_rules) = ranking_rules {
-            let mut builder = CriteriaBuilder::with_capacity(7 + ranking_rules.len());
-            if let Some(ranking_rules_order) = ranking_order {
-                for rule in ranking_rules_order {
-                    match rule.as_str() {
-                        "_sum_of_typos" => builder.push(SumOfTypos),
-                        "_number_of_words" => builder.push(NumberOfWords),
-                        "_word_proximity" => builder.push(WordsProximity),
-                        "_sum_of_words_attribute" => builder.push(SumOfWordsAttribute),
-                        "_sum_of_words_position" => builder.push(SumOfWordsPosition),
-                        "_exact" => builder.push(Exact),
-                        _ => {
-                            let order = match ranking_rules.get(rule.as_str()) {
-                                Some(o) => o,
-                                None => continue,
-                            };
-
-                            let custom_ranking = match order {
-                                RankingOrdering::Asc => {
-                                    SortByAttr::lower_is_better(&ranked_map, &schema, &rule)
-                                        .unwrap()
-                                }
-                                RankingOrdering::Dsc => {
-                                    SortByAttr::higher_is_better(&ranked_map, &schema, &rule)
-                                        .unwrap()
-                                }
-                            };
-
-                            builder.push(custom_ranking);
-                        }
+        // the searchable attributes are reordered in the schema order, the criteria
+        // see the attributes as their positions in the searchable attributes
+        let searchable_attrs = self.attributes_to_search_in.as_ref().map(|fields| {
+            let mut attributes: Vec<_> =
+                fields.iter().filter_map(|f| schema.attribute(f)).collect();
+            attributes.sort_unstable();
+            attributes
+        });
+
+        let attributes_weights =
+            self.criteria_weights(&settings, schema, searchable_attrs.as_ref())?;
+
+        // Change criteria
+        let (ranking_rules, criteria) = self.get_criteria(
+            &settings,
+            &attributes_weights,
+            &ranked_map,
+            &popularity_map,
+            schema,
+        )?;
+        let mut query_builder = self.index.query_builder_with_criteria(criteria);
+
+        if self.ranking_details {
+            query_builder.with_ranking_info();
+        }
+
+        // Filter searchable fields
+        if let Some(attributes) = &searchable_attrs {
+            for attribute in attributes {
+                query_builder.add_searchable_attribute(attribute.0);
+            }
+        }
+
+        let filters = self
+            .filters
+            .iter()
+            .map(|filter| parse_filter(schema, filter))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // the documents matching the filters are found using the bitmaps of their values,
+        // the documents are read while the bitmaps are being filled by a migration
+        let filters_ready = self.index.main.filters_docids_ready(reader);
+        let filters_ready = filters_ready.map_err(meilidb_core::Error::from)?;
+        let (candidates, filters) = if !filters.is_empty() && filters_ready {
+            let candidates = self.index.filtered_documents(reader, &filters)?;
+            (Some(candidates), Vec::new())
+        } else {
+            (None, filters)
+        };
+
+        let RulesEffects {
+            pinned: pins,
+            excluded,
+        } = RulesEffects::from_query(&self.index, reader, &self.query)?;
+
+        // the pinned documents must exist and be accepted by the filters
+        let mut pinned = Vec::with_capacity(pins.len());
+        for (position, id) in pins {
+            let accepted = match &candidates {
+                Some(candidates) => candidates.contains(id.0),
+                None => document_matches_filters(&self.index, reader, &filters, id),
+            };
+            if document_exists(&self.index, reader, id)? && accepted {
+                pinned.push((position, id));
+            }
+        }
+
+        if let Some(candidates) = candidates {
+            query_builder.with_candidates(candidates);
+        }
+
+        if !filters.is_empty() || !excluded.is_empty() {
+            let ref_reader = reader;
+            let ref_index = &self.index;
+
+            query_builder.with_filter(move |id| {
+                !excluded.contains(&id)
+                    && document_matches_filters(ref_index, ref_reader, &filters, id)
+            });
+        }
+
+        // the distinct attribute of the query has priority over the one of the settings
+        let distinct_attribute = match &self.distinct {
+            Some(field) => {
+                let attribute = schema.attribute(field);
+                Some(attribute.ok_or_else(|| Error::UnknownDistinctAttribute(field.clone()))?)
+            }
+            None => match &settings.distinct_field {
+                Some(field) => {
+                    let attribute = schema.attribute(field);
+                    if attribute.is_none() {
+                        warn!("the distinct attribute {} is not found on schema", field);
                     }
+                    attribute
                 }
-                builder.push(DocumentId);
-                return Ok(Some(builder.build()));
+                None => None,
+            },
+        };
+
+        if let Some(attribute) = distinct_attribute {
+            let ref_reader = reader;
+            let ref_index = &self.index;
+
+            query_builder.with_distinct(
+                move |id| distinct_key(ref_index, ref_reader, attribute, id),
+                self.distinct_size,
+            );
+        }
+
+        query_builder.with_fetch_timeout(self.timeout);
+
+        let range = organic_range(self.offset, self.limit, &pinned);
+        let (docs, query_stats) = query_builder.query_with_stats(reader, &self.query, range)?;
+
+        // the pinned documents are not ranked and have no highlights
+        let organic = docs
+            .into_iter()
+            .map(|doc| (doc.id, (doc.highlights, doc.ranking_info)));
+        let docs = merge_pinned(organic, &pinned, self.offset, self.limit)
+            .into_iter()
+            .map(|(id, (highlights, ranking_info))| Document {
+                id,
+                highlights,
+                ranking_info,
+            })
+            .collect();
+
+        // the pinned documents are never part of the organic hits
+        let nb_hits = query_stats.nb_hits + pinned.len();
+
+        Ok((docs, nb_hits, query_stats, ranking_rules))
+    }
+
+    /// Returns the ranking rules used by this search and the corresponding criteria.
+    ///
+    /// The ranking rules of the query have priority over the ones of the settings,
+    /// the schema can change after the settings are validated and make them invalid.
+    pub fn get_criteria(
+        &self,
+        current_settings: &Settings,
+        attributes_weights: &HashMap<u16, u16>,
+        ranked_map: &'a RankedMap,
+        popularity_map: &'a PopularityMap,
+        schema: &Schema,
+    ) -> Result<(Vec<RankingRule>, Criteria<'a>), Error> {
+        for name in self.sort.iter().filter_map(RankingRule::attribute) {
+            match schema.attribute(name) {
+                Some(attribute) if schema.props(attribute).is_ranked() => (),
+                Some(_) => return Err(Error::SortAttributeNotRanked(name.to_owned())),
+                None => return Err(Error::UnknownSortAttribute(name.to_owned())),
+            }
+        }
+
+        let (rules, from_query) = self.ranking_rules(current_settings);
+
+        let criteria = ranking_rules_criteria(
+            &rules,
+            schema,
+            ranked_map,
+            popularity_map,
+            attributes_weights,
+        )
+        .map_err(|e| {
+            if from_query {
+                Error::InvalidRankingRules(e.to_string())
             } else {
-                builder.push(SumOfTypos);
-                builder.push(NumberOfWords);
-                builder.push(WordsProximity);
-                builder.push(SumOfWordsAttribute);
-                builder.push(SumOfWordsPosition);
-                builder.push(Exact);
-                for (rule, order) in ranking_rules.iter() {
-                    let custom_ranking = match order {
-                        RankingOrdering::Asc => {
-                            SortByAttr::lower_is_better(&ranked_map, &schema, &rule).unwrap()
-                        }
-                        RankingOrdering::Dsc => {
-                            SortByAttr::higher_is_better(&ranked_map, &schema, &rule).unwrap()
-                        }
-                    };
-                    builder.push(custom_ranking);
+                Error::Meilidb(e.into())
+            }
+        })?;
+
+        Ok((rules, criteria))
+    }
+
+    /// Returns the ranking rules of this search, the sort rules included, and
+    /// whether they come from the query rather than from the settings.
+    fn ranking_rules(&self, current_settings: &Settings) -> (Vec<RankingRule>, bool) {
+        let (rules, from_query) = match (&self.ranking_rules, &current_settings.ranking_rules) {
+            (Some(rules), _) => (rules.clone(), true),
+            (None, Some(rules)) => (rules.clone(), false),
+            (None, None) => (RankingRule::default_rules(), false),
+        };
+
+        let rules = insert_sort_rules(rules, &self.sort, self.sort_position);
+        (rules, from_query)
+    }
+
+    /// The key of this search in the query cache, it only depends on the parameters of
+    /// the search as the cached searches of an index are removed every time it changes.
+    fn cache_key(&self, index_name: &str) -> QueryCacheKey {
+        let rule_names = |rules: &[RankingRule]| -> Vec<String> {
+            rules.iter().map(ToString::to_string).collect()
+        };
+
+        let mut weights: Option<Vec<_>> = self
+            .attributes_weights
+            .as_ref()
+            .map(|weights| weights.iter().collect());
+        if let Some(weights) = &mut weights {
+            weights.sort_unstable();
+        }
+
+        let mut searchable: Option<Vec<_>> = self
+            .attributes_to_search_in
+            .as_ref()
+            .map(|fields| fields.iter().collect());
+        if let Some(searchable) = &mut searchable {
+            searchable.sort_unstable();
+        }
+
+        let mut hasher = DefaultHasher::new();
+        self.ranking_rules
+            .as_ref()
+            .map(Vec::as_slice)
+            .map(rule_names)
+            .hash(&mut hasher);
+        weights.hash(&mut hasher);
+        searchable.hash(&mut hasher);
+        self.distinct.hash(&mut hasher);
+        self.distinct_size.hash(&mut hasher);
+        rule_names(&self.sort).hash(&mut hasher);
+        self.sort_position.hash(&mut hasher);
+        self.ranking_details.hash(&mut hasher);
+
+        QueryCacheKey::new(
+            index_name,
+            &self.query,
+            self.offset..self.offset + self.limit,
+            self.filters.clone(),
+            hasher.finish(),
+        )
+    }
+
+    /// Returns the weights of the attributes as they are seen by the criteria,
+    /// the weights of the query are applied over the ones of the settings.
+    fn criteria_weights(
+        &self,
+        current_settings: &Settings,
+        schema: &Schema,
+        searchable_attrs: Option<&Vec<SchemaAttr>>,
+    ) -> Result<HashMap<u16, u16>, Error> {
+        let mut weights = HashMap::new();
+
+        // the schema can change after the settings are validated
+        for (name, weight) in current_settings.attributes_weights.iter().flatten() {
+            match schema.attribute(name) {
+                Some(attribute) => {
+                    weights.insert(attribute, *weight);
                 }
-                builder.push(DocumentId);
-                return Ok(Some(builder.build()));
+                None => warn!("the weighted attribute {} is not found on schema", name),
             }
         }
 
-        Ok(None)
+        for (name, weight) in self.attributes_weights.iter().flatten() {
+            let attribute = schema
+                .attribute(name)
+                .ok_or_else(|| Error::AttributeNotFoundOnSchema(name.clone()))?;
+            weights.insert(attribute, *weight);
+        }
+
+        let weights = weights
+            .into_iter()
+            .filter_map(|(attribute, weight)| match searchable_attrs {
+                Some(attributes) => {
+                    let position = attributes.iter().position(|a| *a == attribute)?;
+                    Some((position as u16, weight))
+                }
+                None => Some((attribute.0, weight)),
+            })
+            .collect();
+
+        Ok(weights)
+    }
+}
+
+/// Inserts the sort rules after the `position` first ranking rules, the ranking
+/// rules that sort by the same attributes are replaced by the sort rules.
+fn insert_sort_rules(
+    mut rules: Vec<RankingRule>,
+    sort: &[RankingRule],
+    position: usize,
+) -> Vec<RankingRule> {
+    if sort.is_empty() {
+        return rules;
     }
+
+    let sorted: HashSet<_> = sort.iter().filter_map(RankingRule::attribute).collect();
+    rules.retain(|rule| rule.attribute().map_or(true, |name| !sorted.contains(name)));
+
+    let position = position.min(rules.len());
+    let tail = rules.split_off(position);
+    rules.extend(sort.iter().cloned());
+    rules.extend(tail);
+    rules
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Serialize, Deserialize)]
@@ -377,7 +746,7 @@ impl Ord for MatchPosition {
 
 pub type HighlightInfos = HashMap<String, Value>;
 pub type MatchesInfos = HashMap<String, Vec<MatchPosition>>;
-// pub type RankingInfos = HashMap<String, u64>;
+pub type RankingInfos = IndexMap<String, Value>;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchHit {
@@ -385,6 +754,8 @@ pub struct SearchHit {
     pub hit: IndexMap<String, Value>,
     #[serde(rename = "_matchesInfo", skip_serializing_if = "Option::is_none")]
     pub matches_info: Option<MatchesInfos>,
+    #[serde(rename = "_rankingInfo", skip_serializing_if = "Option::is_none")]
+    pub ranking_info: Option<RankingInfos>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -395,6 +766,9 @@ pub struct SearchResult {
     pub limit: usize,
     pub processing_time_ms: usize,
     pub query: String,
+    /// `None` when the documents come from the query cache.
+    #[serde(skip)]
+    pub query_stats: Option<QueryStats>,
     // pub parsed_query: String,
     // pub params: Option<String>,
 }
@@ -524,9 +898,221 @@ fn calculate_highlights(
     highlight_result
 }
 
+/// Parses a filter of the form `attribute:value` into the
+/// schema attribute and the lowercased value to compare with.
+fn parse_filter(schema: &Schema, filter: &str) -> Result<(SchemaAttr, String), Error> {
+    let mut split = filter.split(':');
+    match (split.next(), split.next()) {
+        (Some(attr), Some(value)) if !value.is_empty() => {
+            let attr = schema
+                .attribute(attr)
+                .ok_or(Error::UnknownFilteredAttribute)?;
+            Ok((attr, value.trim().to_lowercase()))
+        }
+        (_, _) => Err(Error::MissingFilterValue),
+    }
+}
+
+/// Reads the document to check the filters, only used while the
+/// filters docids of the index are being filled by a migration.
+fn document_matches_filters(
+    index: &Index,
+    reader: &heed::RoTxn,
+    filters: &[(SchemaAttr, String)],
+    id: DocumentId,
+) -> bool {
+    filters.iter().all(
+        |(attr, value)| match index.document_attribute::<Value>(reader, id, *attr) {
+            Ok(Some(document_value)) => filter_matches(document_value, value),
+            _ => false,
+        },
+    )
+}
+
+/// Documents without a value for the distinct attribute are all kept,
+/// the strings are compared without their case and surrounding spaces.
+fn distinct_key(
+    index: &Index,
+    reader: &heed::RoTxn,
+    attribute: SchemaAttr,
+    id: DocumentId,
+) -> Option<u64> {
+    let value = index
+        .document_attribute::<Value>(reader, id, attribute)
+        .ok()??;
+
+    let normalized = match value {
+        Value::Null => return None,
+        Value::String(s) => s.trim().to_lowercase(),
+        other => other.to_string(),
+    };
+
+    let mut hasher = DefaultHasher::new();
+    normalized.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+fn number_to_value(number: Number) -> Value {
+    match number {
+        Number::Unsigned(n) => Value::from(n),
+        Number::Signed(n) => Value::from(n),
+        Number::Float(n) => Value::from(n.into_inner()),
+    }
+}
+
+fn document_exists(index: &Index, reader: &heed::RoTxn, id: DocumentId) -> Result<bool, Error> {
+    let mut fields_counts = index
+        .documents_fields_counts
+        .document_fields_counts(reader, id)
+        .map_err(meilidb_core::Error::from)?;
+    Ok(fields_counts.next().is_some())
+}
+
+/// The documents pinned and hidden by the query rules matching a query.
+#[derive(Debug, Default)]
+struct RulesEffects {
+    /// The pinned documents sorted by position, positions and documents are unique.
+    pinned: Vec<(usize, DocumentId)>,
+    /// The documents that must not be returned by the query builder,
+    /// the hidden ones and the pinned ones which are inserted afterward.
+    excluded: HashSet<DocumentId>,
+}
+
+impl RulesEffects {
+    fn from_query(index: &Index, reader: &heed::RoTxn, query: &str) -> Result<RulesEffects, Error> {
+        let query = normalize_query(query);
+        let rules = index
+            .query_rules
+            .matching_rules(reader, &query)
+            .map_err(meilidb_core::Error::from)?;
+        Ok(RulesEffects::from_rules(&rules))
+    }
+
+    /// The first rule wins when rules pin documents at the same position,
+    /// a document hidden by any rule is never pinned.
+    fn from_rules(rules: &[QueryRule]) -> RulesEffects {
+        let mut excluded = HashSet::new();
+        for rule in rules {
+            for identifier in &rule.hidden {
+                excluded.insert(compute_document_id(identifier));
+            }
+        }
+
+        let mut positions = HashSet::new();
+        let mut pinned = Vec::new();
+        for rule in rules {
+            for pin in &rule.pinned {
+                let id = compute_document_id(&pin.id);
+                if excluded.contains(&id) {
+                    continue;
+                }
+                if positions.insert(pin.position) {
+                    excluded.insert(id);
+                    pinned.push((pin.position, id));
+                }
+            }
+        }
+
+        pinned.sort_unstable_by_key(|(position, _)| *position);
+
+        RulesEffects { pinned, excluded }
+    }
+}
+
+/// The range of the results of the query builder to fetch,
+/// the pinned documents take the place of some of them.
+fn organic_range(offset: usize, limit: usize, pinned: &[(usize, DocumentId)]) -> Range<usize> {
+    let end = offset + limit;
+    let before = pinned.iter().filter(|(p, _)| *p < offset).count();
+    let within = pinned
+        .iter()
+        .filter(|(p, _)| *p >= offset && *p < end)
+        .count();
+    (offset - before)..(end - before - within)
+}
+
+/// Inserts the pinned documents of the page at their positions, the pinned documents
+/// are appended when there is not enough results to reach their positions.
+fn merge_pinned<T, I>(
+    organic: I,
+    pinned: &[(usize, DocumentId)],
+    offset: usize,
+    limit: usize,
+) -> Vec<(DocumentId, T)>
+where
+    T: Default,
+    I: IntoIterator<Item = (DocumentId, T)>,
+{
+    let end = offset + limit;
+    let mut organic = organic.into_iter();
+    let mut pinned = pinned
+        .iter()
+        .filter(|(p, _)| *p >= offset && *p < end)
+        .peekable();
+
+    let mut documents = Vec::with_capacity(limit);
+    for position in offset..end {
+        match pinned.peek() {
+            Some((p, id)) if *p == position => {
+                documents.push((*id, T::default()));
+                pinned.next();
+            }
+            _ => match organic.next() {
+                Some(document) => documents.push(document),
+                None => break,
+            },
+        }
+    }
+
+    documents.extend(pinned.map(|(_, id)| (*id, T::default())));
+    documents
+}
+
+fn filter_matches(document_value: Value, value: &str) -> bool {
+    match document_value {
+        Value::String(s) => s.to_lowercase() == value,
+        Value::Bool(b) => (value == "true" && b) || (value == "false" && !b),
+        Value::Number(n) => n.to_string() == value,
+        Value::Array(a) => a.into_iter().any(|s| s.as_str() == Some(value)),
+        _ => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn sort_rules_position() {
+        let rules = vec![
+            RankingRule::Typo,
+            RankingRule::Desc(String::from("price")),
+            RankingRule::Exactness,
+        ];
+        let sort = vec![
+            RankingRule::Asc(String::from("price")),
+            RankingRule::Desc(String::from("rating")),
+        ];
+
+        let expected = vec![
+            RankingRule::Asc(String::from("price")),
+            RankingRule::Desc(String::from("rating")),
+            RankingRule::Typo,
+            RankingRule::Exactness,
+        ];
+        assert_eq!(insert_sort_rules(rules.clone(), &sort, 0), expected);
+
+        let expected = vec![
+            RankingRule::Typo,
+            RankingRule::Exactness,
+            RankingRule::Asc(String::from("price")),
+            RankingRule::Desc(String::from("rating")),
+        ];
+        assert_eq!(insert_sort_rules(rules.clone(), &sort, 42), expected);
+
+        assert_eq!(insert_sort_rules(rules.clone(), &[], 1), rules);
+    }
 
     #[test]
     fn calculate_highlights() {
@@ -565,4 +1151,86 @@ mod tests {
 
         assert_eq!(result, result_expected);
     }
+
+    #[test]
+    fn filter_matches_values() {
+        assert!(filter_matches(json!("Hello"), "hello"));
+        assert!(!filter_matches(json!("Hello"), "world"));
+        assert!(filter_matches(json!(true), "true"));
+        assert!(!filter_matches(json!(false), "true"));
+        assert!(filter_matches(json!(42), "42"));
+        assert!(!filter_matches(json!(42), "4"));
+        assert!(filter_matches(json!(["a", "b"]), "b"));
+        assert!(!filter_matches(json!(null), "null"));
+    }
+
+    #[test]
+    fn rules_effects_conflicts() {
+        use meilidb_core::{Anchoring, PinnedDocument, RuleCondition};
+
+        let rule = |pinned: &[(&str, usize)], hidden: &[&str]| QueryRule {
+            condition: RuleCondition {
+                pattern: String::from("iphone"),
+                anchoring: Anchoring::Is,
+            },
+            pinned: pinned
+                .iter()
+                .map(|(id, position)| PinnedDocument {
+                    id: id.to_string(),
+                    position: *position,
+                })
+                .collect(),
+            hidden: hidden.iter().map(|id| id.to_string()).collect(),
+        };
+
+        let rules = &[
+            rule(&[("b", 3), ("a", 1)], &[]),
+            rule(&[("c", 1), ("d", 2), ("e", 4)], &["e"]),
+        ];
+        let effects = RulesEffects::from_rules(rules);
+
+        let a = compute_document_id("a");
+        let b = compute_document_id("b");
+        let d = compute_document_id("d");
+        let e = compute_document_id("e");
+        assert_eq!(effects.pinned, vec![(1, a), (2, d), (3, b)]);
+
+        let excluded: HashSet<_> = vec![a, b, d, e].into_iter().collect();
+        assert_eq!(effects.excluded, excluded);
+    }
+
+    #[test]
+    fn pinned_documents_pagination() {
+        let pinned = &[
+            (0, DocumentId(100)),
+            (3, DocumentId(101)),
+            (9, DocumentId(102)),
+        ];
+        let organic = |range: Range<usize>| range.map(|i| (DocumentId(i as u64), ()));
+
+        // first page: 0, 3 are pinned
+        let range = organic_range(0, 5, pinned);
+        assert_eq!(range, 0..3);
+        let ids: Vec<_> = merge_pinned(organic(range), pinned, 0, 5)
+            .into_iter()
+            .map(|(id, _)| id.0)
+            .collect();
+        assert_eq!(ids, vec![100, 0, 1, 101, 2]);
+
+        // second page: 9 is pinned, two pinned documents were shown before
+        let range = organic_range(5, 5, pinned);
+        assert_eq!(range, 3..7);
+        let ids: Vec<_> = merge_pinned(organic(range), pinned, 5, 5)
+            .into_iter()
+            .map(|(id, _)| id.0)
+            .collect();
+        assert_eq!(ids, vec![3, 4, 5, 6, 102]);
+
+        // not enough results: the pinned document is appended
+        let ids: Vec<_> = merge_pinned(organic(3..4), pinned, 5, 5)
+            .into_iter()
+            .map(|(id, _)| id.0)
+            .collect();
+        assert_eq!(ids, vec![3, 102]);
+    }
 }
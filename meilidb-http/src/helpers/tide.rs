@@ -1,7 +1,9 @@
 use crate::error::{ResponseError, SResult};
+use crate::middleware::ResolvedIndex;
+use crate::models::tenant_token::{is_tenant_token, TenantClaims, TenantToken};
 use crate::models::token::*;
 use crate::Data;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use heed::types::{SerdeBincode, Str};
 use meilidb_core::Index;
 use serde_json::Value;
@@ -9,69 +11,35 @@ use tide::Context;
 
 pub trait ContextExt {
     fn is_allowed(&self, acl: ACL) -> SResult<()>;
+    fn is_allowed_on_index(&self, acl: ACL, index_name: &str) -> SResult<()>;
+    /// Checks that the request can search, tenant tokens are accepted
+    /// and the filter they embed, that must be added to the search, is returned.
+    fn is_allowed_to_search(&self) -> SResult<Option<String>>;
+    fn is_allowed_to_search_on_index(&self, index_name: &str) -> SResult<()>;
     fn header(&self, name: &str) -> Result<String, ResponseError>;
     fn url_param(&self, name: &str) -> Result<String, ResponseError>;
     fn index(&self) -> Result<Index, ResponseError>;
+    fn index_name(&self) -> Result<String, ResponseError>;
     fn identifier(&self) -> Result<String, ResponseError>;
 }
 
 impl ContextExt for Context<Data> {
     fn is_allowed(&self, acl: ACL) -> SResult<()> {
-        let admin_token = match &self.state().admin_token {
-            Some(admin_token) => admin_token,
-            None => return Ok(()),
-        };
-
-        let user_api_key = self.header("X-Meili-API-Key")?;
-        if user_api_key == *admin_token {
-            return Ok(());
-        }
-        let request_index: Option<String> = None; //self.param::<String>("index").ok();
-
-        let db = &self.state().db;
-        let env = &db.env;
-        let reader = env.read_txn().map_err(ResponseError::internal)?;
-
-        let token_key = format!("{}{}", TOKEN_PREFIX_KEY, user_api_key);
-
-        let token_config = db
-            .common_store()
-            .get::<Str, SerdeBincode<Token>>(&reader, &token_key)
-            .map_err(ResponseError::internal)?
-            .ok_or(ResponseError::not_found(format!(
-                "token key: {}",
-                token_key
-            )))?;
-
-        if token_config.revoked {
-            return Err(ResponseError::invalid_token("token revoked"));
-        }
-
-        if let Some(index) = request_index {
-            if !token_config
-                .indexes
-                .iter()
-                .any(|r| match_wildcard(&r, &index))
-            {
-                return Err(ResponseError::invalid_token(
-                    "token is not allowed to access to this index",
-                ));
-            }
-        }
-
-        if token_config.expires_at < Utc::now() {
-            return Err(ResponseError::invalid_token("token expired"));
-        }
+        // index scoped routes must be checked against the
+        // index the `:index` parameter (or alias) resolves to
+        check_request_token(self, acl, false).map(drop)
+    }
 
-        if token_config.acl.contains(&ACL::All) {
-            return Ok(());
-        }
+    fn is_allowed_on_index(&self, acl: ACL, index_name: &str) -> SResult<()> {
+        check_index_token(self, acl, index_name, false).map(drop)
+    }
 
-        if !token_config.acl.contains(&acl) {
-            return Err(ResponseError::invalid_token("token do not have this ACL"));
-        }
+    fn is_allowed_to_search(&self) -> SResult<Option<String>> {
+        check_request_token(self, ACL::DocumentsRead, true)
+    }
 
-        Ok(())
+    fn is_allowed_to_search_on_index(&self, index_name: &str) -> SResult<()> {
+        check_index_token(self, ACL::DocumentsRead, index_name, true).map(drop)
     }
 
     fn header(&self, name: &str) -> Result<String, ResponseError> {
@@ -93,13 +61,17 @@ impl ContextExt for Context<Data> {
     }
 
     fn index(&self) -> Result<Index, ResponseError> {
-        let index_name = self.url_param("index")?;
-        let index = self
-            .state()
-            .db
-            .open_index(&index_name)
-            .ok_or(ResponseError::index_not_found(index_name))?;
-        Ok(index)
+        match resolved_index(self)? {
+            Some((_, index)) => Ok(index),
+            None => Err(ResponseError::index_not_found(self.url_param("index")?)),
+        }
+    }
+
+    fn index_name(&self) -> Result<String, ResponseError> {
+        match resolved_index(self)? {
+            Some((index_name, _)) => Ok(index_name),
+            None => self.url_param("index"),
+        }
     }
 
     fn identifier(&self) -> Result<String, ResponseError> {
@@ -116,3 +88,252 @@ impl ContextExt for Context<Data> {
         Ok(name)
     }
 }
+
+/// The index the `:index` parameter resolves to, the `IndexMiddleware`
+/// resolves it once so that every call returns the same index.
+fn resolved_index(ctx: &Context<Data>) -> SResult<Option<(String, Index)>> {
+    let index_name = ctx.url_param("index")?;
+    match ctx.extensions().get::<ResolvedIndex>() {
+        Some(ResolvedIndex(resolved)) => Ok(resolved.clone()),
+        None => Ok(ctx.state().db.resolve_index(&index_name)),
+    }
+}
+
+fn check_request_token(
+    ctx: &Context<Data>,
+    acl: ACL,
+    allow_tenant_token: bool,
+) -> SResult<Option<String>> {
+    // index scoped routes must be checked against the
+    // index the `:index` parameter (or alias) resolves to
+    match ctx.param::<String>("index") {
+        Ok(_) => {
+            let index_name = ctx.index_name()?;
+            check_token(ctx, acl, Some(&index_name), allow_tenant_token)
+        }
+        Err(_) => check_token(ctx, acl, None, allow_tenant_token),
+    }
+}
+
+fn check_index_token(
+    ctx: &Context<Data>,
+    acl: ACL,
+    index_name: &str,
+    allow_tenant_token: bool,
+) -> SResult<Option<String>> {
+    match ctx.state().db.alias(index_name) {
+        Some(aliased) => check_token(ctx, acl, Some(&aliased), allow_tenant_token),
+        None => check_token(ctx, acl, Some(index_name), allow_tenant_token),
+    }
+}
+
+/// The API key of a request once it has been verified.
+#[derive(Debug, Clone)]
+pub enum Credentials {
+    /// The admin key, or any key if the server is not protected.
+    Admin,
+    Token(Token),
+    /// A tenant token along with its parent token.
+    TenantToken(Token, TenantClaims),
+    /// A legacy key that could not be migrated, stored with its raw API key.
+    LegacyToken(LegacyToken),
+}
+
+impl Credentials {
+    /// The token the request is counted and throttled with.
+    pub fn token(&self) -> Option<&Token> {
+        match self {
+            Credentials::Token(token) | Credentials::TenantToken(token, _) => Some(token),
+            Credentials::Admin | Credentials::LegacyToken(_) => None,
+        }
+    }
+}
+
+/// Verifies the API key of the request, the `RateLimitMiddleware` stores
+/// the credentials in the context for the routes not to verify it again.
+pub fn authenticate(ctx: &Context<Data>) -> SResult<Credentials> {
+    let admin_token = match &ctx.state().admin_token {
+        Some(admin_token) => admin_token,
+        None => return Ok(Credentials::Admin),
+    };
+
+    let user_api_key = ctx.header("X-Meili-API-Key")?;
+    if user_api_key == *admin_token {
+        return Ok(Credentials::Admin);
+    }
+
+    if is_tenant_token(&user_api_key) {
+        let tenant_token = TenantToken::parse(&user_api_key)
+            .ok_or_else(|| ResponseError::invalid_token("malformed tenant token"))?;
+
+        let token_config = stored_token(ctx, tenant_token.key_id)?;
+
+        if !token_config.verify_tenant_token(&tenant_token, admin_token) {
+            return Err(ResponseError::invalid_token(
+                "invalid tenant token signature",
+            ));
+        }
+
+        return Ok(Credentials::TenantToken(token_config, tenant_token.claims));
+    }
+
+    let token_config = split_api_key(&user_api_key)
+        .ok_or_else(|| ResponseError::invalid_token("malformed API key"))
+        .and_then(|(key_id, secret)| {
+            let token_config = stored_token(ctx, key_id)?;
+            if !token_config.verify_secret(secret) {
+                return Err(ResponseError::invalid_token("invalid API key"));
+            }
+            Ok(token_config)
+        });
+
+    match token_config {
+        Ok(token_config) => Ok(Credentials::Token(token_config)),
+        // the legacy keys that could not be migrated are stored with their raw API key
+        Err(error) => match legacy_token(ctx, &user_api_key)? {
+            Some(legacy) => Ok(Credentials::LegacyToken(legacy)),
+            None => Err(error),
+        },
+    }
+}
+
+/// Checks the API key of the request, returns the filter
+/// embedded in the tenant token if one has been used.
+fn check_token(
+    ctx: &Context<Data>,
+    acl: ACL,
+    request_index: Option<&str>,
+    allow_tenant_token: bool,
+) -> SResult<Option<String>> {
+    let credentials = match ctx.extensions().get::<Credentials>() {
+        Some(credentials) => credentials.clone(),
+        None => authenticate(ctx)?,
+    };
+
+    match credentials {
+        Credentials::Admin => Ok(None),
+        Credentials::Token(token_config) => {
+            check_token_rights(TokenRights::from(&token_config), acl, request_index)?;
+            Ok(None)
+        }
+        Credentials::LegacyToken(legacy) => {
+            check_token_rights(TokenRights::from(&legacy), acl, request_index)?;
+            Ok(None)
+        }
+        Credentials::TenantToken(token_config, claims) => {
+            if !allow_tenant_token {
+                return Err(ResponseError::invalid_token(
+                    "tenant tokens can only be used to search",
+                ));
+            }
+            check_tenant_token(&token_config, claims, acl, request_index)
+        }
+    }
+}
+
+fn check_tenant_token(
+    token_config: &Token,
+    claims: TenantClaims,
+    acl: ACL,
+    request_index: Option<&str>,
+) -> SResult<Option<String>> {
+    if claims.expires_at < Utc::now() {
+        return Err(ResponseError::invalid_token("tenant token expired"));
+    }
+
+    if let Some(index) = request_index {
+        if !claims.indexes.iter().any(|r| match_wildcard(&r, index)) {
+            return Err(ResponseError::invalid_token(
+                "tenant token is not allowed to access to this index",
+            ));
+        }
+    }
+
+    // the tenant token cannot do more than its parent token
+    check_token_rights(TokenRights::from(token_config), acl, request_index)?;
+
+    Ok(claims.filter)
+}
+
+fn stored_token(ctx: &Context<Data>, key_id: &str) -> SResult<Token> {
+    let db = &ctx.state().db;
+    let env = &db.env;
+    let reader = env.read_txn()?;
+
+    let token_key = format!("{}{}", TOKEN_PREFIX_KEY, key_id);
+
+    db.common_store()
+        .get::<Str, SerdeBincode<Token>>(&reader, &token_key)?
+        .ok_or(ResponseError::not_found(format!("token key: {}", key_id)))
+}
+
+fn legacy_token(ctx: &Context<Data>, api_key: &str) -> SResult<Option<LegacyToken>> {
+    let db = &ctx.state().db;
+    let env = &db.env;
+    let reader = env.read_txn()?;
+
+    let token_key = format!("{}{}", LEGACY_TOKEN_PREFIX_KEY, api_key);
+    let token = db
+        .common_store()
+        .get::<Str, SerdeBincode<LegacyToken>>(&reader, &token_key)?;
+
+    Ok(token)
+}
+
+/// What a token allows, the legacy tokens have the same rights as the hashed ones.
+struct TokenRights<'a> {
+    acl: &'a [ACL],
+    indexes: &'a [Wildcard],
+    expires_at: DateTime<Utc>,
+    revoked: bool,
+}
+
+impl<'a> From<&'a Token> for TokenRights<'a> {
+    fn from(token: &'a Token) -> TokenRights<'a> {
+        TokenRights {
+            acl: &token.acl,
+            indexes: &token.indexes,
+            expires_at: token.expires_at,
+            revoked: token.revoked,
+        }
+    }
+}
+
+impl<'a> From<&'a LegacyToken> for TokenRights<'a> {
+    fn from(token: &'a LegacyToken) -> TokenRights<'a> {
+        TokenRights {
+            acl: &token.acl,
+            indexes: &token.indexes,
+            expires_at: token.expires_at,
+            revoked: token.revoked,
+        }
+    }
+}
+
+fn check_token_rights(rights: TokenRights, acl: ACL, request_index: Option<&str>) -> SResult<()> {
+    if rights.revoked {
+        return Err(ResponseError::invalid_token("token revoked"));
+    }
+
+    if let Some(index) = request_index {
+        if !rights.indexes.iter().any(|r| match_wildcard(&r, index)) {
+            return Err(ResponseError::invalid_token(
+                "token is not allowed to access to this index",
+            ));
+        }
+    }
+
+    if rights.expires_at < Utc::now() {
+        return Err(ResponseError::invalid_token("token expired"));
+    }
+
+    if rights.acl.contains(&ACL::All) {
+        return Ok(());
+    }
+
+    if !rights.acl.contains(&acl) {
+        return Err(ResponseError::invalid_token("token do not have this ACL"));
+    }
+
+    Ok(())
+}
@@ -1,11 +1,16 @@
 #[macro_use]
 extern crate envconfig_derive;
 
+pub mod analytics;
 pub mod data;
 pub mod error;
 pub mod helpers;
+pub mod metrics;
+pub mod middleware;
 pub mod models;
 pub mod option;
+pub mod popularity;
 pub mod routes;
+pub mod tls;
 
 use self::data::Data;
@@ -1,12 +1,14 @@
-use http::header::HeaderValue;
+use std::sync::Arc;
+
 use log::info;
 use main_error::MainError;
-use tide::middleware::{CorsMiddleware, CorsOrigin};
 use tide_log::RequestLogger;
 
 use meilidb_http::data::Data;
+use meilidb_http::middleware::{cors_middleware, MetricsMiddleware, RateLimitMiddleware};
 use meilidb_http::option::Opt;
 use meilidb_http::routes;
+use meilidb_http::tls::{self, TlsConfig};
 
 #[cfg(not(target_os = "macos"))]
 #[global_allocator]
@@ -18,19 +20,30 @@ pub fn main() -> Result<(), MainError> {
     let data = Data::new(opt.clone());
     let mut app = tide::App::with_state(data);
 
-    app.middleware(
-        CorsMiddleware::new()
-            .allow_origin(CorsOrigin::from("*"))
-            .allow_methods(HeaderValue::from_static("GET, POST, OPTIONS")),
-    );
+    app.middleware(cors_middleware(&opt)?);
     app.middleware(RequestLogger::new());
+    app.middleware(MetricsMiddleware);
+    app.middleware(RateLimitMiddleware);
     app.middleware(tide_compression::Compression::new());
     app.middleware(tide_compression::Decompression::new());
 
     routes::load_routes(&mut app);
 
-    info!("Server HTTP enabled");
-    app.run(opt.http_addr)?;
+    match (opt.ssl_cert_path, opt.ssl_key_path) {
+        (Some(cert_path), Some(key_path)) => {
+            let tls_config = Arc::new(TlsConfig::load(cert_path, key_path)?);
+            tls_config.clone().reload_on_sighup()?;
+
+            info!("Server HTTPS enabled");
+            let service = app.into_http_service();
+            async_std::task::block_on(tls::serve(service, &opt.http_addr, tls_config))?;
+        }
+        (None, None) => {
+            info!("Server HTTP enabled");
+            app.run(opt.http_addr)?;
+        }
+        _ => return Err("both the TLS certificate and private key paths must be set".into()),
+    }
 
     Ok(())
 }
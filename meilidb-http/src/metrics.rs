@@ -0,0 +1,278 @@
+use std::path::Path;
+use std::time::Duration;
+
+use meilidb_core::{Database, MResult, ProcessedUpdateResult, QueryStats};
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+
+/// The content type of the Prometheus text exposition format.
+pub const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+const HTTP_BUCKETS: &[f64] = &[
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+const UPDATE_BUCKETS: &[f64] = &[
+    0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0, 30.0, 60.0, 300.0, 900.0, 3600.0,
+];
+
+/// The metrics of the server, exposed in the Prometheus text format.
+///
+/// The counters and histograms are updated by the requests and the updates,
+/// the gauges are computed from the database every time the metrics are rendered.
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+    search_duration: HistogramVec,
+    search_timeouts: IntCounterVec,
+    search_cache_hits: IntCounterVec,
+    update_duration: HistogramVec,
+    update_queue_size: IntGaugeVec,
+    documents: IntGaugeVec,
+    database_size: IntGauge,
+}
+
+impl Metrics {
+    pub fn new() -> Metrics {
+        let registry = Registry::new_custom(Some(String::from("meilidb")), None).unwrap();
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "Number of HTTP requests"),
+            &["method", "route", "status"],
+        )
+        .unwrap();
+
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "Time spent to answer the HTTP requests",
+            )
+            .buckets(HTTP_BUCKETS.to_vec()),
+            &["method", "route"],
+        )
+        .unwrap();
+
+        let search_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "search_duration_seconds",
+                "Time spent to find and sort the documents matching a query",
+            )
+            .buckets(HTTP_BUCKETS.to_vec()),
+            &["index"],
+        )
+        .unwrap();
+
+        let search_timeouts = IntCounterVec::new(
+            Opts::new(
+                "search_timeouts_total",
+                "Number of searches stopped by their timeout",
+            ),
+            &["index"],
+        )
+        .unwrap();
+
+        let search_cache_hits = IntCounterVec::new(
+            Opts::new(
+                "search_cache_hits_total",
+                "Number of searches answered by the query cache",
+            ),
+            &["index"],
+        )
+        .unwrap();
+
+        let update_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "update_duration_seconds",
+                "Time spent to process the updates",
+            )
+            .buckets(UPDATE_BUCKETS.to_vec()),
+            &["index", "type", "status"],
+        )
+        .unwrap();
+
+        let update_queue_size = IntGaugeVec::new(
+            Opts::new(
+                "update_queue_size",
+                "Number of updates waiting to be processed",
+            ),
+            &["index"],
+        )
+        .unwrap();
+
+        let documents = IntGaugeVec::new(
+            Opts::new("index_documents", "Number of documents in the index"),
+            &["index"],
+        )
+        .unwrap();
+
+        let database_size = IntGauge::new(
+            "database_size_bytes",
+            "Size of the LMDB file of the database",
+        )
+        .unwrap();
+
+        registry.register(Box::new(http_requests.clone())).unwrap();
+        registry
+            .register(Box::new(http_request_duration.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(search_duration.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(search_timeouts.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(search_cache_hits.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(update_duration.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(update_queue_size.clone()))
+            .unwrap();
+        registry.register(Box::new(documents.clone())).unwrap();
+        registry.register(Box::new(database_size.clone())).unwrap();
+
+        Metrics {
+            registry,
+            http_requests,
+            http_request_duration,
+            search_duration,
+            search_timeouts,
+            search_cache_hits,
+            update_duration,
+            update_queue_size,
+            documents,
+            database_size,
+        }
+    }
+
+    pub fn observe_request(&self, method: &str, route: &str, status: u16, duration: Duration) {
+        let status = status.to_string();
+        self.http_requests
+            .with_label_values(&[method, route, &status])
+            .inc();
+        self.http_request_duration
+            .with_label_values(&[method, route])
+            .observe(as_seconds(duration));
+    }
+
+    /// The searches answered by the query cache have no stats, they are only counted.
+    pub fn observe_search(&self, index_name: &str, stats: Option<QueryStats>) {
+        let stats = match stats {
+            Some(stats) => stats,
+            None => {
+                self.search_cache_hits
+                    .with_label_values(&[index_name])
+                    .inc();
+                return;
+            }
+        };
+
+        self.search_duration
+            .with_label_values(&[index_name])
+            .observe(as_seconds(stats.processing_time));
+        if stats.timed_out {
+            self.search_timeouts.with_label_values(&[index_name]).inc();
+        }
+    }
+
+    pub fn observe_update(&self, index_name: &str, status: &ProcessedUpdateResult) {
+        let update_status = if status.result.is_ok() {
+            "processed"
+        } else {
+            "failed"
+        };
+        self.update_duration
+            .with_label_values(&[index_name, status.update_type.name(), update_status])
+            .observe(as_seconds(status.detailed_duration.main));
+    }
+
+    /// Refreshes the gauges from the database and encodes all the metrics.
+    pub fn render(&self, db: &Database, db_path: &str) -> MResult<String> {
+        self.update_queue_size.reset();
+        self.documents.reset();
+
+        let reader = db.env.read_txn()?;
+        for index_name in db.indexes_names()? {
+            let index = match db.open_index(&index_name) {
+                Some(index) => index,
+                None => continue,
+            };
+
+            let queue_size = index.updates.len(&reader)?;
+            self.update_queue_size
+                .with_label_values(&[&index_name])
+                .set(queue_size as i64);
+
+            let number_of_documents = index.main.number_of_documents(&reader)?;
+            self.documents
+                .with_label_values(&[&index_name])
+                .set(number_of_documents as i64);
+        }
+        reader.abort();
+
+        // the LMDB environment is a single file, its size never shrinks
+        let data_file = Path::new(db_path).join("data.mdb");
+        let database_size = data_file.metadata().map(|m| m.len()).unwrap_or(0);
+        self.database_size.set(database_size as i64);
+
+        let mut buffer = Vec::new();
+        let encoder = TextEncoder::new();
+        encoder
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("metrics are always encodable");
+
+        Ok(String::from_utf8(buffer).expect("metrics are valid UTF-8"))
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Metrics {
+        Metrics::new()
+    }
+}
+
+fn as_seconds(duration: Duration) -> f64 {
+    duration.as_secs() as f64 + f64::from(duration.subsec_nanos()) / 1e9
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_requests_and_searches() {
+        let metrics = Metrics::new();
+        metrics.observe_request("GET", "/indexes/:index", 200, Duration::from_millis(3));
+        metrics.observe_request("GET", "/indexes/:index", 200, Duration::from_millis(4));
+
+        let stats = QueryStats {
+            processing_time: Duration::from_millis(31),
+            timed_out: true,
+            nb_hits: 12,
+        };
+        metrics.observe_search("movies", Some(stats));
+        metrics.observe_search("movies", None);
+
+        let mut buffer = Vec::new();
+        let encoder = TextEncoder::new();
+        encoder
+            .encode(&metrics.registry.gather(), &mut buffer)
+            .unwrap();
+        let text = String::from_utf8(buffer).unwrap();
+
+        assert!(text.contains(
+            r#"meilidb_http_requests_total{method="GET",route="/indexes/:index",status="200"} 2"#
+        ));
+        assert!(text.contains(
+            r#"meilidb_http_request_duration_seconds_count{method="GET",route="/indexes/:index"} 2"#
+        ));
+        assert!(text.contains(r#"meilidb_search_timeouts_total{index="movies"} 1"#));
+        assert!(text.contains(r#"meilidb_search_duration_seconds_count{index="movies"} 1"#));
+        assert!(text.contains(r#"meilidb_search_cache_hits_total{index="movies"} 1"#));
+    }
+}
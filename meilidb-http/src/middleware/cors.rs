@@ -0,0 +1,21 @@
+use http::header::{HeaderValue, InvalidHeaderValue};
+use tide::middleware::{CorsMiddleware, CorsOrigin};
+
+use crate::option::Opt;
+
+/// Builds the CORS middleware from the allowed origins, methods and headers of the options.
+pub fn cors_middleware(opt: &Opt) -> Result<CorsMiddleware, InvalidHeaderValue> {
+    let origin = if opt.cors_allowed_origins.iter().any(|o| o == "*") {
+        CorsOrigin::Any
+    } else {
+        CorsOrigin::List(opt.cors_allowed_origins.clone())
+    };
+
+    let methods = HeaderValue::from_str(&opt.cors_allowed_methods.join(", "))?;
+    let headers = HeaderValue::from_str(&opt.cors_allowed_headers.join(", "))?;
+
+    Ok(CorsMiddleware::new()
+        .allow_origin(origin)
+        .allow_methods(methods)
+        .allow_headers(headers))
+}
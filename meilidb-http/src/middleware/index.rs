@@ -0,0 +1,33 @@
+use futures::future::BoxFuture;
+use meilidb_core::Index;
+use tide::middleware::{Middleware, Next};
+use tide::{Context, Response};
+
+use crate::Data;
+
+/// The index the `:index` parameter of the request resolved to,
+/// `None` if there is no index nor alias with this name.
+#[derive(Clone)]
+pub struct ResolvedIndex(pub Option<(String, Index)>);
+
+/// Resolves the `:index` parameter once for the whole request, the token
+/// checks and the route must not see different indexes if the alias is
+/// made to point to another index in the meantime.
+pub struct IndexMiddleware;
+
+impl Middleware<Data> for IndexMiddleware {
+    fn handle<'a>(
+        &'a self,
+        mut ctx: Context<Data>,
+        next: Next<'a, Data>,
+    ) -> BoxFuture<'a, Response> {
+        Box::pin(async move {
+            if let Ok(name) = ctx.param::<String>("index") {
+                let resolved = ctx.state().db.resolve_index(&name);
+                ctx.extensions_mut().insert(ResolvedIndex(resolved));
+            }
+
+            next.run(ctx).await
+        })
+    }
+}
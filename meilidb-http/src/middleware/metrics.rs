@@ -0,0 +1,198 @@
+use std::time::Instant;
+
+use futures::future::BoxFuture;
+use tide::middleware::{Middleware, Next};
+use tide::{Context, Response};
+
+use crate::Data;
+
+/// The first segments of the paths the server answers to,
+/// the other paths are all counted as a single unknown route.
+///
+/// The routes registered in `load_routes` are all checked to have a pattern by a test.
+const KNOWN_ROUTES: &[&str] = &[
+    "indexes", "aliases", "keys", "health", "stats", "version", "sys-info", "metrics",
+];
+
+/// The static segments found after the first one, any other segment that
+/// is not a parameter makes the whole path count as the unknown route.
+const KNOWN_SEGMENTS: &[&str] = &[
+    "search",
+    "analytics",
+    "events",
+    "updates",
+    "limits",
+    "rename",
+    "clone",
+    "documents",
+    "delete",
+    "synonym",
+    "batch",
+    "clear",
+    "rules",
+    "stop-words",
+    "settings",
+    "swap",
+    "rotate",
+    "usage",
+    "pretty",
+];
+
+/// Counts the requests and measures their latency, by route.
+pub struct MetricsMiddleware;
+
+impl Middleware<Data> for MetricsMiddleware {
+    fn handle<'a>(&'a self, ctx: Context<Data>, next: Next<'a, Data>) -> BoxFuture<'a, Response> {
+        Box::pin(async move {
+            let data = ctx.state().clone();
+            let method = ctx.method().to_string();
+            let route = route_pattern(ctx.uri().path());
+            let start = Instant::now();
+
+            let response = next.run(ctx).await;
+
+            let status = response.status().as_u16();
+            data.metrics
+                .observe_request(&method, &route, status, start.elapsed());
+
+            response
+        })
+    }
+}
+
+/// Replaces the parameters of a path by their names, the metrics
+/// must not be labeled by index names or document ids.
+pub fn route_pattern(path: &str) -> String {
+    let mut segments = path.split('/').filter(|s| !s.is_empty());
+
+    let first = match segments.next() {
+        Some(first) if KNOWN_ROUTES.contains(&first) => first,
+        Some(_) => return String::from("unknown"),
+        None => return String::from("/"),
+    };
+
+    let mut route = format!("/{}", first);
+    let mut previous = first;
+
+    for segment in segments {
+        let pattern = match (previous, segment) {
+            ("indexes", s) if s != "search" => ":index",
+            ("stats", _) => ":index",
+            ("aliases", s) if s != "swap" => ":alias",
+            ("keys", _) => ":key",
+            ("documents", s) if s != "delete" => ":identifier",
+            ("updates", _) => ":update_id",
+            ("synonym", s) if s != "batch" && s != "clear" => ":synonym",
+            ("rules", _) => ":rule",
+            (_, s) if KNOWN_SEGMENTS.contains(&s) => s,
+            (_, _) => return String::from("unknown"),
+        };
+
+        route.push('/');
+        route.push_str(pattern);
+        previous = pattern;
+    }
+
+    route
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parameters_are_replaced() {
+        assert_eq!(route_pattern("/indexes"), "/indexes");
+        assert_eq!(route_pattern("/indexes/search"), "/indexes/search");
+        assert_eq!(route_pattern("/indexes/movies/"), "/indexes/:index");
+        assert_eq!(
+            route_pattern("/indexes/movies/documents/42"),
+            "/indexes/:index/documents/:identifier"
+        );
+        assert_eq!(
+            route_pattern("/indexes/movies/documents/delete"),
+            "/indexes/:index/documents/delete"
+        );
+        assert_eq!(
+            route_pattern("/indexes/documents/updates/3"),
+            "/indexes/:index/updates/:update_id"
+        );
+        assert_eq!(
+            route_pattern("/indexes/movies/synonym/batch"),
+            "/indexes/:index/synonym/batch"
+        );
+        assert_eq!(
+            route_pattern("/indexes/movies/rules/iphone"),
+            "/indexes/:index/rules/:rule"
+        );
+        assert_eq!(route_pattern("/aliases/swap"), "/aliases/swap");
+        assert_eq!(route_pattern("/keys/abcd/usage"), "/keys/:key/usage");
+        assert_eq!(route_pattern("/stats/movies"), "/stats/:index");
+        assert_eq!(route_pattern("/wp-admin/login.php"), "unknown");
+        assert_eq!(route_pattern("/indexes/movies/.env"), "unknown");
+        assert_eq!(route_pattern("/health/a/b/c"), "unknown");
+    }
+
+    /// Returns the paths given to `at` in the routes module joined to the paths
+    /// of the routes they are nested in, the parameters keep their names.
+    fn registered_routes() -> Vec<String> {
+        let source = include_str!("../routes/mod.rs");
+
+        let mut routes = Vec::new();
+        // the paths of the routes being nested along with the depth of their block
+        let mut prefixes: Vec<(String, usize)> = Vec::new();
+        let mut last_path = String::new();
+        let mut nest_pending = false;
+        let mut depth = 0;
+
+        let mut rest = source;
+        while let Some(c) = rest.chars().next() {
+            if rest.starts_with(".at(\"") {
+                let path = &rest[5..];
+                let end = path.find('"').unwrap();
+                let prefix = prefixes.last().map_or("", |(p, _)| p.as_str());
+                last_path = format!("{}{}", prefix, &path[..end]);
+                routes.push(last_path.clone());
+                rest = &path[end..];
+                continue;
+            }
+
+            if rest.starts_with(".nest(") {
+                nest_pending = true;
+            } else if c == '{' {
+                depth += 1;
+                if nest_pending {
+                    prefixes.push((last_path.clone(), depth));
+                    nest_pending = false;
+                }
+            } else if c == '}' {
+                if prefixes.last().map_or(false, |(_, d)| *d == depth) {
+                    prefixes.pop();
+                }
+                depth -= 1;
+            }
+
+            rest = &rest[c.len_utf8()..];
+        }
+
+        routes
+    }
+
+    #[test]
+    fn every_route_has_a_pattern() {
+        let routes = registered_routes();
+        assert!(routes.contains(&String::from("/indexes/:index/documents/:identifier")));
+
+        for route in routes {
+            let segments: Vec<_> = route.split('/').filter(|s| !s.is_empty()).collect();
+            let expected = format!("/{}", segments.join("/"));
+            let path: Vec<_> = segments
+                .iter()
+                .map(|s| if s.starts_with(':') { "value" } else { s })
+                .collect();
+            let path = format!("/{}", path.join("/"));
+
+            assert_eq!(route_pattern(&path), expected, "route {}", route);
+        }
+    }
+}
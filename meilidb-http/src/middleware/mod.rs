@@ -0,0 +1,9 @@
+pub mod cors;
+pub mod index;
+pub mod metrics;
+pub mod rate_limit;
+
+pub use self::cors::cors_middleware;
+pub use self::index::{IndexMiddleware, ResolvedIndex};
+pub use self::metrics::MetricsMiddleware;
+pub use self::rate_limit::{RateLimitMiddleware, RateLimiter};
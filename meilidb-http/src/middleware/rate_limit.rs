@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+use std::mem;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use futures::future::BoxFuture;
+use heed::types::{SerdeBincode, Str};
+use meilidb_core::{Database, MResult};
+use tide::middleware::{Middleware, Next};
+use tide::response::IntoResponse;
+use tide::{Context, Response};
+
+use crate::error::ResponseError;
+use crate::helpers::tide::authenticate;
+use crate::models::token::*;
+use crate::Data;
+
+/// Throttles the requests made with an API key, according to the rate limit of its token,
+/// and counts them. The tenant tokens are throttled with their parent token.
+///
+/// The API key is verified here once for the whole request, an invalid
+/// key is not rejected here but by the routes that require a key.
+pub struct RateLimitMiddleware;
+
+impl Middleware<Data> for RateLimitMiddleware {
+    fn handle<'a>(
+        &'a self,
+        mut ctx: Context<Data>,
+        next: Next<'a, Data>,
+    ) -> BoxFuture<'a, Response> {
+        Box::pin(async move {
+            if let Ok(credentials) = authenticate(&ctx) {
+                if let Some(token) = credentials.token() {
+                    let limiter = &ctx.state().rate_limiter;
+                    if let Err(wait) = limiter.check(&token.key_id, token.rate_limit) {
+                        return ResponseError::too_many_requests(wait).into_response();
+                    }
+                }
+                ctx.extensions_mut().insert(credentials);
+            }
+
+            next.run(ctx).await
+        })
+    }
+}
+
+struct Bucket {
+    limit: RateLimit,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl Bucket {
+    fn new(limit: RateLimit, now: Instant) -> Bucket {
+        Bucket {
+            limit,
+            tokens: f64::from(limit.burst),
+            refilled_at: now,
+        }
+    }
+
+    /// Takes a token from the bucket, returns the time to wait
+    /// before a token is available if the bucket is empty.
+    fn take(&mut self, now: Instant) -> Result<(), Duration> {
+        let rate = f64::from(self.limit.requests_per_second);
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(f64::from(self.limit.burst));
+        self.refilled_at = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / rate))
+        }
+    }
+}
+
+struct Inner {
+    buckets: HashMap<String, Bucket>,
+    pending_usages: HashMap<String, KeyUsage>,
+}
+
+/// The token buckets of the API keys and their usages that are not stored yet.
+pub struct RateLimiter {
+    inner: Mutex<Inner>,
+}
+
+impl Default for RateLimiter {
+    fn default() -> RateLimiter {
+        RateLimiter {
+            inner: Mutex::new(Inner {
+                buckets: HashMap::new(),
+                pending_usages: HashMap::new(),
+            }),
+        }
+    }
+}
+
+impl RateLimiter {
+    /// Counts a request made with this key, returns the time to wait
+    /// before retrying if the key has exceeded its rate limit.
+    pub fn check(&self, key_id: &str, limit: Option<RateLimit>) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut inner = self.inner.lock().unwrap();
+
+        let result = match limit {
+            Some(limit) => {
+                let bucket = inner
+                    .buckets
+                    .entry(key_id.to_owned())
+                    .or_insert_with(|| Bucket::new(limit, now));
+
+                // the limit of the token has been updated
+                if bucket.limit != limit {
+                    *bucket = Bucket::new(limit, now);
+                }
+
+                bucket.take(now)
+            }
+            None => {
+                inner.buckets.remove(key_id);
+                Ok(())
+            }
+        };
+
+        let usage = inner.pending_usages.entry(key_id.to_owned()).or_default();
+        usage.total_requests += 1;
+        usage.throttled_requests += result.is_err() as u64;
+        usage.last_request_at = Some(Utc::now());
+
+        result
+    }
+
+    /// The usage of this key that is not stored yet.
+    pub fn pending_usage(&self, key_id: &str) -> KeyUsage {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .pending_usages
+            .get(key_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Forgets everything about a deleted key.
+    pub fn remove(&self, key_id: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.buckets.remove(key_id);
+        inner.pending_usages.remove(key_id);
+    }
+
+    /// Writes the pending usages to the database, this is done by a background
+    /// thread. The usages are kept to be written later if the write fails.
+    pub fn flush(&self, db: &Database) -> MResult<()> {
+        let pending_usages = {
+            let mut inner = self.inner.lock().unwrap();
+            mem::replace(&mut inner.pending_usages, HashMap::new())
+        };
+
+        if pending_usages.is_empty() {
+            return Ok(());
+        }
+
+        if let Err(e) = write_usages(db, &pending_usages) {
+            // the requests made in the meantime are merged with the restored usages
+            let mut inner = self.inner.lock().unwrap();
+            for (key_id, pending) in pending_usages {
+                inner
+                    .pending_usages
+                    .entry(key_id)
+                    .or_default()
+                    .merge(&pending);
+            }
+            return Err(e);
+        }
+
+        Ok(())
+    }
+}
+
+fn write_usages(db: &Database, pending_usages: &HashMap<String, KeyUsage>) -> MResult<()> {
+    let env = &db.env;
+    let mut writer = env.write_txn()?;
+    let common_store = db.common_store();
+
+    for (key_id, pending) in pending_usages {
+        let usage_key = format!("{}{}", KEY_USAGE_PREFIX_KEY, key_id);
+        let mut usage = common_store
+            .get::<Str, SerdeBincode<KeyUsage>>(&writer, &usage_key)?
+            .unwrap_or_default();
+        usage.merge(pending);
+        common_store.put::<Str, SerdeBincode<KeyUsage>>(&mut writer, &usage_key, &usage)?;
+    }
+
+    writer.commit()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_bucket() {
+        let limit = RateLimit {
+            requests_per_second: 2,
+            burst: 3,
+        };
+        let now = Instant::now();
+        let mut bucket = Bucket::new(limit, now);
+
+        assert!(bucket.take(now).is_ok());
+        assert!(bucket.take(now).is_ok());
+        assert!(bucket.take(now).is_ok());
+
+        let wait = bucket.take(now).unwrap_err();
+        assert_eq!(wait, Duration::from_millis(500));
+
+        // one token is refilled every half second
+        let now = now + Duration::from_millis(500);
+        assert!(bucket.take(now).is_ok());
+        assert!(bucket.take(now).is_err());
+
+        // the bucket never holds more than the burst
+        let now = now + Duration::from_secs(60);
+        for _ in 0..3 {
+            assert!(bucket.take(now).is_ok());
+        }
+        assert!(bucket.take(now).is_err());
+    }
+
+    #[test]
+    fn limiter_counts_usage() {
+        let limiter = RateLimiter::default();
+        let limit = RateLimit {
+            requests_per_second: 1,
+            burst: 1,
+        };
+
+        assert!(limiter.check("ABCDEFGH", Some(limit)).is_ok());
+        assert!(limiter.check("ABCDEFGH", Some(limit)).is_err());
+        assert!(limiter.check("IJKLMNOP", None).is_ok());
+
+        let usage = limiter.pending_usage("ABCDEFGH");
+        assert_eq!(usage.total_requests, 2);
+        assert_eq!(usage.throttled_requests, 1);
+        assert!(usage.last_request_at.is_some());
+
+        let usage = limiter.pending_usage("IJKLMNOP");
+        assert_eq!(usage.total_requests, 1);
+        assert_eq!(usage.throttled_requests, 0);
+    }
+}
@@ -1,3 +1,4 @@
 pub mod schema;
+pub mod tenant_token;
 pub mod token;
 pub mod update_operation;
@@ -0,0 +1,177 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::models::token::{Wildcard, KEY_ID_LENGTH};
+
+/// A tenant token is made of the key id of its parent token, the base64 encoded
+/// JSON claims and the base64 encoded HMAC-SHA256 signature of these claims,
+/// all separated by dots: `{key_id}.{claims}.{signature}`.
+///
+/// The claims are signed with the signing key of the parent API key, it is given when
+/// the API key is created or rotated and derived from the master key and the salt of
+/// the secret, see [`signing_key`]. It is never stored, reading the database is not
+/// enough to forge tenant tokens.
+pub const TENANT_TOKEN_SEPARATOR: char = '.';
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// The restrictions embedded in a tenant token.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct TenantClaims {
+    /// The indexes the tenant token can search in, a subset of the parent ones.
+    pub indexes: Vec<Wildcard>,
+    /// A filter that is added to every search made with this tenant token.
+    pub filter: Option<String>,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+pub struct TenantToken<'a> {
+    pub key_id: &'a str,
+    pub claims: TenantClaims,
+    encoded_claims: &'a str,
+    signature: Vec<u8>,
+}
+
+impl<'a> TenantToken<'a> {
+    /// Decodes a tenant token, returns `None` if it is malformed.
+    ///
+    /// The signature is not checked, it must be verified
+    /// against the parent token with [`TenantToken::is_signed_by`].
+    pub fn parse(token: &'a str) -> Option<TenantToken<'a>> {
+        let mut split = token.split(TENANT_TOKEN_SEPARATOR);
+        let (key_id, encoded_claims, signature) = match (split.next(), split.next(), split.next()) {
+            (Some(k), Some(c), Some(s)) if split.next().is_none() => (k, c, s),
+            _ => return None,
+        };
+
+        if key_id.len() != KEY_ID_LENGTH {
+            return None;
+        }
+
+        let claims = base64::decode_config(encoded_claims, base64::URL_SAFE_NO_PAD).ok()?;
+        let claims = serde_json::from_slice(&claims).ok()?;
+        let signature = base64::decode_config(signature, base64::URL_SAFE_NO_PAD).ok()?;
+
+        Some(TenantToken {
+            key_id,
+            claims,
+            encoded_claims,
+            signature,
+        })
+    }
+
+    /// Whether the claims of this tenant token have been signed with this signing key.
+    pub fn is_signed_by(&self, signing_key: &str) -> bool {
+        let mut mac = new_mac(signing_key);
+        mac.input(self.encoded_claims.as_bytes());
+        mac.verify(&self.signature).is_ok()
+    }
+}
+
+/// Tenant tokens are told apart from API keys by their separators.
+pub fn is_tenant_token(api_key: &str) -> bool {
+    api_key.contains(TENANT_TOKEN_SEPARATOR)
+}
+
+/// The key used to sign the tenant tokens derived from the secret of an API key,
+/// the salt changes with the secret, the tenant tokens do not survive a rotation.
+pub fn signing_key(master_key: &str, key_id: &str, salt: &str) -> String {
+    let mut mac = new_mac(master_key);
+    mac.input(key_id.as_bytes());
+    mac.input(salt.as_bytes());
+    format!("{:x}", mac.result().code())
+}
+
+/// Creates a tenant token derived from an API key, signed with its signing key.
+pub fn sign_tenant_token(key_id: &str, signing_key: &str, claims: &TenantClaims) -> Option<String> {
+    if key_id.len() != KEY_ID_LENGTH {
+        return None;
+    }
+
+    let claims = serde_json::to_vec(claims).ok()?;
+    let encoded_claims = base64::encode_config(&claims, base64::URL_SAFE_NO_PAD);
+
+    let mut mac = new_mac(signing_key);
+    mac.input(encoded_claims.as_bytes());
+    let signature = mac.result().code();
+    let signature = base64::encode_config(&signature, base64::URL_SAFE_NO_PAD);
+
+    Some(format!(
+        "{}{sep}{}{sep}{}",
+        key_id,
+        encoded_claims,
+        signature,
+        sep = TENANT_TOKEN_SEPARATOR
+    ))
+}
+
+fn new_mac(signing_key: &str) -> HmacSha256 {
+    // HMAC accepts keys of any size
+    HmacSha256::new_varkey(signing_key.as_bytes()).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn claims() -> TenantClaims {
+        TenantClaims {
+            indexes: vec![String::from("movies")],
+            filter: Some(String::from("tenant_id:42")),
+            expires_at: Utc::now() + Duration::hours(1),
+        }
+    }
+
+    #[test]
+    fn sign_and_parse() {
+        let key = signing_key("master", "ABCDEFGH", "salt");
+        let token = sign_tenant_token("ABCDEFGH", &key, &claims()).unwrap();
+        assert!(is_tenant_token(&token));
+        assert!(!is_tenant_token("ABCDEFGHsecret"));
+
+        let tenant_token = TenantToken::parse(&token).unwrap();
+        assert_eq!(tenant_token.key_id, "ABCDEFGH");
+        assert_eq!(tenant_token.claims.indexes, vec![String::from("movies")]);
+        assert_eq!(
+            tenant_token.claims.filter,
+            Some(String::from("tenant_id:42"))
+        );
+        assert!(tenant_token.is_signed_by(&key));
+        assert!(!tenant_token.is_signed_by(&signing_key("master", "ABCDEFGH", "other")));
+        // the signing key cannot be derived without the master key
+        assert!(!tenant_token.is_signed_by(&signing_key("other", "ABCDEFGH", "salt")));
+
+        assert!(sign_tenant_token("ABC", &key, &claims()).is_none());
+    }
+
+    #[test]
+    fn tampered_claims() {
+        let key = signing_key("master", "ABCDEFGH", "salt");
+        let token = sign_tenant_token("ABCDEFGH", &key, &claims()).unwrap();
+        let signature = token.rsplit('.').next().unwrap();
+
+        let mut other_claims = claims();
+        other_claims.filter = None;
+        let other = sign_tenant_token("ABCDEFGH", &key, &other_claims).unwrap();
+        let mut parts: Vec<_> = other.split('.').collect();
+        parts[2] = signature;
+        let forged = parts.join(".");
+
+        let tenant_token = TenantToken::parse(&forged).unwrap();
+        assert!(!tenant_token.is_signed_by(&key));
+    }
+
+    #[test]
+    fn malformed_tokens() {
+        assert!(TenantToken::parse("ABCDEFGH").is_none());
+        assert!(TenantToken::parse("ABCDEFGH.e30").is_none());
+        assert!(TenantToken::parse("ABC.e30.e30").is_none());
+        assert!(TenantToken::parse("ABCDEFGH.!!!.e30").is_none());
+        assert!(TenantToken::parse("ABCDEFGH.e30.e30.e30").is_none());
+    }
+}
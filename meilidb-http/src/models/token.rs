@@ -1,7 +1,22 @@
 use chrono::{DateTime, Utc};
+use rand::seq::SliceRandom;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
-pub const TOKEN_PREFIX_KEY: &str = "_token_";
+use crate::models::tenant_token::{signing_key, TenantToken};
+
+/// The prefix of the keys under which the tokens were stored with their raw API key.
+pub const LEGACY_TOKEN_PREFIX_KEY: &str = "_token_";
+/// The prefix of the keys under which the tokens are stored, followed by the key id.
+pub const TOKEN_PREFIX_KEY: &str = "_key_";
+/// The prefix of the keys under which the usage of the tokens is stored, followed by the key id.
+pub const KEY_USAGE_PREFIX_KEY: &str = "_usage_";
+
+/// An API key is made of a public key id followed by a secret,
+/// the key id is used to find the token without storing the secret.
+pub const KEY_ID_LENGTH: usize = 8;
+pub const SECRET_LENGTH: usize = 32;
+const SALT_LENGTH: usize = 16;
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -15,6 +30,8 @@ pub enum ACL {
     Admin,
     #[serde(rename = "*")]
     All,
+    // the tokens are stored with bincode, the new variants must be added last
+    EventsWrite,
 }
 
 pub type Wildcard = String;
@@ -22,6 +39,54 @@ pub type Wildcard = String;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Token {
+    pub key_id: String,
+    pub description: String,
+    pub acl: Vec<ACL>,
+    pub indexes: Vec<Wildcard>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub revoked: bool,
+    pub secret: HashedSecret,
+    /// The secret replaced by the last rotation, it stays valid until its expiration.
+    pub previous_secret: Option<(HashedSecret, DateTime<Utc>)>,
+    /// The number of requests this token can make, unlimited if `None`.
+    pub rate_limit: Option<RateLimit>,
+}
+
+/// The limits of a token bucket, it holds `burst` requests and
+/// is refilled with `requests_per_second` requests every second.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct RateLimit {
+    pub requests_per_second: u32,
+    pub burst: u32,
+}
+
+/// The number of requests made with a token.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyUsage {
+    pub total_requests: u64,
+    pub throttled_requests: u64,
+    pub last_request_at: Option<DateTime<Utc>>,
+}
+
+impl KeyUsage {
+    pub fn merge(&mut self, other: &KeyUsage) {
+        self.total_requests += other.total_requests;
+        self.throttled_requests += other.throttled_requests;
+        self.last_request_at = match (self.last_request_at, other.last_request_at) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+    }
+}
+
+/// The token as it was stored before the secrets were hashed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LegacyToken {
     pub key: String,
     pub description: String,
     pub acl: Vec<ACL>,
@@ -32,6 +97,90 @@ pub struct Token {
     pub revoked: bool,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HashedSecret {
+    salt: String,
+    hash: String,
+}
+
+impl HashedSecret {
+    pub fn new(secret: &str) -> HashedSecret {
+        let salt = generate_random_string(SALT_LENGTH);
+        let hash = hash_secret(&salt, secret);
+        HashedSecret { salt, hash }
+    }
+
+    pub fn matches(&self, secret: &str) -> bool {
+        let hash = hash_secret(&self.salt, secret);
+        constant_time_eq(hash.as_bytes(), self.hash.as_bytes())
+    }
+
+    /// The key the tenant tokens derived from this secret are signed with, it
+    /// is derived from the master key of the server and is never stored.
+    pub fn signing_key(&self, master_key: &str, key_id: &str) -> String {
+        signing_key(master_key, key_id, &self.salt)
+    }
+}
+
+impl Token {
+    /// Whether the secret is the current secret of this token or
+    /// the previous one, if the rotation grace period is not over.
+    pub fn verify_secret(&self, secret: &str) -> bool {
+        if self.secret.matches(secret) {
+            return true;
+        }
+
+        match &self.previous_secret {
+            Some((previous, expires_at)) => *expires_at > Utc::now() && previous.matches(secret),
+            None => false,
+        }
+    }
+
+    /// Whether the tenant token has been signed with the current secret of
+    /// this token or the previous one, if the rotation grace period is not over.
+    pub fn verify_tenant_token(&self, tenant_token: &TenantToken, master_key: &str) -> bool {
+        let signing_key = self.secret.signing_key(master_key, &self.key_id);
+        if tenant_token.is_signed_by(&signing_key) {
+            return true;
+        }
+
+        match &self.previous_secret {
+            Some((previous, expires_at)) => {
+                let signing_key = previous.signing_key(master_key, &self.key_id);
+                *expires_at > Utc::now() && tenant_token.is_signed_by(&signing_key)
+            }
+            None => false,
+        }
+    }
+}
+
+fn hash_secret(salt: &str, secret: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.input(salt.as_bytes());
+    hasher.input(secret.as_bytes());
+    format!("{:x}", hasher.result())
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+pub fn generate_random_string(len: usize) -> String {
+    let mut rng = rand::thread_rng();
+    let sample = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+    (0..len)
+        .map(|_| *sample.choose(&mut rng).unwrap() as char)
+        .collect()
+}
+
+/// Splits an API key into its key id and its secret.
+pub fn split_api_key(api_key: &str) -> Option<(&str, &str)> {
+    if api_key.len() <= KEY_ID_LENGTH || !api_key.is_char_boundary(KEY_ID_LENGTH) {
+        return None;
+    }
+    Some(api_key.split_at(KEY_ID_LENGTH))
+}
+
 fn cleanup_wildcard(input: &str) -> (bool, &str, bool) {
     let first = input.chars().next().filter(|&c| c == '*').is_some();
     let last = input.chars().last().filter(|&c| c == '*').is_some();
@@ -54,6 +203,8 @@ pub fn match_wildcard(pattern: &str, input: &str) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::Duration;
+    use std::mem;
 
     #[test]
     fn test_match_wildcard() {
@@ -69,4 +220,58 @@ mod tests {
         assert!(match_wildcard("*ab*", "ab"));
         assert!(match_wildcard("*😆*", "ab😆dsa"));
     }
+
+    #[test]
+    fn test_hashed_secret() {
+        let secret = HashedSecret::new("hello");
+        assert!(secret.matches("hello"));
+        assert!(!secret.matches("hellO"));
+        assert!(!secret.matches(""));
+
+        // the same secret is not hashed the same way twice
+        let other = HashedSecret::new("hello");
+        assert_ne!(secret.hash, other.hash);
+    }
+
+    #[test]
+    fn test_secret_rotation() {
+        let now = Utc::now();
+        let mut token = Token {
+            key_id: String::from("ABCDEFGH"),
+            description: String::new(),
+            acl: vec![ACL::All],
+            indexes: vec![String::from("*")],
+            created_at: now,
+            updated_at: now,
+            expires_at: now + Duration::days(1),
+            revoked: false,
+            secret: HashedSecret::new("old"),
+            previous_secret: None,
+            rate_limit: None,
+        };
+        assert!(token.verify_secret("old"));
+
+        let previous = mem::replace(&mut token.secret, HashedSecret::new("new"));
+        token.previous_secret = Some((previous, now + Duration::hours(1)));
+        assert!(token.verify_secret("new"));
+        assert!(token.verify_secret("old"));
+        assert!(!token.verify_secret("other"));
+
+        // the grace period is over
+        if let Some((_, expires_at)) = token.previous_secret.as_mut() {
+            *expires_at = now - Duration::hours(1);
+        }
+        assert!(token.verify_secret("new"));
+        assert!(!token.verify_secret("old"));
+    }
+
+    #[test]
+    fn test_split_api_key() {
+        assert_eq!(
+            split_api_key("ABCDEFGHsecret"),
+            Some(("ABCDEFGH", "secret"))
+        );
+        assert_eq!(split_api_key("ABCDEFGH"), None);
+        assert_eq!(split_api_key("ABC"), None);
+    }
 }
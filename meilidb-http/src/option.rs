@@ -16,6 +16,51 @@ struct Vars {
     #[structopt(long)]
     #[envconfig(from = "MEILI_ADMIN_TOKEN")]
     pub admin_token: Option<String>,
+
+    /// The initial size of the database memory map, in bytes.
+    /// The map grows automatically when it is full.
+    #[structopt(long)]
+    #[envconfig(from = "MEILI_MAP_SIZE")]
+    pub map_size: Option<usize>,
+
+    /// The size the database memory map cannot be grown beyond, in bytes.
+    /// The updates that do not fit in a map of this size fail.
+    #[structopt(long)]
+    #[envconfig(from = "MEILI_MAX_MAP_SIZE")]
+    pub max_map_size: Option<usize>,
+
+    /// The maximum number of searches results kept in the query cache,
+    /// the cache is disabled when zero.
+    #[structopt(long)]
+    #[envconfig(from = "MEILI_QUERY_CACHE_SIZE")]
+    pub query_cache_size: Option<usize>,
+
+    /// The path of the PEM encoded TLS certificate chain, TLS is
+    /// enabled when it is set along with the private key path.
+    /// The certificate and the key are reloaded on SIGHUP.
+    #[structopt(long)]
+    #[envconfig(from = "MEILI_SSL_CERT_PATH")]
+    pub ssl_cert_path: Option<String>,
+
+    /// The path of the PEM encoded private key of the TLS certificate.
+    #[structopt(long)]
+    #[envconfig(from = "MEILI_SSL_KEY_PATH")]
+    pub ssl_key_path: Option<String>,
+
+    /// The comma separated origins allowed to do cross-origin requests, `*` allows all of them.
+    #[structopt(long)]
+    #[envconfig(from = "MEILI_CORS_ALLOWED_ORIGINS")]
+    pub cors_allowed_origins: Option<String>,
+
+    /// The comma separated methods allowed in cross-origin requests.
+    #[structopt(long)]
+    #[envconfig(from = "MEILI_CORS_ALLOWED_METHODS")]
+    pub cors_allowed_methods: Option<String>,
+
+    /// The comma separated headers allowed in cross-origin requests.
+    #[structopt(long)]
+    #[envconfig(from = "MEILI_CORS_ALLOWED_HEADERS")]
+    pub cors_allowed_headers: Option<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -23,6 +68,14 @@ pub struct Opt {
     pub database_path: String,
     pub http_addr: String,
     pub admin_token: Option<String>,
+    pub map_size: usize,
+    pub max_map_size: usize,
+    pub query_cache_size: usize,
+    pub ssl_cert_path: Option<String>,
+    pub ssl_key_path: Option<String>,
+    pub cors_allowed_origins: Vec<String>,
+    pub cors_allowed_methods: Vec<String>,
+    pub cors_allowed_headers: Vec<String>,
 }
 
 impl Default for Opt {
@@ -31,6 +84,14 @@ impl Default for Opt {
             database_path: String::from("/tmp/meilidb"),
             http_addr: String::from("127.0.0.1:8080"),
             admin_token: None,
+            map_size: 10 * 1024 * 1024 * 1024,      // 10GB
+            max_map_size: 100 * 1024 * 1024 * 1024, // 100GB
+            query_cache_size: 1000,
+            ssl_cert_path: None,
+            ssl_key_path: None,
+            cors_allowed_origins: vec![String::from("*")],
+            cors_allowed_methods: split_list("GET, POST, PUT, DELETE, OPTIONS"),
+            cors_allowed_headers: split_list("Content-Type, X-Meili-API-Key"),
         }
     }
 }
@@ -51,6 +112,40 @@ impl Opt {
                 .or(args.http_addr)
                 .unwrap_or(default.http_addr),
             admin_token: env.admin_token.or(args.admin_token).or(default.admin_token),
+            map_size: env.map_size.or(args.map_size).unwrap_or(default.map_size),
+            max_map_size: env
+                .max_map_size
+                .or(args.max_map_size)
+                .unwrap_or(default.max_map_size),
+            query_cache_size: env
+                .query_cache_size
+                .or(args.query_cache_size)
+                .unwrap_or(default.query_cache_size),
+            ssl_cert_path: env.ssl_cert_path.or(args.ssl_cert_path),
+            ssl_key_path: env.ssl_key_path.or(args.ssl_key_path),
+            cors_allowed_origins: env
+                .cors_allowed_origins
+                .or(args.cors_allowed_origins)
+                .map(|s| split_list(&s))
+                .unwrap_or(default.cors_allowed_origins),
+            cors_allowed_methods: env
+                .cors_allowed_methods
+                .or(args.cors_allowed_methods)
+                .map(|s| split_list(&s))
+                .unwrap_or(default.cors_allowed_methods),
+            cors_allowed_headers: env
+                .cors_allowed_headers
+                .or(args.cors_allowed_headers)
+                .map(|s| split_list(&s))
+                .unwrap_or(default.cors_allowed_headers),
         }
     }
 }
+
+fn split_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect()
+}
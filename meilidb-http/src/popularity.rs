@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use std::mem;
+use std::sync::Mutex;
+
+use meilidb_core::{Database, DocumentId, MResult, RankingRule};
+
+/// The popularity gained by the documents of every index that is not stored yet,
+/// the popularity map of an index is rewritten once for all the events received.
+#[derive(Default)]
+pub struct PopularityEvents {
+    pending: Mutex<HashMap<String, HashMap<DocumentId, u64>>>,
+}
+
+impl PopularityEvents {
+    pub fn record(&self, index_name: &str, document_id: DocumentId, score: u64) {
+        let mut pending = self.pending.lock().unwrap();
+        let scores = pending.entry(index_name.to_owned()).or_default();
+        let current = scores.entry(document_id).or_insert(0);
+        *current = current.saturating_add(score);
+    }
+
+    /// Forgets the pending events of a deleted index.
+    pub fn remove_index(&self, index_name: &str) {
+        let mut pending = self.pending.lock().unwrap();
+        pending.remove(index_name);
+    }
+
+    pub fn rename_index(&self, index_name: &str, new_name: &str) {
+        let mut pending = self.pending.lock().unwrap();
+        if let Some(scores) = pending.remove(index_name) {
+            pending.insert(new_name.to_owned(), scores);
+        }
+    }
+
+    /// Adds the pending events to the popularity maps of their index, this is done by
+    /// a background thread. The events are kept to be written later if the write fails.
+    pub fn flush(&self, db: &Database) -> MResult<()> {
+        let pending = {
+            let mut pending = self.pending.lock().unwrap();
+            mem::replace(&mut *pending, HashMap::new())
+        };
+
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let ranked_indexes = match write_popularity(db, &pending) {
+            Ok(ranked_indexes) => ranked_indexes,
+            Err(e) => {
+                // the events received in the meantime are merged with the restored ones
+                let mut current = self.pending.lock().unwrap();
+                for (index_name, scores) in pending {
+                    let current_scores = current.entry(index_name).or_default();
+                    for (document_id, score) in scores {
+                        let current = current_scores.entry(document_id).or_insert(0);
+                        *current = current.saturating_add(score);
+                    }
+                }
+                return Err(e);
+            }
+        };
+
+        // the cached searches are outdated when the popularity ranks the documents
+        for index_name in ranked_indexes {
+            db.query_cache().invalidate(&index_name);
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the indexes ranked by popularity in their settings, the searches
+/// ranking the documents by popularity on demand are never cached.
+fn write_popularity(
+    db: &Database,
+    pending: &HashMap<String, HashMap<DocumentId, u64>>,
+) -> MResult<Vec<String>> {
+    let env = &db.env;
+    let mut writer = env.write_txn()?;
+    let mut ranked_indexes = Vec::new();
+
+    for (index_name, scores) in pending {
+        // the index may have been deleted since the events were received
+        let index = match db.open_index(index_name) {
+            Some(index) => index,
+            None => continue,
+        };
+
+        let settings = index.settings(&writer)?;
+        let rules = settings
+            .ranking_rules
+            .unwrap_or_else(RankingRule::default_rules);
+        if rules.contains(&RankingRule::Popularity) {
+            ranked_indexes.push(index_name.clone());
+        }
+
+        let mut popularity_map = index.main.popularity_map(&writer)?.unwrap_or_default();
+
+        for (document_id, score) in scores {
+            // and so may have been the documents
+            let mut fields_counts = index
+                .documents_fields_counts
+                .document_fields_counts(&writer, *document_id)?;
+            if fields_counts.next().is_some() {
+                popularity_map.increase(*document_id, *score);
+            }
+        }
+
+        index
+            .main
+            .put_popularity_map(&mut writer, &popularity_map)?;
+    }
+
+    writer.commit()?;
+
+    Ok(ranked_indexes)
+}
@@ -0,0 +1,117 @@
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use tide::{Context, Response};
+
+use crate::error::{ResponseError, SResult};
+use crate::helpers::tide::ContextExt;
+use crate::models::token::ACL::*;
+use crate::Data;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AliasResponse {
+    alias: String,
+    index: String,
+}
+
+pub async fn list(ctx: Context<Data>) -> SResult<Response> {
+    ctx.is_allowed(IndexesRead)?;
+
+    let mut response: Vec<AliasResponse> = ctx
+        .state()
+        .db
+        .aliases()
+        .into_iter()
+        .filter(|(_, index)| ctx.is_allowed_on_index(IndexesRead, index).is_ok())
+        .map(|(alias, index)| AliasResponse { alias, index })
+        .collect();
+
+    response.sort_unstable_by(|a, b| a.alias.cmp(&b.alias));
+
+    Ok(tide::response::json(response))
+}
+
+pub async fn get(ctx: Context<Data>) -> SResult<Response> {
+    ctx.is_allowed(IndexesRead)?;
+    let alias = ctx.url_param("alias")?;
+    ctx.is_allowed_on_index(IndexesRead, &alias)?;
+
+    let index = ctx
+        .state()
+        .db
+        .alias(&alias)
+        .ok_or(ResponseError::not_found(format!("alias: {}", alias)))?;
+
+    Ok(tide::response::json(AliasResponse { alias, index }))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct UpdateAliasRequest {
+    index: String,
+}
+
+pub async fn update(mut ctx: Context<Data>) -> SResult<Response> {
+    ctx.is_allowed(IndexesWrite)?;
+    let alias = ctx.url_param("alias")?;
+
+    let data: UpdateAliasRequest = ctx.body_json().await.map_err(ResponseError::bad_request)?;
+    ctx.is_allowed_on_index(IndexesWrite, &alias)?;
+    ctx.is_allowed_on_index(IndexesWrite, &data.index)?;
+
+    let db = &ctx.state().db;
+    match db.set_alias(&alias, &data.index) {
+        Ok(()) => (),
+        Err(meilidb_core::Error::IndexNotFound) => {
+            return Err(ResponseError::index_not_found(data.index))
+        }
+        Err(e) => return Err(e.into()),
+    }
+
+    let response = AliasResponse {
+        alias,
+        index: data.index,
+    };
+    Ok(tide::response::json(response))
+}
+
+pub async fn delete(ctx: Context<Data>) -> SResult<StatusCode> {
+    ctx.is_allowed(IndexesWrite)?;
+    let alias = ctx.url_param("alias")?;
+    ctx.is_allowed_on_index(IndexesWrite, &alias)?;
+
+    let found = ctx.state().db.delete_alias(&alias)?;
+
+    if !found {
+        return Err(meilidb_core::Error::AliasNotFound.into());
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct SwapAliasesRequest {
+    aliases: (String, String),
+}
+
+pub async fn swap(mut ctx: Context<Data>) -> SResult<Response> {
+    ctx.is_allowed(IndexesWrite)?;
+
+    let data: SwapAliasesRequest = ctx.body_json().await.map_err(ResponseError::bad_request)?;
+    let (first, second) = data.aliases;
+    ctx.is_allowed_on_index(IndexesWrite, &first)?;
+    ctx.is_allowed_on_index(IndexesWrite, &second)?;
+
+    let db = &ctx.state().db;
+    db.swap_aliases(&first, &second)?;
+
+    let mut response = Vec::with_capacity(2);
+    for alias in vec![first, second] {
+        if let Some(index) = db.alias(&alias) {
+            response.push(AliasResponse { alias, index });
+        }
+    }
+
+    Ok(tide::response::json(response))
+}
@@ -0,0 +1,45 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+use tide::{Context, Response};
+
+use crate::analytics::{aggregate, query_log};
+use crate::error::{ResponseError, SResult};
+use crate::helpers::tide::ContextExt;
+use crate::models::token::ACL::*;
+use crate::Data;
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct AnalyticsQuery {
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    limit: Option<usize>,
+}
+
+pub async fn get_analytics(ctx: Context<Data>) -> SResult<Response> {
+    ctx.is_allowed(Admin)?;
+    let index_name = ctx.index_name()?;
+    ctx.index()?;
+
+    let query: AnalyticsQuery = ctx
+        .url_query()
+        .map_err(|_| ResponseError::bad_request("invalid query parameter"))?;
+
+    // by default the analytics of the last day are returned
+    let to = query.to.unwrap_or_else(Utc::now);
+    let from = query.from.unwrap_or_else(|| to - Duration::days(1));
+    if from > to {
+        return Err(ResponseError::bad_parameter("from", "must be before to"));
+    }
+    let limit = query.limit.unwrap_or(10);
+
+    let data = ctx.state();
+    let env = &data.db.env;
+    let reader = env.read_txn()?;
+    let mut entries = query_log(&data.db, &reader, &index_name)?;
+
+    // the queries not stored yet must be counted too
+    entries.extend(data.analytics.pending(&index_name));
+
+    Ok(tide::response::json(aggregate(&entries, from, to, limit)))
+}
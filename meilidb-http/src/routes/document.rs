@@ -22,11 +22,10 @@ pub async fn get_document(ctx: Context<Data>) -> SResult<Response> {
     let document_id = meilidb_core::serde::compute_document_id(identifier.clone());
 
     let env = &ctx.state().db.env;
-    let reader = env.read_txn().map_err(ResponseError::internal)?;
+    let reader = env.read_txn()?;
 
     let response = index
-        .document::<IndexMap<String, Value>>(&reader, None, document_id)
-        .map_err(ResponseError::internal)?
+        .document::<IndexMap<String, Value>>(&reader, None, document_id)?
         .ok_or(ResponseError::document_not_found(&identifier))?;
 
     if response.is_empty() {
@@ -54,15 +53,13 @@ pub async fn delete_document(ctx: Context<Data>) -> SResult<Response> {
     let document_id = meilidb_core::serde::compute_document_id(identifier.clone());
 
     let env = &ctx.state().db.env;
-    let mut writer = env.write_txn().map_err(ResponseError::internal)?;
+    let mut writer = env.write_txn()?;
 
     let mut documents_deletion = index.documents_deletion();
     documents_deletion.delete_document_by_id(document_id);
-    let update_id = documents_deletion
-        .finalize(&mut writer)
-        .map_err(ResponseError::internal)?;
+    let update_id = documents_deletion.finalize(&mut writer)?;
 
-    writer.commit().map_err(ResponseError::internal)?;
+    writer.commit()?;
 
     let response_body = IndexUpdateResponse { update_id };
     Ok(tide::response::json(response_body)
@@ -88,17 +85,17 @@ pub async fn browse_documents(ctx: Context<Data>) -> SResult<Response> {
     let limit = query.limit.unwrap_or(20);
 
     let env = &ctx.state().db.env;
-    let reader = env.read_txn().map_err(ResponseError::internal)?;
+    let reader = env.read_txn()?;
 
     let documents_ids: Result<BTreeSet<_>, _> =
         match index.documents_fields_counts.documents_ids(&reader) {
             Ok(documents_ids) => documents_ids.skip(offset).take(limit).collect(),
-            Err(e) => return Err(ResponseError::internal(e)),
+            Err(e) => return Err(e.into()),
         };
 
     let documents_ids = match documents_ids {
         Ok(documents_ids) => documents_ids,
-        Err(e) => return Err(ResponseError::internal(e)),
+        Err(e) => return Err(e.into()),
     };
 
     let mut response_body = Vec::<IndexMap<String, Value>>::new();
@@ -162,18 +159,13 @@ pub async fn add_or_update_multiple_documents(mut ctx: Context<Data>) -> SResult
     let index = ctx.index()?;
 
     let env = &ctx.state().db.env;
-    let mut writer = env.write_txn().map_err(ResponseError::internal)?;
+    let mut writer = env.write_txn()?;
 
-    let current_schema = index
-        .main
-        .schema(&writer)
-        .map_err(ResponseError::internal)?;
+    let current_schema = index.main.schema(&writer)?;
     if current_schema.is_none() {
         match data.first().and_then(infered_schema) {
             Some(schema) => {
-                index
-                    .schema_update(&mut writer, schema)
-                    .map_err(ResponseError::internal)?;
+                index.schema_update(&mut writer, schema)?;
             }
             None => return Err(ResponseError::bad_request("Could not infer a schema")),
         }
@@ -185,11 +177,9 @@ pub async fn add_or_update_multiple_documents(mut ctx: Context<Data>) -> SResult
         document_addition.update_document(document);
     }
 
-    let update_id = document_addition
-        .finalize(&mut writer)
-        .map_err(ResponseError::internal)?;
+    let update_id = document_addition.finalize(&mut writer)?;
 
-    writer.commit().map_err(ResponseError::internal)?;
+    writer.commit()?;
 
     let response_body = IndexUpdateResponse { update_id };
     Ok(tide::response::json(response_body)
@@ -206,7 +196,7 @@ pub async fn delete_multiple_documents(mut ctx: Context<Data>) -> SResult<Respon
     let index = ctx.index()?;
 
     let env = &ctx.state().db.env;
-    let mut writer = env.write_txn().map_err(ResponseError::internal)?;
+    let mut writer = env.write_txn()?;
 
     let mut documents_deletion = index.documents_deletion();
 
@@ -217,11 +207,9 @@ pub async fn delete_multiple_documents(mut ctx: Context<Data>) -> SResult<Respon
         }
     }
 
-    let update_id = documents_deletion
-        .finalize(&mut writer)
-        .map_err(ResponseError::internal)?;
+    let update_id = documents_deletion.finalize(&mut writer)?;
 
-    writer.commit().map_err(ResponseError::internal)?;
+    writer.commit()?;
 
     let response_body = IndexUpdateResponse { update_id };
     Ok(tide::response::json(response_body)
@@ -237,11 +225,9 @@ pub async fn clear_all_documents(ctx: Context<Data>) -> SResult<Response> {
     let index = ctx.index()?;
 
     let env = &ctx.state().db.env;
-    let mut writer = env.write_txn().map_err(ResponseError::internal)?;
-    let update_id = index
-        .clear_all(&mut writer)
-        .map_err(ResponseError::internal)?;
-    writer.commit().map_err(ResponseError::internal)?;
+    let mut writer = env.write_txn()?;
+    let update_id = index.clear_all(&mut writer)?;
+    writer.commit()?;
 
     let response_body = IndexUpdateResponse { update_id };
     Ok(tide::response::json(response_body)
@@ -0,0 +1,81 @@
+use http::StatusCode;
+use serde::Deserialize;
+use serde_json::Value;
+use tide::Context;
+
+use crate::error::{ResponseError, SResult};
+use crate::helpers::tide::ContextExt;
+use crate::models::token::ACL::*;
+use crate::Data;
+
+/// A conversion tells much more about the relevance of a document than a click.
+const CLICK_SCORE: u64 = 1;
+const CONVERSION_SCORE: u64 = 5;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EventType {
+    Click,
+    Conversion,
+}
+
+impl EventType {
+    pub fn score(self) -> u64 {
+        match self {
+            EventType::Click => CLICK_SCORE,
+            EventType::Conversion => CONVERSION_SCORE,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct Event {
+    document_id: Value,
+    event_type: EventType,
+}
+
+/// Increases the popularity of the documents the users clicked on or bought,
+/// the popularity is used to rank the documents by the `_popularity` rule.
+///
+/// The events are accepted here and stored in the background,
+/// they are not taken into account by the searches right away.
+pub async fn send_events(mut ctx: Context<Data>) -> SResult<StatusCode> {
+    ctx.is_allowed(EventsWrite)?;
+
+    if !ctx.state().accept_updates() {
+        return Err(ResponseError::Maintenance);
+    }
+
+    let events: Vec<Event> = ctx.body_json().await.map_err(ResponseError::bad_request)?;
+
+    let index = ctx.index()?;
+    let index_name = ctx.index_name()?;
+
+    let env = &ctx.state().db.env;
+    let reader = env.read_txn()?;
+
+    // the events are only recorded once they are all valid
+    let mut scores = Vec::with_capacity(events.len());
+    for event in events {
+        let identifier = meilidb_core::serde::value_to_string(&event.document_id)
+            .ok_or_else(|| ResponseError::bad_request("invalid document id"))?;
+        let document_id = meilidb_core::serde::compute_document_id(identifier.clone());
+
+        let mut fields_counts = index
+            .documents_fields_counts
+            .document_fields_counts(&reader, document_id)?;
+        if fields_counts.next().is_none() {
+            return Err(ResponseError::document_not_found(identifier));
+        }
+
+        scores.push((document_id, event.event_type.score()));
+    }
+
+    let popularity = &ctx.state().popularity;
+    for (document_id, score) in scores {
+        popularity.record(&index_name, document_id, score);
+    }
+
+    Ok(StatusCode::ACCEPTED)
+}
@@ -12,7 +12,7 @@ const UNHEALTHY_KEY: &str = "_is_unhealthy";
 pub async fn get_health(ctx: Context<Data>) -> SResult<()> {
     let db = &ctx.state().db;
     let env = &db.env;
-    let reader = env.read_txn().map_err(ResponseError::internal)?;
+    let reader = env.read_txn()?;
 
     let common_store = ctx.state().db.common_store();
 
@@ -28,16 +28,16 @@ pub async fn set_healthy(ctx: Context<Data>) -> SResult<()> {
 
     let db = &ctx.state().db;
     let env = &db.env;
-    let mut writer = env.write_txn().map_err(ResponseError::internal)?;
+    let mut writer = env.write_txn()?;
 
     let common_store = ctx.state().db.common_store();
     match common_store.delete::<Str>(&mut writer, UNHEALTHY_KEY) {
         Ok(_) => (),
-        Err(e) => return Err(ResponseError::internal(e)),
+        Err(e) => return Err(e.into()),
     }
 
     if let Err(e) = writer.commit() {
-        return Err(ResponseError::internal(e));
+        return Err(e.into());
     }
 
     Ok(())
@@ -48,16 +48,16 @@ pub async fn set_unhealthy(ctx: Context<Data>) -> SResult<()> {
 
     let db = &ctx.state().db;
     let env = &db.env;
-    let mut writer = env.write_txn().map_err(ResponseError::internal)?;
+    let mut writer = env.write_txn()?;
 
     let common_store = ctx.state().db.common_store();
 
     if let Err(e) = common_store.put::<Str, Unit>(&mut writer, UNHEALTHY_KEY, &()) {
-        return Err(ResponseError::internal(e));
+        return Err(e.into());
     }
 
     if let Err(e) = writer.commit() {
-        return Err(ResponseError::internal(e));
+        return Err(e.into());
     }
 
     Ok(())
@@ -1,6 +1,7 @@
 use http::StatusCode;
-use meilidb_core::{ProcessedUpdateResult, UpdateStatus};
+use meilidb_core::{IndexLimits, ProcessedUpdateResult, UpdateStatus};
 use meilidb_schema::Schema;
+use serde::Deserialize;
 use serde_json::json;
 use tide::response::IntoResponse;
 use tide::{Context, Response};
@@ -14,11 +15,13 @@ use crate::Data;
 
 pub async fn list_indexes(ctx: Context<Data>) -> SResult<Response> {
     ctx.is_allowed(IndexesRead)?;
-    let list = ctx
+    let list: Vec<_> = ctx
         .state()
         .db
-        .indexes_names()
-        .map_err(ResponseError::internal)?;
+        .indexes_names()?
+        .into_iter()
+        .filter(|name| ctx.is_allowed_on_index(IndexesRead, name).is_ok())
+        .collect();
     Ok(tide::response::json(list))
 }
 
@@ -28,24 +31,14 @@ pub async fn get_index_schema(ctx: Context<Data>) -> SResult<Response> {
     let index = ctx.index()?;
 
     let env = &ctx.state().db.env;
-    let reader = env.read_txn().map_err(ResponseError::internal)?;
+    let reader = env.read_txn()?;
 
     let schema = index
         .main
-        .schema(&reader)
-        .map_err(ResponseError::create_index)?;
+        .schema(&reader)?
+        .ok_or(meilidb_core::Error::SchemaMissing)?;
 
-    match schema {
-        Some(schema) => {
-            let schema = SchemaBody::from(schema);
-            Ok(tide::response::json(schema))
-        }
-        None => Ok(
-            tide::response::json(json!({ "message": "missing index schema" }))
-                .with_status(StatusCode::NOT_FOUND)
-                .into_response(),
-        ),
-    }
+    Ok(tide::response::json(SchemaBody::from(schema)))
 }
 
 pub async fn create_index(mut ctx: Context<Data>) -> SResult<Response> {
@@ -69,7 +62,7 @@ pub async fn create_index(mut ctx: Context<Data>) -> SResult<Response> {
         Err(meilidb_core::Error::IndexAlreadyExists) => db.open_index(&index_name).ok_or(
             ResponseError::internal("index not found but must have been found"),
         )?,
-        Err(e) => return Err(ResponseError::create_index(e)),
+        Err(e) => return Err(e.into()),
     };
 
     let callback_context = ctx.state().clone();
@@ -82,15 +75,13 @@ pub async fn create_index(mut ctx: Context<Data>) -> SResult<Response> {
     );
 
     let env = &db.env;
-    let mut writer = env.write_txn().map_err(ResponseError::internal)?;
+    let mut writer = env.write_txn()?;
 
     match schema {
         Some(schema) => {
-            let update_id = created_index
-                .schema_update(&mut writer, schema.clone())
-                .map_err(ResponseError::internal)?;
+            let update_id = created_index.schema_update(&mut writer, schema.clone())?;
 
-            writer.commit().map_err(ResponseError::internal)?;
+            writer.commit()?;
 
             let response_body = IndexUpdateResponse { update_id };
             Ok(tide::response::json(response_body)
@@ -115,18 +106,16 @@ pub async fn update_schema(mut ctx: Context<Data>) -> SResult<Response> {
 
     let db = &ctx.state().db;
     let env = &db.env;
-    let mut writer = env.write_txn().map_err(ResponseError::internal)?;
+    let mut writer = env.write_txn()?;
 
     let index = db
         .open_index(&index_name)
         .ok_or(ResponseError::index_not_found(index_name))?;
 
     let schema: meilidb_schema::Schema = schema.into();
-    let update_id = index
-        .schema_update(&mut writer, schema.clone())
-        .map_err(ResponseError::internal)?;
+    let update_id = index.schema_update(&mut writer, schema.clone())?;
 
-    writer.commit().map_err(ResponseError::internal)?;
+    writer.commit()?;
 
     let response_body = IndexUpdateResponse { update_id };
     Ok(tide::response::json(response_body)
@@ -138,16 +127,14 @@ pub async fn get_update_status(ctx: Context<Data>) -> SResult<Response> {
     ctx.is_allowed(IndexesRead)?;
 
     let env = &ctx.state().db.env;
-    let reader = env.read_txn().map_err(ResponseError::internal)?;
+    let reader = env.read_txn()?;
 
     let update_id = ctx
         .param::<u64>("update_id")
         .map_err(|e| ResponseError::bad_parameter("update_id", e))?;
 
     let index = ctx.index()?;
-    let status = index
-        .update_status(&reader, update_id)
-        .map_err(ResponseError::internal)?;
+    let status = index.update_status(&reader, update_id)?;
 
     let response = match status {
         UpdateStatus::Enqueued(data) => {
@@ -160,9 +147,12 @@ pub async fn get_update_status(ctx: Context<Data>) -> SResult<Response> {
                 .with_status(StatusCode::OK)
                 .into_response()
         }
-        UpdateStatus::Unknown => tide::response::json(json!({ "message": "unknown update id" }))
-            .with_status(StatusCode::NOT_FOUND)
-            .into_response(),
+        UpdateStatus::Unknown => {
+            return Err(ResponseError::not_found(format!(
+                "unknown update id: {}",
+                update_id
+            )))
+        }
     };
 
     Ok(response)
@@ -172,12 +162,10 @@ pub async fn get_all_updates_status(ctx: Context<Data>) -> SResult<Response> {
     ctx.is_allowed(IndexesRead)?;
 
     let env = &ctx.state().db.env;
-    let reader = env.read_txn().map_err(ResponseError::internal)?;
+    let reader = env.read_txn()?;
 
     let index = ctx.index()?;
-    let all_status = index
-        .all_updates_status(&reader)
-        .map_err(ResponseError::internal)?;
+    let all_status = index.all_updates_status(&reader)?;
 
     let response = tide::response::json(all_status)
         .with_status(StatusCode::OK)
@@ -188,18 +176,130 @@ pub async fn get_all_updates_status(ctx: Context<Data>) -> SResult<Response> {
 
 pub async fn delete_index(ctx: Context<Data>) -> SResult<StatusCode> {
     ctx.is_allowed(IndexesWrite)?;
-    let _index_name = ctx.url_param("index")?;
-    let _index = ctx.index()?;
+    let index_name = ctx.url_param("index")?;
+
+    let state = ctx.state();
+    let found = state.db.delete_index_with(&index_name, |writer| {
+        state.clear_index_keys(writer, &index_name)
+    })?;
+
+    if !found {
+        return Err(ResponseError::index_not_found(index_name));
+    }
 
-    // ctx.state()
-    //     .db
-    //     .delete_index(&index_name)
-    //     .map_err(ResponseError::internal)?;
+    state.analytics.remove_index(&index_name);
+    state.popularity.remove_index(&index_name);
 
-    Ok(StatusCode::NOT_IMPLEMENTED)
+    Ok(StatusCode::NO_CONTENT)
 }
 
-pub fn index_update_callback(index_name: &str, data: &Data, _status: ProcessedUpdateResult) {
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct IndexNameRequest {
+    name: String,
+}
+
+fn map_index_error(error: meilidb_core::Error, name: &str) -> ResponseError {
+    match error {
+        meilidb_core::Error::IndexNotFound => ResponseError::index_not_found(name),
+        error => error.into(),
+    }
+}
+
+pub async fn rename_index(mut ctx: Context<Data>) -> SResult<Response> {
+    ctx.is_allowed(IndexesWrite)?;
+    let index_name = ctx.url_param("index")?;
+
+    let data: IndexNameRequest = ctx.body_json().await.map_err(ResponseError::bad_request)?;
+    let new_name = data.name;
+    ctx.is_allowed_on_index(IndexesWrite, &new_name)?;
+
+    let state = ctx.state();
+    let db = &state.db;
+    db.rename_index_with(&index_name, &new_name, |writer| {
+        state.rename_index_keys(writer, &index_name, &new_name)
+    })
+    .map_err(|e| map_index_error(e, &index_name))?;
+
+    state.analytics.rename_index(&index_name, &new_name);
+    state.popularity.rename_index(&index_name, &new_name);
+
+    let callback_context = state.clone();
+    let callback_name = new_name.clone();
+    db.set_update_callback(
+        &new_name,
+        Box::new(move |status| {
+            index_update_callback(&callback_name, &callback_context, status);
+        }),
+    );
+
+    Ok(tide::response::json(json!({ "name": new_name })))
+}
+
+pub async fn clone_index(mut ctx: Context<Data>) -> SResult<Response> {
+    ctx.is_allowed(IndexesWrite)?;
+    let index_name = ctx.url_param("index")?;
+
+    let data: IndexNameRequest = ctx.body_json().await.map_err(ResponseError::bad_request)?;
+    let new_name = data.name;
+    ctx.is_allowed_on_index(IndexesWrite, &new_name)?;
+
+    let db = &ctx.state().db;
+    db.clone_index(&index_name, &new_name)
+        .map_err(|e| map_index_error(e, &index_name))?;
+
+    let callback_context = ctx.state().clone();
+    let callback_name = new_name.clone();
+    db.set_update_callback(
+        &new_name,
+        Box::new(move |status| {
+            index_update_callback(&callback_name, &callback_context, status);
+        }),
+    );
+
+    let env = &db.env;
+    let mut writer = env.write_txn()?;
+
+    ctx.state().compute_stats(&mut writer, &new_name)?;
+
+    writer.commit()?;
+
+    Ok(tide::response::json(json!({ "name": new_name }))
+        .with_status(StatusCode::CREATED)
+        .into_response())
+}
+
+pub async fn get_limits(ctx: Context<Data>) -> SResult<Response> {
+    ctx.is_allowed(Admin)?;
+    let index = ctx.index()?;
+
+    let env = &ctx.state().db.env;
+    let reader = env.read_txn()?;
+
+    let limits = index.limits(&reader)?;
+
+    Ok(tide::response::json(limits))
+}
+
+pub async fn update_limits(mut ctx: Context<Data>) -> SResult<Response> {
+    ctx.is_allowed(Admin)?;
+    let index = ctx.index()?;
+
+    let limits: IndexLimits = ctx.body_json().await.map_err(ResponseError::bad_request)?;
+
+    let env = &ctx.state().db.env;
+    let mut writer = env.write_txn()?;
+
+    index.set_limits(&mut writer, &limits)?;
+
+    writer.commit()?;
+
+    Ok(tide::response::json(limits))
+}
+
+pub fn index_update_callback(index_name: &str, data: &Data, status: ProcessedUpdateResult) {
+    data.metrics.observe_update(index_name, &status);
+
     let env = &data.db.env;
     let mut writer = env.write_txn().unwrap();
 
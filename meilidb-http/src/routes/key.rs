@@ -1,9 +1,10 @@
+use std::mem;
+
 use chrono::serde::ts_seconds;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use heed::types::{SerdeBincode, Str};
 use http::StatusCode;
-use rand::seq::SliceRandom;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use tide::response::IntoResponse;
 use tide::{Context, Response};
 
@@ -13,13 +14,63 @@ use crate::models::token::ACL::*;
 use crate::models::token::*;
 use crate::Data;
 
-fn generate_api_key() -> String {
-    let mut rng = rand::thread_rng();
-    let sample = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
-    sample
-        .choose_multiple(&mut rng, 40)
-        .map(|c| *c as char)
-        .collect()
+/// The token as it is shown to the users, without the hashed secrets.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenResponse {
+    key_id: String,
+    description: String,
+    acl: Vec<ACL>,
+    indexes: Vec<Wildcard>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+    revoked: bool,
+    previous_key_expires_at: Option<DateTime<Utc>>,
+    rate_limit: Option<RateLimit>,
+}
+
+impl From<Token> for TokenResponse {
+    fn from(token: Token) -> TokenResponse {
+        TokenResponse {
+            key_id: token.key_id,
+            description: token.description,
+            acl: token.acl,
+            indexes: token.indexes,
+            created_at: token.created_at,
+            updated_at: token.updated_at,
+            expires_at: token.expires_at,
+            revoked: token.revoked,
+            previous_key_expires_at: token.previous_secret.map(|(_, expires_at)| expires_at),
+            rate_limit: token.rate_limit,
+        }
+    }
+}
+
+/// The response of the routes that generate a new secret, this is the only time
+/// the API key and the key to sign its tenant tokens can be read.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenWithKeyResponse {
+    key: String,
+    tenant_signing_key: Option<String>,
+    #[serde(flatten)]
+    token: TokenResponse,
+}
+
+impl TokenWithKeyResponse {
+    fn new(data: &Data, token: Token, secret: &str) -> TokenWithKeyResponse {
+        let tenant_signing_key = data
+            .admin_token
+            .as_ref()
+            .map(|master_key| token.secret.signing_key(master_key, &token.key_id));
+
+        TokenWithKeyResponse {
+            key: format!("{}{}", token.key_id, secret),
+            tenant_signing_key,
+            token: TokenResponse::from(token),
+        }
+    }
 }
 
 pub async fn list(ctx: Context<Data>) -> SResult<Response> {
@@ -27,19 +78,17 @@ pub async fn list(ctx: Context<Data>) -> SResult<Response> {
 
     let db = &ctx.state().db;
     let env = &db.env;
-    let reader = env.read_txn().map_err(ResponseError::internal)?;
+    let reader = env.read_txn()?;
 
     let common_store = db.common_store();
 
-    let mut response: Vec<Token> = Vec::new();
+    let mut response: Vec<TokenResponse> = Vec::new();
 
-    let iter = common_store
-        .prefix_iter::<Str, SerdeBincode<Token>>(&reader, TOKEN_PREFIX_KEY)
-        .map_err(ResponseError::internal)?;
+    let iter = common_store.prefix_iter::<Str, SerdeBincode<Token>>(&reader, TOKEN_PREFIX_KEY)?;
 
     for result in iter {
-        let (_, token) = result.map_err(ResponseError::internal)?;
-        response.push(token);
+        let (_, token) = result?;
+        response.push(TokenResponse::from(token));
     }
 
     Ok(tide::response::json(response))
@@ -51,20 +100,19 @@ pub async fn get(ctx: Context<Data>) -> SResult<Response> {
 
     let db = &ctx.state().db;
     let env = &db.env;
-    let reader = env.read_txn().map_err(ResponseError::internal)?;
+    let reader = env.read_txn()?;
 
     let token_key = format!("{}{}", TOKEN_PREFIX_KEY, request_key);
 
     let token_config = db
         .common_store()
-        .get::<Str, SerdeBincode<Token>>(&reader, &token_key)
-        .map_err(ResponseError::internal)?
+        .get::<Str, SerdeBincode<Token>>(&reader, &token_key)?
         .ok_or(ResponseError::not_found(format!(
             "token key: {}",
             token_key
         )))?;
 
-    Ok(tide::response::json(token_config))
+    Ok(tide::response::json(TokenResponse::from(token_config)))
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -75,18 +123,52 @@ pub struct CreatedRequest {
     indexes: Vec<Wildcard>,
     #[serde(with = "ts_seconds")]
     expires_at: DateTime<Utc>,
+    rate_limit: Option<RateLimit>,
+}
+
+fn check_rate_limit(rate_limit: &Option<RateLimit>) -> SResult<()> {
+    match rate_limit {
+        Some(limit) if limit.requests_per_second == 0 || limit.burst == 0 => Err(
+            ResponseError::bad_request("a rate limit must allow at least one request"),
+        ),
+        _ => Ok(()),
+    }
 }
 
+const KEY_ID_ATTEMPTS: usize = 10;
+
 pub async fn create(mut ctx: Context<Data>) -> SResult<Response> {
     ctx.is_allowed(Admin)?;
 
     let data: CreatedRequest = ctx.body_json().await.map_err(ResponseError::bad_request)?;
+    check_rate_limit(&data.rate_limit)?;
+
+    let db = &ctx.state().db;
+    let env = &db.env;
+    let mut writer = env.write_txn()?;
+
+    let common_store = db.common_store();
+
+    // the key id is random, but must not override another token
+    let mut unused_key_id = None;
+    for _ in 0..KEY_ID_ATTEMPTS {
+        let key_id = generate_random_string(KEY_ID_LENGTH);
+        let token_key = format!("{}{}", TOKEN_PREFIX_KEY, key_id);
+        let exists = common_store
+            .get::<Str, SerdeBincode<Token>>(&writer, &token_key)?
+            .is_some();
+        if !exists {
+            unused_key_id = Some((key_id, token_key));
+            break;
+        }
+    }
 
-    let key = generate_api_key();
-    let token_key = format!("{}{}", TOKEN_PREFIX_KEY, key);
+    let (key_id, token_key) = unused_key_id
+        .ok_or_else(|| ResponseError::internal("no unused key id could be generated"))?;
+    let secret = generate_random_string(SECRET_LENGTH);
 
     let token_definition = Token {
-        key,
+        key_id: key_id.clone(),
         description: data.description,
         acl: data.acl,
         indexes: data.indexes,
@@ -94,19 +176,18 @@ pub async fn create(mut ctx: Context<Data>) -> SResult<Response> {
         created_at: Utc::now(),
         updated_at: Utc::now(),
         revoked: false,
+        secret: HashedSecret::new(&secret),
+        previous_secret: None,
+        rate_limit: data.rate_limit,
     };
 
-    let db = &ctx.state().db;
-    let env = &db.env;
-    let mut writer = env.write_txn().map_err(ResponseError::internal)?;
+    common_store.put::<Str, SerdeBincode<Token>>(&mut writer, &token_key, &token_definition)?;
 
-    db.common_store()
-        .put::<Str, SerdeBincode<Token>>(&mut writer, &token_key, &token_definition)
-        .map_err(ResponseError::internal)?;
+    writer.commit()?;
 
-    writer.commit().map_err(ResponseError::internal)?;
+    let response = TokenWithKeyResponse::new(ctx.state(), token_definition, &secret);
 
-    Ok(tide::response::json(token_definition)
+    Ok(tide::response::json(response)
         .with_status(StatusCode::CREATED)
         .into_response())
 }
@@ -117,6 +198,18 @@ pub struct UpdatedRequest {
     description: Option<String>,
     acl: Option<Vec<ACL>>,
     indexes: Option<Vec<Wildcard>>,
+    /// A `null` rate limit removes the limit of the token.
+    #[serde(default, deserialize_with = "deserialize_some")]
+    rate_limit: Option<Option<RateLimit>>,
+}
+
+/// Distinguishes a field set to `null` from a missing field.
+fn deserialize_some<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Deserialize::deserialize(deserializer).map(Some)
 }
 
 pub async fn update(mut ctx: Context<Data>) -> SResult<Response> {
@@ -124,18 +217,20 @@ pub async fn update(mut ctx: Context<Data>) -> SResult<Response> {
     let request_key = ctx.url_param("key")?;
 
     let data: UpdatedRequest = ctx.body_json().await.map_err(ResponseError::bad_request)?;
+    if let Some(rate_limit) = &data.rate_limit {
+        check_rate_limit(rate_limit)?;
+    }
 
     let db = &ctx.state().db;
     let env = &db.env;
-    let mut writer = env.write_txn().map_err(ResponseError::internal)?;
+    let mut writer = env.write_txn()?;
 
     let common_store = db.common_store();
 
     let token_key = format!("{}{}", TOKEN_PREFIX_KEY, request_key);
 
     let mut token_config = common_store
-        .get::<Str, SerdeBincode<Token>>(&writer, &token_key)
-        .map_err(ResponseError::internal)?
+        .get::<Str, SerdeBincode<Token>>(&writer, &token_key)?
         .ok_or(ResponseError::not_found(format!(
             "token key: {}",
             token_key
@@ -154,15 +249,17 @@ pub async fn update(mut ctx: Context<Data>) -> SResult<Response> {
         token_config.indexes = indexes;
     }
 
+    if let Some(rate_limit) = data.rate_limit {
+        token_config.rate_limit = rate_limit;
+    }
+
     token_config.updated_at = Utc::now();
 
-    common_store
-        .put::<Str, SerdeBincode<Token>>(&mut writer, &token_key, &token_config)
-        .map_err(ResponseError::internal)?;
+    common_store.put::<Str, SerdeBincode<Token>>(&mut writer, &token_key, &token_config)?;
 
-    writer.commit().map_err(ResponseError::internal)?;
+    writer.commit()?;
 
-    Ok(tide::response::json(token_config)
+    Ok(tide::response::json(TokenResponse::from(token_config))
         .with_status(StatusCode::ACCEPTED)
         .into_response())
 }
@@ -173,17 +270,123 @@ pub async fn delete(ctx: Context<Data>) -> SResult<StatusCode> {
 
     let db = &ctx.state().db;
     let env = &db.env;
-    let mut writer = env.write_txn().map_err(ResponseError::internal)?;
+    let mut writer = env.write_txn()?;
 
     let common_store = db.common_store();
 
     let token_key = format!("{}{}", TOKEN_PREFIX_KEY, request_key);
 
-    common_store
-        .delete::<Str>(&mut writer, &token_key)
-        .map_err(ResponseError::internal)?;
+    let usage_key = format!("{}{}", KEY_USAGE_PREFIX_KEY, request_key);
+
+    // the legacy keys that could not be migrated are deleted with their raw API key
+    let legacy_token_key = format!("{}{}", LEGACY_TOKEN_PREFIX_KEY, request_key);
+
+    common_store.delete::<Str>(&mut writer, &token_key)?;
 
-    writer.commit().map_err(ResponseError::internal)?;
+    common_store.delete::<Str>(&mut writer, &usage_key)?;
+
+    common_store.delete::<Str>(&mut writer, &legacy_token_key)?;
+
+    writer.commit()?;
+
+    ctx.state().rate_limiter.remove(&request_key);
 
     Ok(StatusCode::ACCEPTED)
 }
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct RotateRequest {
+    /// The number of seconds the replaced secret stays valid.
+    grace_period: Option<i64>,
+}
+
+const DEFAULT_GRACE_PERIOD: i64 = 24 * 60 * 60; // 24h
+const MAX_GRACE_PERIOD: i64 = 365 * 24 * 60 * 60; // 1 year
+
+pub async fn rotate(mut ctx: Context<Data>) -> SResult<Response> {
+    ctx.is_allowed(Admin)?;
+    let request_key = ctx.url_param("key")?;
+
+    let body = ctx.body_bytes().await.map_err(ResponseError::bad_request)?;
+    let data: RotateRequest = if body.is_empty() {
+        RotateRequest::default()
+    } else {
+        serde_json::from_slice(&body).map_err(ResponseError::bad_request)?
+    };
+
+    let grace_period = data.grace_period.unwrap_or(DEFAULT_GRACE_PERIOD);
+    if grace_period < 0 || grace_period > MAX_GRACE_PERIOD {
+        return Err(ResponseError::bad_parameter(
+            "gracePeriod",
+            format!(
+                "must be a positive number of seconds lower than {}",
+                MAX_GRACE_PERIOD
+            ),
+        ));
+    }
+
+    let previous_expires_at = Utc::now()
+        .checked_add_signed(Duration::seconds(grace_period))
+        .ok_or_else(|| ResponseError::bad_parameter("gracePeriod", "too far in the future"))?;
+
+    let db = &ctx.state().db;
+    let env = &db.env;
+    let mut writer = env.write_txn()?;
+
+    let common_store = db.common_store();
+
+    let token_key = format!("{}{}", TOKEN_PREFIX_KEY, request_key);
+
+    let mut token_config = common_store
+        .get::<Str, SerdeBincode<Token>>(&writer, &token_key)?
+        .ok_or(ResponseError::not_found(format!(
+            "token key: {}",
+            token_key
+        )))?;
+
+    // the previous secret of an earlier rotation is not valid anymore,
+    // only the current one is kept during the grace period
+    let secret = generate_random_string(SECRET_LENGTH);
+    let previous_secret = mem::replace(&mut token_config.secret, HashedSecret::new(&secret));
+    token_config.previous_secret = Some((previous_secret, previous_expires_at));
+    token_config.updated_at = Utc::now();
+
+    common_store.put::<Str, SerdeBincode<Token>>(&mut writer, &token_key, &token_config)?;
+
+    writer.commit()?;
+
+    let response = TokenWithKeyResponse::new(ctx.state(), token_config, &secret);
+
+    Ok(tide::response::json(response))
+}
+
+pub async fn usage(ctx: Context<Data>) -> SResult<Response> {
+    ctx.is_allowed(Admin)?;
+    let request_key = ctx.url_param("key")?;
+
+    let db = &ctx.state().db;
+    let env = &db.env;
+    let reader = env.read_txn()?;
+
+    let common_store = db.common_store();
+
+    let token_key = format!("{}{}", TOKEN_PREFIX_KEY, request_key);
+    let usage_key = format!("{}{}", KEY_USAGE_PREFIX_KEY, request_key);
+
+    common_store
+        .get::<Str, SerdeBincode<Token>>(&reader, &token_key)?
+        .ok_or(ResponseError::not_found(format!(
+            "token key: {}",
+            token_key
+        )))?;
+
+    let mut usage = common_store
+        .get::<Str, SerdeBincode<KeyUsage>>(&reader, &usage_key)?
+        .unwrap_or_default();
+
+    // the latest requests are not stored yet
+    usage.merge(&ctx.state().rate_limiter.pending_usage(&request_key));
+
+    Ok(tide::response::json(usage))
+}
@@ -1,9 +1,14 @@
 use crate::data::Data;
+use crate::middleware::IndexMiddleware;
 
+pub mod alias;
+pub mod analytics;
 pub mod document;
+pub mod events;
 pub mod health;
 pub mod index;
 pub mod key;
+pub mod rule;
 pub mod search;
 pub mod setting;
 pub mod stats;
@@ -11,6 +16,9 @@ pub mod stop_words;
 pub mod synonym;
 
 pub fn load_routes(app: &mut tide::App<Data>) {
+    // the index routes read the index their `:index` parameter resolved to
+    app.middleware(IndexMiddleware);
+
     app.at("").nest(|router| {
         router.at("/indexes").nest(|router| {
             router.at("/").get(index::list_indexes);
@@ -19,6 +27,8 @@ pub fn load_routes(app: &mut tide::App<Data>) {
 
             router.at("/:index").nest(|router| {
                 router.at("/search").get(search::search_with_url_query);
+                router.at("/analytics").get(analytics::get_analytics);
+                router.at("/events").post(events::send_events);
 
                 router.at("/updates").nest(|router| {
                     router.at("/").get(index::get_all_updates_status);
@@ -33,6 +43,14 @@ pub fn load_routes(app: &mut tide::App<Data>) {
                     .put(index::update_schema)
                     .delete(index::delete_index);
 
+                router
+                    .at("/limits")
+                    .get(index::get_limits)
+                    .put(index::update_limits);
+
+                router.at("/rename").post(index::rename_index);
+                router.at("/clone").post(index::clone_index);
+
                 router.at("/documents").nest(|router| {
                     router
                         .at("/")
@@ -65,6 +83,16 @@ pub fn load_routes(app: &mut tide::App<Data>) {
                     router.at("/clear").post(synonym::clear);
                 });
 
+                router.at("/rules").nest(|router| {
+                    router.at("/").get(rule::list);
+
+                    router
+                        .at("/:rule")
+                        .get(rule::get)
+                        .put(rule::update)
+                        .delete(rule::delete);
+                });
+
                 router.at("/stop-words").nest(|router| {
                     router
                         .at("/")
@@ -80,6 +108,18 @@ pub fn load_routes(app: &mut tide::App<Data>) {
             });
         });
 
+        router.at("/aliases").nest(|router| {
+            router.at("/").get(alias::list);
+
+            router.at("/swap").post(alias::swap);
+
+            router
+                .at("/:alias")
+                .get(alias::get)
+                .put(alias::update)
+                .delete(alias::delete);
+        });
+
         router.at("/keys").nest(|router| {
             router.at("/").get(key::list).post(key::create);
 
@@ -88,6 +128,9 @@ pub fn load_routes(app: &mut tide::App<Data>) {
                 .get(key::get)
                 .put(key::update)
                 .delete(key::delete);
+
+            router.at("/:key/rotate").post(key::rotate);
+            router.at("/:key/usage").get(key::usage);
         });
     });
 
@@ -102,6 +145,7 @@ pub fn load_routes(app: &mut tide::App<Data>) {
 
         router.at("/stats").get(stats::get_stats);
         router.at("/stats/:index").get(stats::index_stat);
+        router.at("/metrics").get(stats::get_metrics);
         router.at("/version").get(stats::get_version);
         router.at("/sys-info").get(stats::get_sys_info);
         router
@@ -0,0 +1,154 @@
+use std::collections::{BTreeMap, HashSet};
+
+use http::StatusCode;
+use meilidb_core::{PinnedDocument, QueryRule, RuleCondition};
+use serde::Deserialize;
+use serde_json::Value;
+use tide::{Context, Response};
+
+use crate::analytics::normalize_query;
+use crate::error::{ResponseError, SResult};
+use crate::helpers::tide::ContextExt;
+use crate::models::token::ACL::*;
+use crate::Data;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct PinnedDocumentBody {
+    id: Value,
+    position: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct QueryRuleBody {
+    condition: RuleCondition,
+    #[serde(default)]
+    pinned: Vec<PinnedDocumentBody>,
+    #[serde(default)]
+    hidden: Vec<Value>,
+}
+
+impl QueryRuleBody {
+    /// Normalizes the pattern the same way the queries are and
+    /// checks that the documents are pinned or hidden only once.
+    fn into_rule(self) -> SResult<QueryRule> {
+        let pattern = normalize_query(&self.condition.pattern);
+        if pattern.is_empty() {
+            return Err(ResponseError::bad_parameter(
+                "condition",
+                "the pattern must not be empty",
+            ));
+        }
+
+        let condition = RuleCondition {
+            pattern,
+            anchoring: self.condition.anchoring,
+        };
+
+        let mut hidden = Vec::with_capacity(self.hidden.len());
+        for id in self.hidden {
+            let id = meilidb_core::serde::value_to_string(&id)
+                .ok_or_else(|| ResponseError::bad_parameter("hidden", "invalid document id"))?;
+            if !hidden.contains(&id) {
+                hidden.push(id);
+            }
+        }
+
+        let mut positions = HashSet::new();
+        let mut pinned: Vec<PinnedDocument> = Vec::with_capacity(self.pinned.len());
+        for pin in self.pinned {
+            let id = meilidb_core::serde::value_to_string(&pin.id)
+                .ok_or_else(|| ResponseError::bad_parameter("pinned", "invalid document id"))?;
+
+            if !positions.insert(pin.position) {
+                let message = format!("two documents are pinned at position {}", pin.position);
+                return Err(ResponseError::bad_parameter("pinned", message));
+            }
+
+            if hidden.contains(&id) || pinned.iter().any(|p| p.id == id) {
+                let message = format!("the document {} is pinned or hidden twice", id);
+                return Err(ResponseError::bad_parameter("pinned", message));
+            }
+
+            pinned.push(PinnedDocument {
+                id,
+                position: pin.position,
+            });
+        }
+
+        Ok(QueryRule {
+            condition,
+            pinned,
+            hidden,
+        })
+    }
+}
+
+pub async fn list(ctx: Context<Data>) -> SResult<Response> {
+    ctx.is_allowed(SettingsRead)?;
+    let index = ctx.index()?;
+
+    let env = &ctx.state().db.env;
+    let reader = env.read_txn()?;
+
+    let rules: BTreeMap<_, _> = index.query_rules.rules(&reader)?.into_iter().collect();
+
+    Ok(tide::response::json(rules))
+}
+
+pub async fn get(ctx: Context<Data>) -> SResult<Response> {
+    ctx.is_allowed(SettingsRead)?;
+    let rule_id = ctx.url_param("rule")?;
+    let index = ctx.index()?;
+
+    let env = &ctx.state().db.env;
+    let reader = env.read_txn()?;
+
+    match index.query_rules.rule(&reader, &rule_id)? {
+        Some(rule) => Ok(tide::response::json(rule)),
+        None => Err(ResponseError::not_found(format!("rule: {}", rule_id))),
+    }
+}
+
+pub async fn update(mut ctx: Context<Data>) -> SResult<Response> {
+    ctx.is_allowed(SettingsWrite)?;
+    let rule_id = ctx.url_param("rule")?;
+    let index = ctx.index()?;
+    let index_name = ctx.index_name()?;
+
+    let body: QueryRuleBody = ctx.body_json().await.map_err(ResponseError::bad_request)?;
+    let rule = body.into_rule()?;
+
+    let env = &ctx.state().db.env;
+    let mut writer = env.write_txn()?;
+
+    index.query_rules.put_rule(&mut writer, &rule_id, &rule)?;
+
+    writer.commit()?;
+
+    // the rules pin and hide documents, the cached searches are outdated
+    ctx.state().db.query_cache().invalidate(&index_name);
+
+    Ok(tide::response::json(rule))
+}
+
+pub async fn delete(ctx: Context<Data>) -> SResult<StatusCode> {
+    ctx.is_allowed(SettingsWrite)?;
+    let rule_id = ctx.url_param("rule")?;
+    let index = ctx.index()?;
+    let index_name = ctx.index_name()?;
+
+    let env = &ctx.state().db.env;
+    let mut writer = env.write_txn()?;
+
+    if !index.query_rules.del_rule(&mut writer, &rule_id)? {
+        return Err(ResponseError::not_found(format!("rule: {}", rule_id)));
+    }
+
+    writer.commit()?;
+
+    ctx.state().db.query_cache().invalidate(&index_name);
+
+    Ok(StatusCode::NO_CONTENT)
+}
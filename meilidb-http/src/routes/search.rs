@@ -2,14 +2,14 @@ use std::collections::HashMap;
 use std::collections::HashSet;
 use std::time::Duration;
 
-use meilidb_core::Index;
+use meilidb_core::{Index, RankingRule};
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use serde::{Deserialize, Serialize};
 use tide::querystring::ContextExt as QSContextExt;
 use tide::{Context, Response};
 
 use crate::error::{ResponseError, SResult};
-use crate::helpers::meilidb::{Error, IndexSearchExt, SearchHit};
+use crate::helpers::meilidb::{IndexSearchExt, SearchHit};
 use crate::helpers::tide::ContextExt;
 use crate::Data;
 
@@ -27,20 +27,31 @@ struct SearchQuery {
     filters: Option<String>,
     timeout_ms: Option<u64>,
     matches: Option<bool>,
+    show_ranking_details: Option<bool>,
+    distinct: Option<String>,
+    distinct_size: Option<usize>,
+    ranking_rules: Option<String>,
+    attributes_weights: Option<String>,
+    sort: Option<String>,
+    sort_position: Option<usize>,
 }
 
 pub async fn search_with_url_query(ctx: Context<Data>) -> SResult<Response> {
-    // ctx.is_allowed(DocumentsRead)?;
+    let tenant_filter = ctx.is_allowed_to_search()?;
 
     let index = ctx.index()?;
-    let env = &ctx.state().db.env;
-    let reader = env.read_txn().map_err(ResponseError::internal)?;
+    let index_name = ctx.index_name()?;
+    let db = &ctx.state().db;
+    let cache_version = db.query_cache().version(&index_name);
+    let reader = db.env.read_txn()?;
 
     let query: SearchQuery = ctx
         .url_query()
         .map_err(|_| ResponseError::bad_request("invalid query parameter"))?;
 
     let mut search_builder = index.new_search(query.q.clone());
+    search_builder.log_query(&ctx.state().analytics, &index_name);
+    search_builder.query_cache(db.query_cache(), &index_name, cache_version);
 
     if let Some(offset) = query.offset {
         search_builder.offset(offset);
@@ -79,6 +90,9 @@ pub async fn search_with_url_query(ctx: Context<Data>) -> SResult<Response> {
     if let Some(filters) = query.filters {
         search_builder.filters(filters);
     }
+    if let Some(tenant_filter) = tenant_filter {
+        search_builder.filters(tenant_filter);
+    }
 
     if let Some(timeout_ms) = query.timeout_ms {
         search_builder.timeout(Duration::from_millis(timeout_ms));
@@ -89,12 +103,35 @@ pub async fn search_with_url_query(ctx: Context<Data>) -> SResult<Response> {
             search_builder.get_matches();
         }
     }
+    if let Some(true) = query.show_ranking_details {
+        search_builder.show_ranking_details();
+    }
 
-    let response = match search_builder.search(&reader) {
-        Ok(response) => response,
-        Err(Error::Internal(message)) => return Err(ResponseError::Internal(message)),
-        Err(others) => return Err(ResponseError::bad_request(others)),
-    };
+    if let Some(distinct) = query.distinct {
+        search_builder.distinct(distinct);
+    }
+    if let Some(distinct_size) = query.distinct_size {
+        check_distinct_size(distinct_size)?;
+        search_builder.distinct_size(distinct_size);
+    }
+    if let Some(ranking_rules) = query.ranking_rules {
+        search_builder.ranking_rules(parse_ranking_rules(ranking_rules.split(','))?);
+    }
+    if let Some(attributes_weights) = query.attributes_weights {
+        search_builder.attributes_weights(parse_attributes_weights(&attributes_weights)?);
+    }
+    if let Some(sort) = query.sort {
+        search_builder.sort(parse_sort(&sort)?);
+    }
+    if let Some(sort_position) = query.sort_position {
+        search_builder.sort_position(sort_position);
+    }
+
+    let response = search_builder.search(&reader)?;
+
+    ctx.state()
+        .metrics
+        .observe_search(&index_name, response.query_stats);
 
     Ok(tide::response::json(response))
 }
@@ -113,6 +150,13 @@ struct SearchMultiBody {
     filters: Option<String>,
     timeout_ms: Option<u64>,
     matches: Option<bool>,
+    show_ranking_details: Option<bool>,
+    distinct: Option<String>,
+    distinct_size: Option<usize>,
+    ranking_rules: Option<Vec<String>>,
+    attributes_weights: Option<HashMap<String, u16>>,
+    sort: Option<String>,
+    sort_position: Option<usize>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -126,26 +170,45 @@ struct SearchMultiBodyResponse {
 }
 
 pub async fn search_multi_index(mut ctx: Context<Data>) -> SResult<Response> {
-    // ctx.is_allowed(DocumentsRead)?;
+    let tenant_filter = ctx.is_allowed_to_search()?;
     let body = ctx
         .body_json::<SearchMultiBody>()
         .await
         .map_err(ResponseError::bad_request)?;
 
+    if let Some(distinct_size) = body.distinct_size {
+        check_distinct_size(distinct_size)?;
+    }
+
+    let ranking_rules = match &body.ranking_rules {
+        Some(rules) => Some(parse_ranking_rules(rules.iter().map(String::as_str))?),
+        None => None,
+    };
+
+    let sort = match &body.sort {
+        Some(sort) => parse_sort(sort)?,
+        None => Vec::new(),
+    };
+
     let mut index_list = body.clone().indexes;
 
     for index in index_list.clone() {
         if index == "*" {
+            // only keep the indexes the token is allowed to search in
             index_list = ctx
                 .state()
                 .db
-                .indexes_names()
-                .map_err(ResponseError::internal)?
+                .indexes_names()?
                 .into_iter()
+                .filter(|name| ctx.is_allowed_to_search_on_index(name).is_ok())
                 .collect();
         }
     }
 
+    for index in &index_list {
+        ctx.is_allowed_to_search_on_index(index)?;
+    }
+
     let mut offset = 0;
     let mut count = 20;
 
@@ -159,6 +222,8 @@ pub async fn search_multi_index(mut ctx: Context<Data>) -> SResult<Response> {
     let offset = offset;
     let count = count;
     let db = &ctx.state().db;
+    let metrics = &ctx.state().metrics;
+    let analytics = &ctx.state().analytics;
     let par_body = body.clone();
     let responses_per_index: Vec<SResult<_>> = index_list
         .into_par_iter()
@@ -168,6 +233,7 @@ pub async fn search_multi_index(mut ctx: Context<Data>) -> SResult<Response> {
                 .ok_or(ResponseError::index_not_found(&index_name))?;
 
             let mut search_builder = index.new_search(par_body.query.clone());
+            search_builder.log_query(analytics, &index_name);
 
             search_builder.offset(offset);
             search_builder.limit(count);
@@ -187,6 +253,9 @@ pub async fn search_multi_index(mut ctx: Context<Data>) -> SResult<Response> {
             if let Some(filters) = par_body.filters.clone() {
                 search_builder.filters(filters);
             }
+            if let Some(tenant_filter) = tenant_filter.clone() {
+                search_builder.filters(tenant_filter);
+            }
             if let Some(timeout_ms) = par_body.timeout_ms {
                 search_builder.timeout(Duration::from_secs(timeout_ms));
             }
@@ -195,13 +264,34 @@ pub async fn search_multi_index(mut ctx: Context<Data>) -> SResult<Response> {
                     search_builder.get_matches();
                 }
             }
+            if let Some(true) = par_body.show_ranking_details {
+                search_builder.show_ranking_details();
+            }
+            if let Some(distinct) = par_body.distinct.clone() {
+                search_builder.distinct(distinct);
+            }
+            if let Some(distinct_size) = par_body.distinct_size {
+                search_builder.distinct_size(distinct_size);
+            }
+            if let Some(ranking_rules) = ranking_rules.clone() {
+                search_builder.ranking_rules(ranking_rules);
+            }
+            if let Some(attributes_weights) = par_body.attributes_weights.clone() {
+                search_builder.attributes_weights(attributes_weights);
+            }
+            search_builder.sort(sort.clone());
+            if let Some(sort_position) = par_body.sort_position {
+                search_builder.sort_position(sort_position);
+            }
+
+            let cache_version = db.query_cache().version(&index_name);
+            search_builder.query_cache(db.query_cache(), &index_name, cache_version);
 
             let env = &db.env;
-            let reader = env.read_txn().map_err(ResponseError::internal)?;
+            let reader = env.read_txn()?;
 
-            let response = search_builder
-                .search(&reader)
-                .map_err(ResponseError::internal)?;
+            let response = search_builder.search(&reader)?;
+            metrics.observe_search(&index_name, response.query_stats);
             Ok((index_name, response))
         })
         .collect();
@@ -229,3 +319,76 @@ pub async fn search_multi_index(mut ctx: Context<Data>) -> SResult<Response> {
 
     Ok(tide::response::json(response))
 }
+
+fn check_distinct_size(distinct_size: usize) -> SResult<()> {
+    if distinct_size == 0 {
+        return Err(ResponseError::bad_parameter(
+            "distinctSize",
+            "must be greater than zero",
+        ));
+    }
+    Ok(())
+}
+
+fn parse_ranking_rules<'a, I>(rules: I) -> SResult<Vec<RankingRule>>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    rules
+        .into_iter()
+        .map(|rule| {
+            rule.parse()
+                .map_err(|e| ResponseError::bad_parameter("rankingRules", e))
+        })
+        .collect()
+}
+
+/// Parses a list of sort rules written like `price:asc,rating:desc`.
+fn parse_sort(sort: &str) -> SResult<Vec<RankingRule>> {
+    let mut rules: Vec<RankingRule> = Vec::new();
+    for rule in sort.split(',') {
+        let mut parts = rule.splitn(2, ':').map(str::trim);
+        let rule = match (parts.next(), parts.next()) {
+            (Some(name), Some("asc")) if !name.is_empty() => RankingRule::Asc(name.to_owned()),
+            (Some(name), Some("desc")) if !name.is_empty() => RankingRule::Desc(name.to_owned()),
+            _ => {
+                let message = format!("{} must be written attribute:asc or attribute:desc", rule);
+                return Err(ResponseError::bad_parameter("sort", message));
+            }
+        };
+
+        if rules.iter().any(|r| r.attribute() == rule.attribute()) {
+            let message = format!(
+                "the attribute {} is sorted twice",
+                rule.attribute().unwrap()
+            );
+            return Err(ResponseError::bad_parameter("sort", message));
+        }
+
+        rules.push(rule);
+    }
+
+    Ok(rules)
+}
+
+/// Parses a list of attributes weights written like `title:3,description:1`.
+fn parse_attributes_weights(weights: &str) -> SResult<HashMap<String, u16>> {
+    let mut attributes_weights = HashMap::new();
+    for weight in weights.split(',') {
+        let mut parts = weight.splitn(2, ':').map(str::trim);
+        let name = parts.next().unwrap_or_default();
+        let value = parts.next().and_then(|w| w.parse::<u16>().ok());
+
+        match value {
+            Some(value) if !name.is_empty() => {
+                attributes_weights.insert(name.to_owned(), value);
+            }
+            _ => {
+                let message = format!("{} must be written attribute:weight", weight);
+                return Err(ResponseError::bad_parameter("attributesWeights", message));
+            }
+        }
+    }
+
+    Ok(attributes_weights)
+}
@@ -1,6 +1,9 @@
 use std::collections::HashMap;
 
 use chrono::{DateTime, Utc};
+use http::header::CONTENT_TYPE;
+use http_service::Body;
+use meilidb_core::{IndexLimits, QueryCacheStats};
 use pretty_bytes::converter::convert;
 use serde::Serialize;
 use sysinfo::{NetworkExt, Pid, ProcessExt, ProcessorExt, System, SystemExt};
@@ -9,6 +12,7 @@ use walkdir::WalkDir;
 
 use crate::error::{ResponseError, SResult};
 use crate::helpers::tide::ContextExt;
+use crate::metrics::METRICS_CONTENT_TYPE;
 use crate::models::token::ACL::*;
 use crate::Data;
 
@@ -19,43 +23,59 @@ struct IndexStatsResponse {
     is_indexing: bool,
     last_update: Option<DateTime<Utc>>,
     fields_frequency: HashMap<String, usize>,
+    usage: IndexUsage,
+    query_cache: QueryCacheStats,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct IndexUsage {
+    number_of_documents: u64,
+    index_size: u64,
+    limits: IndexLimits,
 }
 
 pub async fn index_stat(ctx: Context<Data>) -> SResult<Response> {
     ctx.is_allowed(Admin)?;
-    let index_name = ctx.url_param("index")?;
+    let index_name = ctx.index_name()?;
     let index = ctx.index()?;
 
     let env = &ctx.state().db.env;
-    let reader = env.read_txn().map_err(ResponseError::internal)?;
+    let reader = env.read_txn()?;
 
-    let number_of_documents = index
-        .main
-        .number_of_documents(&reader)
-        .map_err(ResponseError::internal)?;
+    let number_of_documents = index.main.number_of_documents(&reader)?;
 
     let fields_frequency = ctx
         .state()
-        .fields_frequency(&reader, &index_name)
-        .map_err(ResponseError::internal)?
+        .fields_frequency(&reader, &index_name)?
         .unwrap_or_default();
 
     let is_indexing = ctx
         .state()
-        .is_indexing(&reader, &index_name)
-        .map_err(ResponseError::internal)?
-        .ok_or(ResponseError::not_found("Index not found"))?;
+        .is_indexing(&reader, &index_name)?
+        .ok_or(ResponseError::index_not_found(&index_name))?;
 
-    let last_update = ctx
-        .state()
-        .last_update(&reader, &index_name)
-        .map_err(ResponseError::internal)?;
+    let last_update = ctx.state().last_update(&reader, &index_name)?;
+
+    let index_size = index.stored_size(&reader)?;
+
+    let limits = index.limits(&reader)?;
+
+    let usage = IndexUsage {
+        number_of_documents,
+        index_size,
+        limits,
+    };
+
+    let query_cache = ctx.state().db.query_cache().stats(&index_name);
 
     let response = IndexStatsResponse {
         number_of_documents,
         is_indexing,
         last_update,
         fields_frequency,
+        usage,
+        query_cache,
     };
     Ok(tide::response::json(response))
 }
@@ -77,35 +97,37 @@ pub async fn get_stats(ctx: Context<Data>) -> SResult<Response> {
             let env = &db.env;
 
             let index = db.open_index(&index_name).unwrap();
-            let reader = env.read_txn().map_err(ResponseError::internal)?;
+            let reader = env.read_txn()?;
 
-            let number_of_documents = index
-                .main
-                .number_of_documents(&reader)
-                .map_err(ResponseError::internal)?;
+            let number_of_documents = index.main.number_of_documents(&reader)?;
 
             let fields_frequency = ctx
                 .state()
-                .fields_frequency(&reader, &index_name)
-                .map_err(ResponseError::internal)?
+                .fields_frequency(&reader, &index_name)?
                 .unwrap_or_default();
 
             let is_indexing = ctx
                 .state()
-                .is_indexing(&reader, &index_name)
-                .map_err(ResponseError::internal)?
-                .ok_or(ResponseError::not_found("Index not found"))?;
+                .is_indexing(&reader, &index_name)?
+                .ok_or(ResponseError::index_not_found(&index_name))?;
 
-            let last_update = ctx
-                .state()
-                .last_update(&reader, &index_name)
-                .map_err(ResponseError::internal)?;
+            let last_update = ctx.state().last_update(&reader, &index_name)?;
+
+            let usage = IndexUsage {
+                number_of_documents,
+                index_size: index.stored_size(&reader)?,
+                limits: index.limits(&reader)?,
+            };
+
+            let query_cache = db.query_cache().stats(&index_name);
 
             let response = IndexStatsResponse {
                 number_of_documents,
                 is_indexing,
                 last_update,
                 fields_frequency,
+                usage,
+                query_cache,
             };
             index_list.insert(index_name, response);
         }
@@ -126,6 +148,19 @@ pub async fn get_stats(ctx: Context<Data>) -> SResult<Response> {
     Ok(tide::response::json(response))
 }
 
+pub async fn get_metrics(ctx: Context<Data>) -> SResult<Response> {
+    ctx.is_allowed(Admin)?;
+    let data = ctx.state();
+    let metrics = data.metrics.render(&data.db, &data.db_path)?;
+
+    let response = http::Response::builder()
+        .header(CONTENT_TYPE, METRICS_CONTENT_TYPE)
+        .body(Body::from(metrics))
+        .map_err(ResponseError::internal)?;
+
+    Ok(response)
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct VersionResponse {
@@ -13,41 +13,36 @@ pub async fn list(ctx: Context<Data>) -> SResult<Response> {
     let index = ctx.index()?;
 
     let env = &ctx.state().db.env;
-    let reader = env.read_txn().map_err(ResponseError::internal)?;
+    let reader = env.read_txn()?;
 
-    let stop_words_fst = index
-        .main
-        .stop_words_fst(&reader)
-        .map_err(ResponseError::internal)?;
+    let stop_words_fst = index.main.stop_words_fst(&reader)?;
 
     let stop_words = stop_words_fst
         .unwrap_or_default()
         .stream()
         .into_strs()
-        .map_err(ResponseError::internal)?;
+        .map_err(meilidb_core::Error::from)?;
 
     Ok(tide::response::json(stop_words))
 }
 
 pub async fn add(mut ctx: Context<Data>) -> SResult<Response> {
-    ctx.is_allowed(SettingsRead)?;
+    ctx.is_allowed(SettingsWrite)?;
     let index = ctx.index()?;
 
     let data: Vec<String> = ctx.body_json().await.map_err(ResponseError::bad_request)?;
 
     let env = &ctx.state().db.env;
-    let mut writer = env.write_txn().map_err(ResponseError::internal)?;
+    let mut writer = env.write_txn()?;
 
     let mut stop_words_addition = index.stop_words_addition();
     for stop_word in data {
         stop_words_addition.add_stop_word(stop_word);
     }
 
-    let update_id = stop_words_addition
-        .finalize(&mut writer)
-        .map_err(ResponseError::internal)?;
+    let update_id = stop_words_addition.finalize(&mut writer)?;
 
-    writer.commit().map_err(ResponseError::internal)?;
+    writer.commit()?;
 
     let response_body = IndexUpdateResponse { update_id };
     Ok(tide::response::json(response_body)
@@ -56,24 +51,22 @@ pub async fn add(mut ctx: Context<Data>) -> SResult<Response> {
 }
 
 pub async fn delete(mut ctx: Context<Data>) -> SResult<Response> {
-    ctx.is_allowed(SettingsRead)?;
+    ctx.is_allowed(SettingsWrite)?;
     let index = ctx.index()?;
 
     let data: Vec<String> = ctx.body_json().await.map_err(ResponseError::bad_request)?;
 
     let env = &ctx.state().db.env;
-    let mut writer = env.write_txn().map_err(ResponseError::internal)?;
+    let mut writer = env.write_txn()?;
 
     let mut stop_words_deletion = index.stop_words_deletion();
     for stop_word in data {
         stop_words_deletion.delete_stop_word(stop_word);
     }
 
-    let update_id = stop_words_deletion
-        .finalize(&mut writer)
-        .map_err(ResponseError::internal)?;
+    let update_id = stop_words_deletion.finalize(&mut writer)?;
 
-    writer.commit().map_err(ResponseError::internal)?;
+    writer.commit()?;
 
     let response_body = IndexUpdateResponse { update_id };
     Ok(tide::response::json(response_body)
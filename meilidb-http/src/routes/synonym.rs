@@ -32,12 +32,9 @@ pub async fn list(ctx: Context<Data>) -> SResult<Response> {
     let index = ctx.index()?;
 
     let env = &ctx.state().db.env;
-    let reader = env.read_txn().map_err(ResponseError::internal)?;
+    let reader = env.read_txn()?;
 
-    let synonyms_fst = index
-        .main
-        .synonyms_fst(&reader)
-        .map_err(ResponseError::internal)?;
+    let synonyms_fst = index.main.synonyms_fst(&reader)?;
 
     let synonyms_fst = synonyms_fst.unwrap_or_default();
     let synonyms_list = synonyms_fst.stream().into_strs().unwrap();
@@ -66,7 +63,7 @@ pub async fn get(ctx: Context<Data>) -> SResult<Response> {
     let index = ctx.index()?;
 
     let env = &ctx.state().db.env;
-    let reader = env.read_txn().map_err(ResponseError::internal)?;
+    let reader = env.read_txn()?;
 
     let synonym_list = index
         .synonyms
@@ -88,7 +85,7 @@ pub async fn create(mut ctx: Context<Data>) -> SResult<Response> {
     let index = ctx.index()?;
 
     let env = &ctx.state().db.env;
-    let mut writer = env.write_txn().map_err(ResponseError::internal)?;
+    let mut writer = env.write_txn()?;
 
     let mut synonyms_addition = index.synonyms_addition();
 
@@ -107,11 +104,9 @@ pub async fn create(mut ctx: Context<Data>) -> SResult<Response> {
         }
     }
 
-    let update_id = synonyms_addition
-        .finalize(&mut writer)
-        .map_err(ResponseError::internal)?;
+    let update_id = synonyms_addition.finalize(&mut writer)?;
 
-    writer.commit().map_err(ResponseError::internal)?;
+    writer.commit()?;
 
     let response_body = IndexUpdateResponse { update_id };
     Ok(tide::response::json(response_body)
@@ -126,15 +121,13 @@ pub async fn update(mut ctx: Context<Data>) -> SResult<Response> {
     let data: Vec<String> = ctx.body_json().await.map_err(ResponseError::bad_request)?;
 
     let env = &ctx.state().db.env;
-    let mut writer = env.write_txn().map_err(ResponseError::internal)?;
+    let mut writer = env.write_txn()?;
 
     let mut synonyms_addition = index.synonyms_addition();
     synonyms_addition.add_synonym(synonym.clone(), data.clone().into_iter());
-    let update_id = synonyms_addition
-        .finalize(&mut writer)
-        .map_err(ResponseError::internal)?;
+    let update_id = synonyms_addition.finalize(&mut writer)?;
 
-    writer.commit().map_err(ResponseError::internal)?;
+    writer.commit()?;
 
     let response_body = IndexUpdateResponse { update_id };
     Ok(tide::response::json(response_body)
@@ -148,15 +141,13 @@ pub async fn delete(ctx: Context<Data>) -> SResult<Response> {
     let index = ctx.index()?;
 
     let env = &ctx.state().db.env;
-    let mut writer = env.write_txn().map_err(ResponseError::internal)?;
+    let mut writer = env.write_txn()?;
 
     let mut synonyms_deletion = index.synonyms_deletion();
     synonyms_deletion.delete_all_alternatives_of(synonym);
-    let update_id = synonyms_deletion
-        .finalize(&mut writer)
-        .map_err(ResponseError::internal)?;
+    let update_id = synonyms_deletion.finalize(&mut writer)?;
 
-    writer.commit().map_err(ResponseError::internal)?;
+    writer.commit()?;
 
     let response_body = IndexUpdateResponse { update_id };
     Ok(tide::response::json(response_body)
@@ -172,7 +163,7 @@ pub async fn batch_write(mut ctx: Context<Data>) -> SResult<Response> {
     let index = ctx.index()?;
 
     let env = &ctx.state().db.env;
-    let mut writer = env.write_txn().map_err(ResponseError::internal)?;
+    let mut writer = env.write_txn()?;
 
     let mut synonyms_addition = index.synonyms_addition();
     for raw in data {
@@ -191,11 +182,9 @@ pub async fn batch_write(mut ctx: Context<Data>) -> SResult<Response> {
             }
         }
     }
-    let update_id = synonyms_addition
-        .finalize(&mut writer)
-        .map_err(ResponseError::internal)?;
+    let update_id = synonyms_addition.finalize(&mut writer)?;
 
-    writer.commit().map_err(ResponseError::internal)?;
+    writer.commit()?;
 
     let response_body = IndexUpdateResponse { update_id };
     Ok(tide::response::json(response_body)
@@ -208,12 +197,9 @@ pub async fn clear(ctx: Context<Data>) -> SResult<Response> {
     let index = ctx.index()?;
 
     let env = &ctx.state().db.env;
-    let mut writer = env.write_txn().map_err(ResponseError::internal)?;
+    let mut writer = env.write_txn()?;
 
-    let synonyms_fst = index
-        .main
-        .synonyms_fst(&writer)
-        .map_err(ResponseError::internal)?;
+    let synonyms_fst = index.main.synonyms_fst(&writer)?;
 
     let synonyms_fst = synonyms_fst.unwrap_or_default();
     let synonyms_list = synonyms_fst.stream().into_strs().unwrap();
@@ -222,11 +208,9 @@ pub async fn clear(ctx: Context<Data>) -> SResult<Response> {
     for synonym in synonyms_list {
         synonyms_deletion.delete_all_alternatives_of(synonym);
     }
-    let update_id = synonyms_deletion
-        .finalize(&mut writer)
-        .map_err(ResponseError::internal)?;
+    let update_id = synonyms_deletion.finalize(&mut writer)?;
 
-    writer.commit().map_err(ResponseError::internal)?;
+    writer.commit()?;
 
     let response_body = IndexUpdateResponse { update_id };
     Ok(tide::response::json(response_body)
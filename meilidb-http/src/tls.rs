@@ -0,0 +1,183 @@
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use std::{error, fmt, thread};
+
+use arc_swap::ArcSwap;
+use async_std::io::timeout;
+use async_std::net::TcpListener;
+use async_tls::TlsAcceptor;
+use futures::executor::ThreadPool;
+use futures::future;
+use futures::stream::StreamExt;
+use http_service::HttpService;
+use http_service_hyper::Server;
+use log::{error, info, warn};
+use rustls::internal::pemfile::{certs, pkcs8_private_keys, rsa_private_keys};
+use rustls::{NoClientAuth, ServerConfig, TLSError};
+
+const MAX_CONCURRENT_HANDSHAKES: usize = 128;
+/// A client that does not complete its handshake in time is disconnected.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug)]
+pub enum Error {
+    Io(PathBuf, io::Error),
+    MissingCertificate(PathBuf),
+    MissingPrivateKey(PathBuf),
+    Tls(TLSError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::Error::*;
+        match self {
+            Io(path, e) => write!(f, "impossible to read {}; {}", path.display(), e),
+            MissingCertificate(path) => {
+                write!(f, "no PEM certificate found in {}", path.display())
+            }
+            MissingPrivateKey(path) => write!(f, "no PEM private key found in {}", path.display()),
+            Tls(e) => write!(f, "invalid certificate or private key; {}", e),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl From<TLSError> for Error {
+    fn from(error: TLSError) -> Error {
+        Error::Tls(error)
+    }
+}
+
+/// The TLS configuration of the server, loaded from the certificate and private key
+/// files, it can be reloaded without restarting the server to renew the certificate.
+pub struct TlsConfig {
+    cert_path: PathBuf,
+    key_path: PathBuf,
+    server_config: ArcSwap<ServerConfig>,
+}
+
+impl TlsConfig {
+    pub fn load<P: AsRef<Path>>(cert_path: P, key_path: P) -> Result<TlsConfig, Error> {
+        let cert_path = cert_path.as_ref().to_path_buf();
+        let key_path = key_path.as_ref().to_path_buf();
+        let server_config = load_server_config(&cert_path, &key_path)?;
+
+        Ok(TlsConfig {
+            cert_path,
+            key_path,
+            server_config: ArcSwap::from_pointee(server_config),
+        })
+    }
+
+    /// Reads the certificate and the private key files again, the current
+    /// configuration is kept if they are invalid. The connections already
+    /// established keep using the configuration they were accepted with.
+    pub fn reload(&self) -> Result<(), Error> {
+        let server_config = load_server_config(&self.cert_path, &self.key_path)?;
+        self.server_config.store(Arc::new(server_config));
+        Ok(())
+    }
+
+    pub fn server_config(&self) -> Arc<ServerConfig> {
+        self.server_config.load_full()
+    }
+
+    /// Spawns a thread that reloads the configuration every time the process receives a SIGHUP.
+    pub fn reload_on_sighup(self: Arc<Self>) -> io::Result<()> {
+        let signals = signal_hook::iterator::Signals::new(&[signal_hook::SIGHUP])?;
+
+        thread::spawn(move || {
+            for _ in signals.forever() {
+                match self.reload() {
+                    Ok(()) => info!("TLS certificate reloaded"),
+                    Err(e) => error!("impossible to reload the TLS certificate; {}", e),
+                }
+            }
+        });
+
+        Ok(())
+    }
+}
+
+fn load_server_config(cert_path: &Path, key_path: &Path) -> Result<ServerConfig, Error> {
+    let open = |path: &Path| {
+        File::open(path)
+            .map(BufReader::new)
+            .map_err(|e| Error::Io(path.to_path_buf(), e))
+    };
+
+    let certs = certs(&mut open(cert_path)?).unwrap_or_default();
+    if certs.is_empty() {
+        return Err(Error::MissingCertificate(cert_path.to_path_buf()));
+    }
+
+    // the private key can either be a PKCS8 or an RSA key
+    let mut keys = pkcs8_private_keys(&mut open(key_path)?).unwrap_or_default();
+    if keys.is_empty() {
+        keys = rsa_private_keys(&mut open(key_path)?).unwrap_or_default();
+    }
+    let key = match keys.into_iter().next() {
+        Some(key) => key,
+        None => return Err(Error::MissingPrivateKey(key_path.to_path_buf())),
+    };
+
+    let mut config = ServerConfig::new(NoClientAuth::new());
+    config.set_single_cert(certs, key)?;
+    config.set_protocols(&[b"http/1.1".to_vec()]);
+
+    Ok(config)
+}
+
+enum AcceptError {
+    Tcp(io::Error),
+    Handshake(io::Error),
+}
+
+/// Serves the HTTP service over TLS on the given address,
+/// every connection is accepted with the current TLS configuration.
+pub async fn serve<S>(service: S, addr: &str, tls_config: Arc<TlsConfig>) -> io::Result<()>
+where
+    S: HttpService,
+{
+    let listener = TcpListener::bind(addr).await?;
+    let spawner = ThreadPool::new()?;
+
+    let incoming = listener
+        .incoming()
+        .map(move |stream| {
+            let acceptor = TlsAcceptor::from(tls_config.server_config());
+            async move {
+                let stream = stream.map_err(AcceptError::Tcp)?;
+                // the slow clients must not hold the handshake slots forever
+                timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream))
+                    .await
+                    .map_err(AcceptError::Handshake)
+            }
+        })
+        // a slow client must not prevent the others from connecting
+        .buffer_unordered(MAX_CONCURRENT_HANDSHAKES)
+        // a failed connection must not stop the server
+        .filter_map(|result| {
+            future::ready(match result {
+                Ok(stream) => Some(Ok::<_, io::Error>(stream)),
+                Err(AcceptError::Tcp(e)) => {
+                    error!("impossible to accept a TCP connection; {}", e);
+                    None
+                }
+                Err(AcceptError::Handshake(e)) => {
+                    warn!("TLS handshake failed; {}", e);
+                    None
+                }
+            })
+        });
+
+    Server::builder(Box::pin(incoming))
+        .with_spawner(spawner)
+        .serve(service)
+        .await
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use http::StatusCode;
+use meilidb_schema::{SchemaBuilder, DISPLAYED, INDEXED};
+use serde_json::json;
+use tempfile::TempDir;
+
+use common::{create_index, hit_ids, request, server, setup_data, Server};
+
+/// Creates two versions of an index of movies, each one with a single
+/// document matching "iphone", and an alias pointing to each one.
+fn setup() -> (Server, TempDir) {
+    let (data, dir) = setup_data();
+
+    for (index_name, id) in &[("movies-v1", 1), ("movies-v2", 2)] {
+        let mut builder = SchemaBuilder::with_identifier("id");
+        builder.new_attribute("id", DISPLAYED);
+        builder.new_attribute("title", DISPLAYED | INDEXED);
+
+        let documents = vec![json!({ "id": id, "title": "iphone" })];
+        create_index(&data, index_name, builder.build(), documents, None);
+    }
+
+    data.db.set_alias("movies", "movies-v1").unwrap();
+    data.db.set_alias("movies-next", "movies-v2").unwrap();
+
+    (server(data), dir)
+}
+
+#[test]
+fn create_and_delete_aliases() {
+    let (mut server, _dir) = setup();
+
+    let body = r#"{ "index": "movies-v2" }"#;
+    let (status, response) = request(&mut server, "PUT", "/aliases/films", Some(body));
+    assert_eq!(status, StatusCode::OK, "{}", response);
+    assert_eq!(response, json!({ "alias": "films", "index": "movies-v2" }));
+
+    let (status, response) = request(&mut server, "GET", "/aliases", None);
+    assert_eq!(status, StatusCode::OK, "{}", response);
+    assert_eq!(
+        response,
+        json!([
+            { "alias": "films", "index": "movies-v2" },
+            { "alias": "movies", "index": "movies-v1" },
+            { "alias": "movies-next", "index": "movies-v2" },
+        ])
+    );
+
+    let (status, response) = request(&mut server, "GET", "/aliases/films", None);
+    assert_eq!(status, StatusCode::OK, "{}", response);
+    assert_eq!(response["index"], "movies-v2");
+
+    // an alias can only point to an existing index and cannot be named like one
+    let body = r#"{ "index": "series" }"#;
+    let (status, _) = request(&mut server, "PUT", "/aliases/films", Some(body));
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let body = r#"{ "index": "movies-v2" }"#;
+    let (status, _) = request(&mut server, "PUT", "/aliases/movies-v1", Some(body));
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = request(&mut server, "DELETE", "/aliases/films", None);
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, _) = request(&mut server, "DELETE", "/aliases/films", None);
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = request(&mut server, "GET", "/aliases/films", None);
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[test]
+fn search_through_swapped_aliases() {
+    let (mut server, _dir) = setup();
+
+    let uri = "/indexes/movies/search?q=iphone";
+    assert_eq!(hit_ids(&mut server, uri), vec![json!(1)]);
+
+    let (status, response) = request(&mut server, "GET", "/stats/movies", None);
+    assert_eq!(status, StatusCode::OK, "{}", response);
+    assert_eq!(response["numberOfDocuments"], 1);
+
+    let body = r#"{ "aliases": ["movies", "movies-next"] }"#;
+    let (status, response) = request(&mut server, "POST", "/aliases/swap", Some(body));
+    assert_eq!(status, StatusCode::OK, "{}", response);
+    assert_eq!(
+        response,
+        json!([
+            { "alias": "movies", "index": "movies-v2" },
+            { "alias": "movies-next", "index": "movies-v1" },
+        ])
+    );
+
+    // the searches must be made on the index the alias now points to
+    assert_eq!(hit_ids(&mut server, uri), vec![json!(2)]);
+    assert_eq!(
+        hit_ids(&mut server, "/indexes/movies-next/search?q=iphone"),
+        vec![json!(1)]
+    );
+
+    let body = r#"{ "aliases": ["movies", "series"] }"#;
+    let (status, _) = request(&mut server, "POST", "/aliases/swap", Some(body));
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,120 @@
+mod common;
+
+use http::StatusCode;
+use meilidb_schema::{SchemaBuilder, DISPLAYED, INDEXED};
+use serde_json::json;
+use tempfile::TempDir;
+
+use meilidb_http::data::Data;
+
+use common::{create_index, hit_ids, request, server, setup_data, Server};
+
+fn setup() -> (Server, Data, TempDir) {
+    let (data, dir) = setup_data();
+    data.db.create_index("movies").unwrap();
+    (server(data.clone()), data, dir)
+}
+
+#[test]
+fn top_queries_and_zero_hit_queries() {
+    let (mut server, data, _dir) = setup();
+
+    data.analytics.record("movies", "Star Wars", 12, 3);
+    data.analytics.record("movies", "star  wars", 10, 1);
+    data.analytics.record("movies", "batman", 4, 2);
+    data.analytics.record("movies", "starw ars", 0, 1);
+    data.analytics.record("movies", "", 30, 1);
+    data.analytics.record("series", "friends", 0, 1);
+
+    let (status, body) = request(
+        &mut server,
+        "GET",
+        "/indexes/movies/analytics?limit=1",
+        None,
+    );
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["totalQueries"], 4);
+    assert_eq!(body["zeroHitQueries"], 1);
+
+    let top = body["topQueries"].as_array().unwrap();
+    assert_eq!(top.len(), 1);
+    assert_eq!(top[0]["query"], "star wars");
+    assert_eq!(top[0]["count"], 2);
+    assert_eq!(top[0]["averageHits"], 11.0);
+    assert_eq!(top[0]["averageProcessingTimeMs"], 2.0);
+
+    let top = body["topZeroHitQueries"].as_array().unwrap();
+    assert_eq!(top[0]["query"], "starw ars");
+
+    // the queries are only counted in their time window
+    let uri = "/indexes/movies/analytics?from=2019-01-01T00:00:00Z&to=2019-01-02T00:00:00Z";
+    let (status, body) = request(&mut server, "GET", uri, None);
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["totalQueries"], 0);
+
+    let uri = "/indexes/movies/analytics?from=2019-01-02T00:00:00Z&to=2019-01-01T00:00:00Z";
+    let (status, _) = request(&mut server, "GET", uri, None);
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[test]
+fn deleted_index_analytics() {
+    let (mut server, data, _dir) = setup();
+
+    data.analytics.record("movies", "batman", 4, 2);
+    let (_, body) = request(&mut server, "GET", "/indexes/movies/analytics", None);
+    assert_eq!(body["totalQueries"], 1);
+
+    let (status, _) = request(&mut server, "DELETE", "/indexes/movies", None);
+    assert!(status.is_success());
+
+    let (status, _) = request(&mut server, "GET", "/indexes/movies/analytics", None);
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    data.db.create_index("movies").unwrap();
+    let (status, body) = request(&mut server, "GET", "/indexes/movies/analytics", None);
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["totalQueries"], 0);
+}
+
+#[test]
+fn stored_and_pending_queries_are_counted_once() {
+    let (mut server, data, _dir) = setup();
+
+    data.analytics.record("movies", "batman", 4, 2);
+    data.analytics.flush(&data.db).unwrap();
+    assert!(data.analytics.pending("movies").is_empty());
+
+    data.analytics.record("movies", "batman", 4, 2);
+    let (_, body) = request(&mut server, "GET", "/indexes/movies/analytics", None);
+    assert_eq!(body["totalQueries"], 2);
+
+    // reading the analytics does not store the pending queries
+    assert_eq!(data.analytics.pending("movies").len(), 1);
+}
+
+#[test]
+fn searches_count_all_their_hits() {
+    let (data, _dir) = setup_data();
+
+    let mut builder = SchemaBuilder::with_identifier("id");
+    builder.new_attribute("id", DISPLAYED);
+    builder.new_attribute("title", DISPLAYED | INDEXED);
+
+    let documents = vec![
+        json!({ "id": 1, "title": "iphone" }),
+        json!({ "id": 2, "title": "iphone case" }),
+        json!({ "id": 3, "title": "iphone charger" }),
+    ];
+    create_index(&data, "products", builder.build(), documents, None);
+    let mut server = server(data.clone());
+
+    // the second search is answered by the query cache
+    let uri = "/indexes/products/search?q=iphone&limit=1";
+    assert_eq!(hit_ids(&mut server, uri).len(), 1);
+    assert_eq!(hit_ids(&mut server, uri).len(), 1);
+
+    let (_, body) = request(&mut server, "GET", "/indexes/products/analytics", None);
+    assert_eq!(body["totalQueries"], 2);
+    assert_eq!(body["topQueries"][0]["averageHits"], 3.0);
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use http::StatusCode;
+use meilidb_schema::{SchemaBuilder, DISPLAYED, INDEXED};
+use serde_json::json;
+use tempfile::TempDir;
+
+use meilidb_http::data::Data;
+
+use common::{create_index, request, server, setup_data, wait_update, Server};
+
+/// Creates an index where the first document matches "shoes" in its
+/// description and the second one matches it in its title.
+fn setup() -> (Server, Data, TempDir) {
+    let (data, dir) = setup_data();
+
+    let mut builder = SchemaBuilder::with_identifier("id");
+    builder.new_attribute("id", DISPLAYED);
+    builder.new_attribute("title", DISPLAYED | INDEXED);
+    builder.new_attribute("description", DISPLAYED | INDEXED);
+
+    let documents = vec![
+        json!({ "id": 1, "title": "boots", "description": "red shoes" }),
+        json!({ "id": 2, "title": "shoes", "description": "leather" }),
+    ];
+    create_index(&data, "shoes", builder.build(), documents, None);
+
+    (server(data.clone()), data, dir)
+}
+
+fn hit_ids(server: &mut Server, query: &str) -> Vec<u64> {
+    let uri = format!("/indexes/shoes/search?q=shoes{}", query);
+    common::hit_ids(server, &uri)
+        .iter()
+        .map(|id| id.as_u64().unwrap())
+        .collect()
+}
+
+#[test]
+fn weighted_attributes() {
+    let (mut server, data, _dir) = setup();
+
+    // without weights the attributes are ranked in the schema order
+    assert_eq!(hit_ids(&mut server, ""), vec![2, 1]);
+
+    let settings = json!({ "attributesWeights": { "description": 3 } });
+    let uri = "/indexes/shoes/settings";
+    let (status, body) = request(&mut server, "POST", uri, Some(&settings.to_string()));
+    assert_eq!(status, StatusCode::ACCEPTED, "{}", body);
+    let update_id = body["updateId"].as_u64().unwrap();
+    assert_eq!(wait_update(&data, "shoes", update_id), Ok(()));
+
+    assert_eq!(hit_ids(&mut server, ""), vec![1, 2]);
+
+    // the searchable attributes do not change the weights
+    let query = "&attributesToSearchIn=description,title";
+    assert_eq!(hit_ids(&mut server, query), vec![1, 2]);
+
+    // the weights of the query are applied over the ones of the settings
+    let query = "&attributesWeights=title:5";
+    assert_eq!(hit_ids(&mut server, query), vec![2, 1]);
+    assert_eq!(hit_ids(&mut server, ""), vec![1, 2]);
+}
+
+#[test]
+fn weights_in_ranking_details() {
+    let (mut server, _data, _dir) = setup();
+
+    let uri =
+        "/indexes/shoes/search?q=shoes&attributesWeights=description:3&showRankingDetails=true";
+    let (status, body) = request(&mut server, "GET", uri, None);
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    assert_eq!(body["hits"][0]["id"], 1);
+    assert_eq!(body["hits"][0]["_rankingInfo"]["attribute"], 3);
+    assert_eq!(body["hits"][1]["_rankingInfo"]["attribute"], 1);
+}
+
+#[test]
+fn invalid_weights() {
+    let (mut server, _data, _dir) = setup();
+
+    let invalid = &[
+        ("title", "bad_parameter"),
+        ("title:-1", "bad_parameter"),
+        ("brand:2", "invalid_search_parameter"),
+    ];
+
+    for (weights, code) in invalid {
+        let uri = format!(
+            "/indexes/shoes/search?q=shoes&attributesWeights={}",
+            weights
+        );
+        let (status, body) = request(&mut server, "GET", &uri, None);
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", weights);
+        assert_eq!(body["errorCode"], *code, "{}", body);
+    }
+}
@@ -0,0 +1,237 @@
+//! The fixtures shared by the integration tests, every test file
+//! only uses a part of them.
+#![allow(dead_code)]
+
+use std::thread;
+use std::time::Duration;
+
+use chrono::{Duration as ChronoDuration, Utc};
+use futures::executor::block_on;
+use futures::io::AsyncReadExt;
+use heed::types::{SerdeBincode, Str};
+use http::{Request, Response, StatusCode};
+use http_service::Body;
+use http_service_mock::{make_server, TestBackend};
+use meilidb_core::{Index, Settings, UpdateStatus};
+use meilidb_schema::Schema;
+use serde_json::Value;
+use tempfile::TempDir;
+
+use meilidb_http::data::Data;
+use meilidb_http::models::token::{
+    generate_random_string, HashedSecret, RateLimit, Token, ACL, KEY_ID_LENGTH, SECRET_LENGTH,
+    TOKEN_PREFIX_KEY,
+};
+use meilidb_http::option::Opt;
+use meilidb_http::routes;
+
+pub const ADMIN_KEY: &str = "admin-key";
+
+pub type Server = TestBackend<tide::Server<Data>>;
+
+/// Opens a new database protected by the admin key, it
+/// is removed when the returned directory is dropped.
+pub fn setup_data() -> (Data, TempDir) {
+    setup_data_with(Opt::default())
+}
+
+pub fn setup_data_with(opt: Opt) -> (Data, TempDir) {
+    let dir = TempDir::new().unwrap();
+
+    let opt = Opt {
+        database_path: dir.path().to_str().unwrap().to_owned(),
+        admin_token: Some(ADMIN_KEY.to_owned()),
+        ..opt
+    };
+
+    (Data::new(opt), dir)
+}
+
+pub fn server(data: Data) -> Server {
+    server_with(data, |_| ())
+}
+
+/// Creates a server of all the routes, the middlewares must be added by the closure.
+pub fn server_with<F>(data: Data, middlewares: F) -> Server
+where
+    F: FnOnce(&mut tide::App<Data>),
+{
+    let mut app = tide::App::with_state(data);
+    middlewares(&mut app);
+    routes::load_routes(&mut app);
+    make_server(app.into_http_service()).unwrap()
+}
+
+/// Waits for the update to be processed and returns its result.
+pub fn wait_update(data: &Data, index_name: &str, update_id: u64) -> Result<(), String> {
+    let index = data.db.open_index(index_name).unwrap();
+    loop {
+        let reader = data.db.env.read_txn().unwrap();
+        match index.update_status(&reader, update_id).unwrap() {
+            UpdateStatus::Processed(status) => return status.result,
+            _ => thread::sleep(Duration::from_millis(10)),
+        }
+    }
+}
+
+/// Creates an index with its schema, documents and settings
+/// and waits for all of them to be processed.
+pub fn create_index(
+    data: &Data,
+    index_name: &str,
+    schema: Schema,
+    documents: Vec<Value>,
+    settings: Option<Settings>,
+) -> Index {
+    let index = data.db.create_index(index_name).unwrap();
+
+    let mut writer = data.db.env.write_txn().unwrap();
+    let mut update_id = index.schema_update(&mut writer, schema).unwrap();
+
+    if !documents.is_empty() {
+        let mut addition = index.documents_addition();
+        for document in documents {
+            addition.update_document(document);
+        }
+        update_id = addition.finalize(&mut writer).unwrap();
+    }
+
+    if let Some(settings) = settings {
+        update_id = index.settings_update(&mut writer, settings).unwrap();
+    }
+
+    writer.commit().unwrap();
+    assert_eq!(wait_update(data, index_name, update_id), Ok(()));
+
+    index
+}
+
+/// Adds the documents to the index and waits for them to be indexed.
+pub fn add_documents(data: &Data, index_name: &str, documents: Vec<Value>) {
+    let index = data.db.open_index(index_name).unwrap();
+
+    let mut writer = data.db.env.write_txn().unwrap();
+    let mut addition = index.documents_addition();
+    for document in documents {
+        addition.update_document(document);
+    }
+    let update_id = addition.finalize(&mut writer).unwrap();
+    writer.commit().unwrap();
+
+    assert_eq!(wait_update(data, index_name, update_id), Ok(()));
+}
+
+/// Sends a request with the admin key and returns its status and JSON body.
+pub fn request(
+    server: &mut Server,
+    method: &str,
+    uri: &str,
+    body: Option<&str>,
+) -> (StatusCode, Value) {
+    request_with_key(server, method, uri, Some(ADMIN_KEY), body)
+}
+
+pub fn request_with_key(
+    server: &mut Server,
+    method: &str,
+    uri: &str,
+    key: Option<&str>,
+    body: Option<&str>,
+) -> (StatusCode, Value) {
+    let response = send(server, method, uri, key, body);
+    let status = response.status();
+    (status, read_json(response))
+}
+
+/// Sends a request and returns the raw response, to read its headers.
+pub fn send(
+    server: &mut Server,
+    method: &str,
+    uri: &str,
+    key: Option<&str>,
+    body: Option<&str>,
+) -> Response<Body> {
+    let mut builder = Request::builder();
+    builder.method(method).uri(uri);
+    if let Some(key) = key {
+        builder.header("X-Meili-API-Key", key);
+    }
+
+    let body = body.map_or_else(Body::empty, |b| Body::from(b.as_bytes().to_vec()));
+    server.simulate(builder.body(body).unwrap()).unwrap()
+}
+
+pub fn read_text(response: Response<Body>) -> String {
+    let mut body = String::new();
+    block_on(response.into_body().read_to_string(&mut body)).unwrap();
+    body
+}
+
+/// Reads the JSON body of the response, `Null` if it is empty.
+pub fn read_json(response: Response<Body>) -> Value {
+    let mut body = Vec::new();
+    block_on(response.into_body().read_to_end(&mut body)).unwrap();
+    serde_json::from_slice(&body).unwrap_or(Value::Null)
+}
+
+/// Returns the identifiers of the documents found by the search.
+pub fn hit_ids(server: &mut Server, uri: &str) -> Vec<Value> {
+    let (status, body) = request(server, "GET", uri, None);
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    body["hits"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|hit| hit["id"].clone())
+        .collect()
+}
+
+/// Stores a new token and returns its key id along with the API key to use it.
+pub fn create_token(
+    data: &Data,
+    acl: Vec<ACL>,
+    indexes: &[&str],
+    rate_limit: Option<RateLimit>,
+) -> (String, String) {
+    let key_id = generate_random_string(KEY_ID_LENGTH);
+    let secret = generate_random_string(SECRET_LENGTH);
+
+    let token = Token {
+        key_id: key_id.clone(),
+        description: String::new(),
+        acl,
+        indexes: indexes.iter().map(|s| s.to_string()).collect(),
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+        expires_at: Utc::now() + ChronoDuration::days(1),
+        revoked: false,
+        secret: HashedSecret::new(&secret),
+        previous_secret: None,
+        rate_limit,
+    };
+
+    let mut writer = data.db.env.write_txn().unwrap();
+    let token_key = format!("{}{}", TOKEN_PREFIX_KEY, key_id);
+    data.db
+        .common_store()
+        .put::<Str, SerdeBincode<Token>>(&mut writer, &token_key, &token)
+        .unwrap();
+    writer.commit().unwrap();
+
+    let api_key = format!("{}{}", key_id, secret);
+    (key_id, api_key)
+}
+
+/// Returns the key the tenant tokens of this token must be signed with.
+pub fn signing_key(data: &Data, key_id: &str) -> String {
+    let reader = data.db.env.read_txn().unwrap();
+    let token_key = format!("{}{}", TOKEN_PREFIX_KEY, key_id);
+    let token = data
+        .db
+        .common_store()
+        .get::<Str, SerdeBincode<Token>>(&reader, &token_key)
+        .unwrap()
+        .unwrap();
+    token.secret.signing_key(ADMIN_KEY, key_id)
+}
@@ -0,0 +1,81 @@
+use http::{Request, Response, StatusCode};
+use http_service::Body;
+use http_service_mock::{make_server, TestBackend};
+use tempfile::TempDir;
+
+use meilidb_http::data::Data;
+use meilidb_http::middleware::cors_middleware;
+use meilidb_http::option::Opt;
+use meilidb_http::routes;
+
+type Server = TestBackend<tide::Server<Data>>;
+
+fn setup(opt: Opt) -> (Server, TempDir) {
+    let dir = TempDir::new().unwrap();
+
+    let opt = Opt {
+        database_path: dir.path().to_str().unwrap().to_owned(),
+        ..opt
+    };
+
+    let mut app = tide::App::with_state(Data::new(opt.clone()));
+    app.middleware(cors_middleware(&opt).unwrap());
+    routes::load_routes(&mut app);
+    let server = make_server(app.into_http_service()).unwrap();
+
+    (server, dir)
+}
+
+fn preflight(server: &mut Server, origin: &str, method: &str) -> Response<Body> {
+    let request = Request::builder()
+        .method("OPTIONS")
+        .uri("/indexes/movies")
+        .header("Origin", origin)
+        .header("Access-Control-Request-Method", method)
+        .body(Body::empty())
+        .unwrap();
+
+    server.simulate(request).unwrap()
+}
+
+#[test]
+fn default_cors_allows_every_method() {
+    let (mut server, _dir) = setup(Opt::default());
+
+    for method in &["GET", "POST", "PUT", "DELETE"] {
+        let response = preflight(&mut server, "https://example.com", method);
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let headers = response.headers();
+        assert_eq!(headers["Access-Control-Allow-Origin"], "*");
+
+        let allowed_methods = headers["Access-Control-Allow-Methods"].to_str().unwrap();
+        assert!(allowed_methods.contains(method), "{}", allowed_methods);
+
+        let allowed_headers = headers["Access-Control-Allow-Headers"].to_str().unwrap();
+        assert!(allowed_headers.contains("X-Meili-API-Key"));
+    }
+}
+
+#[test]
+fn configured_cors_origins() {
+    let opt = Opt {
+        cors_allowed_origins: vec![String::from("https://example.com")],
+        cors_allowed_methods: vec![String::from("GET")],
+        ..Opt::default()
+    };
+    let (mut server, _dir) = setup(opt);
+
+    let response = preflight(&mut server, "https://example.com", "GET");
+    assert_eq!(response.status(), StatusCode::OK);
+    let headers = response.headers();
+    assert_eq!(
+        headers["Access-Control-Allow-Origin"],
+        "https://example.com"
+    );
+    assert_eq!(headers["Access-Control-Allow-Methods"], "GET");
+
+    let response = preflight(&mut server, "https://other.com", "GET");
+    let allowed_origin = response.headers().get("Access-Control-Allow-Origin");
+    assert!(allowed_origin.map_or(true, |origin| origin != "https://other.com"));
+}
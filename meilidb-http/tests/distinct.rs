@@ -0,0 +1,96 @@
+mod common;
+
+use http::StatusCode;
+use meilidb_core::Settings;
+use meilidb_schema::{SchemaBuilder, DISPLAYED, INDEXED};
+use serde_json::{json, Value};
+use tempfile::TempDir;
+
+use common::{create_index, request, server, setup_data, Server};
+
+/// Creates an index with six documents matching "phone", three of them
+/// are from Apple, two from Samsung and the last one has no brand.
+fn setup() -> (Server, TempDir) {
+    let (data, dir) = setup_data();
+
+    let mut builder = SchemaBuilder::with_identifier("id");
+    builder.new_attribute("id", DISPLAYED);
+    builder.new_attribute("title", DISPLAYED | INDEXED);
+    builder.new_attribute("brand", DISPLAYED);
+    builder.new_attribute("color", DISPLAYED);
+
+    let settings = Settings {
+        distinct_field: Some(String::from("brand")),
+        ..Settings::default()
+    };
+
+    let documents = vec![
+        json!({ "id": 1, "title": "phone", "brand": "Apple", "color": "black" }),
+        json!({ "id": 2, "title": "phone", "brand": " apple ", "color": "black" }),
+        json!({ "id": 3, "title": "phone", "brand": "APPLE", "color": "black" }),
+        json!({ "id": 4, "title": "phone", "brand": "Samsung", "color": "black" }),
+        json!({ "id": 5, "title": "phone", "brand": "samsung", "color": "black" }),
+        json!({ "id": 6, "title": "phone", "color": "white" }),
+    ];
+    create_index(&data, "phones", builder.build(), documents, Some(settings));
+
+    (server(data), dir)
+}
+
+fn search(server: &mut Server, query: &str) -> (StatusCode, Value) {
+    let uri = format!("/indexes/phones/search?q=phone&{}", query);
+    request(server, "GET", &uri, None)
+}
+
+fn brands(hits: &Value) -> Vec<String> {
+    let mut brands: Vec<_> = hits
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|hit| match hit["brand"].as_str() {
+            Some(brand) => brand.trim().to_lowercase(),
+            None => String::new(),
+        })
+        .collect();
+    brands.sort();
+    brands
+}
+
+#[test]
+fn distinct_attribute_of_the_settings() {
+    let (mut server, _dir) = setup();
+
+    let (status, body) = search(&mut server, "");
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(brands(&body["hits"]), vec!["", "apple", "samsung"]);
+
+    let (status, body) = search(&mut server, "distinctSize=2");
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let expected = vec!["", "apple", "apple", "samsung", "samsung"];
+    assert_eq!(brands(&body["hits"]), expected);
+}
+
+#[test]
+fn distinct_attribute_of_the_query() {
+    let (mut server, _dir) = setup();
+
+    let (status, body) = search(&mut server, "distinct=color");
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["hits"].as_array().unwrap().len(), 2);
+
+    let (status, body) = search(&mut server, "distinct=color&offset=1");
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["hits"].as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn invalid_distinct_parameters() {
+    let (mut server, _dir) = setup();
+
+    let (status, body) = search(&mut server, "distinct=price");
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+
+    let (status, body) = search(&mut server, "distinctSize=0");
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+    assert_eq!(body["errorCode"], "bad_parameter");
+}
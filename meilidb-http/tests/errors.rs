@@ -0,0 +1,81 @@
+mod common;
+
+use http::StatusCode;
+use serde_json::Value;
+use tempfile::TempDir;
+
+use common::{request_with_key, server, setup_data, Server, ADMIN_KEY};
+
+fn setup() -> (Server, TempDir) {
+    let (data, dir) = setup_data();
+    data.db.create_index("movies").unwrap();
+    (server(data), dir)
+}
+
+fn assert_error(body: &Value, code: &str, error_type: &str) {
+    assert_eq!(body["errorCode"], code, "{}", body);
+    assert_eq!(body["errorType"], error_type, "{}", body);
+    assert!(body["message"].is_string(), "{}", body);
+}
+
+#[test]
+fn authentication_errors() {
+    let (mut server, _dir) = setup();
+
+    let (status, body) = request_with_key(&mut server, "GET", "/indexes", None, None);
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_error(
+        &body,
+        "missing_authorization_header",
+        "authentication_error",
+    );
+
+    let (status, body) = request_with_key(&mut server, "GET", "/indexes", Some("ABCDEFGH"), None);
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_error(&body, "invalid_token", "authentication_error");
+}
+
+#[test]
+fn core_errors_keep_their_code() {
+    let (mut server, _dir) = setup();
+    let key = Some(ADMIN_KEY);
+
+    let (status, body) = request_with_key(&mut server, "GET", "/indexes/series", key, None);
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_error(&body, "index_not_found", "invalid_request_error");
+
+    let (status, body) = request_with_key(&mut server, "GET", "/indexes/movies", key, None);
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_error(&body, "schema_missing", "invalid_request_error");
+
+    let (status, body) =
+        request_with_key(&mut server, "GET", "/indexes/movies/search?q=a", key, None);
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_error(&body, "schema_missing", "invalid_request_error");
+
+    let (status, body) = request_with_key(&mut server, "DELETE", "/aliases/films", key, None);
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_error(&body, "alias_not_found", "invalid_request_error");
+}
+
+#[test]
+fn document_limits_are_payload_errors() {
+    let (mut server, _dir) = setup();
+    let key = Some(ADMIN_KEY);
+
+    let limits = r#"{"maxDocumentSize":10}"#;
+    let (status, body) = request_with_key(
+        &mut server,
+        "PUT",
+        "/indexes/movies/limits",
+        key,
+        Some(limits),
+    );
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let documents = r#"[{"id":1,"title":"a title longer than the limit"}]"#;
+    let uri = "/indexes/movies/documents";
+    let (status, body) = request_with_key(&mut server, "POST", uri, key, Some(documents));
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert_error(&body, "document_limit_exceeded", "invalid_request_error");
+}
@@ -0,0 +1,121 @@
+mod common;
+
+use http::StatusCode;
+use meilidb_core::serde::compute_document_id;
+use meilidb_core::{RankingRule, Settings};
+use meilidb_schema::{SchemaBuilder, DISPLAYED, INDEXED};
+use serde_json::{json, Value};
+use tempfile::TempDir;
+
+use meilidb_http::data::Data;
+
+use common::{create_index, request, server, setup_data, Server};
+
+/// Creates an index with two documents matching "iphone",
+/// ranked by their popularity.
+fn setup() -> (Server, Data, TempDir) {
+    let (data, dir) = setup_data();
+
+    let mut builder = SchemaBuilder::with_identifier("id");
+    builder.new_attribute("id", DISPLAYED);
+    builder.new_attribute("title", DISPLAYED | INDEXED);
+
+    let settings = Settings {
+        ranking_rules: Some(vec![RankingRule::Popularity]),
+        ..Settings::default()
+    };
+
+    let documents = vec![
+        json!({ "id": 1, "title": "iphone" }),
+        json!({ "id": 2, "title": "iphone" }),
+    ];
+    create_index(&data, "movies", builder.build(), documents, Some(settings));
+
+    (server(data.clone()), data, dir)
+}
+
+fn first_hit_id(server: &mut Server) -> Value {
+    let (status, body) = request(server, "GET", "/indexes/movies/search?q=iphone", None);
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    body["hits"][0]["id"].clone()
+}
+
+fn popularity(data: &Data, identifier: &str) -> u64 {
+    let index = data.db.open_index("movies").unwrap();
+    let reader = data.db.env.read_txn().unwrap();
+    let popularity_map = index.main.popularity_map(&reader).unwrap();
+    let document_id = compute_document_id(identifier);
+    popularity_map.map_or(0, |map| map.get(document_id))
+}
+
+#[test]
+fn popular_documents_are_ranked_first() {
+    let (mut server, data, _dir) = setup();
+    let uri = "/indexes/movies/events";
+
+    let events = r#"[{ "documentId": 2, "eventType": "click" }]"#;
+    let (status, _) = request(&mut server, "POST", uri, Some(events));
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert_eq!(popularity(&data, "2"), 0);
+
+    // the events are taken into account once stored
+    data.popularity.flush(&data.db).unwrap();
+    assert_eq!(first_hit_id(&mut server), 2);
+
+    // a conversion is worth more than a click
+    let events = r#"[{ "documentId": "1", "eventType": "conversion" }]"#;
+    let (status, _) = request(&mut server, "POST", uri, Some(events));
+    assert_eq!(status, StatusCode::ACCEPTED);
+    data.popularity.flush(&data.db).unwrap();
+    assert_eq!(first_hit_id(&mut server), 1);
+}
+
+#[test]
+fn invalid_events() {
+    let (mut server, data, _dir) = setup();
+    let uri = "/indexes/movies/events";
+
+    // none of the events of a request are recorded if one is invalid
+    let events = r#"[{ "documentId": 2, "eventType": "click" },
+                     { "documentId": 42, "eventType": "click" }]"#;
+    let (status, body) = request(&mut server, "POST", uri, Some(events));
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["errorCode"], "document_not_found");
+
+    let events = r#"[{ "documentId": 1, "eventType": "like" }]"#;
+    let (status, body) = request(&mut server, "POST", uri, Some(events));
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["errorCode"], "bad_request");
+
+    data.popularity.flush(&data.db).unwrap();
+    assert_eq!(popularity(&data, "1"), 0);
+    assert_eq!(popularity(&data, "2"), 0);
+}
+
+#[test]
+fn events_invalidate_the_cache_of_indexes_ranked_by_popularity() {
+    let (mut server, data, _dir) = setup();
+
+    let mut builder = SchemaBuilder::with_identifier("id");
+    builder.new_attribute("id", DISPLAYED);
+    builder.new_attribute("title", DISPLAYED | INDEXED);
+    let documents = vec![json!({ "id": 1, "title": "iphone" })];
+    create_index(&data, "books", builder.build(), documents, None);
+
+    for index in &["movies", "books"] {
+        let uri = format!("/indexes/{}/search?q=iphone", index);
+        let (status, body) = request(&mut server, "GET", &uri, None);
+        assert_eq!(status, StatusCode::OK, "{}", body);
+
+        let uri = format!("/indexes/{}/events", index);
+        let events = r#"[{ "documentId": 1, "eventType": "click" }]"#;
+        let (status, _) = request(&mut server, "POST", &uri, Some(events));
+        assert_eq!(status, StatusCode::ACCEPTED);
+    }
+
+    data.popularity.flush(&data.db).unwrap();
+
+    // the books are not ranked by their popularity, their searches are still valid
+    assert_eq!(data.db.query_cache().stats("movies").entries, 0);
+    assert_eq!(data.db.query_cache().stats("books").entries, 1);
+}
@@ -0,0 +1,432 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use heed::types::{SerdeBincode, Str};
+use http::StatusCode;
+use tempfile::TempDir;
+
+use meilidb_http::data::Data;
+use meilidb_http::models::tenant_token::{sign_tenant_token, TenantClaims};
+use meilidb_http::models::token::ACL::*;
+use meilidb_http::models::token::{
+    generate_random_string, Token, ACL, KEY_ID_LENGTH, SECRET_LENGTH, TOKEN_PREFIX_KEY,
+};
+
+use common::{send, server, setup_data, Server, ADMIN_KEY};
+
+const ALL_ACLS: &[ACL] = &[
+    IndexesRead,
+    IndexesWrite,
+    DocumentsRead,
+    DocumentsWrite,
+    SettingsRead,
+    SettingsWrite,
+    EventsWrite,
+    Admin,
+];
+
+/// Every route scoped to an index along with the ACL it requires,
+/// the `{}` in the path is replaced by the name of the index.
+const INDEX_ROUTES: &[(&str, &str, ACL, Option<&str>)] = &[
+    ("GET", "/indexes/{}", IndexesRead, None),
+    ("PUT", "/indexes/{}", IndexesWrite, Some("{}")),
+    ("GET", "/indexes/{}/updates", IndexesRead, None),
+    ("GET", "/indexes/{}/updates/0", IndexesRead, None),
+    ("GET", "/indexes/{}/search?q=hello", DocumentsRead, None),
+    ("GET", "/indexes/{}/analytics", Admin, None),
+    (
+        "POST",
+        "/indexes/{}/events",
+        EventsWrite,
+        Some(r#"[{"documentId":1,"eventType":"click"}]"#),
+    ),
+    ("GET", "/indexes/{}/documents", DocumentsRead, None),
+    (
+        "POST",
+        "/indexes/{}/documents",
+        DocumentsWrite,
+        Some(r#"[{"id":1,"title":"hello"}]"#),
+    ),
+    ("DELETE", "/indexes/{}/documents", DocumentsWrite, None),
+    (
+        "POST",
+        "/indexes/{}/documents/delete",
+        DocumentsWrite,
+        Some("[1]"),
+    ),
+    ("GET", "/indexes/{}/documents/1", DocumentsRead, None),
+    ("DELETE", "/indexes/{}/documents/1", DocumentsWrite, None),
+    ("GET", "/indexes/{}/synonym", SettingsRead, None),
+    (
+        "POST",
+        "/indexes/{}/synonym",
+        SettingsWrite,
+        Some(r#"{"input":"a","synonyms":["b"]}"#),
+    ),
+    ("GET", "/indexes/{}/synonym/a", SettingsRead, None),
+    (
+        "PUT",
+        "/indexes/{}/synonym/a",
+        SettingsWrite,
+        Some(r#"["b"]"#),
+    ),
+    ("DELETE", "/indexes/{}/synonym/a", SettingsWrite, None),
+    (
+        "POST",
+        "/indexes/{}/synonym/batch",
+        SettingsWrite,
+        Some("[]"),
+    ),
+    ("POST", "/indexes/{}/synonym/clear", SettingsWrite, None),
+    ("GET", "/indexes/{}/rules", SettingsRead, None),
+    (
+        "PUT",
+        "/indexes/{}/rules/a",
+        SettingsWrite,
+        Some(r#"{"condition":{"pattern":"a","anchoring":"is"},"hidden":[1]}"#),
+    ),
+    ("GET", "/indexes/{}/rules/a", SettingsRead, None),
+    ("DELETE", "/indexes/{}/rules/a", SettingsWrite, None),
+    ("GET", "/indexes/{}/stop-words", SettingsRead, None),
+    (
+        "PUT",
+        "/indexes/{}/stop-words",
+        SettingsWrite,
+        Some(r#"["the"]"#),
+    ),
+    (
+        "DELETE",
+        "/indexes/{}/stop-words",
+        SettingsWrite,
+        Some(r#"["the"]"#),
+    ),
+    ("GET", "/indexes/{}/settings", SettingsRead, None),
+    ("POST", "/indexes/{}/settings", SettingsWrite, Some("{}")),
+    ("GET", "/indexes/{}/limits", Admin, None),
+    ("PUT", "/indexes/{}/limits", Admin, Some("{}")),
+    ("GET", "/stats/{}", Admin, None),
+];
+
+fn setup() -> (Server, Data, TempDir) {
+    let (data, dir) = setup_data();
+    data.db.create_index("movies").unwrap();
+    data.db.create_index("series").unwrap();
+    (server(data.clone()), data, dir)
+}
+
+/// Stores a new token and returns the API key to use it.
+fn create_token(data: &Data, acl: Vec<ACL>, indexes: &[&str]) -> String {
+    let (_, api_key) = common::create_token(data, acl, indexes, None);
+    api_key
+}
+
+fn request(
+    server: &mut Server,
+    method: &str,
+    uri: &str,
+    key: Option<&str>,
+    body: Option<&str>,
+) -> StatusCode {
+    send(server, method, uri, key, body).status()
+}
+
+#[test]
+fn index_routes_respect_acl_and_index_scope() {
+    let (mut server, data, _dir) = setup();
+
+    for (method, path, acl, body) in INDEX_ROUTES {
+        let movies = path.replace("{}", "movies");
+        let series = path.replace("{}", "series");
+
+        // a key with the right ACL but scoped to the movies index only
+        let scoped_key = create_token(&data, vec![acl.clone()], &["movies"]);
+
+        // a key on every index with all the ACLs except the required one
+        let other_acls = ALL_ACLS.iter().filter(|a| *a != acl).cloned().collect();
+        let other_acls_key = create_token(&data, other_acls, &["*"]);
+
+        // a key with every ACL on the indexes starting with "mov"
+        let wildcard_key = create_token(&data, vec![All], &["mov*"]);
+
+        let status = request(&mut server, method, &movies, None, *body);
+        assert_eq!(
+            status,
+            StatusCode::UNAUTHORIZED,
+            "{} {} without key",
+            method,
+            movies
+        );
+
+        let status = request(&mut server, method, &movies, Some(ADMIN_KEY), *body);
+        assert_ne!(
+            status,
+            StatusCode::FORBIDDEN,
+            "{} {} with the admin key",
+            method,
+            movies
+        );
+
+        let status = request(&mut server, method, &movies, Some(&scoped_key), *body);
+        assert_ne!(
+            status,
+            StatusCode::FORBIDDEN,
+            "{} {} with a scoped key",
+            method,
+            movies
+        );
+
+        let status = request(&mut server, method, &series, Some(&scoped_key), *body);
+        assert_eq!(
+            status,
+            StatusCode::FORBIDDEN,
+            "{} {} out of the key scope",
+            method,
+            series
+        );
+
+        let status = request(&mut server, method, &movies, Some(&other_acls_key), *body);
+        assert_eq!(
+            status,
+            StatusCode::FORBIDDEN,
+            "{} {} without the ACL",
+            method,
+            movies
+        );
+
+        let status = request(&mut server, method, &movies, Some(&wildcard_key), *body);
+        assert_ne!(
+            status,
+            StatusCode::FORBIDDEN,
+            "{} {} with a wildcard key",
+            method,
+            movies
+        );
+
+        let status = request(&mut server, method, &series, Some(&wildcard_key), *body);
+        assert_eq!(
+            status,
+            StatusCode::FORBIDDEN,
+            "{} {} out of the wildcard",
+            method,
+            series
+        );
+    }
+}
+
+#[test]
+fn aliases_are_checked_against_their_index() {
+    let (mut server, data, _dir) = setup();
+    data.db.set_alias("films", "series").unwrap();
+
+    let movies_key = create_token(&data, vec![All], &["movies"]);
+    let films_key = create_token(&data, vec![All], &["films"]);
+
+    let status = request(
+        &mut server,
+        "GET",
+        "/indexes/films",
+        Some(&movies_key),
+        None,
+    );
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // the name of the alias itself does not give access to the index
+    let status = request(&mut server, "GET", "/indexes/films", Some(&films_key), None);
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let body = Some(r#"{"index":"series"}"#);
+    let status = request(
+        &mut server,
+        "PUT",
+        "/aliases/other",
+        Some(&movies_key),
+        body,
+    );
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[test]
+fn multi_index_search_is_scoped() {
+    let (mut server, data, _dir) = setup();
+    let search_key = create_token(&data, vec![DocumentsRead], &["movies"]);
+
+    let body = Some(r#"{"indexes":["movies","series"],"query":"hello"}"#);
+    let status = request(
+        &mut server,
+        "POST",
+        "/indexes/search",
+        Some(&search_key),
+        body,
+    );
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let body = Some(r#"{"indexes":["*"],"query":"hello"}"#);
+    let status = request(
+        &mut server,
+        "POST",
+        "/indexes/search",
+        Some(&search_key),
+        body,
+    );
+    assert_ne!(status, StatusCode::FORBIDDEN);
+}
+
+#[test]
+fn index_management_is_scoped() {
+    let (mut server, data, _dir) = setup();
+    let movies_key = create_token(&data, vec![IndexesWrite], &["movies*"]);
+
+    let status = request(
+        &mut server,
+        "POST",
+        "/indexes/series",
+        Some(&movies_key),
+        None,
+    );
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let body = Some(r#"{"name":"series-v2"}"#);
+    let status = request(
+        &mut server,
+        "POST",
+        "/indexes/movies/clone",
+        Some(&movies_key),
+        body,
+    );
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let body = Some(r#"{"name":"movies-v2"}"#);
+    let status = request(
+        &mut server,
+        "POST",
+        "/indexes/movies/clone",
+        Some(&movies_key),
+        body,
+    );
+    assert_eq!(status, StatusCode::CREATED);
+
+    let status = request(
+        &mut server,
+        "DELETE",
+        "/indexes/series",
+        Some(&movies_key),
+        None,
+    );
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let status = request(
+        &mut server,
+        "DELETE",
+        "/indexes/movies-v2",
+        Some(&movies_key),
+        None,
+    );
+    assert_eq!(status, StatusCode::NO_CONTENT);
+}
+
+fn tenant_token(data: &Data, api_key: &str, indexes: &[&str], expires_in: Duration) -> String {
+    let key_id = &api_key[..KEY_ID_LENGTH];
+    let signing_key = common::signing_key(data, key_id);
+    sign_tenant_token_with(key_id, &signing_key, indexes, expires_in)
+}
+
+fn sign_tenant_token_with(
+    key_id: &str,
+    signing_key: &str,
+    indexes: &[&str],
+    expires_in: Duration,
+) -> String {
+    let claims = TenantClaims {
+        indexes: indexes.iter().map(|s| s.to_string()).collect(),
+        filter: Some(String::from("tenant_id:42")),
+        expires_at: Utc::now() + expires_in,
+    };
+    sign_tenant_token(key_id, signing_key, &claims).unwrap()
+}
+
+#[test]
+fn tenant_tokens_are_restricted_to_search() {
+    let (mut server, data, _dir) = setup();
+    let parent_key = create_token(&data, vec![DocumentsRead], &["*"]);
+    let token = tenant_token(&data, &parent_key, &["movies"], Duration::hours(1));
+
+    let status = request(
+        &mut server,
+        "GET",
+        "/indexes/movies/search?q=hello",
+        Some(&token),
+        None,
+    );
+    assert_ne!(status, StatusCode::FORBIDDEN);
+
+    // the tenant token is restricted to its own indexes
+    let status = request(
+        &mut server,
+        "GET",
+        "/indexes/series/search?q=hello",
+        Some(&token),
+        None,
+    );
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let body = Some(r#"{"indexes":["movies","series"],"query":"hello"}"#);
+    let status = request(&mut server, "POST", "/indexes/search", Some(&token), body);
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // the embedded filter would not be applied on the other routes
+    let status = request(
+        &mut server,
+        "GET",
+        "/indexes/movies/documents",
+        Some(&token),
+        None,
+    );
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[test]
+fn tenant_tokens_are_verified() {
+    let (mut server, data, _dir) = setup();
+    let parent_key = create_token(&data, vec![DocumentsRead], &["series"]);
+    let uri = "/indexes/movies/search?q=hello";
+
+    // the tenant token cannot access more than its parent
+    let token = tenant_token(&data, &parent_key, &["*"], Duration::hours(1));
+    let status = request(&mut server, "GET", uri, Some(&token), None);
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let parent_key = create_token(&data, vec![DocumentsRead], &["*"]);
+
+    let token = tenant_token(&data, &parent_key, &["*"], Duration::hours(-1));
+    let status = request(&mut server, "GET", uri, Some(&token), None);
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // signed with a key that is not the signing key of the parent key
+    let key_id = &parent_key[..KEY_ID_LENGTH];
+    let forged_key = generate_random_string(SECRET_LENGTH);
+    let token = sign_tenant_token_with(key_id, &forged_key, &["*"], Duration::hours(1));
+    let status = request(&mut server, "GET", uri, Some(&token), None);
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // the stored token is not enough to sign without the master key
+    let reader = data.db.env.read_txn().unwrap();
+    let token_key = format!("{}{}", TOKEN_PREFIX_KEY, key_id);
+    let stored = data
+        .db
+        .common_store()
+        .get::<Str, SerdeBincode<Token>>(&reader, &token_key)
+        .unwrap()
+        .unwrap();
+    reader.abort();
+    let forged_key = stored.secret.signing_key("not-the-admin-key", key_id);
+    let token = sign_tenant_token_with(key_id, &forged_key, &["*"], Duration::hours(1));
+    let status = request(&mut server, "GET", uri, Some(&token), None);
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let token = tenant_token(&data, &parent_key, &["*"], Duration::hours(1));
+    let status = request(&mut server, "GET", uri, Some(&token), None);
+    assert_ne!(status, StatusCode::FORBIDDEN);
+
+    let status = request(&mut server, "GET", uri, Some("ABCDEFGH.e30.e30"), None);
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
@@ -0,0 +1,174 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use heed::types::{ByteSlice, SerdeBincode, Str};
+use http::StatusCode;
+use serde_json::Value;
+use tempfile::TempDir;
+
+use meilidb_http::data::{migrate_legacy_tokens, Data};
+use meilidb_http::models::tenant_token::{sign_tenant_token, TenantClaims};
+use meilidb_http::models::token::{LegacyToken, ACL, LEGACY_TOKEN_PREFIX_KEY, TOKEN_PREFIX_KEY};
+
+use common::{request, send, server, setup_data, Server};
+
+fn setup() -> (Server, Data, TempDir) {
+    let (data, dir) = setup_data();
+    (server(data.clone()), data, dir)
+}
+
+fn status(server: &mut Server, key: &str) -> StatusCode {
+    send(server, "GET", "/indexes", Some(key), None).status()
+}
+
+/// Creates a key through the API and returns it along with its key id.
+fn create_key(server: &mut Server) -> (String, String) {
+    let expires_at = (Utc::now() + Duration::days(1)).timestamp();
+    let body = format!(
+        r#"{{"description":"","acl":["indexesRead"],"indexes":["*"],"expiresAt":{}}}"#,
+        expires_at
+    );
+
+    let (status, body) = request(server, "POST", "/keys", Some(&body));
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    (string(&body["key"]), string(&body["keyId"]))
+}
+
+fn rotate(server: &mut Server, key_id: &str, body: &str) -> (StatusCode, Value) {
+    let uri = format!("/keys/{}/rotate", key_id);
+    request(server, "POST", &uri, Some(body))
+}
+
+fn string(value: &Value) -> String {
+    value.as_str().unwrap().to_owned()
+}
+
+#[test]
+fn rotated_keys_stay_valid_during_the_grace_period() {
+    let (mut server, _data, _dir) = setup();
+    let (first_key, key_id) = create_key(&mut server);
+    assert_eq!(status(&mut server, &first_key), StatusCode::OK);
+
+    let (status_code, body) = rotate(&mut server, &key_id, r#"{"gracePeriod":3600}"#);
+    assert_eq!(status_code, StatusCode::OK, "{}", body);
+    let second_key = string(&body["key"]);
+    assert!(body["previousKeyExpiresAt"].is_string());
+
+    assert_eq!(status(&mut server, &first_key), StatusCode::OK);
+    assert_eq!(status(&mut server, &second_key), StatusCode::OK);
+
+    // only the secret replaced by the last rotation stays valid
+    let (status_code, body) = rotate(&mut server, &key_id, r#"{"gracePeriod":0}"#);
+    assert_eq!(status_code, StatusCode::OK, "{}", body);
+    let third_key = string(&body["key"]);
+
+    assert_eq!(status(&mut server, &first_key), StatusCode::FORBIDDEN);
+    assert_eq!(status(&mut server, &second_key), StatusCode::FORBIDDEN);
+    assert_eq!(status(&mut server, &third_key), StatusCode::OK);
+}
+
+#[test]
+fn invalid_grace_periods_are_rejected() {
+    let (mut server, _data, _dir) = setup();
+    let (key, key_id) = create_key(&mut server);
+
+    for grace_period in &["-1", "31536001", "9223372036854775807"] {
+        let body = format!(r#"{{"gracePeriod":{}}}"#, grace_period);
+        let (status_code, body) = rotate(&mut server, &key_id, &body);
+        assert_eq!(status_code, StatusCode::BAD_REQUEST, "{}", body);
+    }
+
+    // the secret has not been rotated
+    assert_eq!(status(&mut server, &key), StatusCode::OK);
+}
+
+#[test]
+fn legacy_keys_are_migrated_or_kept() {
+    let (mut server, data, _dir) = setup();
+
+    let legacy_keys = ["ABCDEFGHsecret1", "ABCDEFGHsecret2", "short"];
+
+    let mut writer = data.db.env.write_txn().unwrap();
+    for key in &legacy_keys {
+        let token = LegacyToken {
+            key: key.to_string(),
+            description: key.to_string(),
+            acl: vec![ACL::IndexesRead],
+            indexes: vec!["*".to_owned()],
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            expires_at: Utc::now() + Duration::days(1),
+            revoked: false,
+        };
+        let token_key = format!("{}{}", LEGACY_TOKEN_PREFIX_KEY, key);
+        data.db
+            .common_store()
+            .put::<Str, SerdeBincode<LegacyToken>>(&mut writer, &token_key, &token)
+            .unwrap();
+    }
+    writer.commit().unwrap();
+
+    migrate_legacy_tokens(&data.db).unwrap();
+
+    // the keys that cannot be hashed are kept and still usable
+    let reader = data.db.env.read_txn().unwrap();
+    let common_store = data.db.common_store();
+    let stored = |key: String| common_store.get::<Str, ByteSlice>(&reader, &key).unwrap();
+    assert!(stored(format!("{}ABCDEFGH", TOKEN_PREFIX_KEY)).is_some());
+    assert!(stored(format!("{}ABCDEFGHsecret1", LEGACY_TOKEN_PREFIX_KEY)).is_none());
+    assert!(stored(format!("{}ABCDEFGHsecret2", LEGACY_TOKEN_PREFIX_KEY)).is_some());
+    assert!(stored(format!("{}short", LEGACY_TOKEN_PREFIX_KEY)).is_some());
+    reader.abort();
+
+    for key in &legacy_keys {
+        assert_eq!(status(&mut server, key), StatusCode::OK, "{}", key);
+    }
+
+    // the kept keys are deleted with their raw API key
+    for key in &legacy_keys[1..] {
+        let (status_code, _) = request(&mut server, "DELETE", &format!("/keys/{}", key), None);
+        assert_eq!(status_code, StatusCode::ACCEPTED);
+        assert_eq!(status(&mut server, key), StatusCode::FORBIDDEN, "{}", key);
+    }
+    assert_eq!(status(&mut server, legacy_keys[0]), StatusCode::OK);
+}
+
+fn tenant_token(key_id: &str, signing_key: &str) -> String {
+    let claims = TenantClaims {
+        indexes: vec!["*".to_owned()],
+        filter: None,
+        expires_at: Utc::now() + Duration::hours(1),
+    };
+    sign_tenant_token(key_id, signing_key, &claims).unwrap()
+}
+
+#[test]
+fn tenant_signing_keys_are_given_with_the_secret() {
+    let (mut server, data, _dir) = setup();
+    data.db.create_index("movies").unwrap();
+
+    let expires_at = (Utc::now() + Duration::days(1)).timestamp();
+    let body = format!(
+        r#"{{"description":"","acl":["documentsRead"],"indexes":["*"],"expiresAt":{}}}"#,
+        expires_at
+    );
+    let (status_code, body) = request(&mut server, "POST", "/keys", Some(&body));
+    assert_eq!(status_code, StatusCode::CREATED, "{}", body);
+    let key_id = string(&body["keyId"]);
+    let first_token = tenant_token(&key_id, &string(&body["tenantSigningKey"]));
+
+    // the signing key is only given with a new secret
+    let (_, body) = request(&mut server, "GET", &format!("/keys/{}", key_id), None);
+    assert!(body.get("tenantSigningKey").is_none());
+
+    let uri = "/indexes/movies/search?q=hello";
+    let search =
+        |server: &mut Server, key: &str| send(server, "GET", uri, Some(key), None).status();
+    assert_ne!(search(&mut server, &first_token), StatusCode::FORBIDDEN);
+
+    // the tenant tokens follow the secret they are derived from
+    let (_, body) = rotate(&mut server, &key_id, r#"{"gracePeriod":0}"#);
+    let second_token = tenant_token(&key_id, &string(&body["tenantSigningKey"]));
+    assert_eq!(search(&mut server, &first_token), StatusCode::FORBIDDEN);
+    assert_ne!(search(&mut server, &second_token), StatusCode::FORBIDDEN);
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use http::StatusCode;
+use tempfile::TempDir;
+
+use meilidb_http::middleware::MetricsMiddleware;
+
+use common::{read_text, send, server_with, setup_data, Server, ADMIN_KEY};
+
+fn setup() -> (Server, TempDir) {
+    let (data, dir) = setup_data();
+    data.db.create_index("movies").unwrap();
+
+    let server = server_with(data, |app| {
+        app.middleware(MetricsMiddleware);
+    });
+    (server, dir)
+}
+
+fn get(server: &mut Server, uri: &str, key: Option<&str>) -> (StatusCode, String) {
+    let response = send(server, "GET", uri, key, None);
+    (response.status(), read_text(response))
+}
+
+#[test]
+fn metrics_require_the_admin_key() {
+    let (mut server, _dir) = setup();
+
+    let (status, _) = get(&mut server, "/metrics", None);
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[test]
+fn metrics_text_exposition() {
+    let (mut server, _dir) = setup();
+
+    let (status, _) = get(&mut server, "/indexes", Some(ADMIN_KEY));
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = get(&mut server, "/indexes/series", Some(ADMIN_KEY));
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, body) = get(&mut server, "/metrics", Some(ADMIN_KEY));
+    assert_eq!(status, StatusCode::OK);
+
+    let expected = &[
+        r#"meilidb_http_requests_total{method="GET",route="/indexes",status="200"} 1"#,
+        r#"meilidb_http_requests_total{method="GET",route="/indexes/:index",status="404"} 1"#,
+        r#"meilidb_http_request_duration_seconds_count{method="GET",route="/indexes"} 1"#,
+        r#"meilidb_index_documents{index="movies"} 0"#,
+        r#"meilidb_update_queue_size{index="movies"} 0"#,
+        "# TYPE meilidb_database_size_bytes gauge",
+    ];
+
+    for line in expected {
+        assert!(body.contains(line), "{} not found in\n{}", line, body);
+    }
+}
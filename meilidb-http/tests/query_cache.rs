@@ -0,0 +1,83 @@
+mod common;
+
+use std::thread;
+use std::time::Duration;
+
+use http::StatusCode;
+use meilidb_schema::{SchemaBuilder, DISPLAYED, INDEXED};
+use serde_json::{json, Value};
+use tempfile::TempDir;
+
+use meilidb_http::data::Data;
+
+use common::{add_documents, create_index, hit_ids, request, server, setup_data, Server};
+
+/// Creates an index with a document matching "iphone".
+fn setup() -> (Server, Data, TempDir) {
+    let (data, dir) = setup_data();
+
+    let mut builder = SchemaBuilder::with_identifier("id");
+    builder.new_attribute("id", DISPLAYED);
+    builder.new_attribute("title", DISPLAYED | INDEXED);
+
+    let documents = vec![json!({ "id": 1, "title": "iphone" })];
+    create_index(&data, "movies", builder.build(), documents, None);
+
+    (server(data.clone()), data, dir)
+}
+
+fn cache_stats(server: &mut Server) -> Value {
+    let (status, body) = request(server, "GET", "/stats/movies", None);
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    body["queryCache"].clone()
+}
+
+#[test]
+fn repeated_searches_hit_the_cache() {
+    let (mut server, _data, _dir) = setup();
+
+    assert_eq!(
+        hit_ids(&mut server, "/indexes/movies/search?q=iphone"),
+        vec![json!(1)]
+    );
+    assert_eq!(
+        hit_ids(&mut server, "/indexes/movies/search?q=IPhone"),
+        vec![json!(1)]
+    );
+
+    assert_eq!(
+        cache_stats(&mut server),
+        json!({ "entries": 1, "hits": 1, "misses": 1 })
+    );
+
+    // another page is another search
+    hit_ids(&mut server, "/indexes/movies/search?q=iphone&offset=1");
+    assert_eq!(
+        cache_stats(&mut server),
+        json!({ "entries": 2, "hits": 1, "misses": 2 })
+    );
+}
+
+#[test]
+fn updates_invalidate_the_cache() {
+    let (mut server, data, _dir) = setup();
+
+    assert_eq!(
+        hit_ids(&mut server, "/indexes/movies/search?q=iphone"),
+        vec![json!(1)]
+    );
+
+    add_documents(&data, "movies", vec![json!({ "id": 2, "title": "iphone" })]);
+
+    // the cache is invalidated right after the update is committed
+    for _ in 0..100 {
+        if cache_stats(&mut server)["entries"] == 0 {
+            break;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(cache_stats(&mut server)["entries"], 0);
+
+    let ids = hit_ids(&mut server, "/indexes/movies/search?q=iphone");
+    assert_eq!(ids.len(), 2);
+}
@@ -0,0 +1,168 @@
+mod common;
+
+use http::StatusCode;
+use meilidb_schema::{SchemaBuilder, DISPLAYED, INDEXED};
+use serde_json::{json, Value};
+use tempfile::TempDir;
+
+use common::{create_index, request, server, setup_data, Server};
+
+/// Creates an index with six documents matching "iphone",
+/// the even ones are black and the odd ones are white.
+fn setup() -> (Server, TempDir) {
+    let (data, dir) = setup_data();
+
+    let mut builder = SchemaBuilder::with_identifier("id");
+    builder.new_attribute("id", DISPLAYED);
+    builder.new_attribute("title", DISPLAYED | INDEXED);
+    builder.new_attribute("color", DISPLAYED | INDEXED);
+
+    let documents = (1..=6)
+        .map(|id| {
+            let color = if id % 2 == 0 { "black" } else { "white" };
+            json!({ "id": id, "title": "iphone", "color": color })
+        })
+        .collect();
+    create_index(&data, "phones", builder.build(), documents, None);
+
+    (server(data), dir)
+}
+
+fn put_rule(server: &mut Server, id: &str, rule: Value) {
+    let uri = format!("/indexes/phones/rules/{}", id);
+    let (status, body) = request(server, "PUT", &uri, Some(&rule.to_string()));
+    assert_eq!(status, StatusCode::OK, "{}", body);
+}
+
+fn hit_ids(server: &mut Server, query: &str) -> Vec<u64> {
+    let uri = format!("/indexes/phones/search?{}", query);
+    common::hit_ids(server, &uri)
+        .iter()
+        .map(|id| id.as_u64().unwrap())
+        .collect()
+}
+
+#[test]
+fn pinned_and_hidden_documents() {
+    let (mut server, _dir) = setup();
+
+    let rule = json!({
+        "condition": { "pattern": "iPhone", "anchoring": "is" },
+        "pinned": [{ "id": 5, "position": 0 }, { "id": "3", "position": 3 }],
+        "hidden": [2],
+    });
+    put_rule(&mut server, "iphone", rule);
+
+    let ids = hit_ids(&mut server, "q=iphone");
+    assert_eq!(ids.len(), 5);
+    assert_eq!(ids[0], 5);
+    assert_eq!(ids[3], 3);
+    assert!(!ids.contains(&2));
+
+    // the condition is matched on the normalized query only
+    let ids = hit_ids(&mut server, "q=IPHONE%20%20");
+    assert_eq!(ids[0], 5);
+    let ids = hit_ids(&mut server, "q=iphone%20x");
+    assert!(ids.contains(&2));
+}
+
+#[test]
+fn pinned_documents_keep_pagination_consistent() {
+    let (mut server, _dir) = setup();
+
+    let rule = json!({
+        "condition": { "pattern": "iphone", "anchoring": "is" },
+        "pinned": [{ "id": 6, "position": 1 }, { "id": 4, "position": 3 }],
+    });
+    put_rule(&mut server, "iphone", rule);
+
+    let all = hit_ids(&mut server, "q=iphone");
+    assert_eq!(all.len(), 6);
+    assert_eq!((all[1], all[3]), (6, 4));
+
+    let mut pages = Vec::new();
+    for offset in 0..3 {
+        let query = format!("q=iphone&offset={}&limit=2", offset * 2);
+        pages.extend(hit_ids(&mut server, &query));
+    }
+    assert_eq!(pages, all);
+}
+
+#[test]
+fn pinned_documents_respect_filters() {
+    let (mut server, _dir) = setup();
+
+    let rule = json!({
+        "condition": { "pattern": "iph", "anchoring": "startsWith" },
+        "pinned": [{ "id": 1, "position": 0 }, { "id": 42, "position": 1 }],
+    });
+    put_rule(&mut server, "prefix", rule);
+
+    // the unknown document is never pinned
+    let ids = hit_ids(&mut server, "q=iphone");
+    assert_eq!(ids[0], 1);
+    assert_eq!(ids.len(), 6);
+
+    let ids = hit_ids(&mut server, "q=iphone&filters=color:black");
+    assert_eq!(ids.len(), 3);
+    assert!(ids.iter().all(|id| id % 2 == 0));
+}
+
+#[test]
+fn manage_rules() {
+    let (mut server, _dir) = setup();
+    let uri = "/indexes/phones/rules/iphone";
+
+    let (status, body) = request(&mut server, "GET", uri, None);
+    assert_eq!(status, StatusCode::NOT_FOUND, "{}", body);
+
+    let rule = json!({
+        "condition": { "pattern": "  iPhone  X ", "anchoring": "is" },
+        "hidden": [2, "2"],
+    });
+    put_rule(&mut server, "iphone", rule);
+
+    let expected = json!({
+        "condition": { "pattern": "iphone x", "anchoring": "is" },
+        "pinned": [],
+        "hidden": ["2"],
+    });
+    let (status, body) = request(&mut server, "GET", uri, None);
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, expected);
+
+    let (status, body) = request(&mut server, "GET", "/indexes/phones/rules", None);
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!({ "iphone": expected }));
+
+    let (status, _) = request(&mut server, "DELETE", uri, None);
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = request(&mut server, "DELETE", uri, None);
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[test]
+fn invalid_rules() {
+    let (mut server, _dir) = setup();
+    let uri = "/indexes/phones/rules/iphone";
+
+    let invalid = &[
+        json!({ "condition": { "pattern": "  ", "anchoring": "is" } }),
+        json!({
+            "condition": { "pattern": "iphone", "anchoring": "is" },
+            "pinned": [{ "id": 1, "position": 0 }, { "id": 2, "position": 0 }],
+        }),
+        json!({
+            "condition": { "pattern": "iphone", "anchoring": "is" },
+            "pinned": [{ "id": 1, "position": 0 }],
+            "hidden": [1],
+        }),
+        json!({ "condition": { "pattern": "iphone", "anchoring": "endsWith" } }),
+    ];
+
+    for rule in invalid {
+        let (status, body) = request(&mut server, "PUT", uri, Some(&rule.to_string()));
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", rule);
+        assert!(body["errorCode"].is_string(), "{}", body);
+    }
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use http::{Response, StatusCode};
+use http_service::Body;
+use tempfile::TempDir;
+
+use meilidb_http::data::Data;
+use meilidb_http::middleware::RateLimitMiddleware;
+use meilidb_http::models::token::{RateLimit, ACL};
+
+use common::{read_json, send, server_with, setup_data, Server, ADMIN_KEY};
+
+fn setup() -> (Server, Data, TempDir) {
+    let (data, dir) = setup_data();
+    data.db.create_index("movies").unwrap();
+
+    let server = server_with(data.clone(), |app| {
+        app.middleware(RateLimitMiddleware);
+    });
+    (server, data, dir)
+}
+
+fn create_token(data: &Data, rate_limit: Option<RateLimit>) -> (String, String) {
+    common::create_token(data, vec![ACL::IndexesRead], &["*"], rate_limit)
+}
+
+fn get(server: &mut Server, uri: &str, key: &str) -> Response<Body> {
+    send(server, "GET", uri, Some(key), None)
+}
+
+#[test]
+fn keys_are_throttled() {
+    let (mut server, data, _dir) = setup();
+    let limit = RateLimit {
+        requests_per_second: 1,
+        burst: 2,
+    };
+    let (_, limited_key) = create_token(&data, Some(limit));
+    let (_, unlimited_key) = create_token(&data, None);
+
+    for _ in 0..2 {
+        let response = get(&mut server, "/indexes", &limited_key);
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    let response = get(&mut server, "/indexes", &limited_key);
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()["Retry-After"], "1");
+
+    // the other keys and the admin key are not throttled
+    for _ in 0..5 {
+        let response = get(&mut server, "/indexes", &unlimited_key);
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = get(&mut server, "/indexes", ADMIN_KEY);
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
+
+#[test]
+fn key_usage_is_counted() {
+    let (mut server, data, _dir) = setup();
+    let limit = RateLimit {
+        requests_per_second: 1,
+        burst: 1,
+    };
+    let (key_id, api_key) = create_token(&data, Some(limit));
+
+    for _ in 0..3 {
+        get(&mut server, "/indexes", &api_key);
+    }
+
+    let uri = format!("/keys/{}/usage", key_id);
+    let response = get(&mut server, &uri, ADMIN_KEY);
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let usage = read_json(response);
+    assert_eq!(usage["totalRequests"], 3);
+    assert_eq!(usage["throttledRequests"], 2);
+    assert!(usage["lastRequestAt"].is_string());
+
+    // the stored usages are merged with the pending ones
+    data.rate_limiter.flush(&data.db).unwrap();
+    assert_eq!(data.rate_limiter.pending_usage(&key_id).total_requests, 0);
+    get(&mut server, "/indexes", &api_key);
+
+    let usage = read_json(get(&mut server, &uri, ADMIN_KEY));
+    assert_eq!(usage["totalRequests"], 4);
+}
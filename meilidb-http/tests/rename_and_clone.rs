@@ -0,0 +1,96 @@
+mod common;
+
+use http::StatusCode;
+use meilidb_schema::{SchemaBuilder, DISPLAYED, INDEXED};
+use serde_json::json;
+use tempfile::TempDir;
+
+use meilidb_http::data::Data;
+
+use common::{add_documents, create_index, hit_ids, request, server, setup_data, Server};
+
+/// Creates an index of movies with a document matching "iphone", an alias
+/// pointing to it and an empty index of series.
+fn setup() -> (Server, Data, TempDir) {
+    let (data, dir) = setup_data();
+
+    let mut builder = SchemaBuilder::with_identifier("id");
+    builder.new_attribute("id", DISPLAYED);
+    builder.new_attribute("title", DISPLAYED | INDEXED);
+
+    let schema = builder.build();
+
+    let documents = vec![json!({ "id": 1, "title": "iphone" })];
+    create_index(&data, "movies", schema.clone(), documents, None);
+    create_index(&data, "series", schema, vec![], None);
+
+    data.db.set_alias("films", "movies").unwrap();
+
+    (server(data.clone()), data, dir)
+}
+
+#[test]
+fn rename_index() {
+    let (mut server, _data, _dir) = setup();
+
+    let body = r#"{ "name": "movies-v2" }"#;
+    let (status, response) = request(&mut server, "POST", "/indexes/movies/rename", Some(body));
+    assert_eq!(status, StatusCode::OK, "{}", response);
+    assert_eq!(response, json!({ "name": "movies-v2" }));
+
+    let (status, _) = request(&mut server, "GET", "/indexes/movies/search?q=iphone", None);
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let ids = hit_ids(&mut server, "/indexes/movies-v2/search?q=iphone");
+    assert_eq!(ids, vec![json!(1)]);
+
+    // the aliases follow the renamed index
+    let (status, response) = request(&mut server, "GET", "/aliases/films", None);
+    assert_eq!(status, StatusCode::OK, "{}", response);
+    assert_eq!(response["index"], "movies-v2");
+
+    let ids = hit_ids(&mut server, "/indexes/films/search?q=iphone");
+    assert_eq!(ids, vec![json!(1)]);
+
+    let (status, _) = request(&mut server, "POST", "/indexes/movies/rename", Some(body));
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let body = r#"{ "name": "series" }"#;
+    let (status, _) = request(&mut server, "POST", "/indexes/movies-v2/rename", Some(body));
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let body = r#"{ "name": "films" }"#;
+    let (status, _) = request(&mut server, "POST", "/indexes/movies-v2/rename", Some(body));
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // the index has not been renamed by the failed attempts
+    let ids = hit_ids(&mut server, "/indexes/movies-v2/search?q=iphone");
+    assert_eq!(ids, vec![json!(1)]);
+}
+
+#[test]
+fn clone_index() {
+    let (mut server, data, _dir) = setup();
+
+    let body = r#"{ "name": "movies-copy" }"#;
+    let (status, response) = request(&mut server, "POST", "/indexes/movies/clone", Some(body));
+    assert_eq!(status, StatusCode::CREATED, "{}", response);
+    assert_eq!(response, json!({ "name": "movies-copy" }));
+
+    let ids = hit_ids(&mut server, "/indexes/movies-copy/search?q=iphone");
+    assert_eq!(ids, vec![json!(1)]);
+
+    // the indexes are independent once cloned
+    add_documents(&data, "movies", vec![json!({ "id": 2, "title": "iphone" })]);
+
+    let ids = hit_ids(&mut server, "/indexes/movies/search?q=iphone");
+    assert_eq!(ids.len(), 2);
+    let ids = hit_ids(&mut server, "/indexes/movies-copy/search?q=iphone");
+    assert_eq!(ids, vec![json!(1)]);
+
+    let (status, _) = request(&mut server, "POST", "/indexes/movies/clone", Some(body));
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = request(&mut server, "POST", "/indexes/unknown/clone", Some(body));
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use http::StatusCode;
+use meilidb_schema::{SchemaBuilder, DISPLAYED, INDEXED, RANKED};
+use serde_json::json;
+use tempfile::TempDir;
+
+use meilidb_http::data::Data;
+
+use common::{add_documents, create_index, request, server, setup_data, Server};
+
+fn setup() -> (Server, Data, TempDir) {
+    let (data, dir) = setup_data();
+
+    let mut builder = SchemaBuilder::with_identifier("id");
+    builder.new_attribute("id", DISPLAYED);
+    builder.new_attribute("title", DISPLAYED | INDEXED);
+    builder.new_attribute("release_date", DISPLAYED | RANKED);
+    create_index(&data, "movies", builder.build(), Vec::new(), None);
+
+    (server(data.clone()), data, dir)
+}
+
+fn wait_update(data: &Data, update_id: u64) -> Result<(), String> {
+    common::wait_update(data, "movies", update_id)
+}
+
+#[test]
+fn update_settings() {
+    let (mut server, data, _dir) = setup();
+    let uri = "/indexes/movies/settings";
+
+    let settings = json!({
+        "stopWords": ["The", "of"],
+        "rankingRules": ["typo", " desc(release_date) "],
+        "distinctField": "title",
+        "attributesWeights": { "title": 3 },
+    });
+    let (status, body) = request(&mut server, "POST", uri, Some(&settings.to_string()));
+    assert_eq!(status, StatusCode::ACCEPTED, "{}", body);
+    let update_id = body["updateId"].as_u64().unwrap();
+    assert_eq!(wait_update(&data, update_id), Ok(()));
+
+    // only the given settings are changed
+    let settings = json!({ "stopWords": ["a", "the"] });
+    let (status, body) = request(&mut server, "POST", uri, Some(&settings.to_string()));
+    assert_eq!(status, StatusCode::ACCEPTED, "{}", body);
+    let update_id = body["updateId"].as_u64().unwrap();
+    assert_eq!(wait_update(&data, update_id), Ok(()));
+
+    let expected = json!({
+        "stopWords": ["a", "the"],
+        "rankingRules": ["typo", "desc(release_date)"],
+        "distinctField": "title",
+        "attributesWeights": { "title": 3 },
+    });
+    let (status, body) = request(&mut server, "GET", uri, None);
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, expected);
+}
+
+#[test]
+fn invalid_settings() {
+    let (mut server, _data, _dir) = setup();
+    let uri = "/indexes/movies/settings";
+
+    let invalid = &[
+        json!({ "rankingRules": ["exactness", "exactness"] }),
+        json!({ "rankingRules": ["asc(title)"] }),
+        json!({ "rankingRules": ["asc(price)"] }),
+        json!({ "distinctField": "brand" }),
+        json!({ "attributesWeights": { "brand": 2 } }),
+    ];
+
+    for settings in invalid {
+        let (status, body) = request(&mut server, "POST", uri, Some(&settings.to_string()));
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", settings);
+        assert_eq!(body["errorCode"], "invalid_settings", "{}", body);
+    }
+
+    // the syntax of the rules is checked when the body is parsed
+    let malformed = &[
+        json!({ "rankingRules": "exactness" }),
+        json!({ "rankingRules": ["typos"] }),
+        json!({ "rankingRules": ["desc(release_date"] }),
+    ];
+
+    for settings in malformed {
+        let (status, body) = request(&mut server, "POST", uri, Some(&settings.to_string()));
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", settings);
+        assert_eq!(body["errorCode"], "bad_request", "{}", body);
+    }
+}
+
+#[test]
+fn ranking_rules_override() {
+    let (mut server, data, _dir) = setup();
+
+    let documents = [(1, 2001), (2, 1999), (3, 2010)]
+        .iter()
+        .map(
+            |(id, release_date)| json!({ "id": id, "title": "star", "release_date": release_date }),
+        )
+        .collect();
+    add_documents(&data, "movies", documents);
+
+    let settings = json!({ "rankingRules": ["typo", "desc(release_date)"] });
+    let uri = "/indexes/movies/settings";
+    let (status, body) = request(&mut server, "POST", uri, Some(&settings.to_string()));
+    assert_eq!(status, StatusCode::ACCEPTED, "{}", body);
+    let update_id = body["updateId"].as_u64().unwrap();
+    assert_eq!(wait_update(&data, update_id), Ok(()));
+
+    let mut hit_ids = |query: &str| {
+        let uri = format!("/indexes/movies/search?q=star{}", query);
+        common::hit_ids(&mut server, &uri)
+    };
+
+    assert_eq!(hit_ids(""), vec![json!(3), json!(1), json!(2)]);
+
+    // the rules of the query are only used for this search
+    let ids = hit_ids("&rankingRules=typo,asc(release_date)");
+    assert_eq!(ids, vec![json!(2), json!(1), json!(3)]);
+    assert_eq!(hit_ids(""), vec![json!(3), json!(1), json!(2)]);
+
+    let invalid = &[
+        ("&rankingRules=typos", "bad_parameter"),
+        ("&rankingRules=asc(title)", "invalid_search_parameter"),
+    ];
+
+    for (query, code) in invalid {
+        let uri = format!("/indexes/movies/search?q=star{}", query);
+        let (status, body) = request(&mut server, "GET", &uri, None);
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", query);
+        assert_eq!(body["errorCode"], *code, "{}", body);
+    }
+}
@@ -0,0 +1,134 @@
+mod common;
+
+use http::StatusCode;
+use meilidb_schema::{SchemaBuilder, DISPLAYED, INDEXED, RANKED};
+use serde_json::json;
+use tempfile::TempDir;
+
+use common::{create_index, request, server, setup_data, Server};
+
+/// Creates an index of shoes, only the first one matches the query exactly.
+fn setup() -> (Server, TempDir) {
+    let (data, dir) = setup_data();
+
+    let mut builder = SchemaBuilder::with_identifier("id");
+    builder.new_attribute("id", DISPLAYED);
+    builder.new_attribute("title", DISPLAYED | INDEXED);
+    builder.new_attribute("color", DISPLAYED | INDEXED);
+    builder.new_attribute("price", DISPLAYED | RANKED);
+    builder.new_attribute("rating", DISPLAYED | RANKED);
+
+    let documents = vec![
+        json!({ "id": 1, "title": "sneaker", "price": 90, "rating": 4 }),
+        json!({ "id": 2, "title": "sneakers", "price": 30, "rating": 4 }),
+        json!({ "id": 3, "title": "sneakers", "price": 60, "rating": 5 }),
+    ];
+    create_index(&data, "shoes", builder.build(), documents, None);
+
+    (server(data), dir)
+}
+
+fn hit_ids(server: &mut Server, query: &str) -> Vec<u64> {
+    let uri = format!("/indexes/shoes/search?q=sneaker{}", query);
+    common::hit_ids(server, &uri)
+        .iter()
+        .map(|id| id.as_u64().unwrap())
+        .collect()
+}
+
+#[test]
+fn sort_documents() {
+    let (mut server, _dir) = setup();
+
+    assert_eq!(hit_ids(&mut server, "")[0], 1);
+    assert_eq!(hit_ids(&mut server, "&sort=price:asc"), vec![2, 3, 1]);
+    assert_eq!(hit_ids(&mut server, "&sort=price:desc"), vec![1, 3, 2]);
+    assert_eq!(
+        hit_ids(&mut server, "&sort=rating:desc,price:desc"),
+        vec![3, 1, 2]
+    );
+
+    // the sort only breaks the ties when it is applied after the relevance
+    let ids = hit_ids(&mut server, "&sort=price:asc&sortPosition=6");
+    assert_eq!(ids, vec![1, 2, 3]);
+}
+
+#[test]
+fn sort_multi_index() {
+    let (mut server, _dir) = setup();
+
+    let body = json!({ "indexes": ["shoes"], "query": "sneaker", "sort": "price:asc" });
+    let (status, body) = request(
+        &mut server,
+        "POST",
+        "/indexes/search",
+        Some(&body.to_string()),
+    );
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let ids: Vec<_> = body["hits"]["shoes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|hit| hit["id"].as_u64().unwrap())
+        .collect();
+    assert_eq!(ids, vec![2, 3, 1]);
+}
+
+#[test]
+fn invalid_sort() {
+    let (mut server, _dir) = setup();
+
+    let invalid = &[
+        ("price", "bad_parameter"),
+        ("price:up", "bad_parameter"),
+        ("price:asc,price:desc", "bad_parameter"),
+        ("color:asc", "invalid_search_parameter"),
+        ("brand:asc", "invalid_search_parameter"),
+    ];
+
+    for (sort, code) in invalid {
+        let uri = format!("/indexes/shoes/search?q=sneaker&sort={}", sort);
+        let (status, body) = request(&mut server, "GET", &uri, None);
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", sort);
+        assert_eq!(body["errorCode"], *code, "{}", body);
+    }
+
+    let uri = "/indexes/shoes/search?q=sneaker&sort=color:asc";
+    let (_, body) = request(&mut server, "GET", uri, None);
+    let message = body["message"].as_str().unwrap();
+    assert!(message.contains("not ranked"), "{}", message);
+}
+
+#[test]
+fn ranking_details() {
+    let (mut server, _dir) = setup();
+
+    let uri = "/indexes/shoes/search?q=sneaker&sort=price:asc";
+    let (status, body) = request(&mut server, "GET", uri, None);
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert!(body["hits"][0].get("_rankingInfo").is_none());
+
+    let uri = format!("{}&showRankingDetails=true", uri);
+    let (status, body) = request(&mut server, "GET", &uri, None);
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let hit = &body["hits"][0];
+    assert_eq!(hit["id"], 2);
+
+    let info = hit["_rankingInfo"].as_object().unwrap();
+    let rules: Vec<_> = info.keys().map(String::as_str).collect();
+    let expected = vec![
+        "asc(price)",
+        "typo",
+        "words",
+        "proximity",
+        "attribute",
+        "wordsPosition",
+        "exactness",
+    ];
+    assert_eq!(rules, expected);
+    assert_eq!(info["asc(price)"], 30);
+    assert_eq!(info["typo"], 0);
+    assert_eq!(info["words"], 1);
+}
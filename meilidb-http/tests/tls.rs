@@ -0,0 +1,136 @@
+use std::fs;
+use std::net::TcpListener as StdTcpListener;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use async_std::net::TcpStream;
+use async_std::task;
+use async_tls::TlsConnector;
+use futures::io::{AsyncReadExt, AsyncWriteExt};
+use rustls::{Certificate, ClientConfig};
+use tempfile::TempDir;
+
+use meilidb_http::data::Data;
+use meilidb_http::option::Opt;
+use meilidb_http::routes;
+use meilidb_http::tls::{self, Error, TlsConfig};
+
+/// Writes a new self-signed certificate for localhost and its private key,
+/// returns their paths along with the DER encoded certificate.
+fn self_signed_certificate(dir: &Path) -> (PathBuf, PathBuf, Vec<u8>) {
+    let cert = rcgen::generate_simple_self_signed(vec![String::from("localhost")]).unwrap();
+
+    let cert_path = dir.join("cert.pem");
+    let key_path = dir.join("key.pem");
+    fs::write(&cert_path, cert.serialize_pem().unwrap()).unwrap();
+    fs::write(&key_path, cert.serialize_private_key_pem()).unwrap();
+
+    (cert_path, key_path, cert.serialize_der().unwrap())
+}
+
+#[test]
+fn load_self_signed_certificate() {
+    let dir = TempDir::new().unwrap();
+    let (cert_path, key_path, _) = self_signed_certificate(dir.path());
+
+    assert!(TlsConfig::load(&cert_path, &key_path).is_ok());
+
+    let missing = dir.path().join("missing.pem");
+    match TlsConfig::load(&missing, &key_path) {
+        Err(Error::Io(path, _)) => assert_eq!(path, missing),
+        _ => panic!("a missing certificate must not be loaded"),
+    }
+
+    // the certificate is not a private key
+    match TlsConfig::load(&cert_path, &cert_path) {
+        Err(Error::MissingPrivateKey(path)) => assert_eq!(path, cert_path),
+        _ => panic!("a certificate must not be loaded as a private key"),
+    }
+
+    match TlsConfig::load(&key_path, &key_path) {
+        Err(Error::MissingCertificate(path)) => assert_eq!(path, key_path),
+        _ => panic!("a private key must not be loaded as a certificate"),
+    }
+}
+
+#[test]
+fn reload_certificate() {
+    let dir = TempDir::new().unwrap();
+    let (cert_path, key_path, _) = self_signed_certificate(dir.path());
+
+    let tls_config = TlsConfig::load(&cert_path, &key_path).unwrap();
+    let first = tls_config.server_config();
+
+    self_signed_certificate(dir.path());
+    tls_config.reload().unwrap();
+    let second = tls_config.server_config();
+    assert!(!Arc::ptr_eq(&first, &second));
+
+    // an invalid certificate does not replace the current one
+    fs::write(&cert_path, "invalid certificate").unwrap();
+    assert!(tls_config.reload().is_err());
+    assert!(Arc::ptr_eq(&second, &tls_config.server_config()));
+}
+
+#[test]
+fn serve_over_tls() {
+    let dir = TempDir::new().unwrap();
+    let (cert_path, key_path, cert_der) = self_signed_certificate(dir.path());
+    let tls_config = Arc::new(TlsConfig::load(&cert_path, &key_path).unwrap());
+
+    let opt = Opt {
+        database_path: dir.path().join("db").to_str().unwrap().to_owned(),
+        ..Opt::default()
+    };
+    let mut app = tide::App::with_state(Data::new(opt));
+    routes::load_routes(&mut app);
+
+    // find a free port for the server
+    let addr = StdTcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .to_string();
+
+    let service = app.into_http_service();
+    let server_addr = addr.clone();
+    task::spawn(async move { tls::serve(service, &server_addr, tls_config).await.unwrap() });
+
+    let mut client_config = ClientConfig::new();
+    client_config
+        .root_store
+        .add(&Certificate(cert_der))
+        .unwrap();
+    let connector = TlsConnector::from(Arc::new(client_config));
+
+    let response = task::block_on(async {
+        // wait for the server to listen
+        let mut stream = None;
+        for _ in 0..50 {
+            match TcpStream::connect(&addr).await {
+                Ok(s) => {
+                    stream = Some(s);
+                    break;
+                }
+                Err(_) => thread::sleep(Duration::from_millis(100)),
+            }
+        }
+
+        let mut stream = connector
+            .connect("localhost", stream.expect("server is not listening"))
+            .unwrap()
+            .await
+            .unwrap();
+
+        let request = "GET /health HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n";
+        stream.write_all(request.as_bytes()).await.unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    });
+
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+}